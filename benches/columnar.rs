@@ -0,0 +1,171 @@
+use block_model_utils::block::{BlockCoordinates, BlockIndex, BlockInterface, BlockSize};
+use block_model_utils::block_model::BlockModel;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use serde::{Deserialize, Serialize};
+
+//block with a realistic number of attributes so row scans carry unused fields
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+struct WideBlock {
+    x: f32,
+    y: f32,
+    z: f32,
+    i: usize,
+    j: usize,
+    k: usize,
+    grade: f64,
+    tonnage: f64,
+    density: f64,
+    cu: f64,
+    ag: f64,
+    s: f64,
+    as_ppm: f64,
+    recovery: f64,
+    hardness: f64,
+    rock: u32,
+    domain: u32,
+}
+
+impl BlockInterface for WideBlock {
+    fn coordinates(&self) -> BlockCoordinates {
+        BlockCoordinates {
+            x: self.x,
+            y: self.y,
+            z: self.z,
+        }
+    }
+
+    fn size(&self) -> BlockSize {
+        BlockSize {
+            x_size: 10.0,
+            y_size: 10.0,
+            z_size: 10.0,
+        }
+    }
+
+    fn index(&self) -> BlockIndex {
+        BlockIndex {
+            i: self.i,
+            j: self.j,
+            k: self.k,
+        }
+    }
+
+    fn set_index(&mut self, ind: BlockIndex) {
+        self.i = ind.i;
+        self.j = ind.j;
+        self.k = ind.k;
+    }
+}
+
+fn model(n: usize) -> BlockModel<WideBlock> {
+    let mut blocks = Vec::with_capacity(n * n * n);
+    for i in 0..n {
+        for j in 0..n {
+            for k in 0..n {
+                let v = ((i * 31 + j * 17 + k * 7) % 100) as f64 / 10.0;
+                blocks.push(WideBlock {
+                    x: 10.0 * i as f32,
+                    y: 10.0 * j as f32,
+                    z: 10.0 * k as f32,
+                    i: 0,
+                    j: 0,
+                    k: 0,
+                    grade: v,
+                    tonnage: 2700.0,
+                    density: 2.7,
+                    cu: v / 2.0,
+                    ag: v * 3.0,
+                    s: 1.0,
+                    as_ppm: 5.0,
+                    recovery: 0.9,
+                    hardness: 12.0,
+                    rock: 1,
+                    domain: 2,
+                });
+            }
+        }
+    }
+    BlockModel::from_unindexed(blocks).unwrap()
+}
+
+fn grade_tonnage(c: &mut Criterion) {
+    let rows = model(100);
+    let cols = rows.to_columnar(&[
+        ("grade", &|b: &WideBlock| b.grade),
+        ("tonnage", &|b: &WideBlock| b.tonnage),
+    ]);
+    let (grade, tonnage) = (
+        cols.column("grade").unwrap(),
+        cols.column("tonnage").unwrap(),
+    );
+    let cutoffs = (0..20).map(|n| n as f64 / 2.0).collect::<Vec<_>>();
+
+    let mut group = c.benchmark_group("grade_tonnage");
+    group.bench_function("rows", |bench| {
+        bench.iter(|| {
+            cutoffs
+                .iter()
+                .map(|cutoff| {
+                    rows.iter()
+                        .filter(|b| b.grade >= *cutoff)
+                        .map(|b| b.tonnage)
+                        .sum::<f64>()
+                })
+                .collect::<Vec<_>>()
+        })
+    });
+    group.bench_function("columns", |bench| {
+        bench.iter(|| {
+            cutoffs
+                .iter()
+                .map(|cutoff| {
+                    grade
+                        .iter()
+                        .zip(tonnage.iter())
+                        .filter(|(g, _)| **g >= *cutoff)
+                        .map(|(_, t)| *t)
+                        .sum::<f64>()
+                })
+                .collect::<Vec<_>>()
+        })
+    });
+    group.finish();
+}
+
+fn block_value(c: &mut Criterion) {
+    let rows = model(100);
+    let cols = rows.to_columnar(&[
+        ("grade", &|b: &WideBlock| b.grade),
+        ("tonnage", &|b: &WideBlock| b.tonnage),
+        ("recovery", &|b: &WideBlock| b.recovery),
+    ]);
+    let value = |g: f64, t: f64, r: f64| t * (g * r * 50.0 - 12.0);
+
+    let mut group = c.benchmark_group("block_value");
+    group.bench_function("rows", |bench| {
+        bench.iter(|| {
+            rows.iter()
+                .map(|b| value(b.grade, b.tonnage, b.recovery))
+                .sum::<f64>()
+        })
+    });
+    group.bench_function("columns", |bench| {
+        let (g, t, r) = (
+            cols.column("grade").unwrap(),
+            cols.column("tonnage").unwrap(),
+            cols.column("recovery").unwrap(),
+        );
+        bench.iter(|| {
+            g.iter()
+                .zip(t.iter())
+                .zip(r.iter())
+                .map(|((g, t), r)| value(*g, *t, *r))
+                .sum::<f64>()
+        })
+    });
+    group.finish();
+    black_box(cols);
+}
+
+criterion_group!(benches, grade_tonnage, block_value);
+criterion_main!(benches);
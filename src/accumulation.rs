@@ -0,0 +1,203 @@
+use ndarray::Array2;
+use serde::Serialize;
+
+use crate::block::{BlockIndex, BlockInterface};
+use crate::block_model::BlockModel;
+use crate::io::raster::Raster;
+use crate::storage::BlockStorage;
+
+use std::error::Error;
+use std::path::Path;
+
+//totals of one i, j column, thickness in world units
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct ColumnAccumulation {
+    pub i: usize,
+    pub j: usize,
+    pub x: f64,
+    pub y: f64,
+    pub thickness: f64,
+    pub grade_thickness: f64,
+    pub grade: f64,
+    pub metal: f64,
+    pub ore_tonnes: f64,
+    pub waste_tonnes: f64,
+    pub strip_ratio: f64,
+}
+
+//vertical accumulation of every column, NaN where a column has no blocks, grade thickness
+//and metal only count ore blocks
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnMaps {
+    pub thickness: Array2<f64>,
+    pub grade_thickness: Array2<f64>,
+    pub metal: Array2<f64>,
+    pub ore_tonnes: Array2<f64>,
+    pub waste_tonnes: Array2<f64>,
+    //world xy of the column centres
+    centres: Array2<[f64; 2]>,
+    //empty raster carrying the georeference of models with a plan grid
+    grid: Option<Raster>,
+}
+
+//thickness weighted grade, NaN without ore
+fn column_grade(grade_thickness: f64, thickness: f64) -> f64 {
+    if thickness > 0.0 {
+        grade_thickness / thickness
+    } else {
+        f64::NAN
+    }
+}
+
+//waste over ore tonnes, infinite without ore and NaN for nothing mined, shared with the
+//pushback and shell reports
+pub(crate) fn strip_ratio(ore: f64, waste: f64) -> f64 {
+    if ore > 0.0 {
+        waste / ore
+    } else if waste > 0.0 {
+        f64::INFINITY
+    } else {
+        f64::NAN
+    }
+}
+
+impl ColumnMaps {
+    pub fn dims(&self) -> [usize; 2] {
+        let shape = self.thickness.shape();
+        [shape[0], shape[1]]
+    }
+
+    pub fn grade(&self) -> Array2<f64> {
+        Array2::from_shape_fn(self.thickness.dim(), |c| {
+            column_grade(self.grade_thickness[c], self.thickness[c])
+        })
+    }
+
+    pub fn strip_ratio(&self) -> Array2<f64> {
+        Array2::from_shape_fn(self.thickness.dim(), |c| {
+            strip_ratio(self.ore_tonnes[c], self.waste_tonnes[c])
+        })
+    }
+
+    pub fn column(&self, i: usize, j: usize) -> Option<ColumnAccumulation> {
+        let thickness = *self.thickness.get((i, j))?;
+        if thickness.is_nan() {
+            return None;
+        }
+        let (ore, waste) = (self.ore_tonnes[[i, j]], self.waste_tonnes[[i, j]]);
+        let [x, y] = self.centres[[i, j]];
+        Some(ColumnAccumulation {
+            i,
+            j,
+            x,
+            y,
+            thickness,
+            grade_thickness: self.grade_thickness[[i, j]],
+            grade: column_grade(self.grade_thickness[[i, j]], thickness),
+            metal: self.metal[[i, j]],
+            ore_tonnes: ore,
+            waste_tonnes: waste,
+            strip_ratio: strip_ratio(ore, waste),
+        })
+    }
+
+    pub fn columns(&self) -> impl Iterator<Item = ColumnAccumulation> + '_ {
+        let [ni, nj] = self.dims();
+        (0..ni).flat_map(move |i| (0..nj).filter_map(move |j| self.column(i, j)))
+    }
+
+    //one row per column with blocks
+    pub fn to_csv<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn Error>> {
+        let mut w = csv::Writer::from_path(path)?;
+        for c in self.columns() {
+            w.serialize(c)?;
+        }
+        w.flush()?;
+        Ok(())
+    }
+
+    //one of the maps as a raster, e.g. maps.raster(maps.strip_ratio()) for strip ratio
+    //contours, infinite values become no data, None where the model has no plan grid
+    pub fn raster(&self, values: Array2<f64>) -> Option<Raster> {
+        let grid = self.grid.as_ref()?;
+        assert!(
+            values.dim() == self.thickness.dim(),
+            "values do not match the column dims"
+        );
+        Some(Raster {
+            values: values.mapv(|v| if v.is_finite() { v } else { f64::NAN }),
+            ..grid.clone()
+        })
+    }
+}
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface,
+    S: BlockStorage<B>,
+{
+    //accumulate every column of blocks, ore blocks add their grade times the block height to
+    //the grade thickness and grade times tonnage to the metal, columns need a vertical k axis
+    pub fn column_maps<O, G, T>(&self, ore: O, grade: G, tonnage: T) -> ColumnMaps
+    where
+        O: Fn(&B) -> bool,
+        G: Fn(&B) -> f64,
+        T: Fn(&B) -> f64,
+    {
+        let frame = self.frame();
+        assert!(
+            frame.rotation.dip == 0.0,
+            "column accumulation needs an undipped frame"
+        );
+        let dims = self.dims();
+        let dz = self.block_size().z_size as f64;
+        let shape = (dims[0], dims[1]);
+        let nan = || Array2::from_elem(shape, f64::NAN);
+        let (mut thickness, mut grade_thickness, mut metal) = (nan(), nan(), nan());
+        let (mut ore_tonnes, mut waste_tonnes) = (nan(), nan());
+
+        for i in 0..dims[0] {
+            for j in 0..dims[1] {
+                let mut totals = None;
+                for k in 0..dims[2] {
+                    let Some(b) = self.block(BlockIndex { i, j, k }) else {
+                        continue;
+                    };
+                    let [h, gt, m, o, w] = totals.get_or_insert([0.0; 5]);
+                    if ore(b) {
+                        let g = grade(b);
+                        *h += dz;
+                        *gt += g * dz;
+                        *m += g * tonnage(b);
+                        *o += tonnage(b);
+                    } else {
+                        *w += tonnage(b);
+                    }
+                }
+                if let Some([h, gt, m, o, w]) = totals {
+                    thickness[[i, j]] = h;
+                    grade_thickness[[i, j]] = gt;
+                    metal[[i, j]] = m;
+                    ore_tonnes[[i, j]] = o;
+                    waste_tonnes[[i, j]] = w;
+                }
+            }
+        }
+
+        let centres = Array2::from_shape_fn(shape, |(i, j)| {
+            let c = self.index_to_coordinates(BlockIndex { i, j, k: 0 });
+            [c.x as f64, c.y as f64]
+        });
+        let grid = self.raster(Array2::zeros(shape)).ok();
+
+        ColumnMaps {
+            thickness,
+            grade_thickness,
+            metal,
+            ore_tonnes,
+            waste_tonnes,
+            centres,
+            grid,
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use rayon::prelude::*;
+
+use crate::block::{BlockIndex, BlockInterface};
+use crate::block_model::BlockModel;
+use crate::storage::BlockStorage;
+
+use std::collections::HashMap;
+
+//element-wise operation between two attribute values
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BinaryOp {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Min,
+    Max,
+}
+
+impl BinaryOp {
+    pub fn apply(&self, a: f64, b: f64) -> f64 {
+        match self {
+            BinaryOp::Add => a + b,
+            BinaryOp::Subtract => a - b,
+            BinaryOp::Multiply => a * b,
+            BinaryOp::Divide => a / b,
+            BinaryOp::Min => a.min(b),
+            BinaryOp::Max => a.max(b),
+        }
+    }
+}
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface,
+    S: BlockStorage<B>,
+{
+    //block of other in the cell holding the centroid of ind, the same index on a shared frame
+    fn co_registered<'a, B2, S2>(
+        &self,
+        other: &'a BlockModel<B2, S2>,
+        ind: BlockIndex,
+    ) -> Option<&'a B2>
+    where
+        B2: BlockInterface,
+        S2: BlockStorage<B2>,
+    {
+        other.block(other.frame().index_from(self.frame(), ind)?)
+    }
+
+    //f of every block and its co-registered block in other, blocks other lacks are absent
+    pub fn zip_with<B2, S2, T, F>(&self, other: &BlockModel<B2, S2>, f: F) -> HashMap<BlockIndex, T>
+    where
+        B2: BlockInterface,
+        S2: BlockStorage<B2>,
+        F: Fn(&B, &B2) -> T,
+    {
+        self.indexed_iter()
+            .filter_map(|(ind, b)| Some((ind, f(b, self.co_registered(other, ind)?))))
+            .collect()
+    }
+
+    //attr op other_attr, e.g. the difference between two realisations
+    pub fn combine<B2, S2, F, G>(
+        &self,
+        other: &BlockModel<B2, S2>,
+        attr: F,
+        op: BinaryOp,
+        other_attr: G,
+    ) -> HashMap<BlockIndex, f64>
+    where
+        B2: BlockInterface,
+        S2: BlockStorage<B2>,
+        F: Fn(&B) -> f64,
+        G: Fn(&B2) -> f64,
+    {
+        self.zip_with(other, |a, b| op.apply(attr(a), other_attr(b)))
+    }
+
+    //attr op scalar for every block
+    pub fn combine_scalar<F>(&self, attr: F, op: BinaryOp, scalar: f64) -> HashMap<BlockIndex, f64>
+    where
+        F: Fn(&B) -> f64,
+    {
+        self.indexed_iter()
+            .map(|(ind, b)| (ind, op.apply(attr(b), scalar)))
+            .collect()
+    }
+
+    //attr where condition holds and other_attr elsewhere
+    pub fn choose<B2, S2, C, F, G>(
+        &self,
+        other: &BlockModel<B2, S2>,
+        condition: C,
+        attr: F,
+        other_attr: G,
+    ) -> HashMap<BlockIndex, f64>
+    where
+        B2: BlockInterface,
+        S2: BlockStorage<B2>,
+        C: Fn(&B, &B2) -> bool,
+        F: Fn(&B) -> f64,
+        G: Fn(&B2) -> f64,
+    {
+        self.zip_with(other, |a, b| {
+            if condition(a, b) {
+                attr(a)
+            } else {
+                other_attr(b)
+            }
+        })
+    }
+
+    //store f of every block and its co-registered block in other, blocks other lacks are
+    //left unchanged
+    pub fn assign_zip_with<B2, S2, T, F, G>(&mut self, other: &BlockModel<B2, S2>, f: F, set: G)
+    where
+        B: Send,
+        B2: BlockInterface + Sync,
+        S2: BlockStorage<B2> + Sync,
+        F: Fn(&B, &B2) -> T + Sync,
+        G: Fn(&mut B, T) + Sync,
+    {
+        let (frame, other_frame) = (*self.frame(), *other.frame());
+        self.par_indexed_iter_mut().for_each(|(ind, b)| {
+            let Some(o) = other_frame
+                .index_from(&frame, ind)
+                .and_then(|o| other.block(o))
+            else {
+                return;
+            };
+            let value = f(b, o);
+            set(b, value);
+        });
+    }
+}
@@ -0,0 +1,117 @@
+use ndarray::{s, Array2, ArrayView2};
+
+use crate::block::{BlockIndex, BlockInterface};
+use crate::block_model::BlockModel;
+use crate::storage::BlockStorage;
+
+//owned copy of the blocks on a single bench of a model
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchSlice<B> {
+    k: usize,
+    cells: Array2<Option<B>>,
+}
+
+impl<B> BenchSlice<B> {
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    //i, j dimensions of the bench
+    pub fn dims(&self) -> [usize; 2] {
+        let shape = self.cells.shape();
+        [shape[0], shape[1]]
+    }
+
+    pub fn view(&self) -> ArrayView2<'_, Option<B>> {
+        self.cells.view()
+    }
+
+    pub fn get(&self, i: usize, j: usize) -> Option<&B> {
+        self.cells.get((i, j)).and_then(|b| b.as_ref())
+    }
+
+    //present blocks with their model indices
+    pub fn iter(&self) -> impl Iterator<Item = (BlockIndex, &B)> {
+        let k = self.k;
+        self.cells
+            .indexed_iter()
+            .filter_map(move |((i, j), b)| b.as_ref().map(|b| (BlockIndex { i, j, k }, b)))
+    }
+
+    //number of present blocks
+    pub fn len(&self) -> usize {
+        self.cells.iter().filter(|b| b.is_some()).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn sum<A: Fn(&B) -> f64>(&self, attr: A) -> f64 {
+        self.iter().map(|(_, b)| attr(b)).sum()
+    }
+
+    //None on an empty bench
+    pub fn mean<A: Fn(&B) -> f64>(&self, attr: A) -> Option<f64> {
+        let n = self.len();
+        (n > 0).then(|| self.sum(attr) / n as f64)
+    }
+
+    //None if all weights are zero
+    pub fn weighted_mean<A, W>(&self, attr: A, weight: W) -> Option<f64>
+    where
+        A: Fn(&B) -> f64,
+        W: Fn(&B) -> f64,
+    {
+        let (sum, total) = self.iter().fold((0.0, 0.0), |(s, t), (_, b)| {
+            let w = weight(b);
+            (s + w * attr(b), t + w)
+        });
+        (total != 0.0).then(|| sum / total)
+    }
+
+    pub fn min<A: Fn(&B) -> f64>(&self, attr: A) -> Option<f64> {
+        self.iter().map(|(_, b)| attr(b)).reduce(f64::min)
+    }
+
+    pub fn max<A: Fn(&B) -> f64>(&self, attr: A) -> Option<f64> {
+        self.iter().map(|(_, b)| attr(b)).reduce(f64::max)
+    }
+}
+
+impl<B> BlockModel<B>
+where
+    B: BlockInterface,
+{
+    //borrowed i, j view of bench k, panics if k is outside the model
+    pub fn bench(&self, k: usize) -> ArrayView2<'_, Option<B>> {
+        self.blocks.slice(s![.., .., k])
+    }
+}
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface,
+    S: BlockStorage<B>,
+{
+    //owned copy of bench k, panics if k is outside the model
+    pub fn bench_slice(&self, k: usize) -> BenchSlice<B> {
+        let dims = self.dims();
+        assert!(
+            k < dims[2],
+            "bench {} outside model with {} benches",
+            k,
+            dims[2]
+        );
+
+        let cells = Array2::from_shape_fn((dims[0], dims[1]), |(i, j)| {
+            self.block(BlockIndex { i, j, k }).cloned()
+        });
+        BenchSlice { k, cells }
+    }
+
+    //owned slices of every bench, bottom first
+    pub fn benches(&self) -> Vec<BenchSlice<B>> {
+        (0..self.dims()[2]).map(|k| self.bench_slice(k)).collect()
+    }
+}
@@ -26,6 +26,9 @@ pub struct BlockSize {
     pub z_size: f32,
 }
 
+//numeric block attribute with a display name, for exports and checks over several attributes
+pub type NamedAttribute<'a, B> = (&'a str, &'a dyn Fn(&B) -> f64);
+
 //required interface for blocks to be suitable for use in blockmodel
 pub trait BlockInterface: Clone + PartialEq + for<'a> Deserialize<'a> {
     //coordinates of block in space
@@ -37,4 +40,9 @@ pub trait BlockInterface: Clone + PartialEq + for<'a> Deserialize<'a> {
     //index
     fn index(&self) -> BlockIndex;
     fn set_index(&mut self, ind: BlockIndex);
+
+    //move the block, false for blocks whose coordinates cannot be set
+    fn set_coordinates(&mut self, _coords: BlockCoordinates) -> bool {
+        false
+    }
 }
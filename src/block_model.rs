@@ -1,34 +1,43 @@
 use ndarray::Array3;
-use num;
+use rayon::prelude::*;
 
 use crate::block::{BlockCoordinates, BlockIndex, BlockInterface, BlockSize};
+use crate::categorical::Dictionary;
+use crate::error::BlockModelError;
+use crate::frame::{FrameRotation, ModelFrame};
+use crate::storage::{BlockStorage, SparseStorage};
 
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::error::Error;
+use std::marker::PhantomData;
 
 pub trait BlockDependenceInterface {
-    fn inds<B: BlockInterface>(&self, mdl: &BlockModel<B>, ind: BlockIndex) -> Vec<BlockIndex>;
+    fn inds<B: BlockInterface, S: BlockStorage<B>>(
+        &self,
+        mdl: &BlockModel<B, S>,
+        ind: BlockIndex,
+    ) -> Vec<BlockIndex>;
 }
 
 #[derive(Copy, Clone, Debug, Default, Hash)]
 pub struct SquarePreds;
 
-impl SquarePreds {
-    const PREDS: isize = -1;
-    const SUCCS: isize = 1;
-}
-
 impl BlockDependenceInterface for SquarePreds {
-    fn inds<B: BlockInterface>(&self, mdl: &BlockModel<B>, ind: BlockIndex) -> Vec<BlockIndex> {
+    fn inds<B: BlockInterface, S: BlockStorage<B>>(
+        &self,
+        mdl: &BlockModel<B, S>,
+        ind: BlockIndex,
+    ) -> Vec<BlockIndex> {
         let k = ind.k + 1;
-        if k >= mdl.blocks.raw_dim()[2] {
+        if k >= mdl.dims()[2] {
             return vec![];
         };
 
-        let i_low = num::clamp(ind.i as i64 - 1, 0, mdl.blocks.shape()[0] as i64) as usize;
-        let i_high = num::clamp(ind.i + 2, 0, mdl.blocks.shape()[0]);
+        let i_low = num::clamp(ind.i as i64 - 1, 0, mdl.dims()[0] as i64) as usize;
+        let i_high = num::clamp(ind.i + 2, 0, mdl.dims()[0]);
 
-        let j_low = num::clamp(ind.j as i64 - 1, 0, mdl.blocks.shape()[1] as i64) as usize;
-        let j_high = num::clamp(ind.j + 2, 0, mdl.blocks.shape()[1]);
+        let j_low = num::clamp(ind.j as i64 - 1, 0, mdl.dims()[1] as i64) as usize;
+        let j_high = num::clamp(ind.j + 2, 0, mdl.dims()[1]);
 
         let mut inds = Vec::with_capacity(9);
         for i in i_low..i_high {
@@ -43,17 +52,21 @@ impl BlockDependenceInterface for SquarePreds {
 pub struct SquareSuccs;
 
 impl BlockDependenceInterface for SquareSuccs {
-    fn inds<B: BlockInterface>(&self, mdl: &BlockModel<B>, ind: BlockIndex) -> Vec<BlockIndex> {
+    fn inds<B: BlockInterface, S: BlockStorage<B>>(
+        &self,
+        mdl: &BlockModel<B, S>,
+        ind: BlockIndex,
+    ) -> Vec<BlockIndex> {
         if ind.k == 0 {
             return vec![];
         }
         let k = ind.k - 1;
 
-        let i_low = num::clamp(ind.i as i64 - 1, 0, mdl.blocks.shape()[0] as i64) as usize;
-        let i_high = num::clamp(ind.i + 2, 0, mdl.blocks.shape()[0]);
+        let i_low = num::clamp(ind.i as i64 - 1, 0, mdl.dims()[0] as i64) as usize;
+        let i_high = num::clamp(ind.i + 2, 0, mdl.dims()[0]);
 
-        let j_low = num::clamp(ind.j as i64 - 1, 0, mdl.blocks.shape()[1] as i64) as usize;
-        let j_high = num::clamp(ind.j + 2, 0, mdl.blocks.shape()[1]);
+        let j_low = num::clamp(ind.j as i64 - 1, 0, mdl.dims()[1] as i64) as usize;
+        let j_high = num::clamp(ind.j + 2, 0, mdl.dims()[1]);
 
         let mut inds = Vec::with_capacity(9);
         for i in i_low..i_high {
@@ -67,19 +80,23 @@ impl BlockDependenceInterface for SquareSuccs {
 
 pub struct SquareAdj;
 impl BlockDependenceInterface for SquareAdj {
-    fn inds<B: BlockInterface>(&self, mdl: &BlockModel<B>, ind: BlockIndex) -> Vec<BlockIndex> {
+    fn inds<B: BlockInterface, S: BlockStorage<B>>(
+        &self,
+        mdl: &BlockModel<B, S>,
+        ind: BlockIndex,
+    ) -> Vec<BlockIndex> {
         let k = ind.k;
 
-        let i_low = num::clamp(ind.i as i64 - 1, 0, mdl.blocks.shape()[0] as i64) as usize;
-        let i_high = num::clamp(ind.i + 2, 0, mdl.blocks.shape()[0]);
+        let i_low = num::clamp(ind.i as i64 - 1, 0, mdl.dims()[0] as i64) as usize;
+        let i_high = num::clamp(ind.i + 2, 0, mdl.dims()[0]);
 
-        let j_low = num::clamp(ind.j as i64 - 1, 0, mdl.blocks.shape()[1] as i64) as usize;
-        let j_high = num::clamp(ind.j + 2, 0, mdl.blocks.shape()[1]);
+        let j_low = num::clamp(ind.j as i64 - 1, 0, mdl.dims()[1] as i64) as usize;
+        let j_high = num::clamp(ind.j + 2, 0, mdl.dims()[1]);
 
         let mut inds = Vec::with_capacity(9);
         for i in i_low..i_high {
             for j in j_low..j_high {
-                if mdl.block(BlockIndex { i, j, k }).is_some() {
+                if mdl.get(BlockIndex { i, j, k }).is_some() {
                     inds.push(BlockIndex { i, j, k });
                 }
             }
@@ -88,44 +105,103 @@ impl BlockDependenceInterface for SquareAdj {
     }
 }
 
+//allowed distance of a rotated block centroid from the lattice, in blocks
+const ROTATED_TOLERANCE: f32 = 1e-2;
+
+//blockmodel generic over block type and storage layout, dense by default
 #[derive(Debug)]
-pub struct BlockModel<B>
+pub struct BlockModel<B, S = Array3<Option<B>>>
 where
     B: BlockInterface,
+    S: BlockStorage<B>,
 {
-    pub blocks: Array3<Option<B>>,
+    pub blocks: S,
+    pub(crate) frame: ModelFrame,
+    //dictionaries of categorical attributes by name
+    pub(crate) categories: BTreeMap<String, Dictionary>,
+    _block: PhantomData<B>,
 }
 
-impl<B> BlockModel<B>
+impl<B, S> IntoIterator for BlockModel<B, S>
 where
     B: BlockInterface,
+    S: BlockStorage<B>,
 {
+    type Item = (BlockIndex, B);
+    type IntoIter = S::IntoBlocks;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.blocks.into_blocks()
+    }
+}
+
+//blockmodel storing only present blocks
+pub type SparseBlockModel<B> = BlockModel<B, SparseStorage<B>>;
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface,
+    S: BlockStorage<B>,
+{
+    //index of a block on the lattice defined by origin and block_size
+    pub(crate) fn block_ind(
+        b: &B,
+        origin: BlockCoordinates,
+        block_size: BlockSize,
+    ) -> Result<BlockIndex, BlockModelError> {
+        let coords = b.coordinates();
+        let i = (coords.x - origin.x) / block_size.x_size;
+        let j = (coords.y - origin.y) / block_size.y_size;
+        let k = (coords.z - origin.z) / block_size.z_size;
+
+        //incorrect block size
+        if i.fract() != 0.0 || j.fract() != 0.0 || k.fract() != 0.0 {
+            return Err(BlockModelError::MisalignedBlock { coords });
+        }
+
+        Ok(BlockIndex {
+            i: i as usize,
+            j: j as usize,
+            k: k as usize,
+        })
+    }
+
     fn gen_inds(
-        blocks: &Vec<B>,
+        blocks: &[B],
         origin: BlockCoordinates,
         block_size: BlockSize,
-    ) -> Vec<BlockIndex> {
+    ) -> Result<Vec<BlockIndex>, BlockModelError> {
         blocks
             .iter()
-            .map(|ub| {
-                let coords = ub.coordinates();
-                let i = (coords.x - origin.x) / block_size.x_size;
-                let j = (coords.y - origin.y) / block_size.y_size;
-                let k = (coords.z - origin.z) / block_size.z_size;
-
-                //incorrect block size
-                assert!(i.fract() == 0.0 && j.fract() == 0.0 && k.fract() == 0.0);
-
-                BlockIndex {
-                    i: i as usize,
-                    j: j as usize,
-                    k: k as usize,
-                }
-            })
-            .collect::<Vec<_>>()
+            .map(|b| Self::block_ind(b, origin, block_size))
+            .collect()
+    }
+
+    //common size of all blocks
+    fn common_size(blocks: &[B]) -> Result<BlockSize, BlockModelError> {
+        match blocks {
+            [head, tail @ ..] => tail
+                .iter()
+                .all(|b| head.size() == b.size())
+                .then(|| head.size())
+                .ok_or(BlockModelError::MixedBlockSizes),
+            _ => Err(BlockModelError::Empty),
+        }
+    }
+
+    //smallest dimensions containing all indices
+    fn index_dims(inds: &[BlockIndex]) -> [usize; 3] {
+        let (max_i, max_j, max_k) = inds.iter().fold((0, 0, 0), |(mut i, mut j, mut k), ib| {
+            i = i.max(ib.i);
+            j = j.max(ib.j);
+            k = k.max(ib.k);
+            (i, j, k)
+        });
+
+        [max_i + 1, max_j + 1, max_k + 1]
     }
 
-    pub fn from_unindexed(blocks: Vec<B>) -> Self {
+    pub fn from_unindexed(blocks: Vec<B>) -> Result<Self, BlockModelError> {
         let mut blocks = blocks;
 
         //get origin of model
@@ -147,48 +223,221 @@ where
         };
 
         //get block dims and ensure all same size
-        let dims = match blocks.as_slice() {
-            [head, tail @ ..] => tail
-                .iter()
-                .all(|b| head.size() == b.size())
-                .then(|| head.size()),
-            _ => None,
+        let block_size = Self::common_size(&blocks)?;
+
+        //Generate indexed block set, misaligned blocks may come from a rotated model
+        let inds = match Self::gen_inds(&blocks, origin, block_size) {
+            Ok(inds) => inds,
+            Err(e @ BlockModelError::MisalignedBlock { .. }) => {
+                return match Self::detect_rotation(&blocks, block_size) {
+                    Some(rotation) => Self::from_unindexed_rotated(blocks, rotation),
+                    None => Err(e),
+                };
+            }
+            Err(e) => return Err(e),
         };
 
-        if dims == None {
-            panic!()
-        };
+        blocks
+            .iter_mut()
+            .zip(inds.iter())
+            .for_each(|(b, ind)| b.set_index(*ind));
 
-        //Generate indexed block set
-        let inds = Self::gen_inds(&blocks, origin, dims.unwrap());
+        let frame = ModelFrame::new(origin, block_size, Self::index_dims(&inds));
+        Self::from_parts(blocks, inds, frame)
+    }
+
+    //blocks on a lattice with known rotation, centroids may be off the lattice by
+    //ROTATED_TOLERANCE of a block to absorb rounding in rotated coordinates
+    pub fn from_unindexed_rotated(
+        blocks: Vec<B>,
+        rotation: FrameRotation,
+    ) -> Result<Self, BlockModelError> {
+        let mut blocks = blocks;
+        let block_size = Self::common_size(&blocks)?;
+
+        let (origin, inds) = Self::rotated_inds(&blocks, block_size, rotation);
+        let inds = blocks
+            .iter()
+            .zip(inds)
+            .map(|(b, ind)| {
+                ind.ok_or(BlockModelError::MisalignedBlock {
+                    coords: b.coordinates(),
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
 
         blocks
             .iter_mut()
             .zip(inds.iter())
             .for_each(|(b, ind)| b.set_index(*ind));
 
-        Self::from_indexed(blocks, inds)
+        let frame =
+            ModelFrame::new(origin, block_size, Self::index_dims(&inds)).with_rotation(rotation);
+        Self::from_parts(blocks, inds, frame)
     }
 
-    pub fn from_indexed(blocks: Vec<B>, inds: Vec<BlockIndex>) -> Self {
-        //Find model dimensions
-        let (max_i, max_j, max_k) = inds.iter().fold((0, 0, 0), |(mut i, mut j, mut k), ib| {
-            i = i.max(ib.i);
-            j = j.max(ib.j);
-            k = k.max(ib.k);
-            (i, j, k)
+    //origin and index of every block on the lattice along rotated axes, None for blocks off
+    //the lattice
+    pub(crate) fn rotated_inds(
+        blocks: &[B],
+        block_size: BlockSize,
+        rotation: FrameRotation,
+    ) -> (BlockCoordinates, Vec<Option<BlockIndex>>) {
+        //block centroids along the model axes
+        let zero = BlockCoordinates {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        };
+        let axes = ModelFrame::new(zero, block_size, [0; 3]).with_rotation(rotation);
+        let local = blocks
+            .iter()
+            .map(|b| axes.to_local(b.coordinates()))
+            .collect::<Vec<_>>();
+
+        let min = local.iter().fold([f32::MAX; 3], |mut min, l| {
+            (0..3).for_each(|d| min[d] = min[d].min(l[d]));
+            min
         });
+        let inds = local
+            .iter()
+            .map(|l| Self::rotated_ind(*l, min, block_size))
+            .collect();
+        (axes.to_world(min), inds)
+    }
+
+    //index of a centroid at offset local along the rotated axes, min is the offset of the
+    //origin, None off the lattice
+    fn rotated_ind(local: [f32; 3], min: [f32; 3], block_size: BlockSize) -> Option<BlockIndex> {
+        let size = [block_size.x_size, block_size.y_size, block_size.z_size];
+        let mut ind = [0; 3];
+        for d in 0..3 {
+            let pos = (local[d] - min[d]) / size[d];
+            if (pos - pos.round()).abs() > ROTATED_TOLERANCE {
+                return None;
+            }
+            ind[d] = pos.round() as usize;
+        }
+
+        Some(BlockIndex {
+            i: ind[0],
+            j: ind[1],
+            k: ind[2],
+        })
+    }
+
+    //horizontal offset of c from the first block if both lie on the same bench apart
+    fn bench_offset(
+        first: BlockCoordinates,
+        c: BlockCoordinates,
+        block_size: BlockSize,
+    ) -> Option<(f32, f32)> {
+        let (dx, dy) = (c.x - first.x, c.y - first.y);
+        ((c.z - first.z).abs() < block_size.z_size / 2.0
+            && dx.hypot(dy) > ROTATED_TOLERANCE * block_size.x_size)
+            .then_some((dx, dy))
+    }
+
+    //azimuth of a rotated model from the nearest block on the same bench as the first block
+    pub(crate) fn detect_rotation(blocks: &[B], block_size: BlockSize) -> Option<FrameRotation> {
+        let first = blocks.first()?.coordinates();
+        let nearest = blocks
+            .iter()
+            .filter_map(|b| Self::bench_offset(first, b.coordinates(), block_size))
+            .min_by(|a, b| a.0.hypot(a.1).total_cmp(&b.0.hypot(b.1)))?;
+        Self::neighbour_rotation(nearest, block_size)
+    }
+
+    //azimuth of the lattice from the offset of the nearest neighbour on a bench, None for
+    //unrotated lattices or neighbours off both axes
+    fn neighbour_rotation((dx, dy): (f32, f32), block_size: BlockSize) -> Option<FrameRotation> {
+        let dist = dx.hypot(dy);
+        let bearing = dx.atan2(dy).to_degrees();
+        let close = |size: f32| (dist - size).abs() <= ROTATED_TOLERANCE * size;
+
+        //the neighbour lies along either the j or the i axis
+        let azimuth = if close(block_size.y_size) {
+            bearing
+        } else if close(block_size.x_size) {
+            bearing - 90.0
+        } else {
+            return None;
+        };
+
+        //reversed axes describe the same lattice
+        let period = if block_size.x_size == block_size.y_size {
+            90.0
+        } else {
+            180.0
+        };
+        let azimuth = azimuth.rem_euclid(period);
+        if azimuth.min(period - azimuth) < 1e-3 {
+            return None;
+        }
+
+        Some(FrameRotation::new(azimuth, 0.0))
+    }
+
+    pub fn from_indexed(
+        mut blocks: Vec<B>,
+        inds: Vec<BlockIndex>,
+    ) -> Result<Self, BlockModelError> {
+        let block_size = Self::common_size(&blocks)?;
+        if blocks.len() != inds.len() {
+            return Err(BlockModelError::MismatchedIndices {
+                blocks: blocks.len(),
+                inds: inds.len(),
+            });
+        }
 
-        //create array to store blocks
-        let mut block_arr = Array3::from_elem((max_i + 1, max_j + 1, max_k + 1), None);
+        //recover origin from first block
+        let coords = blocks[0].coordinates();
+        let ind = inds[0];
+        let origin = BlockCoordinates {
+            x: coords.x - ind.i as f32 * block_size.x_size,
+            y: coords.y - ind.j as f32 * block_size.y_size,
+            z: coords.z - ind.k as f32 * block_size.z_size,
+        };
+
+        blocks
+            .iter_mut()
+            .zip(inds.iter())
+            .for_each(|(b, ind)| b.set_index(*ind));
+
+        let frame = ModelFrame::new(origin, block_size, Self::index_dims(&inds));
+        Self::from_parts(blocks, inds, frame)
+    }
+
+    pub(crate) fn from_parts(
+        blocks: Vec<B>,
+        inds: Vec<BlockIndex>,
+        frame: ModelFrame,
+    ) -> Result<Self, BlockModelError> {
+        //create storage for blocks
+        let mut storage = S::with_dims(frame.dims);
 
         //populate bm
-        blocks.into_iter().zip(inds).for_each(|(b, ind)| {
-            let BlockIndex { i, j, k } = ind;
-            block_arr[[i, j, k]] = Some(b);
-        });
+        for (b, ind) in blocks.into_iter().zip(inds) {
+            if storage.insert(ind, b).is_some() {
+                return Err(BlockModelError::DuplicateIndex { ind });
+            }
+        }
 
-        Self { blocks: block_arr }
+        Ok(Self {
+            blocks: storage,
+            frame,
+            categories: BTreeMap::new(),
+            _block: PhantomData,
+        })
+    }
+
+    pub(crate) fn from_storage(blocks: S, frame: ModelFrame) -> Self {
+        Self {
+            blocks,
+            frame,
+            categories: BTreeMap::new(),
+            _block: PhantomData,
+        }
     }
 
     pub fn from_unindexed_csv(file: String) -> Result<Self, Box<dyn Error>> {
@@ -203,15 +452,270 @@ where
             blocks.push(block);
         }
 
-        Ok(Self::from_unindexed(blocks))
+        Ok(Self::from_unindexed(blocks)?)
+    }
+
+    //two pass variant of from_unindexed_csv that never buffers the blocks,
+    //the first pass finds the frame and the second writes blocks straight into storage,
+    //rotated models fall back like from_unindexed and take two more passes
+    pub fn from_unindexed_csv_streaming(file: String) -> Result<Self, Box<dyn Error>> {
+        let mut rdr = csv::Reader::from_path(&file)?;
+
+        let mut block_size = None;
+        let mut min = [f32::MAX; 3];
+        let mut max = [f32::MIN; 3];
+        let mut first = None;
+        let mut nearest: Option<(f32, f32)> = None;
+        for result in rdr.deserialize() {
+            let block: B = result?;
+            match block_size {
+                None => block_size = Some(block.size()),
+                Some(size) if size != block.size() => {
+                    return Err(BlockModelError::MixedBlockSizes.into())
+                }
+                _ => {}
+            }
+
+            let coords = block.coordinates();
+            for (d, c) in [coords.x, coords.y, coords.z].into_iter().enumerate() {
+                min[d] = min[d].min(c);
+                max[d] = max[d].max(c);
+            }
+
+            //nearest neighbour of the first block on its bench, for rotation detection
+            let first = *first.get_or_insert(coords);
+            if let Some(o) = Self::bench_offset(first, coords, block.size()) {
+                if nearest.is_none_or(|n| o.0.hypot(o.1) < n.0.hypot(n.1)) {
+                    nearest = Some(o);
+                }
+            }
+        }
+
+        let block_size = block_size.ok_or(BlockModelError::Empty)?;
+        let origin = BlockCoordinates {
+            x: min[0],
+            y: min[1],
+            z: min[2],
+        };
+
+        //extent of the furthest block, alignment is checked per block below
+        let far = BlockCoordinates {
+            x: max[0],
+            y: max[1],
+            z: max[2],
+        };
+        let far = ModelFrame::new(origin, block_size, [usize::MAX; 3])
+            .coordinates_to_index(far)
+            .ok_or(BlockModelError::MisalignedBlock { coords: far })?;
+
+        let frame = ModelFrame::new(origin, block_size, [far.i + 1, far.j + 1, far.k + 1]);
+        let mut storage = S::with_dims(frame.dims);
+
+        let mut rdr = csv::Reader::from_path(&file)?;
+        for result in rdr.deserialize() {
+            let mut block: B = result?;
+            let ind = match Self::block_ind(&block, origin, block_size) {
+                Ok(ind) => ind,
+                Err(e @ BlockModelError::MisalignedBlock { .. }) => {
+                    return match nearest.and_then(|n| Self::neighbour_rotation(n, block_size)) {
+                        Some(rotation) => {
+                            Self::from_unindexed_csv_streaming_rotated(&file, block_size, rotation)
+                        }
+                        None => Err(e.into()),
+                    };
+                }
+                Err(e) => return Err(e.into()),
+            };
+            block.set_index(ind);
+
+            if storage.insert(ind, block).is_some() {
+                return Err(BlockModelError::DuplicateIndex { ind }.into());
+            }
+        }
+
+        Ok(Self::from_storage(storage, frame))
+    }
+
+    //streaming counterpart of from_unindexed_rotated, one pass finds the extent along the
+    //rotated axes and another writes the blocks
+    fn from_unindexed_csv_streaming_rotated(
+        file: &str,
+        block_size: BlockSize,
+        rotation: FrameRotation,
+    ) -> Result<Self, Box<dyn Error>> {
+        let zero = BlockCoordinates {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        };
+        let axes = ModelFrame::new(zero, block_size, [0; 3]).with_rotation(rotation);
+
+        let mut min = [f32::MAX; 3];
+        let mut max = [f32::MIN; 3];
+        for result in csv::Reader::from_path(file)?.deserialize() {
+            let block: B = result?;
+            let local = axes.to_local(block.coordinates());
+            for d in 0..3 {
+                min[d] = min[d].min(local[d]);
+                max[d] = max[d].max(local[d]);
+            }
+        }
+
+        //extent of the furthest block, alignment is checked per block below
+        let size = [block_size.x_size, block_size.y_size, block_size.z_size];
+        let dims = [0, 1, 2].map(|d| ((max[d] - min[d]) / size[d]).round() as usize + 1);
+        let frame = ModelFrame::new(axes.to_world(min), block_size, dims).with_rotation(rotation);
+        let mut storage = S::with_dims(frame.dims);
+
+        for result in csv::Reader::from_path(file)?.deserialize() {
+            let mut block: B = result?;
+            let coords = block.coordinates();
+            let ind = Self::rotated_ind(axes.to_local(coords), min, block_size)
+                .ok_or(BlockModelError::MisalignedBlock { coords })?;
+            block.set_index(ind);
+
+            if storage.insert(ind, block).is_some() {
+                return Err(BlockModelError::DuplicateIndex { ind }.into());
+            }
+        }
+
+        Ok(Self::from_storage(storage, frame))
+    }
+
+    pub fn frame(&self) -> &ModelFrame {
+        &self.frame
+    }
+
+    //centroid of block (0, 0, 0)
+    pub fn origin(&self) -> BlockCoordinates {
+        self.frame.origin
+    }
+
+    pub fn block_size(&self) -> BlockSize {
+        self.frame.block_size
+    }
+
+    //i, j, k dimensions of model
+    pub fn dims(&self) -> [usize; 3] {
+        self.frame.dims
+    }
+
+    pub fn index_to_coordinates(&self, ind: BlockIndex) -> BlockCoordinates {
+        self.frame.index_to_coordinates(ind)
+    }
+
+    pub fn coordinates_to_index(&self, coords: BlockCoordinates) -> Option<BlockIndex> {
+        self.frame.coordinates_to_index(coords)
+    }
+
+    //number of present blocks
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    //block containing the world coordinates, None outside the model or in empty cells
+    pub fn block_at(&self, x: f32, y: f32, z: f32) -> Option<(BlockIndex, &B)> {
+        let ind = self.coordinates_to_index(BlockCoordinates { x, y, z })?;
+        self.get(ind).map(|b| (ind, b))
+    }
+
+    //panics if ind is outside the model (dense layouts)
+    pub fn block(&self, ind: BlockIndex) -> Option<&B> {
+        self.blocks.get(ind)
+    }
+
+    pub fn block_mut(&mut self, ind: BlockIndex) -> Option<&mut B> {
+        self.blocks.get_mut(ind)
+    }
+
+    //bounds checked access, None if ind is outside the model or empty
+    pub fn get(&self, ind: BlockIndex) -> Option<&B> {
+        if !self.frame.contains(ind) {
+            return None;
+        }
+        self.blocks.get(ind)
+    }
+
+    pub fn get_mut(&mut self, ind: BlockIndex) -> Option<&mut B> {
+        if !self.frame.contains(ind) {
+            return None;
+        }
+        self.blocks.get_mut(ind)
+    }
+
+    //iterators over present blocks, order depends on the storage layout
+    pub fn iter(&self) -> impl Iterator<Item = &B> {
+        self.blocks.iter().map(|(_, b)| b)
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut B> {
+        self.blocks.iter_mut().map(|(_, b)| b)
+    }
+
+    pub fn indexed_iter(&self) -> impl Iterator<Item = (BlockIndex, &B)> {
+        self.blocks.iter()
+    }
+
+    pub fn indexed_iter_mut(&mut self) -> impl Iterator<Item = (BlockIndex, &mut B)> {
+        self.blocks.iter_mut()
+    }
+
+    //parallel iterators over present blocks, order is unspecified
+    pub fn par_iter(&self) -> impl ParallelIterator<Item = &B>
+    where
+        B: Sync,
+    {
+        self.blocks.par_iter().map(|(_, b)| b)
     }
 
-    pub fn block(&self, ind: BlockIndex) -> &Option<B> {
-        &self.blocks[[ind.i, ind.j, ind.k]]
+    pub fn par_iter_mut(&mut self) -> impl ParallelIterator<Item = &mut B>
+    where
+        B: Send,
+    {
+        self.blocks.par_iter_mut().map(|(_, b)| b)
     }
 
-    pub fn block_mut(&mut self, ind: BlockIndex) -> &mut Option<B> {
-        &mut self.blocks[[ind.i, ind.j, ind.k]]
+    pub fn par_indexed_iter(&self) -> impl ParallelIterator<Item = (BlockIndex, &B)>
+    where
+        B: Sync,
+    {
+        self.blocks.par_iter()
+    }
+
+    pub fn par_indexed_iter_mut(&mut self) -> impl ParallelIterator<Item = (BlockIndex, &mut B)>
+    where
+        B: Send,
+    {
+        self.blocks.par_iter_mut()
+    }
+
+    //attribute over the full lattice, empty cells hold NaN
+    pub fn attribute_array<F: Fn(&B) -> f64>(&self, attr: F) -> Array3<f32> {
+        let mut values = Array3::from_elem(self.dims(), f32::NAN);
+        for (ind, b) in self.indexed_iter() {
+            values[[ind.i, ind.j, ind.k]] = attr(b) as f32;
+        }
+        values
+    }
+
+    //attribute over the full lattice with empty cells set to fill, and the mask of
+    //present cells
+    pub fn masked_attribute_array<F: Fn(&B) -> f64>(
+        &self,
+        attr: F,
+        fill: f32,
+    ) -> (Array3<f32>, Array3<bool>) {
+        let mut values = Array3::from_elem(self.dims(), fill);
+        let mut mask = Array3::from_elem(self.dims(), false);
+        for (ind, b) in self.indexed_iter() {
+            values[[ind.i, ind.j, ind.k]] = attr(b) as f32;
+            mask[[ind.i, ind.j, ind.k]] = true;
+        }
+        (values, mask)
     }
 
     pub fn dependent_block_inds<BDI: BlockDependenceInterface>(
@@ -219,7 +723,52 @@ where
         ind: BlockIndex,
         bdi: BDI,
     ) -> Vec<BlockIndex> {
-        bdi.inds(&self, ind)
+        bdi.inds(self, ind)
+    }
+
+    //ind and every present block that must be removed before it
+    pub fn cone<D: BlockDependenceInterface>(
+        &self,
+        ind: BlockIndex,
+        dep: &D,
+    ) -> HashSet<BlockIndex> {
+        let mut cone = HashSet::from([ind]);
+        let mut queue = VecDeque::from([ind]);
+
+        while let Some(n) = queue.pop_front() {
+            for p in dep.inds(self, n) {
+                if self.get(p).is_some() && cone.insert(p) {
+                    queue.push_back(p);
+                }
+            }
+        }
+        cone
+    }
+
+    //ind and every present block whose cone contains it
+    //walks the dependence of the whole model, prefer PrecedenceGraph for repeated queries
+    pub fn inverse_cone<D: BlockDependenceInterface>(
+        &self,
+        ind: BlockIndex,
+        dep: &D,
+    ) -> HashSet<BlockIndex> {
+        let mut succs = HashMap::<BlockIndex, Vec<BlockIndex>>::new();
+        for (n, _) in self.indexed_iter() {
+            for p in dep.inds(self, n) {
+                succs.entry(p).or_default().push(n);
+            }
+        }
+
+        let mut cone = HashSet::from([ind]);
+        let mut queue = VecDeque::from([ind]);
+        while let Some(n) = queue.pop_front() {
+            for s in succs.get(&n).into_iter().flatten() {
+                if cone.insert(*s) {
+                    queue.push_back(*s);
+                }
+            }
+        }
+        cone
     }
     pub fn from_indexed_csv(file: String) -> Result<Self, Box<dyn Error>> {
         //create reader and storage for blocks
@@ -234,6 +783,6 @@ where
             blocks.push(block);
         }
 
-        Ok(Self::from_indexed(blocks, inds))
+        Ok(Self::from_indexed(blocks, inds)?)
     }
 }
@@ -0,0 +1,159 @@
+use serde::{Deserialize, Serialize};
+
+use crate::block::BlockInterface;
+use crate::block_model::BlockModel;
+use crate::dynamic::{AttributeValue, DynamicBlock};
+use crate::storage::BlockStorage;
+
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::path::Path;
+
+//dictionary code of a categorical value, e.g. a rock type, compared and hashed without
+//touching its name
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default, Serialize, Deserialize,
+)]
+pub struct Category(pub u32);
+
+//names of the categories of one attribute, codes are assigned in order of first encoding
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Dictionary {
+    names: Vec<String>,
+    codes: HashMap<String, Category>,
+}
+
+#[derive(Serialize)]
+struct DictionaryRow<'a> {
+    code: u32,
+    name: &'a str,
+}
+
+impl Dictionary {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //dictionary of the names in order
+    pub fn from_names<I, S>(names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut dict = Self::new();
+        for n in names {
+            dict.encode(n.as_ref());
+        }
+        dict
+    }
+
+    //code of name, adding it if new
+    pub fn encode(&mut self, name: &str) -> Category {
+        if let Some(c) = self.codes.get(name) {
+            return *c;
+        }
+        let c = Category(self.names.len() as u32);
+        self.names.push(name.to_string());
+        self.codes.insert(name.to_string(), c);
+        c
+    }
+
+    pub fn code(&self, name: &str) -> Option<Category> {
+        self.codes.get(name).copied()
+    }
+
+    pub fn name(&self, category: Category) -> Option<&str> {
+        self.names.get(category.0 as usize).map(|n| n.as_str())
+    }
+
+    //names ordered by code
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    //code and name columns, the lookup table for exports holding codes
+    pub fn to_csv<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn Error>> {
+        let mut w = csv::Writer::from_path(path)?;
+        for (n, name) in self.names.iter().enumerate() {
+            w.serialize(DictionaryRow {
+                code: n as u32,
+                name,
+            })?;
+        }
+        w.flush()?;
+        Ok(())
+    }
+}
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface,
+    S: BlockStorage<B>,
+{
+    //dictionary shared by the blocks holding categories of attribute
+    pub fn dictionary(&self, attribute: &str) -> Option<&Dictionary> {
+        self.categories.get(attribute)
+    }
+
+    //dictionary of attribute, created empty if missing
+    pub fn dictionary_mut(&mut self, attribute: &str) -> &mut Dictionary {
+        self.categories.entry(attribute.to_string()).or_default()
+    }
+
+    pub fn set_dictionary(&mut self, attribute: &str, dictionary: Dictionary) {
+        self.categories.insert(attribute.to_string(), dictionary);
+    }
+
+    pub fn dictionaries(&self) -> &BTreeMap<String, Dictionary> {
+        &self.categories
+    }
+
+    //name of a category of attribute
+    pub fn category_name(&self, attribute: &str, category: Category) -> Option<&str> {
+        self.dictionary(attribute)?.name(category)
+    }
+}
+
+impl<S: BlockStorage<DynamicBlock>> BlockModel<DynamicBlock, S> {
+    //replace the text values of attribute with categories of the model dictionary, number of
+    //blocks encoded
+    pub fn encode_categorical(&mut self, attribute: &str) -> usize {
+        let mut dict = self.categories.remove(attribute).unwrap_or_default();
+        let mut count = 0;
+        for b in self.iter_mut() {
+            if let Some(AttributeValue::Text(t)) = b.attributes.get(attribute) {
+                let c = dict.encode(t);
+                b.set(attribute, AttributeValue::Category(c));
+                count += 1;
+            }
+        }
+        self.categories.insert(attribute.to_string(), dict);
+        count
+    }
+
+    //restore text values of attribute from its dictionary, e.g. before a text export, codes
+    //missing from the dictionary are left as is, number of blocks decoded
+    pub fn decode_categorical(&mut self, attribute: &str) -> usize {
+        let Some(dict) = self.categories.get(attribute) else {
+            return 0;
+        };
+        let mut count = 0;
+        for (_, b) in self.blocks.iter_mut() {
+            if let Some(AttributeValue::Category(c)) = b.attributes.get(attribute) {
+                if let Some(name) = dict.name(*c) {
+                    b.set(attribute, AttributeValue::Text(name.to_string()));
+                    count += 1;
+                }
+            }
+        }
+        count
+    }
+}
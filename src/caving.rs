@@ -0,0 +1,283 @@
+use ndarray::Array2;
+
+use crate::block::{BlockIndex, BlockInterface};
+use crate::block_model::BlockModel;
+use crate::geometry::polygon::{ring_area, MultiPolygon, Polygon};
+use crate::selection::Selection;
+use crate::storage::BlockStorage;
+
+use std::collections::HashMap;
+
+//block cave draw from an undercut at elevation, columns are drawn between the minimum and
+//maximum height of draw, heights and elevation in world units
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BlockCave {
+    pub elevation: f64,
+    pub min_height: f64,
+    pub max_height: f64,
+}
+
+impl BlockCave {
+    pub fn new(elevation: f64, min_height: f64, max_height: f64) -> Self {
+        assert!(
+            min_height >= 0.0 && min_height <= max_height,
+            "heights of draw must not be negative with min not above max"
+        );
+        Self {
+            elevation,
+            min_height,
+            max_height,
+        }
+    }
+}
+
+//best draw of one i, j column
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DrawColumn {
+    pub i: usize,
+    pub j: usize,
+    //benches drawn from the footprint bench up
+    pub benches: usize,
+    pub height: f64,
+    pub value: f64,
+}
+
+//best height of draw of every column above a footprint bench, NaN values where a column has
+//no blocks within the heights of draw
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaveFootprint {
+    //footprint bench and the elevation of its floor
+    pub k: usize,
+    pub elevation: f64,
+    pub benches: Array2<usize>,
+    pub values: Array2<f64>,
+    //world xy of lattice corner a, b, the south west corner of column a, b
+    corners: Array2<[f64; 2]>,
+    bench_height: f64,
+}
+
+impl CaveFootprint {
+    pub fn dims(&self) -> [usize; 2] {
+        let shape = self.values.shape();
+        [shape[0], shape[1]]
+    }
+
+    pub fn column(&self, i: usize, j: usize) -> Option<DrawColumn> {
+        let value = *self.values.get((i, j))?;
+        (!value.is_nan()).then(|| DrawColumn {
+            i,
+            j,
+            benches: self.benches[[i, j]],
+            height: self.benches[[i, j]] as f64 * self.bench_height,
+            value,
+        })
+    }
+
+    pub fn columns(&self) -> impl Iterator<Item = DrawColumn> + '_ {
+        let [ni, nj] = self.dims();
+        (0..ni).flat_map(move |i| (0..nj).filter_map(move |j| self.column(i, j)))
+    }
+
+    //columns worth drawing
+    pub fn economic(&self) -> Array2<bool> {
+        self.values.mapv(|v| v > 0.0)
+    }
+
+    //value of drawing every economic column
+    pub fn value(&self) -> f64 {
+        self.columns()
+            .filter(|c| c.value > 0.0)
+            .map(|c| c.value)
+            .sum()
+    }
+
+    //blocks drawn by the economic columns on the model lattice
+    pub fn selection(&self, dims: [usize; 3]) -> Selection {
+        Selection::from_inds(
+            dims,
+            self.columns().filter(|c| c.value > 0.0).flat_map(|c| {
+                (self.k..self.k + c.benches).map(move |k| BlockIndex { i: c.i, j: c.j, k })
+            }),
+        )
+    }
+
+    //world outline of the economic columns traced along block edges, islands of waste
+    //become holes
+    pub fn outline(&self) -> MultiPolygon {
+        let economic = self.economic();
+        let [ni, nj] = self.dims();
+        let inside = |i: i64, j: i64| {
+            i >= 0
+                && j >= 0
+                && (i as usize) < ni
+                && (j as usize) < nj
+                && economic[[i as usize, j as usize]]
+        };
+
+        //boundary edges running anticlockwise around the economic area
+        let mut edges: HashMap<[i64; 2], Vec<[i64; 2]>> = HashMap::new();
+        for ((i, j), e) in economic.indexed_iter() {
+            if !*e {
+                continue;
+            }
+            let (i, j) = (i as i64, j as i64);
+            let sides = [
+                ((0, -1), [i, j], [i + 1, j]),
+                ((1, 0), [i + 1, j], [i + 1, j + 1]),
+                ((0, 1), [i + 1, j + 1], [i, j + 1]),
+                ((-1, 0), [i, j + 1], [i, j]),
+            ];
+            for ((di, dj), from, to) in sides {
+                if !inside(i + di, j + dj) {
+                    edges.entry(from).or_default().push(to);
+                }
+            }
+        }
+
+        let mut rings = Vec::new();
+        while let Some(&start) = edges.keys().next() {
+            let mut ring = vec![start];
+            let mut at = start;
+            loop {
+                let next = edges.get_mut(&at).unwrap().pop().unwrap();
+                if edges[&at].is_empty() {
+                    edges.remove(&at);
+                }
+                if next == start {
+                    break;
+                }
+                ring.push(next);
+                at = next;
+            }
+            //drop corners on straight runs
+            let n = ring.len();
+            let ring = (0..n)
+                .filter(|v| {
+                    let (p, c, q) = (ring[(v + n - 1) % n], ring[*v], ring[(v + 1) % n]);
+                    (c[0] - p[0]) * (q[1] - c[1]) != (c[1] - p[1]) * (q[0] - c[0])
+                })
+                .map(|v| ring[v])
+                .collect::<Vec<_>>();
+            rings.push(ring);
+        }
+
+        let world = |ring: &[[i64; 2]]| {
+            ring.iter()
+                .map(|c| self.corners[[c[0] as usize, c[1] as usize]])
+                .collect::<Vec<_>>()
+        };
+        let lattice = |ring: &[[i64; 2]]| {
+            ring.iter()
+                .map(|c| [c[0] as f64, c[1] as f64])
+                .collect::<Vec<_>>()
+        };
+        let (exteriors, holes): (Vec<_>, Vec<_>) =
+            rings.iter().partition(|r| ring_area(&lattice(r)) > 0.0);
+
+        let mut polygons = exteriors
+            .iter()
+            .map(|r| (Polygon::new(lattice(r)), Polygon::new(world(r))))
+            .collect::<Vec<_>>();
+        for hole in holes {
+            //centre of the economic column outside the first edge of the hole
+            let (a, b) = (hole[0], hole[1 % hole.len()]);
+            let (x, y) = (
+                (a[0] + b[0]) as f64 / 2.0 - (b[1] - a[1]) as f64 / 2.0,
+                (a[1] + b[1]) as f64 / 2.0 + (b[0] - a[0]) as f64 / 2.0,
+            );
+            let parent = polygons
+                .iter_mut()
+                .filter(|(l, _)| l.contains(x, y))
+                .min_by(|(l, _), (m, _)| l.area().total_cmp(&m.area()));
+            if let Some((l, w)) = parent {
+                l.holes.push(lattice(hole));
+                w.holes.push(world(hole));
+            }
+        }
+        MultiPolygon::new(polygons.into_iter().map(|(_, w)| w).collect())
+    }
+}
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface,
+    S: BlockStorage<B>,
+{
+    //running totals of value up column i, j from bench k, missing blocks add nothing
+    pub fn draw_column<F>(&self, i: usize, j: usize, k: usize, value: F) -> Vec<f64>
+    where
+        F: Fn(&B) -> f64,
+    {
+        (k..self.dims()[2])
+            .scan(0.0, |total, k| {
+                *total += self.block(BlockIndex { i, j, k }).map_or(0.0, &value);
+                Some(*total)
+            })
+            .collect()
+    }
+
+    //best height of draw of every column above the footprint bench, the bench whose floor is
+    //nearest the undercut elevation, columns need a vertical k axis
+    pub fn cave_footprint<F>(&self, cave: &BlockCave, value: F) -> CaveFootprint
+    where
+        F: Fn(&B) -> f64,
+    {
+        let frame = self.frame();
+        assert!(
+            frame.rotation.dip == 0.0,
+            "cave columns need an undipped frame"
+        );
+        let dims = self.dims();
+        let size = self.block_size();
+        let dz = size.z_size as f64;
+        let base = frame.origin.z as f64 - dz / 2.0;
+        let k = ((cave.elevation - base) / dz).round();
+        assert!(
+            k >= 0.0 && (k as usize) < dims[2],
+            "undercut elevation {} outside the model",
+            cave.elevation
+        );
+        let k = k as usize;
+
+        let min = ((cave.min_height / dz) - 1e-9).ceil().max(1.0) as usize;
+        let max = ((cave.max_height / dz) + 1e-9).floor() as usize;
+        let max = max.min(dims[2] - k);
+
+        let mut benches = Array2::zeros((dims[0], dims[1]));
+        let mut values = Array2::from_elem((dims[0], dims[1]), f64::NAN);
+        for i in 0..dims[0] {
+            for j in 0..dims[1] {
+                let present = (k..k + max).any(|k| self.block(BlockIndex { i, j, k }).is_some());
+                if !present || min > max {
+                    continue;
+                }
+                let totals = self.draw_column(i, j, k, &value);
+                let (n, v) = (min..=max)
+                    .map(|n| (n, totals[n - 1]))
+                    .max_by(|a, b| a.1.total_cmp(&b.1).then(b.0.cmp(&a.0)))
+                    .unwrap();
+                benches[[i, j]] = n;
+                values[[i, j]] = v;
+            }
+        }
+
+        let z = (k as f32 - 0.5) * size.z_size;
+        let corners = Array2::from_shape_fn((dims[0] + 1, dims[1] + 1), |(a, b)| {
+            let c = frame.to_world([
+                (a as f32 - 0.5) * size.x_size,
+                (b as f32 - 0.5) * size.y_size,
+                z,
+            ]);
+            [c.x as f64, c.y as f64]
+        });
+
+        CaveFootprint {
+            k,
+            elevation: base + k as f64 * dz,
+            benches,
+            values,
+            corners,
+            bench_height: dz,
+        }
+    }
+}
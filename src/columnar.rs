@@ -0,0 +1,528 @@
+use ndarray::{Array3, Zip};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::block::{BlockCoordinates, BlockIndex, BlockInterface, BlockSize, NamedAttribute};
+use crate::block_model::BlockModel;
+use crate::error::BlockModelError;
+use crate::frame::ModelFrame;
+use crate::io::GridBlock;
+use crate::storage::{BlockStorage, DenseIntoBlocks};
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+//blockmodel storing each numeric attribute in its own array, so scans over one attribute
+//touch only that attribute, empty cells hold NaN in every column
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnarModel {
+    frame: ModelFrame,
+    present: Array3<bool>,
+    names: Vec<String>,
+    columns: Vec<Array3<f64>>,
+}
+
+//view of one present cell of a columnar model
+#[derive(Debug, Clone, Copy)]
+pub struct ColumnarBlock<'a> {
+    model: &'a ColumnarModel,
+    ind: BlockIndex,
+}
+
+impl ColumnarBlock<'_> {
+    pub fn index(&self) -> BlockIndex {
+        self.ind
+    }
+
+    pub fn coordinates(&self) -> BlockCoordinates {
+        self.model.index_to_coordinates(self.ind)
+    }
+
+    pub fn size(&self) -> BlockSize {
+        self.model.block_size()
+    }
+
+    pub fn get(&self, name: &str) -> Option<f64> {
+        Some(self.value(self.model.column_index(name)?))
+    }
+
+    //value of column c, panics if c is not a column
+    pub fn value(&self, c: usize) -> f64 {
+        self.model.columns[c][[self.ind.i, self.ind.j, self.ind.k]]
+    }
+}
+
+impl ColumnarModel {
+    //empty model without columns
+    pub fn new(frame: ModelFrame) -> Self {
+        Self {
+            frame,
+            present: Array3::from_elem(frame.dims, false),
+            names: Vec::new(),
+            columns: Vec::new(),
+        }
+    }
+
+    //copy the given attributes of a blockmodel into columns
+    pub fn from_model<B, S>(mdl: &BlockModel<B, S>, attributes: &[NamedAttribute<B>]) -> Self
+    where
+        B: BlockInterface,
+        S: BlockStorage<B>,
+    {
+        let mut model = Self::new(*mdl.frame());
+        for (name, _) in attributes {
+            model.add_column(name);
+        }
+
+        let mut values = vec![0.0; attributes.len()];
+        for (ind, b) in mdl.indexed_iter() {
+            for (v, (_, attr)) in values.iter_mut().zip(attributes) {
+                *v = attr(b);
+            }
+            model.insert(ind, &values);
+        }
+        model
+    }
+
+    //build a blockmodel with one block per present cell
+    pub fn to_model<B, S, F>(&self, build: F) -> Result<BlockModel<B, S>, BlockModelError>
+    where
+        B: BlockInterface,
+        S: BlockStorage<B>,
+        F: Fn(&GridBlock) -> B,
+    {
+        let (blocks, inds) = self
+            .indexed_iter()
+            .map(|(ind, b)| {
+                let cell = GridBlock {
+                    index: ind,
+                    coordinates: b.coordinates(),
+                    size: b.size(),
+                    attributes: self
+                        .names
+                        .iter()
+                        .enumerate()
+                        .map(|(c, name)| (name.clone(), b.value(c)))
+                        .collect::<HashMap<_, _>>(),
+                };
+
+                let mut block = build(&cell);
+                block.set_index(ind);
+                (block, ind)
+            })
+            .unzip();
+
+        BlockModel::from_parts(blocks, inds, self.frame)
+    }
+
+    pub fn frame(&self) -> &ModelFrame {
+        &self.frame
+    }
+
+    pub fn origin(&self) -> BlockCoordinates {
+        self.frame.origin
+    }
+
+    pub fn block_size(&self) -> BlockSize {
+        self.frame.block_size
+    }
+
+    pub fn dims(&self) -> [usize; 3] {
+        self.frame.dims
+    }
+
+    pub fn index_to_coordinates(&self, ind: BlockIndex) -> BlockCoordinates {
+        self.frame.index_to_coordinates(ind)
+    }
+
+    pub fn coordinates_to_index(&self, coords: BlockCoordinates) -> Option<BlockIndex> {
+        self.frame.coordinates_to_index(coords)
+    }
+
+    //number of present cells
+    pub fn len(&self) -> usize {
+        self.present.iter().filter(|p| **p).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        !self.present.iter().any(|p| *p)
+    }
+
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn column_index(&self, name: &str) -> Option<usize> {
+        self.names.iter().position(|n| n == name)
+    }
+
+    pub fn column(&self, name: &str) -> Option<&Array3<f64>> {
+        Some(&self.columns[self.column_index(name)?])
+    }
+
+    //mutable access to a column, writes to empty cells are never read back
+    pub fn column_mut(&mut self, name: &str) -> Option<&mut Array3<f64>> {
+        let c = self.column_index(name)?;
+        Some(&mut self.columns[c])
+    }
+
+    pub fn present(&self) -> &Array3<bool> {
+        &self.present
+    }
+
+    //add a column filled with NaN, returning its position, existing columns are reused
+    pub fn add_column(&mut self, name: &str) -> usize {
+        if let Some(c) = self.column_index(name) {
+            return c;
+        }
+        self.names.push(name.to_string());
+        self.columns
+            .push(Array3::from_elem(self.frame.dims, f64::NAN));
+        self.columns.len() - 1
+    }
+
+    //add a column computed from each present cell
+    pub fn add_column_with<F>(&mut self, name: &str, value: F) -> usize
+    where
+        F: Fn(ColumnarBlock) -> f64 + Sync,
+    {
+        let c = self.add_column(name);
+        let values = Zip::indexed(&self.present).par_map_collect(|(i, j, k), p| {
+            if *p {
+                value(ColumnarBlock {
+                    model: self,
+                    ind: BlockIndex { i, j, k },
+                })
+            } else {
+                f64::NAN
+            }
+        });
+        self.columns[c] = values;
+        c
+    }
+
+    //mark a cell present with one value per column, panics if ind is outside the model
+    pub fn insert(&mut self, ind: BlockIndex, values: &[f64]) {
+        assert_eq!(values.len(), self.columns.len(), "one value per column");
+        let cell = [ind.i, ind.j, ind.k];
+        self.present[cell] = true;
+        for (column, v) in self.columns.iter_mut().zip(values) {
+            column[cell] = *v;
+        }
+    }
+
+    //clear a cell, returning whether it was present
+    pub fn remove(&mut self, ind: BlockIndex) -> bool {
+        let cell = [ind.i, ind.j, ind.k];
+        let was = std::mem::replace(&mut self.present[cell], false);
+        self.columns.iter_mut().for_each(|c| c[cell] = f64::NAN);
+        was
+    }
+
+    //bounds checked access, None if ind is outside the model or empty
+    pub fn get(&self, ind: BlockIndex) -> Option<ColumnarBlock<'_>> {
+        (self.frame.contains(ind) && self.present[[ind.i, ind.j, ind.k]])
+            .then_some(ColumnarBlock { model: self, ind })
+    }
+
+    pub fn block_at(&self, x: f32, y: f32, z: f32) -> Option<ColumnarBlock<'_>> {
+        self.get(self.coordinates_to_index(BlockCoordinates { x, y, z })?)
+    }
+
+    //present cells in i, j, k order
+    pub fn indexed_iter(&self) -> impl Iterator<Item = (BlockIndex, ColumnarBlock<'_>)> {
+        self.present
+            .indexed_iter()
+            .filter(|(_, p)| **p)
+            .map(move |((i, j, k), _)| {
+                let ind = BlockIndex { i, j, k };
+                (ind, ColumnarBlock { model: self, ind })
+            })
+    }
+
+    //values of column c at the present cells
+    pub fn values(&self, c: usize) -> impl Iterator<Item = f64> + '_ {
+        self.columns[c]
+            .iter()
+            .zip(self.present.iter())
+            .filter(|(_, p)| **p)
+            .map(|(v, _)| *v)
+    }
+
+    pub fn par_values(&self, c: usize) -> impl ParallelIterator<Item = f64> + '_ {
+        Zip::from(&self.columns[c])
+            .and(&self.present)
+            .into_par_iter()
+            .filter(|(_, p)| **p)
+            .map(|(v, _)| *v)
+    }
+}
+
+impl ColumnarModel {
+    //blockmodel over the columns without copying them, for generic algorithms
+    pub fn into_block_model(self) -> BlockModel<ColumnarRow, ColumnarStorage> {
+        let frame = self.frame;
+        BlockModel::from_storage(ColumnarStorage::new(self), frame)
+    }
+}
+
+impl BlockModel<ColumnarRow, ColumnarStorage> {
+    //columns of the model, rebuilt first if blocks were changed through the storage
+    pub fn columnar(&mut self) -> &ColumnarModel {
+        self.blocks.model.frame = self.frame;
+        self.blocks.columnar()
+    }
+
+    pub fn into_columnar(mut self) -> ColumnarModel {
+        self.blocks.model.frame = self.frame;
+        self.blocks.into_columnar()
+    }
+}
+
+//block of a columnar storage, values are in column order
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ColumnarRow {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub x_size: f32,
+    pub y_size: f32,
+    pub z_size: f32,
+    pub values: Vec<f64>,
+    i: usize,
+    j: usize,
+    k: usize,
+}
+
+impl ColumnarRow {
+    pub fn new(coordinates: BlockCoordinates, size: BlockSize, values: Vec<f64>) -> Self {
+        Self {
+            x: coordinates.x,
+            y: coordinates.y,
+            z: coordinates.z,
+            x_size: size.x_size,
+            y_size: size.y_size,
+            z_size: size.z_size,
+            values,
+            i: 0,
+            j: 0,
+            k: 0,
+        }
+    }
+
+    //value of column c, NaN if the row has no such column
+    pub fn value(&self, c: usize) -> f64 {
+        self.values.get(c).copied().unwrap_or(f64::NAN)
+    }
+}
+
+impl BlockInterface for ColumnarRow {
+    fn coordinates(&self) -> BlockCoordinates {
+        BlockCoordinates {
+            x: self.x,
+            y: self.y,
+            z: self.z,
+        }
+    }
+
+    fn size(&self) -> BlockSize {
+        BlockSize {
+            x_size: self.x_size,
+            y_size: self.y_size,
+            z_size: self.z_size,
+        }
+    }
+
+    fn index(&self) -> BlockIndex {
+        BlockIndex {
+            i: self.i,
+            j: self.j,
+            k: self.k,
+        }
+    }
+
+    fn set_index(&mut self, ind: BlockIndex) {
+        self.i = ind.i;
+        self.j = ind.j;
+        self.k = ind.k;
+    }
+
+    fn set_coordinates(&mut self, coords: BlockCoordinates) -> bool {
+        self.x = coords.x;
+        self.y = coords.y;
+        self.z = coords.z;
+        true
+    }
+}
+
+//block storage over a columnar model so generic algorithms run on it, the rows handed out
+//are built from the columns on first access, changes through the storage go to the rows and
+//the columns are rebuilt from them when next requested
+#[derive(Debug, Clone)]
+pub struct ColumnarStorage {
+    model: ColumnarModel,
+    rows: OnceLock<Array3<Option<ColumnarRow>>>,
+    //rows changed since the columns were last rebuilt
+    stale: bool,
+}
+
+impl ColumnarStorage {
+    pub fn new(model: ColumnarModel) -> Self {
+        Self {
+            model,
+            rows: OnceLock::new(),
+            stale: false,
+        }
+    }
+
+    fn rows(&self) -> &Array3<Option<ColumnarRow>> {
+        self.rows.get_or_init(|| {
+            let m = &self.model;
+            Array3::from_shape_fn(m.dims(), |(i, j, k)| {
+                let ind = BlockIndex { i, j, k };
+                m.present[[i, j, k]].then(|| ColumnarRow {
+                    values: m.columns.iter().map(|c| c[[i, j, k]]).collect(),
+                    i,
+                    j,
+                    k,
+                    ..ColumnarRow::new(m.index_to_coordinates(ind), m.block_size(), Vec::new())
+                })
+            })
+        })
+    }
+
+    fn rows_mut(&mut self) -> &mut Array3<Option<ColumnarRow>> {
+        self.rows();
+        self.stale = true;
+        self.rows.get_mut().unwrap()
+    }
+
+    //columns rebuilt from the rows if they changed, rows with more values than there are
+    //columns add columns named by position
+    pub fn columnar(&mut self) -> &ColumnarModel {
+        if std::mem::take(&mut self.stale) {
+            let rows = self.rows.get().unwrap();
+            let width = rows
+                .iter()
+                .flatten()
+                .map(|r| r.values.len())
+                .max()
+                .unwrap_or(0);
+            for c in self.model.columns.len()..width {
+                self.model.add_column(&c.to_string());
+            }
+            let model = &mut self.model;
+            Zip::from(&mut model.present)
+                .and(rows)
+                .for_each(|p, r| *p = r.is_some());
+            for (c, column) in model.columns.iter_mut().enumerate() {
+                Zip::from(column)
+                    .and(rows)
+                    .for_each(|v, r| *v = r.as_ref().map_or(f64::NAN, |r| r.value(c)));
+            }
+        }
+        &self.model
+    }
+
+    pub fn into_columnar(mut self) -> ColumnarModel {
+        self.columnar();
+        self.model
+    }
+}
+
+impl PartialEq for ColumnarStorage {
+    fn eq(&self, other: &Self) -> bool {
+        self.rows() == other.rows()
+    }
+}
+
+impl BlockStorage<ColumnarRow> for ColumnarStorage {
+    type IntoBlocks = DenseIntoBlocks<ColumnarRow>;
+
+    //empty storage without columns on a unit lattice, blocks bring their own coordinates
+    fn with_dims(dims: [usize; 3]) -> Self {
+        let origin = BlockCoordinates {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        };
+        let size = BlockSize {
+            x_size: 1.0,
+            y_size: 1.0,
+            z_size: 1.0,
+        };
+        Self::new(ColumnarModel::new(ModelFrame::new(origin, size, dims)))
+    }
+
+    fn dims(&self) -> [usize; 3] {
+        self.model.dims()
+    }
+
+    fn get(&self, ind: BlockIndex) -> Option<&ColumnarRow> {
+        self.rows()[[ind.i, ind.j, ind.k]].as_ref()
+    }
+
+    fn get_mut(&mut self, ind: BlockIndex) -> Option<&mut ColumnarRow> {
+        self.rows_mut()[[ind.i, ind.j, ind.k]].as_mut()
+    }
+
+    fn insert(&mut self, ind: BlockIndex, block: ColumnarRow) -> Option<ColumnarRow> {
+        self.rows_mut()[[ind.i, ind.j, ind.k]].replace(block)
+    }
+
+    fn remove(&mut self, ind: BlockIndex) -> Option<ColumnarRow> {
+        self.rows_mut()[[ind.i, ind.j, ind.k]].take()
+    }
+
+    fn iter<'a>(&'a self) -> impl Iterator<Item = (BlockIndex, &'a ColumnarRow)>
+    where
+        ColumnarRow: 'a,
+    {
+        BlockStorage::iter(self.rows())
+    }
+
+    fn iter_mut<'a>(&'a mut self) -> impl Iterator<Item = (BlockIndex, &'a mut ColumnarRow)>
+    where
+        ColumnarRow: 'a,
+    {
+        BlockStorage::iter_mut(self.rows_mut())
+    }
+
+    fn into_blocks(mut self) -> Self::IntoBlocks {
+        self.rows();
+        self.rows.take().unwrap().into_blocks()
+    }
+
+    fn par_iter<'a>(&'a self) -> impl ParallelIterator<Item = (BlockIndex, &'a ColumnarRow)>
+    where
+        ColumnarRow: Sync + 'a,
+    {
+        BlockStorage::par_iter(self.rows())
+    }
+
+    fn par_iter_mut<'a>(
+        &'a mut self,
+    ) -> impl ParallelIterator<Item = (BlockIndex, &'a mut ColumnarRow)>
+    where
+        ColumnarRow: Send + 'a,
+    {
+        BlockStorage::par_iter_mut(self.rows_mut())
+    }
+
+    fn len(&self) -> usize {
+        match self.rows.get() {
+            Some(rows) => BlockStorage::len(rows),
+            None => self.model.len(),
+        }
+    }
+}
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface,
+    S: BlockStorage<B>,
+{
+    pub fn to_columnar(&self, attributes: &[NamedAttribute<B>]) -> ColumnarModel {
+        ColumnarModel::from_model(self, attributes)
+    }
+}
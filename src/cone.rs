@@ -0,0 +1,109 @@
+use rayon::prelude::*;
+
+use crate::block::{BlockIndex, BlockInterface};
+use crate::block_model::BlockModel;
+use crate::graph::PrecedenceGraph;
+use crate::storage::BlockStorage;
+
+use std::collections::HashMap;
+
+//net value of the extraction cone of every block in a precedence graph
+//changing a block value only touches the blocks whose cone contains it
+#[derive(Debug, Clone)]
+pub struct ConeValues<'a> {
+    graph: &'a PrecedenceGraph,
+    values: Vec<f64>,
+    cone_values: Vec<f64>,
+}
+
+impl<'a> ConeValues<'a> {
+    pub fn new<B, S, F>(graph: &'a PrecedenceGraph, mdl: &BlockModel<B, S>, value: F) -> Self
+    where
+        B: BlockInterface + Sync,
+        S: BlockStorage<B> + Sync,
+        F: Fn(&B) -> f64 + Sync,
+    {
+        let values = graph
+            .inds()
+            .par_iter()
+            .map(|ind| mdl.block(*ind).map_or(0.0, &value))
+            .collect::<Vec<_>>();
+
+        let cone_values = (0..graph.len())
+            .into_par_iter()
+            .map(|n| graph.cone(n).iter().map(|m| values[*m]).sum())
+            .collect();
+
+        Self {
+            graph,
+            values,
+            cone_values,
+        }
+    }
+
+    pub fn graph(&self) -> &PrecedenceGraph {
+        self.graph
+    }
+
+    pub fn value(&self, ind: BlockIndex) -> Option<f64> {
+        self.graph.node(ind).map(|n| self.values[n])
+    }
+
+    //total value of ind and every block that must be removed before it
+    pub fn cone_value(&self, ind: BlockIndex) -> Option<f64> {
+        self.graph.node(ind).map(|n| self.cone_values[n])
+    }
+
+    pub fn cone_values(&self) -> HashMap<BlockIndex, f64> {
+        self.graph
+            .inds()
+            .iter()
+            .copied()
+            .zip(self.cone_values.iter().copied())
+            .collect()
+    }
+
+    //replace block values, returning the number of cone values touched
+    //blocks outside the graph are ignored
+    pub fn set_values<I>(&mut self, changes: I) -> usize
+    where
+        I: IntoIterator<Item = (BlockIndex, f64)>,
+    {
+        let mut touched = 0;
+        for (ind, value) in changes {
+            let Some(n) = self.graph.node(ind) else {
+                continue;
+            };
+
+            let delta = value - self.values[n];
+            if delta == 0.0 {
+                continue;
+            }
+            self.values[n] = value;
+
+            let affected = self.graph.inverse_cone(n);
+            affected.iter().for_each(|m| self.cone_values[*m] += delta);
+            touched += affected.len();
+        }
+        touched
+    }
+
+    //re-evaluate value for the given blocks after they change in mdl
+    pub fn update<B, S, F>(
+        &mut self,
+        mdl: &BlockModel<B, S>,
+        inds: &[BlockIndex],
+        value: F,
+    ) -> usize
+    where
+        B: BlockInterface,
+        S: BlockStorage<B>,
+        F: Fn(&B) -> f64,
+    {
+        let changes = inds
+            .iter()
+            .map(|ind| (*ind, mdl.get(*ind).map_or(0.0, &value)))
+            .collect::<Vec<_>>();
+        self.set_values(changes)
+    }
+}
@@ -0,0 +1,117 @@
+use crate::block::BlockInterface;
+use crate::block_model::BlockModel;
+use crate::storage::BlockStorage;
+
+#[cfg(feature = "proj")]
+use crate::block::{BlockCoordinates, BlockIndex};
+#[cfg(feature = "proj")]
+use crate::error::CrsError;
+#[cfg(feature = "proj")]
+use crate::frame::{FrameRotation, ModelFrame};
+#[cfg(feature = "proj")]
+use proj::Proj;
+#[cfg(feature = "proj")]
+use std::collections::HashMap;
+
+#[cfg(feature = "proj")]
+fn transformer(from: Option<u32>, to: u32) -> Result<Proj, CrsError> {
+    let from = from.ok_or(CrsError::MissingCrs)?;
+    Proj::new_known_crs(&format!("EPSG:{from}"), &format!("EPSG:{to}"), None)
+        .map_err(|e| CrsError::Projection(e.to_string()))
+}
+
+//horizontal transformation, elevations are kept
+#[cfg(feature = "proj")]
+fn convert(proj: &Proj, c: BlockCoordinates) -> Result<BlockCoordinates, CrsError> {
+    let (x, y) = proj
+        .convert((c.x as f64, c.y as f64))
+        .map_err(|e| CrsError::Projection(e.to_string()))?;
+    Ok(BlockCoordinates {
+        x: x as f32,
+        y: y as f32,
+        z: c.z,
+    })
+}
+
+#[cfg(feature = "proj")]
+impl ModelFrame {
+    //frame in the target crs, the origin is transformed and the azimuth turned by the
+    //convergence along the j axis of the model, block sizes are kept so the scale difference
+    //between both systems is ignored
+    pub fn reproject(&self, target_epsg: u32) -> Result<ModelFrame, CrsError> {
+        let proj = transformer(self.epsg, target_epsg)?;
+
+        let length = self.block_size.y_size * self.dims[1].max(1) as f32;
+        let (sa, ca) = self.rotation.azimuth.to_radians().sin_cos();
+        let ahead = BlockCoordinates {
+            x: self.origin.x + sa * length,
+            y: self.origin.y + ca * length,
+            z: self.origin.z,
+        };
+        let origin = convert(&proj, self.origin)?;
+        let ahead = convert(&proj, ahead)?;
+        let azimuth = (ahead.x - origin.x).atan2(ahead.y - origin.y).to_degrees();
+
+        Ok(ModelFrame {
+            origin,
+            rotation: FrameRotation::new(azimuth, self.rotation.dip),
+            epsg: Some(target_epsg),
+            ..*self
+        })
+    }
+}
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface,
+    S: BlockStorage<B>,
+{
+    pub fn epsg(&self) -> Option<u32> {
+        self.frame.epsg
+    }
+
+    //declare the crs of the world coordinates without moving the model
+    pub fn set_epsg(&mut self, epsg: Option<u32>) {
+        self.frame.epsg = epsg;
+    }
+
+    //move the frame into the target crs, see ModelFrame::reproject, so models of different
+    //mine grids can share one system, block coordinates are transformed exactly and indices
+    //kept, block types that cannot set their coordinates are rejected before any change
+    #[cfg(feature = "proj")]
+    pub fn reproject(&mut self, target_epsg: u32) -> Result<(), CrsError> {
+        let proj = transformer(self.frame.epsg, target_epsg)?;
+        let frame = self.frame.reproject(target_epsg)?;
+        let mut moved = self
+            .indexed_iter()
+            .map(|(ind, b)| Ok((ind, convert(&proj, b.coordinates())?)))
+            .collect::<Result<HashMap<_, _>, CrsError>>()?;
+
+        if let Some((ind, b)) = self.indexed_iter().next() {
+            if !b.clone().set_coordinates(moved[&ind]) {
+                return Err(CrsError::FixedCoordinates);
+            }
+        }
+        for (ind, b) in self.indexed_iter_mut() {
+            let coords = moved.remove(&ind).expect("block was transformed");
+            if !b.set_coordinates(coords) {
+                return Err(CrsError::FixedCoordinates);
+            }
+        }
+        self.frame = frame;
+        Ok(())
+    }
+
+    //centroids of the present blocks transformed exactly into the target crs, for export
+    //where the lattice approximation of reproject is not enough
+    #[cfg(feature = "proj")]
+    pub fn reprojected_centroids(
+        &self,
+        target_epsg: u32,
+    ) -> Result<HashMap<BlockIndex, BlockCoordinates>, CrsError> {
+        let proj = transformer(self.frame.epsg, target_epsg)?;
+        self.indexed_iter()
+            .map(|(ind, _)| Ok((ind, convert(&proj, self.index_to_coordinates(ind))?)))
+            .collect()
+    }
+}
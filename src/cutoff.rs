@@ -0,0 +1,272 @@
+use crate::block::BlockInterface;
+use crate::block_model::BlockModel;
+use crate::storage::BlockStorage;
+
+//grade tonnage curve of a deposit, blocks sorted by descending grade
+#[derive(Debug, Clone, PartialEq)]
+pub struct GradeTonnage {
+    grades: Vec<f64>,
+    //tonnage and metal of the blocks at or above grades[n]
+    tonnages: Vec<f64>,
+    metals: Vec<f64>,
+}
+
+impl GradeTonnage {
+    //(grade, tonnage) pairs, NaN grades are skipped
+    pub fn new(mut blocks: Vec<(f64, f64)>) -> Self {
+        blocks.retain(|(g, _)| !g.is_nan());
+        blocks.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        let (mut tonnage, mut metal) = (0.0, 0.0);
+        let mut tonnages = Vec::with_capacity(blocks.len());
+        let mut metals = Vec::with_capacity(blocks.len());
+        for (g, t) in blocks.iter() {
+            tonnage += t;
+            metal += g * t;
+            tonnages.push(tonnage);
+            metals.push(metal);
+        }
+
+        Self {
+            grades: blocks.into_iter().map(|(g, _)| g).collect(),
+            tonnages,
+            metals,
+        }
+    }
+
+    //(grade, tonnage) of every present block, tonnage e.g. from DensityModel::tonnage
+    pub fn from_model<B, S, G, T>(mdl: &BlockModel<B, S>, grade: G, tonnage: T) -> Self
+    where
+        B: BlockInterface,
+        S: BlockStorage<B>,
+        G: Fn(&B) -> f64,
+        T: Fn(&B) -> f64,
+    {
+        Self::new(
+            mdl.indexed_iter()
+                .map(|(_, b)| (grade(b), tonnage(b)))
+                .collect(),
+        )
+    }
+
+    pub fn tonnage(&self) -> f64 {
+        self.tonnages.last().copied().unwrap_or(0.0)
+    }
+
+    //(tonnage, metal) at or above cutoff
+    fn above(&self, cutoff: f64) -> (f64, f64) {
+        let n = self.grades.partition_point(|g| *g >= cutoff);
+        if n == 0 {
+            return (0.0, 0.0);
+        }
+        (self.tonnages[n - 1], self.metals[n - 1])
+    }
+
+    pub fn tonnage_above(&self, cutoff: f64) -> f64 {
+        self.above(cutoff).0
+    }
+
+    //None when nothing reaches the cutoff
+    pub fn mean_grade_above(&self, cutoff: f64) -> Option<f64> {
+        let (t, m) = self.above(cutoff);
+        (t > 0.0).then(|| m / t)
+    }
+
+    fn grade_range(&self) -> (f64, f64) {
+        let hi = self.grades.first().copied().unwrap_or(0.0);
+        let lo = self.grades.last().copied().unwrap_or(0.0);
+        (lo, hi)
+    }
+}
+
+//mine, mill and market economics of a single product operation, capacities are per period
+//and may be infinite, costs are per tonne except selling cost per unit of metal
+#[derive(Debug, Clone, PartialEq)]
+pub struct LaneParameters {
+    pub mining_capacity: f64,
+    pub processing_capacity: f64,
+    //recovered metal units sold per period
+    pub market_capacity: f64,
+    pub price: f64,
+    pub selling_cost: f64,
+    pub recovery: f64,
+    pub mining_cost: f64,
+    pub processing_cost: f64,
+    pub fixed_cost: f64,
+    pub discount_rate: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CutoffPeriod {
+    pub cutoff: f64,
+    pub mined_tonnage: f64,
+    pub ore_tonnage: f64,
+    pub waste_tonnage: f64,
+    pub metal: f64,
+    pub cash_flow: f64,
+}
+
+//declining cutoff policy with its net present value
+#[derive(Debug, Clone, PartialEq)]
+pub struct CutoffPolicy {
+    pub periods: Vec<CutoffPeriod>,
+    pub npv: f64,
+    pub iterations: usize,
+}
+
+impl CutoffPolicy {
+    pub fn cutoffs(&self) -> Vec<f64> {
+        self.periods.iter().map(|p| p.cutoff).collect()
+    }
+}
+
+//periods beyond which a policy is cut short, guards against zero capacities
+const MAX_PERIODS: usize = 10_000;
+
+//g in lo..=hi where the monotone f(g) crosses target, an end of the range if it never does
+fn balance<F: Fn(f64) -> f64>(lo: f64, hi: f64, target: f64, f: F) -> f64 {
+    let (f_lo, f_hi) = (f(lo), f(hi));
+    let increasing = f_hi >= f_lo;
+    if (target <= f_lo) == increasing {
+        return lo;
+    }
+    if (target >= f_hi) == increasing {
+        return hi;
+    }
+
+    let (mut lo, mut hi) = (lo, hi);
+    for _ in 0..100 {
+        let mid = 0.5 * (lo + hi);
+        if (f(mid) < target) == increasing {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    0.5 * (lo + hi)
+}
+
+fn median(a: f64, b: f64, c: f64) -> f64 {
+    a.max(b).min(a.min(b).max(c))
+}
+
+impl LaneParameters {
+    //optimal cutoff of one period given the value of the remaining reserves, the middle of
+    //the three pairwise balanced optima
+    fn cutoff(&self, dist: &GradeTonnage, value: f64) -> f64 {
+        let margin = self.price - self.selling_cost;
+        let opportunity = self.fixed_cost + self.discount_rate * value;
+
+        let g_m = self.processing_cost / (margin * self.recovery);
+        let g_h = (self.processing_cost + opportunity / self.processing_capacity)
+            / (margin * self.recovery);
+        let g_k =
+            self.processing_cost / ((margin - opportunity / self.market_capacity) * self.recovery);
+        let g_k = if g_k > 0.0 { g_k } else { f64::INFINITY };
+
+        let (lo, hi) = dist.grade_range();
+        let total = dist.tonnage();
+        let ore_ratio = |g: f64| dist.tonnage_above(g) / total;
+        let metal_ratio = |g: f64| dist.above(g).1 * self.recovery / total;
+        let ore_grade = |g: f64| dist.mean_grade_above(g).unwrap_or(hi) * self.recovery;
+
+        let g_mh = balance(
+            lo,
+            hi,
+            self.processing_capacity / self.mining_capacity,
+            ore_ratio,
+        );
+        let g_mk = balance(
+            lo,
+            hi,
+            self.market_capacity / self.mining_capacity,
+            metal_ratio,
+        );
+        let g_hk = balance(
+            lo,
+            hi,
+            self.market_capacity / self.processing_capacity,
+            ore_grade,
+        );
+
+        median(
+            median(g_m, g_h, g_mh),
+            median(g_m, g_k, g_mk),
+            median(g_h, g_k, g_hk),
+        )
+    }
+
+    //cutoff policy over the reserves, assuming each period mines a representative slice of
+    //the remaining material, iterated until the npv changes by less than tolerance
+    pub fn optimize(&self, dist: &GradeTonnage, tolerance: f64) -> CutoffPolicy {
+        assert!(
+            self.mining_capacity > 0.0,
+            "mining capacity must be positive"
+        );
+
+        let total = dist.tonnage();
+        let mut values: Vec<f64> = Vec::new();
+        let mut npv = f64::NEG_INFINITY;
+        let mut iterations = 0;
+        loop {
+            iterations += 1;
+            let mut periods = Vec::new();
+            let mut remaining = total;
+            while remaining > total * 1e-12 && periods.len() < MAX_PERIODS {
+                let value = values.get(periods.len()).copied().unwrap_or(0.0);
+                let cutoff = self.cutoff(dist, value);
+
+                let (ore, metal) = dist.above(cutoff);
+                let (ore_ratio, metal_ratio) = (ore / total, metal * self.recovery / total);
+                let mined = [
+                    self.mining_capacity,
+                    self.processing_capacity / ore_ratio,
+                    self.market_capacity / metal_ratio,
+                ]
+                .into_iter()
+                .fold(remaining, f64::min);
+
+                let ore = mined * ore_ratio;
+                let metal = mined * metal_ratio;
+                //fraction of the period needed by the limiting capacity
+                let time = [
+                    mined / self.mining_capacity,
+                    ore / self.processing_capacity,
+                    metal / self.market_capacity,
+                ]
+                .into_iter()
+                .fold(0.0, f64::max);
+
+                let cash_flow = (self.price - self.selling_cost) * metal
+                    - self.processing_cost * ore
+                    - self.mining_cost * mined
+                    - self.fixed_cost * time;
+                periods.push(CutoffPeriod {
+                    cutoff,
+                    mined_tonnage: mined,
+                    ore_tonnage: ore,
+                    waste_tonnage: mined - ore,
+                    metal,
+                    cash_flow,
+                });
+                remaining -= mined;
+            }
+
+            //value of the remaining reserves at the start of every period
+            values = vec![0.0; periods.len() + 1];
+            for (n, p) in periods.iter().enumerate().rev() {
+                values[n] = (p.cash_flow + values[n + 1]) / (1.0 + self.discount_rate);
+            }
+
+            let converged = (values[0] - npv).abs() <= tolerance;
+            npv = values[0];
+            if converged || iterations >= 100 {
+                return CutoffPolicy {
+                    periods,
+                    npv,
+                    iterations,
+                };
+            }
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use crate::block::BlockInterface;
+use crate::block_model::BlockModel;
+use crate::storage::BlockStorage;
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+//density of a block, tonnes follow from the block volume so statistics, grade tonnage curves
+//and reconciliation can share one tonnage through DensityModel::tonnage
+pub trait DensityModel<B: BlockInterface> {
+    fn density(&self, block: &B) -> f64;
+
+    fn tonnes(&self, block: &B) -> f64 {
+        block.volume() * self.density(block)
+    }
+
+    //metal content of the block at grade
+    fn metal(&self, block: &B, grade: f64) -> f64 {
+        self.tonnes(block) * grade
+    }
+
+    //tonnage closure for the apis weighting by tonnes, e.g. stats_by, diff and
+    //GradeTonnage::from_model
+    fn tonnage(&self) -> impl Fn(&B) -> f64 + '_
+    where
+        Self: Sized,
+    {
+        move |b| self.tonnes(b)
+    }
+}
+
+//block volume and tonnage helpers
+pub trait BlockTonnage: BlockInterface {
+    fn volume(&self) -> f64 {
+        let s = self.size();
+        s.x_size as f64 * s.y_size as f64 * s.z_size as f64
+    }
+
+    fn tonnes<D: DensityModel<Self>>(&self, density: &D) -> f64 {
+        density.tonnes(self)
+    }
+
+    //metal content of the grade attribute
+    fn metal<D, G>(&self, density: &D, grade: G) -> f64
+    where
+        D: DensityModel<Self>,
+        G: Fn(&Self) -> f64,
+    {
+        density.metal(self, grade(self))
+    }
+}
+
+impl<B: BlockInterface> BlockTonnage for B {}
+
+//one density for every block
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConstantDensity(pub f64);
+
+impl<B: BlockInterface> DensityModel<B> for ConstantDensity {
+    fn density(&self, _: &B) -> f64 {
+        self.0
+    }
+}
+
+//density read from a block attribute, default replaces missing values, NaN or not positive
+#[derive(Debug, Clone, Copy)]
+pub struct AttributeDensity<F> {
+    pub density: F,
+    pub default: f64,
+}
+
+impl<F> AttributeDensity<F> {
+    pub fn new(density: F, default: f64) -> Self {
+        Self { density, default }
+    }
+}
+
+impl<B, F> DensityModel<B> for AttributeDensity<F>
+where
+    B: BlockInterface,
+    F: Fn(&B) -> f64,
+{
+    fn density(&self, block: &B) -> f64 {
+        let d = (self.density)(block);
+        if d > 0.0 {
+            d
+        } else {
+            self.default
+        }
+    }
+}
+
+//density per code, e.g. rock type, default covers codes without a density
+#[derive(Debug, Clone)]
+pub struct LookupDensity<K, F> {
+    pub code: F,
+    pub densities: HashMap<K, f64>,
+    pub default: f64,
+}
+
+impl<K, F> LookupDensity<K, F>
+where
+    K: Hash + Eq,
+{
+    pub fn new(code: F, default: f64) -> Self {
+        Self {
+            code,
+            densities: HashMap::new(),
+            default,
+        }
+    }
+
+    pub fn with_density(mut self, code: K, density: f64) -> Self {
+        self.densities.insert(code, density);
+        self
+    }
+}
+
+impl<B, K, F> DensityModel<B> for LookupDensity<K, F>
+where
+    B: BlockInterface,
+    K: Hash + Eq,
+    F: Fn(&B) -> K,
+{
+    fn density(&self, block: &B) -> f64 {
+        self.densities
+            .get(&(self.code)(block))
+            .copied()
+            .unwrap_or(self.default)
+    }
+}
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface,
+    S: BlockStorage<B>,
+{
+    pub fn total_tonnes<D: DensityModel<B>>(&self, density: &D) -> f64 {
+        self.indexed_iter().map(|(_, b)| density.tonnes(b)).sum()
+    }
+
+    //metal of the grade attribute over every block, NaN grades are skipped
+    pub fn total_metal<D, G>(&self, density: &D, grade: G) -> f64
+    where
+        D: DensityModel<B>,
+        G: Fn(&B) -> f64,
+    {
+        self.indexed_iter()
+            .map(|(_, b)| (b, grade(b)))
+            .filter(|(_, g)| !g.is_nan())
+            .map(|(b, g)| density.metal(b, g))
+            .sum()
+    }
+}
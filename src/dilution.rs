@@ -0,0 +1,202 @@
+use crate::block::{BlockIndex, BlockInterface};
+use crate::block_model::BlockModel;
+use crate::geometry::mesh::TriangleMesh;
+use crate::morphology::shift;
+use crate::storage::BlockStorage;
+
+use std::collections::HashMap;
+
+//contact the skin of an ore body is measured from
+#[derive(Debug, Clone, Copy)]
+pub enum DilutionContact<'a> {
+    //faces of the nearest present block that is not ore
+    Waste,
+    //modelled contact surface, e.g. a hangingwall, measured to the cells along the surface so
+    //accurate to about one block
+    Surface(&'a TriangleMesh),
+}
+
+//dilution and ore loss of ore blocks within skin of the contact, both are full at the contact
+//and taper linearly to nothing at skin unless taper is off
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ContactDilution {
+    pub skin: f64,
+    //diluting tonnage as a fraction of the block tonnage
+    pub dilution: f64,
+    //fraction of the block tonnage lost
+    pub ore_loss: f64,
+    //grade of the diluting material
+    pub dilution_grade: f64,
+    pub taper: bool,
+}
+
+impl ContactDilution {
+    pub fn new(skin: f64, dilution: f64, ore_loss: f64) -> Self {
+        assert!(skin > 0.0, "skin distance must be positive");
+        assert!(
+            dilution >= 0.0 && (0.0..=1.0).contains(&ore_loss),
+            "dilution must not be negative and ore loss must be in 0 to 1"
+        );
+        Self {
+            skin,
+            dilution,
+            ore_loss,
+            dilution_grade: 0.0,
+            taper: true,
+        }
+    }
+
+    pub fn with_dilution_grade(self, grade: f64) -> Self {
+        Self {
+            dilution_grade: grade,
+            ..self
+        }
+    }
+
+    pub fn without_taper(self) -> Self {
+        Self {
+            taper: false,
+            ..self
+        }
+    }
+
+    //share of the dilution and ore loss applied at distance from the contact
+    pub fn factor(&self, distance: f64) -> f64 {
+        if distance >= self.skin {
+            0.0
+        } else if self.taper {
+            1.0 - distance / self.skin
+        } else {
+            1.0
+        }
+    }
+
+    //diluted block of in situ tonnage and grade at distance from the contact, None beyond
+    //the skin
+    pub fn dilute(&self, tonnage: f64, grade: f64, distance: Option<f64>) -> DilutedBlock {
+        let f = distance.map_or(0.0, |d| self.factor(d));
+        let ore_tonnage = tonnage * (1.0 - self.ore_loss * f);
+        let dilution_tonnage = tonnage * self.dilution * f;
+        let recovered = ore_tonnage + dilution_tonnage;
+        DilutedBlock {
+            distance,
+            ore_tonnage,
+            dilution_tonnage,
+            tonnage: recovered,
+            grade: if recovered > 0.0 {
+                (ore_tonnage * grade + dilution_tonnage * self.dilution_grade) / recovered
+            } else {
+                f64::NAN
+            },
+        }
+    }
+}
+
+//ore block after contact dilution
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DilutedBlock {
+    //to the contact, None beyond the skin
+    pub distance: Option<f64>,
+    //in situ ore recovered
+    pub ore_tonnage: f64,
+    //waste mined with the ore
+    pub dilution_tonnage: f64,
+    //recovered tonnage, ore plus dilution
+    pub tonnage: f64,
+    //diluted grade, NaN if nothing is recovered
+    pub grade: f64,
+}
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface,
+    S: BlockStorage<B>,
+{
+    //every ore block diluted at the distance from its centroid to the contact
+    pub fn contact_dilution<O, T, G>(
+        &self,
+        ore: O,
+        tonnage: T,
+        grade: G,
+        contact: DilutionContact,
+        dilution: &ContactDilution,
+    ) -> HashMap<BlockIndex, DilutedBlock>
+    where
+        O: Fn(&B) -> bool,
+        T: Fn(&B) -> f64,
+        G: Fn(&B) -> f64,
+    {
+        let size = self.block_size();
+        let size = [size.x_size, size.y_size, size.z_size].map(|s| s as f64);
+        let skin = dilution.skin;
+
+        let distance: Box<dyn Fn(BlockIndex) -> Option<f64>> = match contact {
+            DilutionContact::Waste => {
+                let waste = self.select(|b| !ore(b));
+                let centroid = self.distance_to(&waste);
+                let half_diagonal = size.iter().map(|s| s * s).sum::<f64>().sqrt() / 2.0;
+                let reach = size.map(|s| (skin / s + 0.5).ceil() as i64);
+                let dims = self.dims();
+                //nearest face of a waste cell in the window the skin can reach, only blocks
+                //whose centroid distance allows one within the skin are searched
+                Box::new(move |ind| {
+                    if centroid[&ind] - half_diagonal >= skin {
+                        return None;
+                    }
+                    let mut nearest = f64::INFINITY;
+                    for di in -reach[0]..=reach[0] {
+                        for dj in -reach[1]..=reach[1] {
+                            for dk in -reach[2]..=reach[2] {
+                                let offset = [di, dj, dk];
+                                if !shift(ind, offset, dims).is_some_and(|w| waste.contains(w)) {
+                                    continue;
+                                }
+                                let gap = (0..3)
+                                    .map(|a| {
+                                        (size[a] * (offset[a].abs() as f64 - 0.5).max(0.0)).powi(2)
+                                    })
+                                    .sum::<f64>()
+                                    .sqrt();
+                                nearest = nearest.min(gap);
+                            }
+                        }
+                    }
+                    (nearest < skin).then_some(nearest)
+                })
+            }
+            DilutionContact::Surface(mesh) => {
+                let distances = self.distance_to_surface(mesh);
+                Box::new(move |ind| Some(distances[&ind]).filter(|d| *d < skin))
+            }
+        };
+
+        self.indexed_iter()
+            .filter(|(_, b)| ore(b))
+            .map(|(ind, b)| (ind, dilution.dilute(tonnage(b), grade(b), distance(ind))))
+            .collect()
+    }
+
+    //store the diluted block of every ore block through set, e.g. as diluted grade and
+    //recovered tonnage attributes
+    pub fn assign_contact_dilution<O, T, G, F>(
+        &mut self,
+        ore: O,
+        tonnage: T,
+        grade: G,
+        contact: DilutionContact,
+        dilution: &ContactDilution,
+        set: F,
+    ) where
+        O: Fn(&B) -> bool,
+        T: Fn(&B) -> f64,
+        G: Fn(&B) -> f64,
+        F: Fn(&mut B, DilutedBlock),
+    {
+        let diluted = self.contact_dilution(ore, tonnage, grade, contact, dilution);
+        for (ind, d) in diluted.into_iter() {
+            if let Some(b) = self.block_mut(ind) {
+                set(b, d);
+            }
+        }
+    }
+}
@@ -0,0 +1,140 @@
+use ndarray::{Array3, Axis, Zip};
+
+use crate::block::{BlockIndex, BlockInterface};
+use crate::block_model::BlockModel;
+use crate::geometry::mesh::TriangleMesh;
+use crate::geometry::surface::Surface;
+use crate::selection::Selection;
+use crate::storage::BlockStorage;
+use crate::zones::Connectivity;
+
+use std::collections::HashMap;
+
+//exact squared distance transform of one lattice line with cell spacing w,
+//lower envelope of parabolas after Felzenszwalb and Huttenlocher
+fn transform_line(f: &mut [f64], w: f64) {
+    let w2 = w * w;
+    let sites = (0..f.len())
+        .filter(|q| f[*q].is_finite())
+        .collect::<Vec<_>>();
+    if sites.is_empty() {
+        return;
+    }
+
+    //intersection of the parabolas rooted at q and v
+    let meet = |f: &[f64], q: usize, v: usize| {
+        let (qf, vf) = (q as f64, v as f64);
+        ((f[q] + w2 * qf * qf) - (f[v] + w2 * vf * vf)) / (2.0 * w2 * (qf - vf))
+    };
+
+    let mut hull: Vec<usize> = Vec::with_capacity(sites.len());
+    let mut starts: Vec<f64> = Vec::with_capacity(sites.len());
+    for &q in sites.iter() {
+        loop {
+            match hull.last() {
+                None => {
+                    hull.push(q);
+                    starts.push(f64::NEG_INFINITY);
+                    break;
+                }
+                Some(&v) => {
+                    let s = meet(f, q, v);
+                    if s <= *starts.last().unwrap() {
+                        hull.pop();
+                        starts.pop();
+                    } else {
+                        hull.push(q);
+                        starts.push(s);
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    let values = hull.iter().map(|v| f[*v]).collect::<Vec<_>>();
+    let mut h = 0;
+    for (p, out) in f.iter_mut().enumerate() {
+        while h + 1 < hull.len() && starts[h + 1] < p as f64 {
+            h += 1;
+        }
+        let d = p as f64 - hull[h] as f64;
+        *out = w2 * d * d + values[h];
+    }
+}
+
+//squared world distance from every cell to the nearest seed cell
+fn squared_distance(seeds: Array3<bool>, spacing: [f64; 3]) -> Array3<f64> {
+    let mut dist = seeds.mapv(|s| if s { 0.0 } else { f64::INFINITY });
+    for (axis, w) in spacing.into_iter().enumerate() {
+        Zip::from(dist.lanes_mut(Axis(axis))).par_for_each(|mut lane| {
+            let mut line = lane.to_vec();
+            transform_line(&mut line, w);
+            lane.iter_mut().zip(line).for_each(|(d, v)| *d = v);
+        });
+    }
+    dist
+}
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface,
+    S: BlockStorage<B>,
+{
+    fn spacing(&self) -> [f64; 3] {
+        let size = self.block_size();
+        [size.x_size as f64, size.y_size as f64, size.z_size as f64]
+    }
+
+    fn present_distances(&self, dist: Array3<f64>) -> HashMap<BlockIndex, f64> {
+        self.indexed_iter()
+            .map(|(ind, _)| (ind, dist[[ind.i, ind.j, ind.k]].sqrt()))
+            .collect()
+    }
+
+    //centroid distance in world units from every present block to the nearest selected cell,
+    //infinite if the selection is empty
+    pub fn distance_to(&self, selection: &Selection) -> HashMap<BlockIndex, f64> {
+        assert_eq!(
+            selection.dims(),
+            self.dims(),
+            "selection spans a different lattice"
+        );
+
+        let mut seeds = Array3::from_elem(self.dims(), false);
+        selection
+            .iter()
+            .for_each(|ind| seeds[[ind.i, ind.j, ind.k]] = true);
+        self.present_distances(squared_distance(seeds, self.spacing()))
+    }
+
+    //centroid distance in world units from every present block to the surface, measured to
+    //the nearest cell below the surface with a face neighbour above it, so accurate to
+    //about one block, cells outside the surface footprint count as above
+    pub fn distance_to_surface(&self, mesh: &TriangleMesh) -> HashMap<BlockIndex, f64> {
+        let surface = Surface::new(mesh);
+        let frame = self.frame();
+
+        let below = Array3::from_shape_fn(self.dims(), |(i, j, k)| {
+            let c = frame.index_to_coordinates(BlockIndex { i, j, k });
+            surface
+                .elevation(c.x as f64, c.y as f64)
+                .is_some_and(|z| (c.z as f64) < z)
+        });
+
+        let [ni, nj, nk] = self.dims().map(|n| n as i64);
+        let offsets = Connectivity::Face.offsets();
+        let seeds = Array3::from_shape_fn(self.dims(), |(i, j, k)| {
+            below[[i, j, k]]
+                && offsets.iter().any(|[di, dj, dk]| {
+                    let (i, j, k) = (i as i64 + di, j as i64 + dj, k as i64 + dk);
+                    (0..ni).contains(&i)
+                        && (0..nj).contains(&j)
+                        && (0..nk).contains(&k)
+                        && !below[[i as usize, j as usize, k as usize]]
+                })
+        });
+
+        self.present_distances(squared_distance(seeds, self.spacing()))
+    }
+}
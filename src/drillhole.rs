@@ -0,0 +1,589 @@
+use serde::{Deserialize, Serialize};
+
+use crate::block::{BlockCoordinates, BlockInterface};
+use crate::block_model::BlockModel;
+use crate::error::DrillholeError;
+use crate::estimation::Sample;
+use crate::io::NullPolicy;
+use crate::storage::BlockStorage;
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::Path;
+
+//collar position of a hole
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Collar {
+    pub hole: String,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+//downhole survey station, azimuth clockwise from north and dip negative downwards, in degrees
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Survey {
+    pub hole: String,
+    pub depth: f64,
+    pub azimuth: f64,
+    pub dip: f64,
+}
+
+//sampled interval of a hole, one value per assay column, NaN where not assayed
+#[derive(Debug, Clone, PartialEq)]
+pub struct Assay {
+    pub hole: String,
+    pub from: f64,
+    pub to: f64,
+    pub values: Vec<f64>,
+}
+
+//assayed interval of a desurveyed hole
+#[derive(Debug, Clone, PartialEq)]
+pub struct Interval {
+    pub from: f64,
+    pub to: f64,
+    pub values: Vec<f64>,
+}
+
+fn direction(azimuth: f64, dip: f64) -> [f64; 3] {
+    let (a, d) = (azimuth.to_radians(), dip.to_radians());
+    [a.sin() * d.cos(), a.cos() * d.cos(), d.sin()]
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Station {
+    depth: f64,
+    position: [f64; 3],
+    direction: [f64; 3],
+}
+
+impl Station {
+    //position and direction s along the minimum curvature arc of the given length that
+    //turns the station direction into to
+    fn arc(&self, to: [f64; 3], length: f64, s: f64) -> ([f64; 3], [f64; 3]) {
+        let t1 = self.direction;
+        let cos = (0..3).map(|d| t1[d] * to[d]).sum::<f64>();
+        let dogleg = cos.clamp(-1.0, 1.0).acos();
+        if dogleg < 1e-9 || length <= 0.0 {
+            return ([0, 1, 2].map(|d| self.position[d] + s * t1[d]), t1);
+        }
+
+        let f = s / length;
+        let (a, b) = (
+            ((1.0 - f) * dogleg).sin() / dogleg.sin(),
+            (f * dogleg).sin() / dogleg.sin(),
+        );
+        let t = [0, 1, 2].map(|d| a * t1[d] + b * to[d]);
+        let partial = f * dogleg;
+        let ratio = if partial < 1e-9 {
+            1.0
+        } else {
+            2.0 / partial * (partial / 2.0).tan()
+        };
+        (
+            [0, 1, 2].map(|d| self.position[d] + s / 2.0 * (t1[d] + t[d]) * ratio),
+            t,
+        )
+    }
+}
+
+//hole desurveyed by minimum curvature between survey stations, straight past the last
+//station, holes without surveys are vertical
+#[derive(Debug, Clone, PartialEq)]
+pub struct Drillhole {
+    pub name: String,
+    stations: Vec<Station>,
+    //sorted by depth without overlaps
+    pub intervals: Vec<Interval>,
+}
+
+impl Drillhole {
+    fn new(name: &str, collar: [f64; 3], surveys: &[&Survey]) -> Self {
+        let first = surveys
+            .first()
+            .map_or(direction(0.0, -90.0), |s| direction(s.azimuth, s.dip));
+        let mut stations = vec![Station {
+            depth: 0.0,
+            position: collar,
+            direction: first,
+        }];
+        for s in surveys.iter().filter(|s| s.depth > 0.0) {
+            let last = *stations.last().unwrap();
+            let to = direction(s.azimuth, s.dip);
+            let length = s.depth - last.depth;
+            if length <= 0.0 {
+                continue;
+            }
+            stations.push(Station {
+                depth: s.depth,
+                position: last.arc(to, length, length).0,
+                direction: to,
+            });
+        }
+        Self {
+            name: name.to_string(),
+            stations,
+            intervals: Vec::new(),
+        }
+    }
+
+    fn trace(&self, depth: f64) -> ([f64; 3], [f64; 3]) {
+        let n = self.stations.partition_point(|s| s.depth <= depth).max(1);
+        let station = &self.stations[n - 1];
+        match self.stations.get(n) {
+            Some(next) => station.arc(
+                next.direction,
+                next.depth - station.depth,
+                depth - station.depth,
+            ),
+            None => station.arc(station.direction, 0.0, depth - station.depth),
+        }
+    }
+
+    pub fn collar(&self) -> [f64; 3] {
+        self.stations[0].position
+    }
+
+    //world coordinates depth down the hole
+    pub fn position(&self, depth: f64) -> [f64; 3] {
+        self.trace(depth).0
+    }
+
+    //unit direction of the hole at depth
+    pub fn direction(&self, depth: f64) -> [f64; 3] {
+        self.trace(depth).1
+    }
+
+    //depths where the trace crosses the planes at elevation origin + n * height between from
+    //and to, found by bisection between steps short enough to cross one plane at most
+    fn bench_crossings(&self, from: f64, to: f64, origin: f64, height: f64) -> Vec<f64> {
+        let bench = |depth: f64| ((self.position(depth)[2] - origin) / height).floor();
+        let steps = ((to - from) / (height / 4.0)).ceil().max(1.0) as usize;
+        let step = (to - from) / steps as f64;
+
+        let mut crossings = Vec::new();
+        for n in 0..steps {
+            let (mut a, mut b) = (from + n as f64 * step, from + (n + 1) as f64 * step);
+            let start = bench(a);
+            if bench(b) == start {
+                continue;
+            }
+            for _ in 0..40 {
+                let mid = (a + b) / 2.0;
+                if bench(mid) == start {
+                    a = mid;
+                } else {
+                    b = mid;
+                }
+            }
+            crossings.push((a + b) / 2.0);
+        }
+        crossings
+    }
+}
+
+//how each run of a hole is split into composites
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CompositeMethod {
+    //fixed downhole length from the top of the run
+    Length(f64),
+    //split where the trace crosses the bench planes at elevation origin + n * height
+    Bench { origin: f64, height: f64 },
+    //one composite per run
+    Run,
+}
+
+//length weighted compositing of assays, NaN values carry no weight
+#[derive(Debug, Clone, PartialEq)]
+pub struct Compositing {
+    pub method: CompositeMethod,
+    //column whose changes, e.g. domain codes, start a new run, otherwise a hole is one run
+    pub domain: Option<String>,
+    //composites with less sampled length, e.g. remnants at the end of a run, are dropped
+    pub min_length: f64,
+}
+
+impl Compositing {
+    pub fn new(method: CompositeMethod) -> Self {
+        Self {
+            method,
+            domain: None,
+            min_length: 0.0,
+        }
+    }
+
+    pub fn with_domain(mut self, column: &str) -> Self {
+        self.domain = Some(column.to_string());
+        self
+    }
+
+    pub fn with_min_length(mut self, length: f64) -> Self {
+        self.min_length = length;
+        self
+    }
+}
+
+//desurveyed interval with values per column
+#[derive(Debug, Clone, PartialEq)]
+pub struct Composite {
+    //index of the hole in the drillholes
+    pub hole: usize,
+    pub from: f64,
+    pub to: f64,
+    //world coordinates of the mid point along the trace
+    pub centroid: [f64; 3],
+    pub values: Vec<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Composites {
+    pub holes: Vec<String>,
+    pub names: Vec<String>,
+    pub composites: Vec<Composite>,
+}
+
+impl Composites {
+    pub fn column(&self, name: &str) -> Option<usize> {
+        self.names.iter().position(|n| n == name)
+    }
+
+    //estimation samples of one column at the centroids, NaN values are skipped
+    pub fn samples(&self, attribute: &str) -> Vec<Sample> {
+        let c = self
+            .column(attribute)
+            .unwrap_or_else(|| panic!("column {attribute} is not in the composites"));
+        self.composites
+            .iter()
+            .filter(|comp| !comp.values[c].is_nan())
+            .map(|comp| {
+                let [x, y, z] = comp.centroid;
+                Sample::new(x, y, z, comp.values[c]).with_hole(comp.hole)
+            })
+            .collect()
+    }
+
+    //add column name holding value of the block containing each centroid, NaN outside the
+    //model, e.g. to code composites with the domain of the model
+    pub fn flag<B, S, F>(&mut self, mdl: &BlockModel<B, S>, name: &str, value: F)
+    where
+        B: BlockInterface,
+        S: BlockStorage<B>,
+        F: Fn(&B) -> f64,
+    {
+        let frame = mdl.frame();
+        self.names.push(name.to_string());
+        for comp in self.composites.iter_mut() {
+            let [x, y, z] = comp.centroid;
+            let coords = BlockCoordinates {
+                x: x as f32,
+                y: y as f32,
+                z: z as f32,
+            };
+            comp.values.push(
+                frame
+                    .coordinates_to_index(coords)
+                    .and_then(|ind| mdl.block(ind))
+                    .map_or(f64::NAN, &value),
+            );
+        }
+    }
+
+    //hole, from, to, x, y, z and one column per value
+    pub fn to_csv<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn Error>> {
+        let mut w = csv::Writer::from_path(path)?;
+        let mut header = ["hole", "from", "to", "x", "y", "z"]
+            .map(String::from)
+            .to_vec();
+        header.extend(self.names.iter().cloned());
+        w.write_record(&header)?;
+        for comp in self.composites.iter() {
+            let mut record = vec![self.holes[comp.hole].clone()];
+            record.extend(
+                [comp.from, comp.to]
+                    .iter()
+                    .chain(comp.centroid.iter())
+                    .chain(comp.values.iter())
+                    .map(|v| v.to_string()),
+            );
+            w.write_record(&record)?;
+        }
+        w.flush()?;
+        Ok(())
+    }
+}
+
+//desurveyed holes sharing one set of assay columns
+#[derive(Debug, Clone, PartialEq)]
+pub struct Drillholes {
+    names: Vec<String>,
+    holes: Vec<Drillhole>,
+}
+
+fn same(a: f64, b: f64) -> bool {
+    a == b || (a.is_nan() && b.is_nan())
+}
+
+impl Drillholes {
+    //holes in collar order, assays hold one value per named column
+    pub fn new(
+        collars: &[Collar],
+        surveys: &[Survey],
+        assays: &[Assay],
+        names: &[&str],
+    ) -> Result<Self, DrillholeError> {
+        let mut lookup = HashMap::new();
+        for (n, c) in collars.iter().enumerate() {
+            if lookup.insert(c.hole.as_str(), n).is_some() {
+                return Err(DrillholeError::DuplicateCollar {
+                    hole: c.hole.clone(),
+                });
+            }
+        }
+        let find = |hole: &str| {
+            lookup
+                .get(hole)
+                .copied()
+                .ok_or_else(|| DrillholeError::UnknownHole {
+                    hole: hole.to_string(),
+                })
+        };
+
+        let mut by_hole = vec![Vec::new(); collars.len()];
+        for s in surveys.iter() {
+            by_hole[find(&s.hole)?].push(s);
+        }
+        let mut holes = collars
+            .iter()
+            .zip(by_hole.iter_mut())
+            .map(|(c, surveys)| {
+                surveys.sort_by(|a, b| a.depth.total_cmp(&b.depth));
+                Drillhole::new(&c.hole, [c.x, c.y, c.z], surveys)
+            })
+            .collect::<Vec<_>>();
+
+        for a in assays.iter() {
+            assert!(
+                a.values.len() == names.len(),
+                "one value is needed per assay column"
+            );
+            if a.to.partial_cmp(&a.from) != Some(std::cmp::Ordering::Greater) {
+                return Err(DrillholeError::InvalidInterval {
+                    hole: a.hole.clone(),
+                    from: a.from,
+                    to: a.to,
+                });
+            }
+            holes[find(&a.hole)?].intervals.push(Interval {
+                from: a.from,
+                to: a.to,
+                values: a.values.clone(),
+            });
+        }
+        for hole in holes.iter_mut() {
+            hole.intervals.sort_by(|a, b| a.from.total_cmp(&b.from));
+            if let Some(w) = hole.intervals.windows(2).find(|w| w[1].from < w[0].to) {
+                return Err(DrillholeError::OverlappingIntervals {
+                    hole: hole.name.clone(),
+                    depth: w[1].from,
+                });
+            }
+        }
+
+        Ok(Self {
+            names: names.iter().map(|n| n.to_string()).collect(),
+            holes,
+        })
+    }
+
+    //collars with hole, x, y, z columns, surveys with hole, depth, azimuth, dip columns and
+    //assays with hole, from, to columns, every other assay column is read as a value, blank
+    //or non numeric fields as NaN
+    pub fn from_csv<P: AsRef<Path>>(
+        collars: P,
+        surveys: P,
+        assays: P,
+    ) -> Result<Self, Box<dyn Error>> {
+        Self::from_csv_with(collars, surveys, assays, &NullPolicy::default())
+    }
+
+    //from_csv with assay values matching nulls, e.g. -99 sentinels, read as NaN
+    pub fn from_csv_with<P: AsRef<Path>>(
+        collars: P,
+        surveys: P,
+        assays: P,
+        nulls: &NullPolicy,
+    ) -> Result<Self, Box<dyn Error>> {
+        let collars = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_path(collars)?
+            .deserialize()
+            .collect::<Result<Vec<Collar>, _>>()?;
+        let surveys = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_path(surveys)?
+            .deserialize()
+            .collect::<Result<Vec<Survey>, _>>()?;
+
+        let mut rdr = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_path(assays)?;
+        let headers = rdr.headers()?.clone();
+        let position = |name: &str| {
+            headers
+                .iter()
+                .position(|h| h == name)
+                .ok_or_else(|| format!("assay table has no {name} column"))
+        };
+        let (hole, from, to) = (position("hole")?, position("from")?, position("to")?);
+        let columns = (0..headers.len())
+            .filter(|c| ![hole, from, to].contains(c))
+            .collect::<Vec<_>>();
+        let names = columns.iter().map(|c| &headers[*c]).collect::<Vec<_>>();
+
+        let mut table = Vec::new();
+        for record in rdr.records() {
+            let record = record?;
+            let value = |c: usize| {
+                if nulls.is_null_text(&record[c]) {
+                    f64::NAN
+                } else {
+                    record[c].parse::<f64>().unwrap_or(f64::NAN)
+                }
+            };
+            table.push(Assay {
+                hole: record[hole].to_string(),
+                from: record[from].parse()?,
+                to: record[to].parse()?,
+                values: columns.iter().map(|c| value(*c)).collect(),
+            });
+        }
+
+        Ok(Self::new(&collars, &surveys, &table, &names)?)
+    }
+
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn holes(&self) -> &[Drillhole] {
+        &self.holes
+    }
+
+    pub fn hole(&self, name: &str) -> Option<&Drillhole> {
+        self.holes.iter().find(|h| h.name == name)
+    }
+
+    fn composites(&self, composites: Vec<Composite>) -> Composites {
+        Composites {
+            holes: self.holes.iter().map(|h| h.name.clone()).collect(),
+            names: self.names.clone(),
+            composites,
+        }
+    }
+
+    //every assayed interval positioned at its mid point
+    pub fn desurvey(&self) -> Composites {
+        let composites = self
+            .holes
+            .iter()
+            .enumerate()
+            .flat_map(|(n, hole)| {
+                hole.intervals.iter().map(move |iv| Composite {
+                    hole: n,
+                    from: iv.from,
+                    to: iv.to,
+                    centroid: hole.position((iv.from + iv.to) / 2.0),
+                    values: iv.values.clone(),
+                })
+            })
+            .collect();
+        self.composites(composites)
+    }
+
+    //length weighted composites of every hole, panics if the domain column is not an assay
+    //column
+    pub fn composite(&self, compositing: &Compositing) -> Composites {
+        let domain = compositing.domain.as_ref().map(|d| {
+            self.names
+                .iter()
+                .position(|n| n == d)
+                .unwrap_or_else(|| panic!("column {d} is not an assay column"))
+        });
+        if let CompositeMethod::Length(l) = compositing.method {
+            assert!(l > 0.0, "composite length must be positive");
+        }
+        if let CompositeMethod::Bench { height, .. } = compositing.method {
+            assert!(height > 0.0, "bench height must be positive");
+        }
+
+        let mut composites = Vec::new();
+        for (n, hole) in self.holes.iter().enumerate() {
+            //(from, to) of runs of intervals sharing a domain value
+            let mut runs: Vec<(f64, f64)> = Vec::new();
+            for (m, iv) in hole.intervals.iter().enumerate() {
+                let continues = m > 0
+                    && domain.is_none_or(|d| same(iv.values[d], hole.intervals[m - 1].values[d]));
+                match runs.last_mut() {
+                    Some(run) if continues => run.1 = iv.to,
+                    _ => runs.push((iv.from, iv.to)),
+                }
+            }
+
+            for (from, to) in runs {
+                let mut breaks = vec![from];
+                match compositing.method {
+                    CompositeMethod::Length(l) => {
+                        let mut depth = from + l;
+                        while depth < to - 1e-9 {
+                            breaks.push(depth);
+                            depth += l;
+                        }
+                    }
+                    CompositeMethod::Bench { origin, height } => {
+                        breaks.extend(hole.bench_crossings(from, to, origin, height));
+                    }
+                    CompositeMethod::Run => {}
+                }
+                breaks.push(to);
+
+                for w in breaks.windows(2) {
+                    let (a, b) = (w[0], w[1]);
+                    //crossings at the ends of a run
+                    if b - a <= 1e-9 {
+                        continue;
+                    }
+                    let mut sampled = 0.0;
+                    let mut sums = vec![(0.0, 0.0); self.names.len()];
+                    for iv in hole.intervals.iter() {
+                        let overlap = iv.to.min(b) - iv.from.max(a);
+                        if overlap <= 0.0 {
+                            continue;
+                        }
+                        sampled += overlap;
+                        for (sum, v) in sums.iter_mut().zip(iv.values.iter()) {
+                            if !v.is_nan() {
+                                sum.0 += v * overlap;
+                                sum.1 += overlap;
+                            }
+                        }
+                    }
+                    if sampled <= 0.0 || sampled < compositing.min_length {
+                        continue;
+                    }
+                    composites.push(Composite {
+                        hole: n,
+                        from: a,
+                        to: b,
+                        centroid: hole.position((a + b) / 2.0),
+                        values: sums
+                            .iter()
+                            .map(|(s, l)| if *l > 0.0 { s / l } else { f64::NAN })
+                            .collect(),
+                    });
+                }
+            }
+        }
+        self.composites(composites)
+    }
+}
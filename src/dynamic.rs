@@ -0,0 +1,238 @@
+use serde::{Deserialize, Serialize};
+
+use crate::block::{BlockCoordinates, BlockIndex, BlockInterface, BlockSize};
+use crate::block_model::BlockModel;
+use crate::categorical::Category;
+use crate::storage::BlockStorage;
+
+use std::collections::{BTreeMap, HashMap};
+
+//type of a dynamic attribute
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AttributeType {
+    Bool,
+    Int,
+    Float,
+    Text,
+    Category,
+}
+
+//attribute value, untagged so self describing readers such as csv infer the type
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum AttributeValue {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Text(String),
+    //code in the model dictionary of the attribute, written as the code, read back as Int
+    Category(Category),
+}
+
+impl AttributeValue {
+    pub fn kind(&self) -> AttributeType {
+        match self {
+            Self::Bool(_) => AttributeType::Bool,
+            Self::Int(_) => AttributeType::Int,
+            Self::Float(_) => AttributeType::Float,
+            Self::Text(_) => AttributeType::Text,
+            Self::Category(_) => AttributeType::Category,
+        }
+    }
+
+    //numeric value, integers are widened
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Int(v) => Some(*v as f64),
+            Self::Float(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Self::Int(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Bool(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    pub fn as_category(&self) -> Option<Category> {
+        match self {
+            Self::Category(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::Text(v) => Some(v),
+            _ => None,
+        }
+    }
+}
+
+//block with a fixed geometry and arbitrary named attributes, every column other than
+//the coordinates, sizes and index is collected into attributes, empty fields are skipped
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DynamicBlock {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub x_size: f32,
+    pub y_size: f32,
+    pub z_size: f32,
+    #[serde(default)]
+    pub i: usize,
+    #[serde(default)]
+    pub j: usize,
+    #[serde(default)]
+    pub k: usize,
+    #[serde(flatten, deserialize_with = "present_attributes")]
+    pub attributes: HashMap<String, AttributeValue>,
+}
+
+fn present_attributes<'de, D>(de: D) -> Result<HashMap<String, AttributeValue>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let all = HashMap::<String, Option<AttributeValue>>::deserialize(de)?;
+    Ok(all
+        .into_iter()
+        .filter_map(|(name, v)| match v {
+            Some(AttributeValue::Text(t)) if t.is_empty() => None,
+            v => v.map(|v| (name, v)),
+        })
+        .collect())
+}
+
+impl DynamicBlock {
+    pub fn new(coordinates: BlockCoordinates, size: BlockSize) -> Self {
+        Self {
+            x: coordinates.x,
+            y: coordinates.y,
+            z: coordinates.z,
+            x_size: size.x_size,
+            y_size: size.y_size,
+            z_size: size.z_size,
+            i: 0,
+            j: 0,
+            k: 0,
+            attributes: HashMap::new(),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&AttributeValue> {
+        self.attributes.get(name)
+    }
+
+    pub fn get_f64(&self, name: &str) -> Option<f64> {
+        self.get(name)?.as_f64()
+    }
+
+    pub fn get_i64(&self, name: &str) -> Option<i64> {
+        self.get(name)?.as_i64()
+    }
+
+    pub fn get_bool(&self, name: &str) -> Option<bool> {
+        self.get(name)?.as_bool()
+    }
+
+    pub fn get_str(&self, name: &str) -> Option<&str> {
+        self.get(name)?.as_str()
+    }
+
+    pub fn get_category(&self, name: &str) -> Option<Category> {
+        self.get(name)?.as_category()
+    }
+
+    //previous value of the attribute
+    pub fn set(&mut self, name: &str, value: AttributeValue) -> Option<AttributeValue> {
+        self.attributes.insert(name.to_string(), value)
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<AttributeValue> {
+        self.attributes.remove(name)
+    }
+}
+
+impl BlockInterface for DynamicBlock {
+    fn coordinates(&self) -> BlockCoordinates {
+        BlockCoordinates {
+            x: self.x,
+            y: self.y,
+            z: self.z,
+        }
+    }
+
+    fn size(&self) -> BlockSize {
+        BlockSize {
+            x_size: self.x_size,
+            y_size: self.y_size,
+            z_size: self.z_size,
+        }
+    }
+
+    fn index(&self) -> BlockIndex {
+        BlockIndex {
+            i: self.i,
+            j: self.j,
+            k: self.k,
+        }
+    }
+
+    fn set_index(&mut self, ind: BlockIndex) {
+        self.i = ind.i;
+        self.j = ind.j;
+        self.k = ind.k;
+    }
+
+    fn set_coordinates(&mut self, coords: BlockCoordinates) -> bool {
+        self.x = coords.x;
+        self.y = coords.y;
+        self.z = coords.z;
+        true
+    }
+}
+
+impl<S: BlockStorage<DynamicBlock>> BlockModel<DynamicBlock, S> {
+    //type of every attribute present on any block, integer columns holding floats
+    //are reported as float and other conflicts as text
+    pub fn schema(&self) -> BTreeMap<String, AttributeType> {
+        let mut schema = BTreeMap::new();
+        for b in self.iter() {
+            for (name, v) in b.attributes.iter() {
+                let kind = v.kind();
+                schema
+                    .entry(name.clone())
+                    .and_modify(|t: &mut AttributeType| {
+                        *t = match (*t, kind) {
+                            (a, b) if a == b => a,
+                            (AttributeType::Int, AttributeType::Float)
+                            | (AttributeType::Float, AttributeType::Int) => AttributeType::Float,
+                            _ => AttributeType::Text,
+                        }
+                    })
+                    .or_insert(kind);
+            }
+        }
+        schema
+    }
+
+    pub fn attribute_names(&self) -> Vec<String> {
+        self.schema().into_keys().collect()
+    }
+
+    //number of blocks holding the attribute
+    pub fn attribute_count(&self, name: &str) -> usize {
+        self.iter()
+            .filter(|b| b.attributes.contains_key(name))
+            .count()
+    }
+}
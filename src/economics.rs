@@ -0,0 +1,282 @@
+use rayon::prelude::*;
+
+use crate::block::{BlockIndex, BlockInterface};
+use crate::block_model::BlockModel;
+use crate::storage::BlockStorage;
+
+use std::collections::HashMap;
+
+//economic parameters of a block, all costs are per tonne and price is per unit of metal
+pub trait EconomicModel<B> {
+    fn tonnage(&self, block: &B) -> f64;
+    //metal units per tonne
+    fn grade(&self, block: &B) -> f64;
+    fn price(&self, block: &B) -> f64;
+    //selling cost per unit of metal
+    fn selling_cost(&self, block: &B) -> f64;
+    //fraction of contained metal recovered by processing
+    fn recovery(&self, block: &B) -> f64;
+    fn mining_cost(&self, block: &B) -> f64;
+    fn processing_cost(&self, block: &B) -> f64;
+
+    //value if the block is sent to the plant
+    fn process_value(&self, block: &B) -> f64 {
+        let t = self.tonnage(block);
+        let metal = t * self.grade(block) * self.recovery(block);
+        metal * (self.price(block) - self.selling_cost(block))
+            - t * (self.mining_cost(block) + self.processing_cost(block))
+    }
+
+    //value if the block is sent to the waste dump
+    fn waste_value(&self, block: &B) -> f64 {
+        -self.tonnage(block) * self.mining_cost(block)
+    }
+
+    //value of the best destination
+    fn value(&self, block: &B) -> f64 {
+        self.process_value(block).max(self.waste_value(block))
+    }
+
+    fn is_ore(&self, block: &B) -> bool {
+        self.process_value(block) > self.waste_value(block)
+    }
+}
+
+//single product economics with mining cost increasing linearly with depth
+//grade and tonnage are read from the block
+#[derive(Debug, Clone)]
+pub struct SimpleEconomicModel<G, T> {
+    pub price: f64,
+    pub selling_cost: f64,
+    pub recovery: f64,
+    pub processing_cost: f64,
+    //mining cost at and above the reference elevation
+    pub mining_cost: f64,
+    pub reference_elevation: f32,
+    //additional mining cost per metre below the reference elevation
+    pub mining_cost_per_metre: f64,
+    pub grade: G,
+    pub tonnage: T,
+}
+
+impl<B, G, T> EconomicModel<B> for SimpleEconomicModel<G, T>
+where
+    B: BlockInterface,
+    G: Fn(&B) -> f64,
+    T: Fn(&B) -> f64,
+{
+    fn tonnage(&self, block: &B) -> f64 {
+        (self.tonnage)(block)
+    }
+
+    fn grade(&self, block: &B) -> f64 {
+        (self.grade)(block)
+    }
+
+    fn price(&self, _block: &B) -> f64 {
+        self.price
+    }
+
+    fn selling_cost(&self, _block: &B) -> f64 {
+        self.selling_cost
+    }
+
+    fn recovery(&self, _block: &B) -> f64 {
+        self.recovery
+    }
+
+    fn mining_cost(&self, block: &B) -> f64 {
+        let depth = (self.reference_elevation - block.coordinates().z).max(0.0);
+        self.mining_cost + self.mining_cost_per_metre * depth as f64
+    }
+
+    fn processing_cost(&self, _block: &B) -> f64 {
+        self.processing_cost
+    }
+}
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface + Sync,
+    S: BlockStorage<B> + Sync,
+{
+    //value of every present block, computed in parallel
+    pub fn economic_values<E>(&self, econ: &E) -> HashMap<BlockIndex, f64>
+    where
+        E: EconomicModel<B> + Sync,
+    {
+        self.par_indexed_iter()
+            .map(|(ind, b)| (ind, econ.value(b)))
+            .collect()
+    }
+
+    //store the value of every present block through set, computed in parallel
+    pub fn assign_economic_values<E, F>(&mut self, econ: &E, set: F)
+    where
+        B: Send,
+        E: EconomicModel<B> + Sync,
+        F: Fn(&mut B, f64) + Sync,
+    {
+        self.par_iter_mut().for_each(|b| {
+            let value = econ.value(b);
+            set(b, value);
+        });
+    }
+}
+
+//processing route a mined block can be sent to, recovery and cost per tonne may vary by
+//block, capacity is in tonnes and infinite when unlimited
+pub struct Destination<B> {
+    pub name: String,
+    pub recovery: Box<dyn Fn(&B) -> f64 + Send + Sync>,
+    pub cost: Box<dyn Fn(&B) -> f64 + Send + Sync>,
+    pub capacity: f64,
+}
+
+impl<B> Destination<B> {
+    pub fn new<R, C>(name: &str, recovery: R, cost: C, capacity: f64) -> Self
+    where
+        R: Fn(&B) -> f64 + Send + Sync + 'static,
+        C: Fn(&B) -> f64 + Send + Sync + 'static,
+    {
+        Self {
+            name: name.to_string(),
+            recovery: Box::new(recovery),
+            cost: Box::new(cost),
+            capacity,
+        }
+    }
+
+    //unlimited dump recovering nothing at no cost beyond mining
+    pub fn waste(name: &str) -> Self {
+        Self::new(name, |_| 0.0, |_| 0.0, f64::INFINITY)
+    }
+
+    //value of mining block and sending it here
+    pub fn value<E: EconomicModel<B>>(&self, econ: &E, block: &B) -> f64 {
+        let t = econ.tonnage(block);
+        let metal = t * econ.grade(block) * (self.recovery)(block);
+        metal * (econ.price(block) - econ.selling_cost(block))
+            - t * (econ.mining_cost(block) + (self.cost)(block))
+    }
+}
+
+impl<B> std::fmt::Debug for Destination<B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Destination")
+            .field("name", &self.name)
+            .field("capacity", &self.capacity)
+            .finish()
+    }
+}
+
+//destination chosen for every routed block, blocks no destination had room for are absent
+#[derive(Debug, Clone, PartialEq)]
+pub struct Routing {
+    pub names: Vec<String>,
+    pub destinations: HashMap<BlockIndex, usize>,
+    //totals per destination
+    pub tonnages: Vec<f64>,
+    pub values: Vec<f64>,
+}
+
+impl Routing {
+    pub(crate) fn empty<B>(destinations: &[Destination<B>]) -> Self {
+        Self {
+            names: destinations.iter().map(|d| d.name.clone()).collect(),
+            destinations: HashMap::new(),
+            tonnages: vec![0.0; destinations.len()],
+            values: vec![0.0; destinations.len()],
+        }
+    }
+
+    pub fn destination(&self, ind: BlockIndex) -> Option<usize> {
+        self.destinations.get(&ind).copied()
+    }
+
+    pub fn destination_name(&self, ind: BlockIndex) -> Option<&str> {
+        self.destination(ind).map(|d| self.names[d].as_str())
+    }
+
+    pub fn value(&self) -> f64 {
+        self.values.iter().sum()
+    }
+
+    //add blocks routed within their own capacities, blocks with the most to lose by missing
+    //their best destination are routed first
+    pub(crate) fn route<'a, B, E, I>(
+        &mut self,
+        blocks: I,
+        econ: &E,
+        destinations: &[Destination<B>],
+    ) where
+        B: 'a,
+        E: EconomicModel<B>,
+        I: Iterator<Item = (BlockIndex, &'a B)>,
+    {
+        //destinations of each block in descending value
+        let mut options = blocks
+            .map(|(ind, b)| {
+                let mut values = destinations
+                    .iter()
+                    .enumerate()
+                    .map(|(d, dest)| (d, dest.value(econ, b)))
+                    .collect::<Vec<_>>();
+                values.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+                let regret = match values.as_slice() {
+                    [first, second, ..] => first.1 - second.1,
+                    _ => f64::INFINITY,
+                };
+                (ind, econ.tonnage(b), regret, values)
+            })
+            .collect::<Vec<_>>();
+        options.sort_by(|a, b| b.2.total_cmp(&a.2).then(a.0.cmp(&b.0)));
+
+        let mut remaining = destinations.iter().map(|d| d.capacity).collect::<Vec<_>>();
+        for (ind, t, _, values) in options {
+            if let Some((d, v)) = values.into_iter().find(|(d, _)| t <= remaining[*d]) {
+                remaining[d] -= t;
+                self.destinations.insert(ind, d);
+                self.tonnages[d] += t;
+                self.values[d] += v;
+            }
+        }
+    }
+}
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface,
+    S: BlockStorage<B>,
+{
+    //route every present block to a destination, capacities cover the whole model
+    pub fn route_blocks<E>(&self, econ: &E, destinations: &[Destination<B>]) -> Routing
+    where
+        E: EconomicModel<B>,
+    {
+        let mut routing = Routing::empty(destinations);
+        routing.route(self.indexed_iter(), econ, destinations);
+        routing
+    }
+
+    //route every present block and write the chosen destination
+    pub fn assign_destinations<E, F>(
+        &mut self,
+        econ: &E,
+        destinations: &[Destination<B>],
+        set: F,
+    ) -> Routing
+    where
+        B: Send,
+        E: EconomicModel<B>,
+        F: Fn(&mut B, usize) + Sync,
+    {
+        let routing = self.route_blocks(econ, destinations);
+        self.par_indexed_iter_mut().for_each(|(ind, b)| {
+            if let Some(d) = routing.destinations.get(&ind) {
+                set(b, *d);
+            }
+        });
+        routing
+    }
+}
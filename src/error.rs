@@ -0,0 +1,138 @@
+use crate::block::{BlockCoordinates, BlockIndex};
+
+use std::error::Error;
+use std::fmt;
+
+//errors raised while constructing or operating on a blockmodel
+#[derive(Debug, Clone, PartialEq)]
+pub enum BlockModelError {
+    //blocks do not all share the same size
+    MixedBlockSizes,
+    //block centroid does not lie on the model lattice
+    MisalignedBlock { coords: BlockCoordinates },
+    //no blocks supplied
+    Empty,
+    //two blocks map to the same index
+    DuplicateIndex { ind: BlockIndex },
+    //precedence relation contains a cycle through block
+    CyclicPrecedence { ind: BlockIndex },
+    //models do not share block size, rotation and lattice
+    IncompatibleFrames,
+    //realization attributes differ from those of the set
+    MismatchedAttributes,
+    //models encode a categorical attribute with different dictionaries
+    MismatchedDictionaries { attribute: String },
+    //number of indices supplied differs from the number of blocks
+    MismatchedIndices { blocks: usize, inds: usize },
+}
+
+impl fmt::Display for BlockModelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MixedBlockSizes => write!(f, "blocks do not all share the same size"),
+            Self::MisalignedBlock { coords } => write!(
+                f,
+                "block at ({}, {}, {}) does not lie on the model lattice",
+                coords.x, coords.y, coords.z
+            ),
+            Self::Empty => write!(f, "no blocks supplied"),
+            Self::DuplicateIndex { ind } => write!(
+                f,
+                "multiple blocks share index ({}, {}, {})",
+                ind.i, ind.j, ind.k
+            ),
+            Self::CyclicPrecedence { ind } => write!(
+                f,
+                "precedence cycle through block ({}, {}, {})",
+                ind.i, ind.j, ind.k
+            ),
+            Self::IncompatibleFrames => {
+                write!(f, "models do not share block size, rotation and lattice")
+            }
+            Self::MismatchedAttributes => {
+                write!(f, "realization attributes differ from those of the set")
+            }
+            Self::MismatchedDictionaries { attribute } => {
+                write!(f, "models encode {attribute} with different dictionaries")
+            }
+            Self::MismatchedIndices { blocks, inds } => {
+                write!(f, "{inds} indices supplied for {blocks} blocks")
+            }
+        }
+    }
+}
+
+impl Error for BlockModelError {}
+
+//errors raised while building drillholes from collar, survey and assay tables
+#[derive(Debug, Clone, PartialEq)]
+pub enum DrillholeError {
+    //survey or assay references a hole without a collar
+    UnknownHole { hole: String },
+    //collar listed more than once
+    DuplicateCollar { hole: String },
+    //interval does not end below its start
+    InvalidInterval { hole: String, from: f64, to: f64 },
+    //interval starts above the end of the previous one
+    OverlappingIntervals { hole: String, depth: f64 },
+}
+
+impl fmt::Display for DrillholeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownHole { hole } => write!(f, "hole {hole} has no collar"),
+            Self::DuplicateCollar { hole } => write!(f, "hole {hole} has more than one collar"),
+            Self::InvalidInterval { hole, from, to } => {
+                write!(f, "interval {from} to {to} of hole {hole} is empty")
+            }
+            Self::OverlappingIntervals { hole, depth } => {
+                write!(f, "intervals of hole {hole} overlap at depth {depth}")
+            }
+        }
+    }
+}
+
+impl Error for DrillholeError {}
+
+//errors raised while parsing or evaluating a filter expression
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueryError {
+    //invalid expression text at byte offset pos
+    Parse { pos: usize, message: String },
+    //operator applied to values of the wrong type
+    Type { message: String },
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse { pos, message } => write!(f, "{} at position {}", message, pos),
+            Self::Type { message } => write!(f, "{}", message),
+        }
+    }
+}
+
+impl Error for QueryError {}
+
+//errors raised while changing the coordinate reference system of a model
+#[derive(Debug, Clone, PartialEq)]
+pub enum CrsError {
+    //model frame has no epsg code to transform from
+    MissingCrs,
+    //proj could not build or apply the transformation
+    Projection(String),
+    //block type keeps its own coordinates and cannot move them
+    FixedCoordinates,
+}
+
+impl fmt::Display for CrsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingCrs => write!(f, "model frame has no coordinate reference system"),
+            Self::Projection(message) => write!(f, "{message}"),
+            Self::FixedCoordinates => write!(f, "block coordinates cannot be set"),
+        }
+    }
+}
+
+impl Error for CrsError {}
@@ -0,0 +1,271 @@
+use rayon::prelude::*;
+use serde::Serialize;
+
+use crate::block::{BlockIndex, BlockInterface};
+use crate::block_model::BlockModel;
+use crate::estimation::search::SearchNeighborhood;
+use crate::estimation::Sample;
+use crate::spatial::KdTree;
+use crate::storage::BlockStorage;
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::Path;
+
+//resource confidence category, ordered from most to least confident
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+pub enum ResourceClass {
+    Measured,
+    Indicated,
+    Inferred,
+    Unclassified,
+}
+
+impl ResourceClass {
+    pub const ALL: [ResourceClass; 4] = [
+        ResourceClass::Measured,
+        ResourceClass::Indicated,
+        ResourceClass::Inferred,
+        ResourceClass::Unclassified,
+    ];
+
+    //conventional integer code, 1 measured to 3 inferred and 0 unclassified
+    pub fn code(&self) -> u8 {
+        match self {
+            ResourceClass::Measured => 1,
+            ResourceClass::Indicated => 2,
+            ResourceClass::Inferred => 3,
+            ResourceClass::Unclassified => 0,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ResourceClass::Measured => "measured",
+            ResourceClass::Indicated => "indicated",
+            ResourceClass::Inferred => "inferred",
+            ResourceClass::Unclassified => "unclassified",
+        }
+    }
+}
+
+//conditions a block must meet to take class, unset limits are not checked
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClassificationRule {
+    pub class: ResourceClass,
+    //at least min_samples of the neighborhood must lie inside its ellipsoid
+    pub search: SearchNeighborhood,
+    //largest mean anisotropic distance to the samples found
+    pub max_distance: Option<f64>,
+    //largest kriging variance
+    pub max_variance: Option<f64>,
+    //largest drill spacing
+    pub max_spacing: Option<f64>,
+}
+
+impl ClassificationRule {
+    pub fn new(class: ResourceClass, search: SearchNeighborhood) -> Self {
+        Self {
+            class,
+            search,
+            max_distance: None,
+            max_variance: None,
+            max_spacing: None,
+        }
+    }
+
+    fn accepts(
+        &self,
+        samples: &[Sample],
+        tree: &KdTree,
+        at: [f64; 3],
+        variance: Option<f64>,
+        spacing: Option<f64>,
+    ) -> bool {
+        let below = |limit: Option<f64>, value: Option<f64>| match (limit, value) {
+            (None, _) => true,
+            (Some(limit), Some(v)) => v <= limit,
+            (Some(_), None) => false,
+        };
+        if !below(self.max_variance, variance) || !below(self.max_spacing, spacing) {
+            return false;
+        }
+
+        let Some(found) = self.search.search_indexed(samples, tree, at) else {
+            return false;
+        };
+        match self.max_distance {
+            Some(limit) if !found.is_empty() => {
+                found.iter().map(|(_, d)| d).sum::<f64>() / found.len() as f64 <= limit
+            }
+            _ => true,
+        }
+    }
+}
+
+//block attributes used by the variance and spacing limits of the rules
+pub struct ClassificationInputs<'a, B> {
+    pub variance: Option<&'a (dyn Fn(&B) -> f64 + Sync)>,
+    pub spacing: Option<&'a (dyn Fn(&B) -> f64 + Sync)>,
+}
+
+impl<B> Default for ClassificationInputs<'_, B> {
+    fn default() -> Self {
+        Self {
+            variance: None,
+            spacing: None,
+        }
+    }
+}
+
+impl<B> Clone for ClassificationInputs<'_, B> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<B> Copy for ClassificationInputs<'_, B> {}
+
+//one row of a classification summary, grade is NaN without tonnage
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ClassSummary {
+    pub class: ResourceClass,
+    pub num_blocks: usize,
+    pub tonnage: f64,
+    pub grade: f64,
+    pub metal: f64,
+}
+
+//quantities per class, one row for every class including empty ones
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClassificationSummary {
+    pub rows: Vec<ClassSummary>,
+}
+
+impl ClassificationSummary {
+    pub fn get(&self, class: ResourceClass) -> &ClassSummary {
+        self.rows.iter().find(|r| r.class == class).unwrap()
+    }
+
+    pub fn to_csv<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn Error>> {
+        let mut w = csv::Writer::from_path(path)?;
+        for row in self.rows.iter() {
+            w.serialize(row)?;
+        }
+        w.flush()?;
+        Ok(())
+    }
+}
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface + Sync,
+    S: BlockStorage<B> + Sync,
+{
+    //class checking a block centroid against the rules in order, the first rule met wins and
+    //blocks meeting none are unclassified, NaN variance or spacing fails the limit
+    fn classifier<'a>(
+        &'a self,
+        samples: &'a [Sample],
+        rules: &'a [ClassificationRule],
+        inputs: ClassificationInputs<'a, B>,
+    ) -> impl Fn(BlockIndex, &B) -> ResourceClass + Sync + 'a {
+        for rule in rules.iter() {
+            assert!(
+                rule.max_variance.is_none() || inputs.variance.is_some(),
+                "variance limit without a variance attribute"
+            );
+            assert!(
+                rule.max_spacing.is_none() || inputs.spacing.is_some(),
+                "spacing limit without a spacing attribute"
+            );
+        }
+
+        let trees = rules
+            .iter()
+            .map(|r| r.search.index(samples))
+            .collect::<Vec<_>>();
+        let frame = *self.frame();
+        move |ind, b| {
+            let c = frame.index_to_coordinates(ind);
+            let at = [c.x as f64, c.y as f64, c.z as f64];
+            let variance = inputs.variance.map(|f| f(b)).filter(|v| !v.is_nan());
+            let spacing = inputs.spacing.map(|f| f(b)).filter(|v| !v.is_nan());
+            rules
+                .iter()
+                .zip(trees.iter())
+                .find(|(rule, tree)| rule.accepts(samples, tree, at, variance, spacing))
+                .map_or(ResourceClass::Unclassified, |(rule, _)| rule.class)
+        }
+    }
+
+    //class of every block, rules are usually ordered measured, indicated then inferred
+    pub fn classify(
+        &self,
+        samples: &[Sample],
+        rules: &[ClassificationRule],
+        inputs: ClassificationInputs<B>,
+    ) -> HashMap<BlockIndex, ResourceClass> {
+        let class = self.classifier(samples, rules, inputs);
+        self.par_indexed_iter()
+            .map(|(ind, b)| (ind, class(ind, b)))
+            .collect()
+    }
+
+    //store the class of every block through set, e.g. as its code
+    pub fn assign_classification<F>(
+        &mut self,
+        samples: &[Sample],
+        rules: &[ClassificationRule],
+        inputs: ClassificationInputs<B>,
+        set: F,
+    ) where
+        B: Send,
+        F: Fn(&mut B, ResourceClass) + Sync,
+    {
+        let classes = self.classify(samples, rules, inputs);
+        self.par_indexed_iter_mut().for_each(|(ind, b)| {
+            if let Some(c) = classes.get(&ind) {
+                set(b, *c);
+            }
+        });
+    }
+
+    //tonnage, grade and metal of the blocks in each class, blocks absent from classes are
+    //unclassified
+    pub fn classification_summary<T, G>(
+        &self,
+        classes: &HashMap<BlockIndex, ResourceClass>,
+        tonnage: T,
+        grade: G,
+    ) -> ClassificationSummary
+    where
+        T: Fn(&B) -> f64,
+        G: Fn(&B) -> f64,
+    {
+        let mut rows = ResourceClass::ALL.map(|class| ClassSummary {
+            class,
+            num_blocks: 0,
+            tonnage: 0.0,
+            grade: f64::NAN,
+            metal: 0.0,
+        });
+        for (ind, b) in self.indexed_iter() {
+            let class = classes
+                .get(&ind)
+                .copied()
+                .unwrap_or(ResourceClass::Unclassified);
+            let row = &mut rows[ResourceClass::ALL.iter().position(|c| *c == class).unwrap()];
+            let t = tonnage(b);
+            row.num_blocks += 1;
+            row.tonnage += t;
+            row.metal += t * grade(b);
+        }
+        for row in rows.iter_mut() {
+            row.grade = row.metal / row.tonnage;
+        }
+        ClassificationSummary {
+            rows: rows.to_vec(),
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use serde::Serialize;
+
+use crate::block::{BlockIndex, BlockInterface};
+use crate::block_model::BlockModel;
+use crate::estimation::Sample;
+use crate::spatial::KdTree;
+use crate::storage::BlockStorage;
+
+use std::error::Error;
+use std::path::Path;
+
+//values at one distance from the contact
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct ContactBin {
+    //signed distance at the bin centre, negative inside the first domain
+    pub distance: f64,
+    pub count: usize,
+    //NaN for empty bins
+    pub mean: f64,
+}
+
+//mean value against signed distance to the contact of two domains, a sharp step across zero
+//calls for a hard estimation boundary and a gradual change for a soft one
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContactProfile {
+    pub bin_width: f64,
+    //ordered from the far side of the first domain to the far side of the second
+    pub bins: Vec<ContactBin>,
+}
+
+impl ContactProfile {
+    //profile of (signed distance, value) pairs binned out to max_distance either side
+    pub fn new(values: &[(f64, f64)], bin_width: f64, max_distance: f64) -> Self {
+        assert!(
+            bin_width > 0.0 && max_distance > 0.0,
+            "bin width and distance must be positive"
+        );
+        let per_side = (max_distance / bin_width).ceil() as usize;
+        let mut sums = vec![(0, 0.0); 2 * per_side];
+        for (d, v) in values.iter() {
+            if v.is_nan() || d.abs() > max_distance {
+                continue;
+            }
+            //bins hold (n w, (n + 1) w] either side
+            let n = ((d.abs() / bin_width).ceil() as usize).clamp(1, per_side) - 1;
+            let bin = if *d < 0.0 {
+                per_side - 1 - n
+            } else {
+                per_side + n
+            };
+            sums[bin].0 += 1;
+            sums[bin].1 += v;
+        }
+
+        Self {
+            bin_width,
+            bins: sums
+                .into_iter()
+                .enumerate()
+                .map(|(n, (count, sum))| ContactBin {
+                    distance: (n as f64 - per_side as f64 + 0.5) * bin_width,
+                    count,
+                    mean: if count > 0 {
+                        sum / count as f64
+                    } else {
+                        f64::NAN
+                    },
+                })
+                .collect(),
+        }
+    }
+
+    //profile of samples in domains a and b, distance to the nearest sample of the other
+    //domain, one domain per sample
+    pub fn from_samples<D: PartialEq>(
+        samples: &[Sample],
+        domains: &[D],
+        a: D,
+        b: D,
+        bin_width: f64,
+        max_distance: f64,
+    ) -> Self {
+        assert!(
+            domains.len() == samples.len(),
+            "one domain is needed per sample"
+        );
+        let side = |d: &D| {
+            let points = samples
+                .iter()
+                .zip(domains.iter())
+                .filter(|(_, sd)| *sd == d)
+                .map(|(s, _)| s.position())
+                .collect::<Vec<_>>();
+            KdTree::new(&points)
+        };
+        let (tree_a, tree_b) = (side(&a), side(&b));
+
+        let values = samples
+            .iter()
+            .zip(domains.iter())
+            .filter_map(|(s, d)| {
+                let (other, sign) = if *d == a {
+                    (&tree_b, -1.0)
+                } else if *d == b {
+                    (&tree_a, 1.0)
+                } else {
+                    return None;
+                };
+                let (_, distance) = *other.nearest(s.position(), 1).first()?;
+                Some((sign * distance, s.value))
+            })
+            .collect::<Vec<_>>();
+        Self::new(&values, bin_width, max_distance)
+    }
+
+    //mean within window of the contact in the second domain over that in the first, near one
+    //for a soft boundary
+    pub fn contrast(&self, window: f64) -> f64 {
+        let mean = |inside: &dyn Fn(f64) -> bool| {
+            let (n, sum) = self
+                .bins
+                .iter()
+                .filter(|b| inside(b.distance) && b.count > 0)
+                .fold((0, 0.0), |(n, s), b| {
+                    (n + b.count, s + b.mean * b.count as f64)
+                });
+            sum / n as f64
+        };
+        mean(&|d| d > 0.0 && d < window) / mean(&|d| d < 0.0 && d > -window)
+    }
+
+    pub fn to_csv<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn Error>> {
+        let mut w = csv::Writer::from_path(path)?;
+        for b in self.bins.iter() {
+            w.serialize(b)?;
+        }
+        w.flush()?;
+        Ok(())
+    }
+}
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface,
+    S: BlockStorage<B>,
+{
+    //profile of the blocks in domains a and b, centroid distance to the nearest block of the
+    //other domain
+    pub fn contact_profile<D, F, V>(
+        &self,
+        domain: F,
+        value: V,
+        a: D,
+        b: D,
+        bin_width: f64,
+        max_distance: f64,
+    ) -> ContactProfile
+    where
+        D: PartialEq,
+        F: Fn(&B) -> D,
+        V: Fn(&B) -> f64,
+    {
+        let to_a = self.distance_to(&self.select(|blk| domain(blk) == a));
+        let to_b = self.distance_to(&self.select(|blk| domain(blk) == b));
+        let signed = |ind: BlockIndex, d: &D| {
+            if *d == a {
+                Some(-to_b[&ind])
+            } else if *d == b {
+                Some(to_a[&ind])
+            } else {
+                None
+            }
+        };
+
+        let values = self
+            .indexed_iter()
+            .filter_map(|(ind, blk)| Some((signed(ind, &domain(blk))?, value(blk))))
+            .collect::<Vec<_>>();
+        ContactProfile::new(&values, bin_width, max_distance)
+    }
+}
@@ -0,0 +1,112 @@
+use rayon::prelude::*;
+use serde::Serialize;
+
+use crate::estimation::{PointEstimator, Sample};
+
+use std::error::Error;
+use std::path::Path;
+
+//estimate of one sample from the others
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct CrossValidationPoint {
+    pub sample: usize,
+    pub actual: f64,
+    //NaN when the sample could not be estimated
+    pub estimate: f64,
+}
+
+impl CrossValidationPoint {
+    pub fn error(&self) -> f64 {
+        self.estimate - self.actual
+    }
+}
+
+//error statistics over the estimated samples
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ErrorStatistics {
+    pub count: usize,
+    //mean of estimate less actual
+    pub mean_error: f64,
+    pub mean_squared_error: f64,
+    pub correlation: f64,
+    //slope of the regression of actual on estimate, one without conditional bias
+    pub slope: f64,
+}
+
+impl ErrorStatistics {
+    pub fn new(points: &[CrossValidationPoint]) -> Self {
+        let valid = points
+            .iter()
+            .filter(|p| !p.estimate.is_nan() && !p.actual.is_nan())
+            .collect::<Vec<_>>();
+        let n = valid.len() as f64;
+        let mean =
+            |f: &dyn Fn(&CrossValidationPoint) -> f64| valid.iter().map(|p| f(p)).sum::<f64>() / n;
+
+        let (mean_actual, mean_estimate) = (mean(&|p| p.actual), mean(&|p| p.estimate));
+        let covariance = mean(&|p| (p.actual - mean_actual) * (p.estimate - mean_estimate));
+        let var_actual = mean(&|p| (p.actual - mean_actual).powi(2));
+        let var_estimate = mean(&|p| (p.estimate - mean_estimate).powi(2));
+
+        Self {
+            count: valid.len(),
+            mean_error: mean(&|p| p.error()),
+            mean_squared_error: mean(&|p| p.error().powi(2)),
+            correlation: covariance / (var_actual * var_estimate).sqrt(),
+            slope: covariance / var_estimate,
+        }
+    }
+}
+
+//leave one out estimates of every sample with their error statistics
+#[derive(Debug, Clone, PartialEq)]
+pub struct CrossValidation {
+    pub points: Vec<CrossValidationPoint>,
+    pub statistics: ErrorStatistics,
+}
+
+impl CrossValidation {
+    //every sample estimated from the others, exclude_hole also leaves out the samples of its
+    //drillhole so neighbouring composites do not flatter the estimate
+    pub fn run<E>(samples: &[Sample], estimator: &E, exclude_hole: bool) -> Self
+    where
+        E: PointEstimator + Sync,
+    {
+        let points = (0..samples.len())
+            .into_par_iter()
+            .map(|n| {
+                let held = &samples[n];
+                let others = samples
+                    .iter()
+                    .enumerate()
+                    .filter(|(m, s)| {
+                        *m != n && !(exclude_hole && held.hole.is_some() && s.hole == held.hole)
+                    })
+                    .map(|(_, s)| *s)
+                    .collect::<Vec<_>>();
+                CrossValidationPoint {
+                    sample: n,
+                    actual: held.value,
+                    estimate: estimator
+                        .estimate_at(&others, held.position())
+                        .unwrap_or(f64::NAN),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        Self {
+            statistics: ErrorStatistics::new(&points),
+            points,
+        }
+    }
+
+    //one row per sample
+    pub fn to_csv<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn Error>> {
+        let mut w = csv::Writer::from_path(path)?;
+        for p in self.points.iter() {
+            w.serialize(p)?;
+        }
+        w.flush()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,163 @@
+use crate::estimation::Sample;
+
+use std::collections::HashMap;
+
+//which declustered mean picks the best cell size, clustering in high grade areas calls for
+//the smallest mean and clustering in low grade areas for the largest
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub enum DeclusterObjective {
+    #[default]
+    MinimizeMean,
+    MaximizeMean,
+}
+
+//weights of one cell size, normalized to a mean of one
+#[derive(Debug, Clone, PartialEq)]
+pub struct Declustering {
+    pub cell_size: f64,
+    pub weights: Vec<f64>,
+    //declustered mean of the sample values
+    pub mean: f64,
+}
+
+//declustered mean of every cell size tried and the best one
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeclusteringSearch {
+    pub sizes: Vec<f64>,
+    pub means: Vec<f64>,
+    pub best: Declustering,
+}
+
+//cell declustering, every sample weighs the inverse of the number of samples sharing its cell
+//averaged over several grid origins to damp the effect of the origin
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CellDeclustering {
+    //y and z cell sizes relative to the x size
+    pub anisotropy: [f64; 2],
+    //grid origins shifted diagonally in even steps across one cell
+    pub offsets: usize,
+}
+
+impl Default for CellDeclustering {
+    fn default() -> Self {
+        Self {
+            anisotropy: [1.0, 1.0],
+            offsets: 4,
+        }
+    }
+}
+
+impl CellDeclustering {
+    //weights for cells cell_size wide in x, samples with a NaN value get no weight, the mean
+    //is NaN without a valued sample
+    pub fn decluster(&self, samples: &[Sample], cell_size: f64) -> Declustering {
+        assert!(cell_size > 0.0, "cell size must be positive");
+        assert!(self.offsets > 0, "at least one grid origin is needed");
+
+        let n = samples.iter().filter(|s| !s.value.is_nan()).count() as f64;
+        if n == 0.0 {
+            return Declustering {
+                cell_size,
+                weights: vec![0.0; samples.len()],
+                mean: f64::NAN,
+            };
+        }
+
+        let size = [
+            cell_size,
+            cell_size * self.anisotropy[0],
+            cell_size * self.anisotropy[1],
+        ];
+        let min = samples.iter().fold([f64::INFINITY; 3], |m, s| {
+            let p = s.position();
+            [0, 1, 2].map(|d| m[d].min(p[d]))
+        });
+
+        let mut weights = vec![0.0; samples.len()];
+        for o in 0..self.offsets {
+            let shift = o as f64 / self.offsets as f64;
+            let cell = |s: &Sample| {
+                let p = s.position();
+                [0, 1, 2].map(|d| ((p[d] - min[d]) / size[d] + shift).floor() as i64)
+            };
+            let mut counts: HashMap<[i64; 3], usize> = HashMap::new();
+            for s in samples.iter().filter(|s| !s.value.is_nan()) {
+                *counts.entry(cell(s)).or_default() += 1;
+            }
+            for (w, s) in weights.iter_mut().zip(samples.iter()) {
+                if !s.value.is_nan() {
+                    *w += 1.0 / counts[&cell(s)] as f64;
+                }
+            }
+        }
+
+        let total = weights.iter().sum::<f64>();
+        for w in weights.iter_mut() {
+            *w *= n / total;
+        }
+        let mean = weights
+            .iter()
+            .zip(samples.iter())
+            .filter(|(w, _)| **w > 0.0)
+            .map(|(w, s)| w * s.value)
+            .sum::<f64>()
+            / n;
+
+        Declustering {
+            cell_size,
+            weights,
+            mean,
+        }
+    }
+
+    //declustered mean of every size, the best by objective is kept
+    pub fn search(
+        &self,
+        samples: &[Sample],
+        sizes: &[f64],
+        objective: DeclusterObjective,
+    ) -> DeclusteringSearch {
+        assert!(!sizes.is_empty(), "at least one cell size is needed");
+
+        let mut means = Vec::with_capacity(sizes.len());
+        let mut best: Option<Declustering> = None;
+        for size in sizes.iter() {
+            let d = self.decluster(samples, *size);
+            means.push(d.mean);
+            let better = best.as_ref().is_none_or(|b| match objective {
+                DeclusterObjective::MinimizeMean => d.mean < b.mean,
+                DeclusterObjective::MaximizeMean => d.mean > b.mean,
+            });
+            if better {
+                best = Some(d);
+            }
+        }
+
+        DeclusteringSearch {
+            sizes: sizes.to_vec(),
+            means,
+            best: best.unwrap(),
+        }
+    }
+
+    //search over count sizes evenly spaced from min to max
+    pub fn search_range(
+        &self,
+        samples: &[Sample],
+        min: f64,
+        max: f64,
+        count: usize,
+        objective: DeclusterObjective,
+    ) -> DeclusteringSearch {
+        assert!(count > 0 && min <= max, "invalid cell size range");
+        let step = if count > 1 {
+            (max - min) / (count - 1) as f64
+        } else {
+            0.0
+        };
+        let sizes = (0..count)
+            .map(|n| min + n as f64 * step)
+            .collect::<Vec<_>>();
+        self.search(samples, &sizes, objective)
+    }
+}
@@ -0,0 +1,154 @@
+use rayon::prelude::*;
+
+use crate::estimation::Sample;
+
+//direction along which sample pairs are collected, angles in degrees
+//azimuth is clockwise from +y and dip is below horizontal
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VariogramDirection {
+    pub azimuth: f64,
+    pub dip: f64,
+    //maximum angle between a pair vector and the direction
+    pub angle_tolerance: f64,
+    //maximum distance of a pair vector from the direction line
+    pub bandwidth: f64,
+}
+
+impl VariogramDirection {
+    pub fn new(azimuth: f64, dip: f64, angle_tolerance: f64, bandwidth: f64) -> Self {
+        Self {
+            azimuth,
+            dip,
+            angle_tolerance,
+            bandwidth,
+        }
+    }
+
+    //accepts pairs in every direction
+    pub fn omnidirectional() -> Self {
+        Self::new(0.0, 0.0, 90.0, f64::INFINITY)
+    }
+
+    fn unit(&self) -> [f64; 3] {
+        let (sa, ca) = self.azimuth.to_radians().sin_cos();
+        let (sd, cd) = self.dip.to_radians().sin_cos();
+        [sa * cd, ca * cd, -sd]
+    }
+}
+
+//lag bins centred on lag, 2 * lag, .., num_lags * lag
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LagParameters {
+    pub lag: f64,
+    pub num_lags: usize,
+    //maximum distance of a pair from its bin centre, usually half the lag
+    pub tolerance: f64,
+}
+
+impl LagParameters {
+    pub fn new(lag: f64, num_lags: usize, tolerance: f64) -> Self {
+        assert!(lag > 0.0, "lag must be positive");
+        Self {
+            lag,
+            num_lags,
+            tolerance,
+        }
+    }
+}
+
+//row of an experimental variogram table
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VariogramLag {
+    //mean separation of the pairs in the bin
+    pub lag: f64,
+    pub gamma: f64,
+    pub pairs: usize,
+}
+
+#[derive(Clone)]
+struct Bins {
+    dist: Vec<f64>,
+    sq_diff: Vec<f64>,
+    pairs: Vec<usize>,
+}
+
+impl Bins {
+    fn new(n: usize) -> Self {
+        Self {
+            dist: vec![0.0; n],
+            sq_diff: vec![0.0; n],
+            pairs: vec![0; n],
+        }
+    }
+
+    fn merge(mut self, other: Self) -> Self {
+        for b in 0..self.pairs.len() {
+            self.dist[b] += other.dist[b];
+            self.sq_diff[b] += other.sq_diff[b];
+            self.pairs[b] += other.pairs[b];
+        }
+        self
+    }
+}
+
+//semivariogram of sample values along direction, empty bins and NaN values are omitted
+pub fn experimental_variogram(
+    samples: &[Sample],
+    direction: &VariogramDirection,
+    lags: &LagParameters,
+) -> Vec<VariogramLag> {
+    let u = direction.unit();
+    let cos_tol = direction.angle_tolerance.min(90.0).to_radians().cos();
+
+    let bins = (0..samples.len())
+        .into_par_iter()
+        .fold(
+            || Bins::new(lags.num_lags),
+            |mut bins, a| {
+                for b in samples[a + 1..].iter() {
+                    let s = samples[a];
+                    if s.value.is_nan() || b.value.is_nan() {
+                        continue;
+                    }
+                    let d = [b.x - s.x, b.y - s.y, b.z - s.z];
+                    let h = (d[0] * d[0] + d[1] * d[1] + d[2] * d[2]).sqrt();
+                    if h == 0.0 {
+                        continue;
+                    }
+
+                    //pairs count in both senses of the direction
+                    let along = (d[0] * u[0] + d[1] * u[1] + d[2] * u[2]).abs();
+                    let across = (h * h - along * along).max(0.0).sqrt();
+                    if along / h < cos_tol - 1e-12 || across > direction.bandwidth {
+                        continue;
+                    }
+
+                    let bin = (h / lags.lag).round() as usize;
+                    if bin == 0
+                        || bin > lags.num_lags
+                        || (h - bin as f64 * lags.lag).abs() > lags.tolerance
+                    {
+                        continue;
+                    }
+
+                    bins.dist[bin - 1] += h;
+                    bins.sq_diff[bin - 1] += (b.value - s.value).powi(2);
+                    bins.pairs[bin - 1] += 1;
+                }
+                bins
+            },
+        )
+        .reduce(|| Bins::new(lags.num_lags), Bins::merge);
+
+    (0..lags.num_lags)
+        .filter(|b| bins.pairs[*b] > 0)
+        .map(|b| {
+            let n = bins.pairs[b] as f64;
+            VariogramLag {
+                lag: bins.dist[b] / n,
+                gamma: bins.sq_diff[b] / (2.0 * n),
+                pairs: bins.pairs[b],
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,89 @@
+use rayon::prelude::*;
+
+use crate::block::{BlockIndex, BlockInterface};
+use crate::block_model::BlockModel;
+use crate::estimation::search::SearchNeighborhood;
+use crate::estimation::Sample;
+use crate::spatial::KdTree;
+use crate::storage::BlockStorage;
+
+use std::collections::HashMap;
+
+//inverse distance weighting using anisotropic distances from the search ellipsoid
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InverseDistance {
+    pub power: f64,
+    pub search: SearchNeighborhood,
+}
+
+impl InverseDistance {
+    pub fn new(power: f64, search: SearchNeighborhood) -> Self {
+        assert!(power >= 0.0, "idw power must not be negative");
+        Self { power, search }
+    }
+
+    //estimate at a point, a sample at distance zero takes all the weight
+    pub fn estimate(&self, samples: &[Sample], at: [f64; 3]) -> Option<f64> {
+        self.combine(samples, &self.search.search(samples, at)?)
+    }
+
+    //estimate using a tree from SearchNeighborhood::index
+    pub fn estimate_indexed(&self, samples: &[Sample], tree: &KdTree, at: [f64; 3]) -> Option<f64> {
+        self.combine(samples, &self.search.search_indexed(samples, tree, at)?)
+    }
+
+    fn combine(&self, samples: &[Sample], found: &[(usize, f64)]) -> Option<f64> {
+        if found.is_empty() {
+            return None;
+        }
+
+        if let Some((n, _)) = found.iter().find(|(_, d)| *d <= f64::EPSILON) {
+            return Some(samples[*n].value);
+        }
+
+        let (sum, total) = found.iter().fold((0.0, 0.0), |(s, t), (n, d)| {
+            let w = d.powf(-self.power);
+            (s + w * samples[*n].value, t + w)
+        });
+        Some(sum / total)
+    }
+}
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface + Sync,
+    S: BlockStorage<B> + Sync,
+{
+    //idw estimate at every block centroid, blocks without enough samples are absent
+    pub fn idw_estimates(
+        &self,
+        samples: &[Sample],
+        idw: &InverseDistance,
+    ) -> HashMap<BlockIndex, f64> {
+        let tree = idw.search.index(samples);
+        self.par_indexed_iter()
+            .filter_map(|(ind, _)| {
+                let c = self.index_to_coordinates(ind);
+                let at = [c.x as f64, c.y as f64, c.z as f64];
+                idw.estimate_indexed(samples, &tree, at).map(|v| (ind, v))
+            })
+            .collect()
+    }
+
+    //store idw estimates through set, blocks without enough samples are left unchanged
+    pub fn assign_idw<F>(&mut self, samples: &[Sample], idw: &InverseDistance, set: F)
+    where
+        B: Send,
+        F: Fn(&mut B, f64) + Sync,
+    {
+        let frame = *self.frame();
+        let tree = idw.search.index(samples);
+        self.par_indexed_iter_mut().for_each(|(ind, b)| {
+            let c = frame.index_to_coordinates(ind);
+            let at = [c.x as f64, c.y as f64, c.z as f64];
+            if let Some(v) = idw.estimate_indexed(samples, &tree, at) {
+                set(b, v);
+            }
+        });
+    }
+}
@@ -0,0 +1,504 @@
+use rayon::prelude::*;
+
+use crate::block::{BlockIndex, BlockInterface};
+use crate::block_model::BlockModel;
+use crate::estimation::search::SearchEllipsoid;
+use crate::estimation::search::SearchNeighborhood;
+use crate::estimation::variogram::{StructureType, VariogramModel, VariogramStructure};
+use crate::estimation::{solve_linear, Sample};
+use crate::spatial::KdTree;
+use crate::storage::BlockStorage;
+
+use std::collections::HashMap;
+
+//kriged value and its kriging variance
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KrigingEstimate {
+    pub value: f64,
+    pub variance: f64,
+}
+
+//ordinary kriging of block centroids from point samples
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrdinaryKriging {
+    pub variogram: VariogramModel,
+    pub search: SearchNeighborhood,
+}
+
+impl OrdinaryKriging {
+    pub fn new(variogram: VariogramModel, search: SearchNeighborhood) -> Self {
+        Self { variogram, search }
+    }
+
+    //None if the search fails or the kriging system is singular
+    pub fn estimate(&self, samples: &[Sample], at: [f64; 3]) -> Option<KrigingEstimate> {
+        self.solve(samples, &self.search.search(samples, at)?, at)
+    }
+
+    //estimate using a tree from SearchNeighborhood::index
+    pub fn estimate_indexed(
+        &self,
+        samples: &[Sample],
+        tree: &KdTree,
+        at: [f64; 3],
+    ) -> Option<KrigingEstimate> {
+        self.solve(samples, &self.search.search_indexed(samples, tree, at)?, at)
+    }
+
+    fn solve(
+        &self,
+        samples: &[Sample],
+        found: &[(usize, f64)],
+        at: [f64; 3],
+    ) -> Option<KrigingEstimate> {
+        let n = found.len();
+        if n == 0 {
+            return None;
+        }
+
+        let offset = |a: [f64; 3], b: [f64; 3]| [a[0] - b[0], a[1] - b[1], a[2] - b[2]];
+        let pos = found
+            .iter()
+            .map(|(s, _)| samples[*s].position())
+            .collect::<Vec<_>>();
+
+        //covariance system bordered by the unbiasedness constraint
+        let mut lhs = vec![vec![1.0; n + 1]; n + 1];
+        lhs[n][n] = 0.0;
+        for a in 0..n {
+            for b in a..n {
+                let c = self.variogram.covariance(offset(pos[a], pos[b]));
+                lhs[a][b] = c;
+                lhs[b][a] = c;
+            }
+        }
+
+        let mut rhs = pos
+            .iter()
+            .map(|p| self.variogram.covariance(offset(*p, at)))
+            .collect::<Vec<_>>();
+        rhs.push(1.0);
+
+        let weights = solve_linear(lhs, rhs.clone())?;
+        let value = found
+            .iter()
+            .zip(weights.iter())
+            .map(|((s, _), w)| w * samples[*s].value)
+            .sum();
+        let variance = self.variogram.sill()
+            - weights[..n]
+                .iter()
+                .zip(rhs.iter())
+                .map(|(w, c)| w * c)
+                .sum::<f64>()
+            - weights[n];
+
+        Some(KrigingEstimate { value, variance })
+    }
+}
+
+//basic structure of a coregionalization, sills[a][b] is the contribution to the cross
+//covariance of variables a and b
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoregionalizedStructure {
+    pub kind: StructureType,
+    pub anisotropy: SearchEllipsoid,
+    pub sills: Vec<Vec<f64>>,
+}
+
+//linear model of coregionalization, every direct and cross variogram is a combination of the
+//same basic structures with positive semi definite coefficient matrices
+#[derive(Debug, Clone, PartialEq)]
+pub struct Coregionalization {
+    pub nugget: Vec<Vec<f64>>,
+    pub structures: Vec<CoregionalizedStructure>,
+}
+
+//whether a symmetric matrix has a cholesky factorisation up to rounding
+fn positive_semi_definite(m: &[Vec<f64>]) -> bool {
+    let n = m.len();
+    let mut l = vec![vec![0.0; n]; n];
+    let scale = (0..n).map(|i| m[i][i].abs()).fold(0.0, f64::max).max(1.0);
+    for i in 0..n {
+        for j in 0..=i {
+            let sum = m[i][j] - (0..j).map(|k| l[i][k] * l[j][k]).sum::<f64>();
+            if i == j {
+                if sum < -1e-9 * scale {
+                    return false;
+                }
+                l[i][i] = sum.max(0.0).sqrt();
+            } else if l[j][j] > 0.0 {
+                l[i][j] = sum / l[j][j];
+            } else if sum.abs() > 1e-9 * scale {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+impl Coregionalization {
+    //panics unless the matrices are square, symmetric, positive semi definite and sized alike
+    pub fn new(nugget: Vec<Vec<f64>>, structures: Vec<CoregionalizedStructure>) -> Self {
+        let n = nugget.len();
+        for m in std::iter::once(&nugget).chain(structures.iter().map(|s| &s.sills)) {
+            assert!(
+                m.len() == n && m.iter().all(|row| row.len() == n),
+                "coregionalization matrices must be square and of the same size"
+            );
+            assert!(
+                (0..n).all(|a| (0..n).all(|b| (m[a][b] - m[b][a]).abs() <= 1e-12)),
+                "coregionalization matrices must be symmetric"
+            );
+            assert!(
+                positive_semi_definite(m),
+                "coregionalization matrices must be positive semi definite"
+            );
+        }
+        Self { nugget, structures }
+    }
+
+    pub fn num_variables(&self) -> usize {
+        self.nugget.len()
+    }
+
+    //direct variogram of variable a, or cross variogram of a and b as a variogram model
+    pub fn variogram(&self, a: usize, b: usize) -> VariogramModel {
+        VariogramModel {
+            nugget: self.nugget[a][b],
+            structures: self
+                .structures
+                .iter()
+                .map(|s| VariogramStructure::new(s.kind, s.sills[a][b], s.anisotropy))
+                .collect(),
+        }
+    }
+
+    //cross covariance of variables a and b at a lag
+    pub fn covariance(&self, a: usize, b: usize, offset: [f64; 3]) -> f64 {
+        let nugget = if offset == [0.0; 3] {
+            self.nugget[a][b]
+        } else {
+            0.0
+        };
+        nugget
+            + self
+                .structures
+                .iter()
+                .map(|s| {
+                    let shape = VariogramStructure::new(s.kind, 1.0, s.anisotropy).gamma(offset);
+                    s.sills[a][b] * (1.0 - shape)
+                })
+                .sum::<f64>()
+    }
+}
+
+//ordinary co-kriging of one target variable from samples of every variable of the model,
+//the target weights sum to one and those of each other variable to zero
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoKriging {
+    pub model: Coregionalization,
+    //applied to each variable, the target must meet min_samples
+    pub search: SearchNeighborhood,
+    pub target: usize,
+}
+
+impl CoKriging {
+    pub fn new(model: Coregionalization, search: SearchNeighborhood, target: usize) -> Self {
+        assert!(
+            target < model.num_variables(),
+            "target is not a variable of the model"
+        );
+        Self {
+            model,
+            search,
+            target,
+        }
+    }
+
+    fn check(&self, data: &[&[Sample]]) {
+        assert!(
+            data.len() == self.model.num_variables(),
+            "one sample set is needed per variable"
+        );
+    }
+
+    //None if the target search fails or the system is singular, data holds the samples of
+    //each variable in model order
+    pub fn estimate(&self, data: &[&[Sample]], at: [f64; 3]) -> Option<KrigingEstimate> {
+        self.check(data);
+        let found = (0..data.len())
+            .map(|v| self.found(v, self.search.search(data[v], at)))
+            .collect::<Option<Vec<_>>>()?;
+        self.solve(data, &found, at)
+    }
+
+    //one tree per variable from SearchNeighborhood::index
+    pub fn index(&self, data: &[&[Sample]]) -> Vec<KdTree> {
+        self.check(data);
+        data.iter().map(|d| self.search.index(d)).collect()
+    }
+
+    pub fn estimate_indexed(
+        &self,
+        data: &[&[Sample]],
+        trees: &[KdTree],
+        at: [f64; 3],
+    ) -> Option<KrigingEstimate> {
+        let found = trees
+            .iter()
+            .enumerate()
+            .map(|(v, tree)| self.found(v, self.search.search_indexed(data[v], tree, at)))
+            .collect::<Option<Vec<_>>>()?;
+        self.solve(data, &found, at)
+    }
+
+    //secondary variables below min_samples contribute no samples
+    fn found(&self, v: usize, found: Option<Vec<(usize, f64)>>) -> Option<Vec<(usize, f64)>> {
+        match found {
+            None if v == self.target => None,
+            found => Some(found.unwrap_or_default()),
+        }
+    }
+
+    fn solve(
+        &self,
+        data: &[&[Sample]],
+        found: &[Vec<(usize, f64)>],
+        at: [f64; 3],
+    ) -> Option<KrigingEstimate> {
+        //(variable, position, value) of every selected sample
+        let points = found
+            .iter()
+            .enumerate()
+            .flat_map(|(v, f)| {
+                f.iter()
+                    .map(move |(s, _)| (v, data[v][*s].position(), data[v][*s].value))
+            })
+            .collect::<Vec<_>>();
+        let (n, nv) = (points.len(), data.len());
+        if n == 0 {
+            return None;
+        }
+
+        let offset = |a: [f64; 3], b: [f64; 3]| [a[0] - b[0], a[1] - b[1], a[2] - b[2]];
+        //covariances bordered by one unbiasedness row per variable
+        let mut lhs = vec![vec![0.0; n + nv]; n + nv];
+        for a in 0..n {
+            for b in a..n {
+                let c = self.model.covariance(
+                    points[a].0,
+                    points[b].0,
+                    offset(points[a].1, points[b].1),
+                );
+                lhs[a][b] = c;
+                lhs[b][a] = c;
+            }
+            lhs[a][n + points[a].0] = 1.0;
+            lhs[n + points[a].0][a] = 1.0;
+        }
+        let mut rhs = points
+            .iter()
+            .map(|(v, p, _)| self.model.covariance(*v, self.target, offset(*p, at)))
+            .collect::<Vec<_>>();
+        rhs.extend((0..nv).map(|v| (v == self.target) as usize as f64));
+
+        //variables without samples leave an empty constraint row
+        let used = (0..nv)
+            .filter(|v| points.iter().any(|p| p.0 == *v))
+            .collect::<Vec<_>>();
+        let keep = (0..n).chain(used.iter().map(|v| n + v)).collect::<Vec<_>>();
+        let lhs = keep
+            .iter()
+            .map(|r| keep.iter().map(|c| lhs[*r][*c]).collect())
+            .collect();
+        let rhs_kept = keep.iter().map(|r| rhs[*r]).collect::<Vec<_>>();
+
+        let weights = solve_linear(lhs, rhs_kept.clone())?;
+        let value = points
+            .iter()
+            .zip(weights.iter())
+            .map(|((_, _, z), w)| w * z)
+            .sum();
+        let target_multiplier = used
+            .iter()
+            .position(|v| *v == self.target)
+            .map_or(0.0, |p| weights[n + p]);
+        let variance = self.model.covariance(self.target, self.target, [0.0; 3])
+            - weights[..n]
+                .iter()
+                .zip(rhs.iter())
+                .map(|(w, c)| w * c)
+                .sum::<f64>()
+            - target_multiplier;
+
+        Some(KrigingEstimate { value, variance })
+    }
+}
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface + Sync,
+    S: BlockStorage<B> + Sync,
+{
+    //kriged estimate at every block centroid, blocks without a solution are absent
+    pub fn kriging_estimates(
+        &self,
+        samples: &[Sample],
+        ok: &OrdinaryKriging,
+    ) -> HashMap<BlockIndex, KrigingEstimate> {
+        let tree = ok.search.index(samples);
+        self.par_indexed_iter()
+            .filter_map(|(ind, _)| {
+                let c = self.index_to_coordinates(ind);
+                let at = [c.x as f64, c.y as f64, c.z as f64];
+                ok.estimate_indexed(samples, &tree, at).map(|e| (ind, e))
+            })
+            .collect()
+    }
+
+    //store estimate and variance through set, blocks without a solution are left unchanged
+    pub fn assign_kriging<F>(&mut self, samples: &[Sample], ok: &OrdinaryKriging, set: F)
+    where
+        B: Send,
+        F: Fn(&mut B, KrigingEstimate) + Sync,
+    {
+        let frame = *self.frame();
+        let tree = ok.search.index(samples);
+        self.par_indexed_iter_mut().for_each(|(ind, b)| {
+            let c = frame.index_to_coordinates(ind);
+            let at = [c.x as f64, c.y as f64, c.z as f64];
+            if let Some(e) = ok.estimate_indexed(samples, &tree, at) {
+                set(b, e);
+            }
+        });
+    }
+
+    //co-kriged estimate of the target at every block centroid, blocks without a solution
+    //are absent
+    pub fn cokriging_estimates(
+        &self,
+        data: &[&[Sample]],
+        ck: &CoKriging,
+    ) -> HashMap<BlockIndex, KrigingEstimate> {
+        let trees = ck.index(data);
+        self.par_indexed_iter()
+            .filter_map(|(ind, _)| {
+                let c = self.index_to_coordinates(ind);
+                let at = [c.x as f64, c.y as f64, c.z as f64];
+                ck.estimate_indexed(data, &trees, at).map(|e| (ind, e))
+            })
+            .collect()
+    }
+
+    //store estimate and variance through set, blocks without a solution are left unchanged
+    pub fn assign_cokriging<F>(&mut self, data: &[&[Sample]], ck: &CoKriging, set: F)
+    where
+        B: Send,
+        F: Fn(&mut B, KrigingEstimate) + Sync,
+    {
+        let frame = *self.frame();
+        let trees = ck.index(data);
+        self.par_indexed_iter_mut().for_each(|(ind, b)| {
+            let c = frame.index_to_coordinates(ind);
+            let at = [c.x as f64, c.y as f64, c.z as f64];
+            if let Some(e) = ck.estimate_indexed(data, &trees, at) {
+                set(b, e);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn structure(sill: f64) -> VariogramStructure {
+        VariogramStructure::new(
+            StructureType::Spherical,
+            sill,
+            SearchEllipsoid::isotropic(100.0),
+        )
+    }
+
+    fn search() -> SearchNeighborhood {
+        SearchNeighborhood::new(SearchEllipsoid::isotropic(200.0), 1, 16)
+    }
+
+    fn samples(value: impl Fn(usize) -> f64) -> Vec<Sample> {
+        [
+            [0.0, 0.0],
+            [40.0, 10.0],
+            [15.0, 55.0],
+            [70.0, 60.0],
+            [90.0, 5.0],
+        ]
+        .iter()
+        .enumerate()
+        .map(|(n, [x, y])| Sample::new(*x, *y, 0.0, value(n)))
+        .collect()
+    }
+
+    fn cokriging() -> CoKriging {
+        let model = Coregionalization::new(
+            vec![vec![0.0; 2]; 2],
+            vec![CoregionalizedStructure {
+                kind: StructureType::Spherical,
+                anisotropy: SearchEllipsoid::isotropic(100.0),
+                sills: vec![vec![1.0, 0.6], vec![0.6, 1.0]],
+            }],
+        );
+        CoKriging::new(model, search(), 0)
+    }
+
+    #[test]
+    fn weights_sum_to_one() {
+        //a constant is only reproduced when the weights sum to one
+        let ok = OrdinaryKriging::new(VariogramModel::new(0.2, vec![structure(0.8)]), search());
+        let e = ok.estimate(&samples(|_| 7.5), [30.0, 30.0, 0.0]).unwrap();
+        assert!((e.value - 7.5).abs() < 1e-9);
+        assert!(e.variance > 0.0);
+    }
+
+    #[test]
+    fn exact_at_data_points() {
+        let ok = OrdinaryKriging::new(VariogramModel::new(0.0, vec![structure(1.0)]), search());
+        let data = samples(|n| n as f64 * 1.5 + 2.0);
+        for s in data.iter() {
+            let e = ok.estimate(&data, s.position()).unwrap();
+            assert!((e.value - s.value).abs() < 1e-9);
+            assert!(e.variance.abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn cokriging_weights_sum_to_one_and_zero() {
+        //the target constant is reproduced whatever the secondary constant only when the
+        //target weights sum to one and the secondary weights to zero
+        let ck = cokriging();
+        let target = samples(|_| 2.0);
+        for secondary in [5.0, 50.0] {
+            let other = samples(|_| secondary)
+                .into_iter()
+                .map(|s| Sample::new(s.x + 20.0, s.y + 20.0, 0.0, s.value))
+                .collect::<Vec<_>>();
+            let e = ck.estimate(&[&target, &other], [30.0, 30.0, 0.0]).unwrap();
+            assert!((e.value - 2.0).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn cokriging_exact_at_target_data() {
+        let ck = cokriging();
+        let target = samples(|n| n as f64 - 1.0);
+        let other = samples(|n| (n * n) as f64)
+            .into_iter()
+            .map(|s| Sample::new(s.x + 20.0, s.y + 20.0, 0.0, s.value))
+            .collect::<Vec<_>>();
+        for s in target.iter() {
+            let e = ck.estimate(&[&target, &other], s.position()).unwrap();
+            assert!((e.value - s.value).abs() < 1e-9);
+            assert!(e.variance.abs() < 1e-9);
+        }
+    }
+}
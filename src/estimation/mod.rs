@@ -0,0 +1,107 @@
+pub mod classification;
+pub mod contact;
+pub mod cross_validation;
+pub mod declustering;
+pub mod experimental;
+pub mod idw;
+pub mod kriging;
+pub mod nearest;
+pub mod search;
+pub mod spacing;
+pub mod support;
+pub mod swath;
+pub mod uc;
+pub mod variogram;
+
+use serde::{Deserialize, Serialize};
+
+//point sample of an attribute, e.g. a composited drillhole interval
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Sample {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub value: f64,
+    //drillhole the sample came from, used to limit samples per hole
+    #[serde(default)]
+    pub hole: Option<usize>,
+}
+
+impl Sample {
+    pub fn new(x: f64, y: f64, z: f64, value: f64) -> Self {
+        Self {
+            x,
+            y,
+            z,
+            value,
+            hole: None,
+        }
+    }
+
+    pub fn with_hole(self, hole: usize) -> Self {
+        Self {
+            hole: Some(hole),
+            ..self
+        }
+    }
+
+    pub fn position(&self) -> [f64; 3] {
+        [self.x, self.y, self.z]
+    }
+}
+
+//estimator of an attribute at a point from samples, e.g. for cross validation
+pub trait PointEstimator {
+    //None where the point cannot be estimated
+    fn estimate_at(&self, samples: &[Sample], at: [f64; 3]) -> Option<f64>;
+}
+
+impl PointEstimator for nearest::NearestNeighbor {
+    fn estimate_at(&self, samples: &[Sample], at: [f64; 3]) -> Option<f64> {
+        self.estimate(samples, at)
+    }
+}
+
+impl PointEstimator for idw::InverseDistance {
+    fn estimate_at(&self, samples: &[Sample], at: [f64; 3]) -> Option<f64> {
+        self.estimate(samples, at)
+    }
+}
+
+impl PointEstimator for kriging::OrdinaryKriging {
+    fn estimate_at(&self, samples: &[Sample], at: [f64; 3]) -> Option<f64> {
+        Some(self.estimate(samples, at)?.value)
+    }
+}
+
+//gaussian elimination with partial pivoting, None if the system is singular
+pub(crate) fn solve_linear(mut a: Vec<Vec<f64>>, mut b: Vec<f64>) -> Option<Vec<f64>> {
+    let n = b.len();
+    for col in 0..n {
+        let pivot = (col..n).max_by(|r, s| a[*r][col].abs().total_cmp(&a[*s][col].abs()))?;
+        if a[pivot][col].abs() < 1e-12 {
+            return None;
+        }
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+
+        for row in col + 1..n {
+            let f = a[row][col] / a[col][col];
+            if f == 0.0 {
+                continue;
+            }
+            let (upper, lower) = a.split_at_mut(row);
+            for (x, p) in lower[0][col..].iter_mut().zip(upper[col][col..].iter()) {
+                *x -= f * p;
+            }
+            b[row] -= f * b[col];
+        }
+    }
+
+    let mut x = vec![0.0; n];
+    for row in (0..n).rev() {
+        let rest = (row + 1..n).map(|c| a[row][c] * x[c]).sum::<f64>();
+        x[row] = (b[row] - rest) / a[row][row];
+    }
+    Some(x)
+}
@@ -0,0 +1,75 @@
+use rayon::prelude::*;
+
+use crate::block::{BlockIndex, BlockInterface};
+use crate::block_model::BlockModel;
+use crate::estimation::search::SearchNeighborhood;
+use crate::estimation::Sample;
+use crate::spatial::KdTree;
+use crate::storage::BlockStorage;
+
+use std::collections::HashMap;
+
+//value of the closest selected sample by anisotropic distance, e.g. a polygonal declustered
+//reference model for validating other estimates
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NearestNeighbor {
+    pub search: SearchNeighborhood,
+}
+
+impl NearestNeighbor {
+    pub fn new(search: SearchNeighborhood) -> Self {
+        Self { search }
+    }
+
+    pub fn estimate(&self, samples: &[Sample], at: [f64; 3]) -> Option<f64> {
+        let found = self.search.search(samples, at)?;
+        Some(samples[found.first()?.0].value)
+    }
+
+    //estimate using a tree from SearchNeighborhood::index
+    pub fn estimate_indexed(&self, samples: &[Sample], tree: &KdTree, at: [f64; 3]) -> Option<f64> {
+        let found = self.search.search_indexed(samples, tree, at)?;
+        Some(samples[found.first()?.0].value)
+    }
+}
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface + Sync,
+    S: BlockStorage<B> + Sync,
+{
+    //nearest neighbour estimate at every block centroid, blocks without enough samples are
+    //absent
+    pub fn nn_estimates(
+        &self,
+        samples: &[Sample],
+        nn: &NearestNeighbor,
+    ) -> HashMap<BlockIndex, f64> {
+        let tree = nn.search.index(samples);
+        self.par_indexed_iter()
+            .filter_map(|(ind, _)| {
+                let c = self.index_to_coordinates(ind);
+                let at = [c.x as f64, c.y as f64, c.z as f64];
+                nn.estimate_indexed(samples, &tree, at).map(|v| (ind, v))
+            })
+            .collect()
+    }
+
+    //store nearest neighbour estimates through set, blocks without enough samples are left
+    //unchanged
+    pub fn assign_nn<F>(&mut self, samples: &[Sample], nn: &NearestNeighbor, set: F)
+    where
+        B: Send,
+        F: Fn(&mut B, f64) + Sync,
+    {
+        let frame = *self.frame();
+        let tree = nn.search.index(samples);
+        self.par_indexed_iter_mut().for_each(|(ind, b)| {
+            let c = frame.index_to_coordinates(ind);
+            let at = [c.x as f64, c.y as f64, c.z as f64];
+            if let Some(v) = nn.estimate_indexed(samples, &tree, at) {
+                set(b, v);
+            }
+        });
+    }
+}
@@ -0,0 +1,289 @@
+use serde::Serialize;
+
+use crate::block::{BlockIndex, BlockInterface};
+use crate::block_model::BlockModel;
+use crate::estimation::Sample;
+use crate::spatial::KdTree;
+use crate::storage::BlockStorage;
+
+use rayon::prelude::*;
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::Path;
+
+//anisotropic search ellipsoid, angles in degrees
+//azimuth is the bearing of the major axis clockwise from +y, dip plunges the major axis
+//below horizontal and rake rotates the semi and minor axes about the major axis
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SearchEllipsoid {
+    //major, semi-major and minor radii
+    pub ranges: [f64; 3],
+    pub azimuth: f64,
+    pub dip: f64,
+    pub rake: f64,
+}
+
+impl SearchEllipsoid {
+    pub fn new(ranges: [f64; 3], azimuth: f64, dip: f64, rake: f64) -> Self {
+        assert!(
+            ranges.iter().all(|r| *r > 0.0),
+            "ellipsoid ranges must be positive"
+        );
+        Self {
+            ranges,
+            azimuth,
+            dip,
+            rake,
+        }
+    }
+
+    pub fn isotropic(range: f64) -> Self {
+        Self::new([range; 3], 0.0, 0.0, 0.0)
+    }
+
+    //world directions of the major, semi-major and minor axes
+    pub fn axes(&self) -> [[f64; 3]; 3] {
+        let (sa, ca) = self.azimuth.to_radians().sin_cos();
+        let (sd, cd) = self.dip.to_radians().sin_cos();
+        let (sr, cr) = self.rake.to_radians().sin_cos();
+
+        let major = [sa * cd, ca * cd, -sd];
+        //horizontal axis to the left of the major axis and the axis perpendicular to both
+        let semi = [-ca, sa, 0.0];
+        let minor = [
+            major[1] * semi[2] - major[2] * semi[1],
+            major[2] * semi[0] - major[0] * semi[2],
+            major[0] * semi[1] - major[1] * semi[0],
+        ];
+
+        let mix = |a: [f64; 3], b: [f64; 3], s: f64, c: f64| [0, 1, 2].map(|d| c * a[d] + s * b[d]);
+        [major, mix(semi, minor, sr, cr), mix(minor, semi, -sr, cr)]
+    }
+
+    //offset expressed along the ellipsoid axes and scaled by the ranges, 1 on the surface
+    pub fn normalized_distance(&self, offset: [f64; 3]) -> f64 {
+        self.axes()
+            .iter()
+            .zip(self.ranges.iter())
+            .map(|(axis, r)| {
+                let along = axis[0] * offset[0] + axis[1] * offset[1] + axis[2] * offset[2];
+                (along / r).powi(2)
+            })
+            .sum::<f64>()
+            .sqrt()
+    }
+
+    //normalized distance in units of the major range
+    pub fn anisotropic_distance(&self, offset: [f64; 3]) -> f64 {
+        self.normalized_distance(offset) * self.ranges[0]
+    }
+
+    //octant of an offset in the ellipsoid axes, one bit per axis set when positive along it
+    pub fn octant(&self, offset: [f64; 3]) -> usize {
+        self.axes()
+            .iter()
+            .enumerate()
+            .map(|(n, axis)| {
+                let along = axis[0] * offset[0] + axis[1] * offset[1] + axis[2] * offset[2];
+                ((along > 0.0) as usize) << n
+            })
+            .sum()
+    }
+}
+
+//configuration selecting the samples used to estimate a location, shared by the nearest
+//neighbour, idw, kriging and co-kriging estimators
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SearchNeighborhood {
+    pub ellipsoid: SearchEllipsoid,
+    //locations with fewer samples in the ellipsoid are not estimated
+    pub min_samples: usize,
+    //closest samples kept, by anisotropic distance
+    pub max_samples: usize,
+    //closest samples kept from each octant of the ellipsoid, declusters the selection
+    pub max_per_octant: Option<usize>,
+    //closest samples kept from each drillhole, samples without a hole are not limited
+    pub max_per_hole: Option<usize>,
+}
+
+impl SearchNeighborhood {
+    pub fn new(ellipsoid: SearchEllipsoid, min_samples: usize, max_samples: usize) -> Self {
+        assert!(
+            min_samples <= max_samples,
+            "min_samples must not exceed max_samples"
+        );
+        Self {
+            ellipsoid,
+            min_samples,
+            max_samples,
+            max_per_octant: None,
+            max_per_hole: None,
+        }
+    }
+
+    pub fn with_max_per_octant(self, max: usize) -> Self {
+        Self {
+            max_per_octant: Some(max),
+            ..self
+        }
+    }
+
+    pub fn with_max_per_hole(self, max: usize) -> Self {
+        Self {
+            max_per_hole: Some(max),
+            ..self
+        }
+    }
+
+    //indices of the selected samples with their anisotropic distance, nearest first,
+    //None if fewer than min_samples are inside the ellipsoid, samples with a NaN value are
+    //never selected
+    pub fn search(&self, samples: &[Sample], at: [f64; 3]) -> Option<Vec<(usize, f64)>> {
+        let mut found = samples
+            .iter()
+            .enumerate()
+            .filter_map(|(n, s)| {
+                let offset = [s.x - at[0], s.y - at[1], s.z - at[2]];
+                let h = self.ellipsoid.normalized_distance(offset);
+                (h <= 1.0).then_some((n, h * self.ellipsoid.ranges[0]))
+            })
+            .collect::<Vec<_>>();
+
+        found.sort_by(|a, b| a.1.total_cmp(&b.1));
+        self.select(samples, found, at)
+    }
+
+    //kd-tree over samples measuring distance with the search ellipsoid
+    pub fn index(&self, samples: &[Sample]) -> KdTree {
+        KdTree::from_samples(samples, Some(&self.ellipsoid))
+    }
+
+    //same selection as search using a tree of samples built by index
+    pub fn search_indexed(
+        &self,
+        samples: &[Sample],
+        tree: &KdTree,
+        at: [f64; 3],
+    ) -> Option<Vec<(usize, f64)>> {
+        self.select(samples, tree.within(at, self.ellipsoid.ranges[0]), at)
+    }
+
+    //keep the closest max_samples of samples sorted by distance within the octant and hole
+    //limits
+    fn select(
+        &self,
+        samples: &[Sample],
+        found: Vec<(usize, f64)>,
+        at: [f64; 3],
+    ) -> Option<Vec<(usize, f64)>> {
+        let mut octants = [0; 8];
+        let mut holes: HashMap<usize, usize> = HashMap::new();
+        let mut selected = Vec::with_capacity(self.max_samples.min(found.len()));
+        for (n, d) in found.into_iter() {
+            if selected.len() == self.max_samples {
+                break;
+            }
+            let s = &samples[n];
+            if s.value.is_nan() {
+                continue;
+            }
+            let octant = self
+                .ellipsoid
+                .octant([s.x - at[0], s.y - at[1], s.z - at[2]]);
+            let full_octant = self
+                .max_per_octant
+                .is_some_and(|max| octants[octant] >= max);
+            let full_hole = match (self.max_per_hole, s.hole) {
+                (Some(max), Some(hole)) => holes.get(&hole).is_some_and(|c| *c >= max),
+                _ => false,
+            };
+            if full_octant || full_hole {
+                continue;
+            }
+            octants[octant] += 1;
+            if let Some(hole) = s.hole {
+                *holes.entry(hole).or_default() += 1;
+            }
+            selected.push((n, d));
+        }
+        (selected.len() >= self.min_samples).then_some(selected)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct SearchRecordRow {
+    i: usize,
+    j: usize,
+    k: usize,
+    sample: usize,
+    x: f64,
+    y: f64,
+    z: f64,
+    distance: f64,
+}
+
+//debug record of the samples a neighborhood selects for every block, the same selection the
+//estimators sharing the neighborhood use
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchRecord {
+    //(sample index, anisotropic distance) nearest first, blocks with too few samples are absent
+    pub selected: HashMap<BlockIndex, Vec<(usize, f64)>>,
+}
+
+impl SearchRecord {
+    pub fn samples(&self, ind: BlockIndex) -> Option<&[(usize, f64)]> {
+        self.selected.get(&ind).map(|s| s.as_slice())
+    }
+
+    //one row per block and selected sample, sorted by block index
+    pub fn to_csv<P: AsRef<Path>>(
+        &self,
+        path: P,
+        samples: &[Sample],
+    ) -> Result<(), Box<dyn Error>> {
+        let mut inds = self.selected.keys().copied().collect::<Vec<_>>();
+        inds.sort();
+
+        let mut w = csv::Writer::from_path(path)?;
+        for ind in inds.into_iter() {
+            for (n, distance) in self.selected[&ind].iter() {
+                let s = &samples[*n];
+                w.serialize(SearchRecordRow {
+                    i: ind.i,
+                    j: ind.j,
+                    k: ind.k,
+                    sample: *n,
+                    x: s.x,
+                    y: s.y,
+                    z: s.z,
+                    distance: *distance,
+                })?;
+            }
+        }
+        w.flush()?;
+        Ok(())
+    }
+}
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface + Sync,
+    S: BlockStorage<B> + Sync,
+{
+    //samples informing every block centroid under search
+    pub fn search_record(&self, samples: &[Sample], search: &SearchNeighborhood) -> SearchRecord {
+        let tree = search.index(samples);
+        let selected = self
+            .par_indexed_iter()
+            .filter_map(|(ind, _)| {
+                let c = self.index_to_coordinates(ind);
+                let at = [c.x as f64, c.y as f64, c.z as f64];
+                search
+                    .search_indexed(samples, &tree, at)
+                    .map(|found| (ind, found))
+            })
+            .collect();
+        SearchRecord { selected }
+    }
+}
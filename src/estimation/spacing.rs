@@ -0,0 +1,117 @@
+use crate::block::{BlockIndex, BlockInterface};
+use crate::block_model::BlockModel;
+use crate::estimation::search::SearchEllipsoid;
+use crate::estimation::Sample;
+use crate::spatial::KdTree;
+use crate::storage::BlockStorage;
+
+use rayon::prelude::*;
+
+use std::collections::HashMap;
+use std::f64::consts::PI;
+
+//how the holes around a location are summarized, samples without a hole count as their own
+//hole
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SpacingMeasure {
+    //distinct holes with a sample inside the ellipsoid
+    HoleCount,
+    //mean distance to the closest sample of each of the n closest holes
+    MeanDistance(usize),
+    //side of the square grid with the density of holes found in the plan ellipse of the
+    //major and semi-major ranges, sqrt(pi a b / holes)
+    EquivalentGrid,
+}
+
+//drill spacing around block centroids from composites, e.g. for spacing based classification
+//rules or infill planning
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DrillSpacing {
+    pub ellipsoid: SearchEllipsoid,
+    pub measure: SpacingMeasure,
+}
+
+impl DrillSpacing {
+    pub fn new(ellipsoid: SearchEllipsoid, measure: SpacingMeasure) -> Self {
+        if let SpacingMeasure::MeanDistance(n) = measure {
+            assert!(n > 0, "at least one hole is needed");
+        }
+        Self { ellipsoid, measure }
+    }
+
+    //kd-tree over samples measuring distance with the ellipsoid
+    pub fn index(&self, samples: &[Sample]) -> KdTree {
+        KdTree::from_samples(samples, Some(&self.ellipsoid))
+    }
+
+    //measure at a location using a tree built by index, None without a hole in the ellipsoid
+    //or with fewer holes than a mean distance needs
+    pub fn spacing(&self, samples: &[Sample], tree: &KdTree, at: [f64; 3]) -> Option<f64> {
+        //closest euclidean distance per hole
+        let mut holes: HashMap<Result<usize, usize>, f64> = HashMap::new();
+        for (n, _) in tree.within(at, self.ellipsoid.ranges[0]) {
+            let s = &samples[n];
+            let d = (0..3)
+                .map(|d| (s.position()[d] - at[d]).powi(2))
+                .sum::<f64>()
+                .sqrt();
+            let closest = holes.entry(s.hole.ok_or(n)).or_insert(f64::INFINITY);
+            *closest = closest.min(d);
+        }
+        if holes.is_empty() {
+            return None;
+        }
+
+        match self.measure {
+            SpacingMeasure::HoleCount => Some(holes.len() as f64),
+            SpacingMeasure::MeanDistance(n) => {
+                if holes.len() < n {
+                    return None;
+                }
+                let mut distances = holes.into_values().collect::<Vec<_>>();
+                distances.sort_by(|a, b| a.total_cmp(b));
+                Some(distances[..n].iter().sum::<f64>() / n as f64)
+            }
+            SpacingMeasure::EquivalentGrid => {
+                let [a, b, _] = self.ellipsoid.ranges;
+                Some((PI * a * b / holes.len() as f64).sqrt())
+            }
+        }
+    }
+}
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface + Sync,
+    S: BlockStorage<B> + Sync,
+{
+    //spacing at every block centroid, blocks without a measure are absent
+    pub fn drill_spacing(
+        &self,
+        samples: &[Sample],
+        spacing: &DrillSpacing,
+    ) -> HashMap<BlockIndex, f64> {
+        let tree = spacing.index(samples);
+        self.par_indexed_iter()
+            .filter_map(|(ind, _)| {
+                let c = self.index_to_coordinates(ind);
+                let at = [c.x as f64, c.y as f64, c.z as f64];
+                spacing.spacing(samples, &tree, at).map(|s| (ind, s))
+            })
+            .collect()
+    }
+
+    //store the spacing of every block with a measure through set
+    pub fn assign_drill_spacing<F>(&mut self, samples: &[Sample], spacing: &DrillSpacing, set: F)
+    where
+        B: Send,
+        F: Fn(&mut B, f64) + Sync,
+    {
+        let spacings = self.drill_spacing(samples, spacing);
+        self.par_indexed_iter_mut().for_each(|(ind, b)| {
+            if let Some(s) = spacings.get(&ind) {
+                set(b, *s);
+            }
+        });
+    }
+}
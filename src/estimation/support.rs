@@ -0,0 +1,125 @@
+use crate::cutoff::GradeTonnage;
+use crate::estimation::variogram::VariogramModel;
+use crate::estimation::Sample;
+
+//how the point distribution is shrunk towards its mean
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SupportCorrection {
+    //z_v = m + sqrt(f) (z - m), keeps the shape of the distribution
+    Affine,
+    //z_v = a z^b, keeps a lognormal shape and positive values, rescaled to keep the mean
+    IndirectLognormal,
+}
+
+//point to block support correction of a distribution, f is the ratio of the block variance
+//to the point variance
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChangeOfSupport {
+    pub method: SupportCorrection,
+    pub variance_ratio: f64,
+}
+
+//weighted mean and variance of the non NaN values
+fn moments(values: &[(f64, f64)]) -> (f64, f64) {
+    let valid = || values.iter().filter(|(v, _)| !v.is_nan());
+    let total = valid().map(|(_, w)| w).sum::<f64>();
+    let mean = valid().map(|(v, w)| v * w).sum::<f64>() / total;
+    let variance = valid().map(|(v, w)| w * (v - mean).powi(2)).sum::<f64>() / total;
+    (mean, variance)
+}
+
+impl ChangeOfSupport {
+    pub fn new(method: SupportCorrection, variance_ratio: f64) -> Self {
+        assert!(
+            variance_ratio > 0.0 && variance_ratio <= 1.0,
+            "variance ratio must be in (0, 1]"
+        );
+        Self {
+            method,
+            variance_ratio,
+        }
+    }
+
+    //ratio from the block variance of a point variogram, whose sill is the point variance,
+    //for blocks of size discretized into discretization points
+    pub fn from_variogram(
+        method: SupportCorrection,
+        variogram: &VariogramModel,
+        size: [f64; 3],
+        discretization: [usize; 3],
+    ) -> Self {
+        let f = variogram.block_variance(size, discretization) / variogram.sill();
+        Self::new(method, f)
+    }
+
+    //block support values of weighted point values, e.g. declustered composites, NaN values
+    //stay NaN, indirect lognormal values must not be negative
+    pub fn correct(&self, values: &[(f64, f64)]) -> Vec<f64> {
+        let (mean, variance) = moments(values);
+        let f = self.variance_ratio;
+        match self.method {
+            SupportCorrection::Affine => values
+                .iter()
+                .map(|(v, _)| mean + f.sqrt() * (v - mean))
+                .collect(),
+            SupportCorrection::IndirectLognormal => {
+                let cv2 = variance / (mean * mean);
+                let b = ((f * cv2 + 1.0).ln() / (cv2 + 1.0).ln()).sqrt();
+                let a = mean / (f * cv2 + 1.0).sqrt() * ((cv2 + 1.0).sqrt() / mean).powf(b);
+                let corrected = values
+                    .iter()
+                    .map(|(v, _)| a * v.powf(b))
+                    .collect::<Vec<_>>();
+
+                let (corrected_mean, _) = moments(
+                    &corrected
+                        .iter()
+                        .zip(values.iter())
+                        .map(|(c, (_, w))| (*c, *w))
+                        .collect::<Vec<_>>(),
+                );
+                corrected
+                    .into_iter()
+                    .map(|c| c * mean / corrected_mean)
+                    .collect()
+            }
+        }
+    }
+
+    //samples with block support values, weights default to one
+    pub fn correct_samples(&self, samples: &[Sample], weights: Option<&[f64]>) -> Vec<Sample> {
+        if let Some(weights) = weights {
+            assert!(
+                weights.len() == samples.len(),
+                "one weight is needed per sample"
+            );
+        }
+        let values = samples
+            .iter()
+            .enumerate()
+            .map(|(n, s)| (s.value, weights.map_or(1.0, |w| w[n])))
+            .collect::<Vec<_>>();
+        samples
+            .iter()
+            .zip(self.correct(&values))
+            .map(|(s, v)| Sample { value: v, ..*s })
+            .collect()
+    }
+
+    //grade tonnage curve of the corrected distribution, total tonnage is shared by weight
+    pub fn grade_tonnage(&self, values: &[(f64, f64)], tonnage: f64) -> GradeTonnage {
+        let total = values
+            .iter()
+            .filter(|(v, _)| !v.is_nan())
+            .map(|(_, w)| w)
+            .sum::<f64>();
+        GradeTonnage::new(
+            self.correct(values)
+                .into_iter()
+                .zip(values.iter())
+                .filter(|(c, _)| !c.is_nan())
+                .map(|(c, (_, w))| (c, w * tonnage / total))
+                .collect(),
+        )
+    }
+}
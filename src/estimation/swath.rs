@@ -0,0 +1,172 @@
+use serde::Serialize;
+
+use crate::block::BlockInterface;
+use crate::block_model::BlockModel;
+use crate::estimation::Sample;
+use crate::storage::BlockStorage;
+
+use std::error::Error;
+use std::path::Path;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SwathAxis {
+    X,
+    Y,
+    Z,
+}
+
+impl SwathAxis {
+    fn component(&self, p: [f64; 3]) -> f64 {
+        match self {
+            SwathAxis::X => p[0],
+            SwathAxis::Y => p[1],
+            SwathAxis::Z => p[2],
+        }
+    }
+}
+
+//one slice of a swath, means are NaN for slices without values
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SwathBin {
+    pub from: f64,
+    pub to: f64,
+    pub model_count: usize,
+    pub model_mean: f64,
+    pub reference_count: usize,
+    pub reference_mean: f64,
+}
+
+//mean of a model attribute and of a reference, samples or another model, in consecutive
+//world slices along an axis
+#[derive(Debug, Clone, PartialEq)]
+pub struct Swath {
+    pub axis: SwathAxis,
+    pub width: f64,
+    pub bins: Vec<SwathBin>,
+}
+
+impl Swath {
+    //slices aligned to multiples of width covering every value, NaN values are skipped
+    fn new(
+        axis: SwathAxis,
+        width: f64,
+        model: Vec<(f64, f64)>,
+        reference: Vec<(f64, f64)>,
+    ) -> Self {
+        assert!(width > 0.0, "swath width must be positive");
+
+        let valid = |v: &(f64, f64)| v.0.is_finite() && !v.1.is_nan();
+        let model = model.into_iter().filter(valid).collect::<Vec<_>>();
+        let reference = reference.into_iter().filter(valid).collect::<Vec<_>>();
+        let (lo, hi) = model
+            .iter()
+            .chain(reference.iter())
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), (c, _)| {
+                (lo.min(*c), hi.max(*c))
+            });
+        if lo > hi {
+            return Self {
+                axis,
+                width,
+                bins: Vec::new(),
+            };
+        }
+
+        let first = (lo / width).floor();
+        let num_bins = ((hi / width).floor() - first) as usize + 1;
+        let bin = |c: f64| (((c / width).floor() - first) as usize).min(num_bins - 1);
+        let sums = |values: &[(f64, f64)]| {
+            let mut sums = vec![(0, 0.0); num_bins];
+            for (c, v) in values.iter() {
+                let s = &mut sums[bin(*c)];
+                s.0 += 1;
+                s.1 += v;
+            }
+            sums
+        };
+        let mean = |(count, sum): (usize, f64)| {
+            if count > 0 {
+                sum / count as f64
+            } else {
+                f64::NAN
+            }
+        };
+
+        let bins = sums(&model)
+            .into_iter()
+            .zip(sums(&reference))
+            .enumerate()
+            .map(|(n, (m, r))| SwathBin {
+                from: (first + n as f64) * width,
+                to: (first + n as f64 + 1.0) * width,
+                model_count: m.0,
+                model_mean: mean(m),
+                reference_count: r.0,
+                reference_mean: mean(r),
+            })
+            .collect();
+        Self { axis, width, bins }
+    }
+
+    //one row per slice
+    pub fn to_csv<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn Error>> {
+        let mut w = csv::Writer::from_path(path)?;
+        for bin in self.bins.iter() {
+            w.serialize(bin)?;
+        }
+        w.flush()?;
+        Ok(())
+    }
+}
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface,
+    S: BlockStorage<B>,
+{
+    //(axis coordinate, value) of every present block at its centroid
+    fn swath_values<F: Fn(&B) -> f64>(&self, axis: SwathAxis, attr: F) -> Vec<(f64, f64)> {
+        self.indexed_iter()
+            .map(|(ind, b)| {
+                let c = self.index_to_coordinates(ind);
+                let p = [c.x as f64, c.y as f64, c.z as f64];
+                (axis.component(p), attr(b))
+            })
+            .collect()
+    }
+
+    //block means against sample means, e.g. estimates against the composites they came from
+    pub fn swath<F>(&self, axis: SwathAxis, width: f64, attr: F, samples: &[Sample]) -> Swath
+    where
+        F: Fn(&B) -> f64,
+    {
+        let reference = samples
+            .iter()
+            .map(|s| (axis.component(s.position()), s.value))
+            .collect();
+        Swath::new(axis, width, self.swath_values(axis, attr), reference)
+    }
+
+    //block means against the block means of another model, e.g. two estimation runs
+    pub fn swath_against<F, B2, S2, G>(
+        &self,
+        axis: SwathAxis,
+        width: f64,
+        attr: F,
+        other: &BlockModel<B2, S2>,
+        other_attr: G,
+    ) -> Swath
+    where
+        F: Fn(&B) -> f64,
+        B2: BlockInterface,
+        S2: BlockStorage<B2>,
+        G: Fn(&B2) -> f64,
+    {
+        Swath::new(
+            axis,
+            width,
+            self.swath_values(axis, attr),
+            other.swath_values(axis, other_attr),
+        )
+    }
+}
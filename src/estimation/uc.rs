@@ -0,0 +1,412 @@
+use crate::block::{BlockIndex, BlockInterface};
+use crate::block_model::BlockModel;
+use crate::estimation::Sample;
+use crate::storage::BlockStorage;
+
+use std::collections::HashMap;
+
+//standard normal distribution function, relative error below 1.2e-7
+pub(crate) fn normal_cdf(x: f64) -> f64 {
+    let z = x.abs() / std::f64::consts::SQRT_2;
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let poly = -z * z - 1.265_512_23
+        + t * (1.000_023_68
+            + t * (0.374_091_96
+                + t * (0.096_784_18
+                    + t * (-0.186_288_06
+                        + t * (0.278_868_07
+                            + t * (-1.135_203_98
+                                + t * (1.488_515_87 + t * (-0.822_152_23 + t * 0.170_872_77))))))));
+    let tail = 0.5 * t * poly.exp();
+    if x >= 0.0 {
+        1.0 - tail
+    } else {
+        tail
+    }
+}
+
+//quantile of the standard normal distribution, rational approximation with relative error
+//below 1.2e-9, infinite at 0 and 1
+pub(crate) fn normal_quantile(p: f64) -> f64 {
+    const A: [f64; 6] = [
+        -3.969_683_028_665_376e1,
+        2.209_460_984_245_205e2,
+        -2.759_285_104_469_687e2,
+        1.383_577_518_672_69e2,
+        -3.066_479_806_614_716e1,
+        2.506_628_277_459_239,
+    ];
+    const B: [f64; 5] = [
+        -5.447_609_879_822_406e1,
+        1.615_858_368_580_409e2,
+        -1.556_989_798_598_866e2,
+        6.680_131_188_771_972e1,
+        -1.328_068_155_288_572e1,
+    ];
+    const C: [f64; 6] = [
+        -7.784_894_002_430_293e-3,
+        -3.223_964_580_411_365e-1,
+        -2.400_758_277_161_838,
+        -2.549_732_539_343_734,
+        4.374_664_141_464_968,
+        2.938_163_982_698_783,
+    ];
+    const D: [f64; 4] = [
+        7.784_695_709_041_462e-3,
+        3.224_671_290_700_398e-1,
+        2.445_134_137_142_996,
+        3.754_408_661_907_416,
+    ];
+
+    if p <= 0.0 {
+        return f64::NEG_INFINITY;
+    }
+    if p >= 1.0 {
+        return f64::INFINITY;
+    }
+    let tail = |q: f64| {
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    };
+    if p < 0.024_25 {
+        tail((-2.0 * p.ln()).sqrt())
+    } else if p > 1.0 - 0.024_25 {
+        -tail((-2.0 * (1.0 - p).ln()).sqrt())
+    } else {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+    }
+}
+
+//gaussian values beyond which the distributions are truncated
+const GAUSSIAN_LIMIT: f64 = 6.0;
+//steps of the tabulated transforms and of the integrals over gaussian values
+const GAUSSIAN_STEPS: usize = 600;
+
+fn gaussian_nodes() -> impl Iterator<Item = f64> {
+    let step = 2.0 * GAUSSIAN_LIMIT / GAUSSIAN_STEPS as f64;
+    (0..=GAUSSIAN_STEPS).map(move |n| -GAUSSIAN_LIMIT + n as f64 * step)
+}
+
+fn normal_density(u: f64) -> f64 {
+    (-0.5 * u * u).exp() / (2.0 * std::f64::consts::PI).sqrt()
+}
+
+//trapezoid integral of f against the normal density over lo..hi
+fn integrate_gaussian<F: Fn(f64) -> f64>(lo: f64, hi: f64, f: F) -> f64 {
+    let (lo, hi) = (lo.max(-GAUSSIAN_LIMIT), hi.min(GAUSSIAN_LIMIT));
+    if lo >= hi {
+        return 0.0;
+    }
+    let step = (hi - lo) / GAUSSIAN_STEPS as f64;
+    (0..=GAUSSIAN_STEPS)
+        .map(|n| {
+            let u = lo + n as f64 * step;
+            let w = if n == 0 || n == GAUSSIAN_STEPS {
+                0.5
+            } else {
+                1.0
+            };
+            w * f(u) * normal_density(u)
+        })
+        .sum::<f64>()
+        * step
+}
+
+//empirical transform between point values and standard normal scores, values are linear
+//between scores and constant beyond the extreme data
+#[derive(Debug, Clone, PartialEq)]
+pub struct GaussianAnamorphosis {
+    values: Vec<f64>,
+    scores: Vec<f64>,
+}
+
+impl GaussianAnamorphosis {
+    //scores at the midpoint of the cumulative weight of each value, e.g. declustering
+    //weights, NaN values and non positive weights are skipped
+    pub fn new(values: &[(f64, f64)]) -> Self {
+        let mut values = values
+            .iter()
+            .filter(|(v, w)| !v.is_nan() && *w > 0.0)
+            .copied()
+            .collect::<Vec<_>>();
+        assert!(!values.is_empty(), "anamorphosis needs at least one value");
+        values.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let total = values.iter().map(|(_, w)| w).sum::<f64>();
+        let mut cumulative = 0.0;
+        let scores = values
+            .iter()
+            .map(|(_, w)| {
+                cumulative += w;
+                normal_quantile((cumulative - w / 2.0) / total)
+            })
+            .collect();
+        Self {
+            values: values.into_iter().map(|(v, _)| v).collect(),
+            scores,
+        }
+    }
+
+    pub fn from_samples(samples: &[Sample], weights: Option<&[f64]>) -> Self {
+        Self::new(
+            &samples
+                .iter()
+                .enumerate()
+                .map(|(n, s)| (s.value, weights.map_or(1.0, |w| w[n])))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    //linear interpolation of to at the position of x in the increasing from
+    fn interpolate(from: &[f64], to: &[f64], x: f64) -> f64 {
+        let n = from.partition_point(|f| *f < x);
+        if n == 0 {
+            return to[0];
+        }
+        if n == from.len() {
+            return to[n - 1];
+        }
+        let span = from[n] - from[n - 1];
+        if span <= 0.0 {
+            return to[n];
+        }
+        to[n - 1] + (x - from[n - 1]) / span * (to[n] - to[n - 1])
+    }
+
+    //point value of a gaussian score
+    pub fn value(&self, y: f64) -> f64 {
+        Self::interpolate(&self.scores, &self.values, y)
+    }
+
+    //gaussian score of a point value
+    pub fn score(&self, z: f64) -> f64 {
+        Self::interpolate(&self.values, &self.scores, z)
+    }
+
+    //transform of values averaged at the support with change of support coefficient r under
+    //the discrete gaussian model, the expected point value given the support score
+    fn support_value(&self, r: f64, y: f64) -> f64 {
+        let spread = (1.0 - r * r).max(0.0).sqrt();
+        integrate_gaussian(f64::NEG_INFINITY, f64::INFINITY, |u| {
+            self.value(r * y + spread * u)
+        })
+    }
+
+    //variance of the values at the support with coefficient r
+    fn support_variance(&self, r: f64) -> f64 {
+        let table = SupportTable::new(self, r);
+        let mean = integrate_gaussian(f64::NEG_INFINITY, f64::INFINITY, |y| table.value(y));
+        integrate_gaussian(f64::NEG_INFINITY, f64::INFINITY, |y| {
+            (table.value(y) - mean).powi(2)
+        })
+    }
+
+    //change of support coefficient whose support variance is variance, found by bisection
+    pub fn support_coefficient(&self, variance: f64) -> f64 {
+        let (mut lo, mut hi) = (0.0, 1.0);
+        for _ in 0..40 {
+            let mid = 0.5 * (lo + hi);
+            if self.support_variance(mid) < variance {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+        0.5 * (lo + hi)
+    }
+}
+
+//support transform tabulated on the gaussian nodes
+#[derive(Debug, Clone, PartialEq)]
+struct SupportTable {
+    scores: Vec<f64>,
+    values: Vec<f64>,
+}
+
+impl SupportTable {
+    fn new(anamorphosis: &GaussianAnamorphosis, r: f64) -> Self {
+        let scores = gaussian_nodes().collect::<Vec<_>>();
+        let values = scores
+            .iter()
+            .map(|y| anamorphosis.support_value(r, *y))
+            .collect();
+        Self { scores, values }
+    }
+
+    fn value(&self, y: f64) -> f64 {
+        GaussianAnamorphosis::interpolate(&self.scores, &self.values, y)
+    }
+
+    fn score(&self, z: f64) -> f64 {
+        GaussianAnamorphosis::interpolate(&self.values, &self.scores, z)
+    }
+}
+
+//recoverable resources of one panel at a cutoff as fractions per panel tonne, grade is NaN
+//without tonnage
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RecoverableResource {
+    pub cutoff: f64,
+    pub tonnage: f64,
+    pub metal: f64,
+    pub grade: f64,
+}
+
+//uniform conditioning of panel estimates to selective mining unit support, r is the change
+//of support coefficient of the smu and s that of the kriged panel estimates
+#[derive(Debug, Clone, PartialEq)]
+pub struct UniformConditioning {
+    pub anamorphosis: GaussianAnamorphosis,
+    pub smu_coefficient: f64,
+    pub panel_coefficient: f64,
+    smu: SupportTable,
+    panel: SupportTable,
+}
+
+impl UniformConditioning {
+    //coefficients from the smu block variance, e.g. VariogramModel::block_variance, and the
+    //variance of the kriged panel estimates
+    pub fn new(anamorphosis: GaussianAnamorphosis, smu_variance: f64, panel_variance: f64) -> Self {
+        assert!(
+            panel_variance <= smu_variance,
+            "panel estimate variance must not exceed the smu variance"
+        );
+        let r = anamorphosis.support_coefficient(smu_variance);
+        let s = anamorphosis.support_coefficient(panel_variance);
+        Self::with_coefficients(anamorphosis, r, s)
+    }
+
+    pub fn with_coefficients(anamorphosis: GaussianAnamorphosis, r: f64, s: f64) -> Self {
+        assert!(
+            0.0 < s && s <= r && r <= 1.0,
+            "coefficients must satisfy 0 < s <= r <= 1"
+        );
+        Self {
+            smu: SupportTable::new(&anamorphosis, r),
+            panel: SupportTable::new(&anamorphosis, s),
+            anamorphosis,
+            smu_coefficient: r,
+            panel_coefficient: s,
+        }
+    }
+
+    //smu gaussian given the panel estimate is centre + spread u for a standard normal u, the
+    //correlation s / r is kept below one so the conditional distribution does not collapse
+    fn conditional(&self, estimate: f64) -> (f64, f64) {
+        let rho = (self.panel_coefficient / self.smu_coefficient).min(1.0 - 1e-6);
+        (rho * self.panel.score(estimate), (1.0 - rho * rho).sqrt())
+    }
+
+    //smu tonnage fraction and metal in the slice of the conditional distribution u in lo..hi
+    fn slice(&self, estimate: f64, lo: f64, hi: f64) -> (f64, f64) {
+        let (centre, spread) = self.conditional(estimate);
+        let tonnage = normal_cdf(hi.min(GAUSSIAN_LIMIT)) - normal_cdf(lo.max(-GAUSSIAN_LIMIT));
+        let metal = integrate_gaussian(lo, hi, |u| self.smu.value(centre + spread * u));
+        (tonnage.max(0.0), metal)
+    }
+
+    //tonnage, metal and grade of the smus above each cutoff inside a panel
+    pub fn panel(&self, estimate: f64, cutoffs: &[f64]) -> Vec<RecoverableResource> {
+        let (centre, spread) = self.conditional(estimate);
+        cutoffs
+            .iter()
+            .map(|cutoff| {
+                let lo = (self.smu.score(*cutoff) - centre) / spread;
+                let (tonnage, metal) = self.slice(estimate, lo, f64::INFINITY);
+                RecoverableResource {
+                    cutoff: *cutoff,
+                    tonnage,
+                    metal,
+                    grade: metal / tonnage,
+                }
+            })
+            .collect()
+    }
+
+    //grades of num_smus equal tonnage smus of a panel, richest first, the mean of each
+    //ranked slice of the conditional smu distribution
+    pub fn localize(&self, estimate: f64, num_smus: usize) -> Vec<f64> {
+        let (centre, spread) = self.conditional(estimate);
+        let bound = |k: usize| match k {
+            0 => f64::INFINITY,
+            k if k == num_smus => f64::NEG_INFINITY,
+            k => normal_quantile(1.0 - k as f64 / num_smus as f64),
+        };
+        (0..num_smus)
+            .map(|k| {
+                let (tonnage, metal) = self.slice(estimate, bound(k + 1), bound(k));
+                if tonnage > 0.0 {
+                    metal / tonnage
+                } else {
+                    //slice beyond the truncated tails
+                    let u = bound(k + 1).clamp(-GAUSSIAN_LIMIT, GAUSSIAN_LIMIT);
+                    self.smu.value(centre + spread * u)
+                }
+            })
+            .collect()
+    }
+}
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface,
+    S: BlockStorage<B>,
+{
+    //recoverable resources above each cutoff of every panel of self from its kriged
+    //estimate, panels with a NaN estimate are absent
+    pub fn uniform_conditioning<E>(
+        &self,
+        estimate: E,
+        uc: &UniformConditioning,
+        cutoffs: &[f64],
+    ) -> HashMap<BlockIndex, Vec<RecoverableResource>>
+    where
+        E: Fn(&B) -> f64,
+    {
+        self.indexed_iter()
+            .filter_map(|(ind, b)| {
+                let e = estimate(b);
+                (!e.is_nan()).then(|| (ind, uc.panel(e, cutoffs)))
+            })
+            .collect()
+    }
+
+    //localized uniform conditioning, the smus of self in each panel, by centroid, are ranked
+    //by rank, e.g. a direct smu kriging, and take the grade of the matching slice of the
+    //panel distribution, smus outside panels or in panels with a NaN estimate are absent
+    pub fn localized_uniform_conditioning<B2, S2, E, R>(
+        &self,
+        panels: &BlockModel<B2, S2>,
+        estimate: E,
+        rank: R,
+        uc: &UniformConditioning,
+    ) -> HashMap<BlockIndex, f64>
+    where
+        B2: BlockInterface,
+        S2: BlockStorage<B2>,
+        E: Fn(&B2) -> f64,
+        R: Fn(&B) -> f64,
+    {
+        let mut members: HashMap<BlockIndex, Vec<(f64, BlockIndex)>> = HashMap::new();
+        for (ind, b) in self.indexed_iter() {
+            if let Some(panel) = panels.frame().index_from(self.frame(), ind) {
+                members.entry(panel).or_default().push((rank(b), ind));
+            }
+        }
+
+        let mut grades = HashMap::new();
+        for (panel, mut smus) in members.into_iter() {
+            let Some(e) = panels.block(panel).map(&estimate).filter(|e| !e.is_nan()) else {
+                continue;
+            };
+            smus.sort_by(|a, b| b.0.total_cmp(&a.0));
+            for ((_, ind), g) in smus.iter().zip(uc.localize(e, smus.len())) {
+                grades.insert(*ind, g);
+            }
+        }
+        grades
+    }
+}
@@ -0,0 +1,109 @@
+use crate::estimation::search::SearchEllipsoid;
+
+//shape of a nested variogram structure
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StructureType {
+    Spherical,
+    //practical range, reaches 95% of the sill at the range
+    Exponential,
+    //practical range, reaches 95% of the sill at the range
+    Gaussian,
+}
+
+//single nested structure, ranges and orientation come from the anisotropy ellipsoid
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VariogramStructure {
+    pub kind: StructureType,
+    //sill contribution of the structure
+    pub sill: f64,
+    pub anisotropy: SearchEllipsoid,
+}
+
+impl VariogramStructure {
+    pub fn new(kind: StructureType, sill: f64, anisotropy: SearchEllipsoid) -> Self {
+        Self {
+            kind,
+            sill,
+            anisotropy,
+        }
+    }
+
+    pub fn gamma(&self, offset: [f64; 3]) -> f64 {
+        //lag measured in ranges
+        let h = self.anisotropy.normalized_distance(offset);
+        let shape = match self.kind {
+            StructureType::Spherical if h >= 1.0 => 1.0,
+            StructureType::Spherical => 1.5 * h - 0.5 * h.powi(3),
+            StructureType::Exponential => 1.0 - (-3.0 * h).exp(),
+            StructureType::Gaussian => 1.0 - (-3.0 * h * h).exp(),
+        };
+        self.sill * shape
+    }
+}
+
+//nugget plus nested structures
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct VariogramModel {
+    pub nugget: f64,
+    pub structures: Vec<VariogramStructure>,
+}
+
+impl VariogramModel {
+    pub fn new(nugget: f64, structures: Vec<VariogramStructure>) -> Self {
+        assert!(nugget >= 0.0, "nugget must not be negative");
+        Self { nugget, structures }
+    }
+
+    //total sill, the covariance at zero lag
+    pub fn sill(&self) -> f64 {
+        self.nugget + self.structures.iter().map(|s| s.sill).sum::<f64>()
+    }
+
+    //semivariance at a lag vector, zero at zero lag
+    pub fn gamma(&self, offset: [f64; 3]) -> f64 {
+        if offset == [0.0; 3] {
+            return 0.0;
+        }
+        self.nugget + self.structures.iter().map(|s| s.gamma(offset)).sum::<f64>()
+    }
+
+    pub fn covariance(&self, offset: [f64; 3]) -> f64 {
+        self.sill() - self.gamma(offset)
+    }
+
+    //mean semivariance between the points of a regular discretization of a block, the
+    //gamma bar of the block with itself, the nugget is counted in full as it acts below the
+    //discretization scale
+    pub fn average_gamma(&self, size: [f64; 3], discretization: [usize; 3]) -> f64 {
+        assert!(
+            discretization.iter().all(|n| *n > 0),
+            "discretization must have at least one point per axis"
+        );
+        let mut points = Vec::new();
+        for a in 0..discretization[0] {
+            for b in 0..discretization[1] {
+                for c in 0..discretization[2] {
+                    let at = [a, b, c];
+                    points.push([0, 1, 2].map(|d| {
+                        ((at[d] as f64 + 0.5) / discretization[d] as f64 - 0.5) * size[d]
+                    }));
+                }
+            }
+        }
+
+        let mut sum = 0.0;
+        for p in points.iter() {
+            for q in points.iter() {
+                let offset = [p[0] - q[0], p[1] - q[1], p[2] - q[2]];
+                sum += self.structures.iter().map(|s| s.gamma(offset)).sum::<f64>();
+            }
+        }
+        self.nugget + sum / (points.len() * points.len()) as f64
+    }
+
+    //variance of block values of the given size within the deposit, the sill less the
+    //block gamma bar
+    pub fn block_variance(&self, size: [f64; 3], discretization: [usize; 3]) -> f64 {
+        self.sill() - self.average_gamma(size, discretization)
+    }
+}
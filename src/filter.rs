@@ -0,0 +1,183 @@
+use ndarray::Array3;
+use rayon::prelude::*;
+
+use crate::block::{BlockIndex, BlockInterface};
+use crate::block_model::BlockModel;
+use crate::morphology::StructuringElement;
+use crate::storage::BlockStorage;
+
+use std::collections::HashMap;
+
+//statistic of the values in the window
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WindowStatistic {
+    Mean,
+    Median,
+    Min,
+    Max,
+    //mean weighted by a gaussian of the offset, standard deviations in world units per axis
+    Gaussian([f64; 3]),
+}
+
+//how offsets falling beyond the lattice are treated, absent blocks and NaN values are always
+//skipped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EdgeMode {
+    //dropped, the window shrinks at the edge
+    #[default]
+    Shrink,
+    //read from the nearest cell on the edge
+    Clamp,
+    //mirrored about the edge cell
+    Reflect,
+}
+
+//moving window over an attribute, e.g. to smooth simulated grades or build a trend model
+#[derive(Debug, Clone, PartialEq)]
+pub struct MovingWindow {
+    pub element: StructuringElement,
+    pub statistic: WindowStatistic,
+    pub edge: EdgeMode,
+}
+
+//position along an axis of n cells, None when dropped
+fn edge_position(p: i64, n: usize, edge: EdgeMode) -> Option<usize> {
+    let n = n as i64;
+    if (0..n).contains(&p) {
+        return Some(p as usize);
+    }
+    match edge {
+        EdgeMode::Shrink => None,
+        EdgeMode::Clamp => Some(p.clamp(0, n - 1) as usize),
+        EdgeMode::Reflect => {
+            if n == 1 {
+                return Some(0);
+            }
+            //period of the mirrored sequence 0..n-1..1
+            let period = 2 * (n - 1);
+            let m = p.rem_euclid(period);
+            Some((if m < n { m } else { period - m }) as usize)
+        }
+    }
+}
+
+impl MovingWindow {
+    pub fn new(element: StructuringElement, statistic: WindowStatistic) -> Self {
+        if let WindowStatistic::Gaussian(sigma) = statistic {
+            assert!(
+                sigma.iter().all(|s| *s > 0.0),
+                "gaussian standard deviations must be positive"
+            );
+        }
+        Self {
+            element,
+            statistic,
+            edge: EdgeMode::default(),
+        }
+    }
+
+    pub fn with_edge(self, edge: EdgeMode) -> Self {
+        Self { edge, ..self }
+    }
+
+    //statistic of (value, weight) pairs, NaN if empty
+    fn evaluate(&self, values: &mut [(f64, f64)]) -> f64 {
+        if values.is_empty() {
+            return f64::NAN;
+        }
+        match self.statistic {
+            WindowStatistic::Mean => {
+                values.iter().map(|(v, _)| v).sum::<f64>() / values.len() as f64
+            }
+            WindowStatistic::Gaussian(_) => {
+                let total = values.iter().map(|(_, w)| w).sum::<f64>();
+                values.iter().map(|(v, w)| v * w).sum::<f64>() / total
+            }
+            WindowStatistic::Median => {
+                values.sort_by(|a, b| a.0.total_cmp(&b.0));
+                let n = values.len();
+                if n % 2 == 1 {
+                    values[n / 2].0
+                } else {
+                    (values[n / 2 - 1].0 + values[n / 2].0) / 2.0
+                }
+            }
+            WindowStatistic::Min => values.iter().map(|(v, _)| *v).fold(f64::INFINITY, f64::min),
+            WindowStatistic::Max => values
+                .iter()
+                .map(|(v, _)| *v)
+                .fold(f64::NEG_INFINITY, f64::max),
+        }
+    }
+}
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface + Sync,
+    S: BlockStorage<B> + Sync,
+{
+    //window statistic of attr around every present block, NaN where the window holds no
+    //valid value
+    pub fn moving_window<F>(&self, attr: F, window: &MovingWindow) -> HashMap<BlockIndex, f64>
+    where
+        F: Fn(&B) -> f64,
+    {
+        let dims = self.dims();
+        let mut values = Array3::from_elem(dims, f64::NAN);
+        for (ind, b) in self.indexed_iter() {
+            values[[ind.i, ind.j, ind.k]] = attr(b);
+        }
+
+        let size = self.block_size();
+        let size = [size.x_size, size.y_size, size.z_size].map(|s| s as f64);
+        let weights = window
+            .element
+            .offsets()
+            .iter()
+            .map(|o| match window.statistic {
+                WindowStatistic::Gaussian(sigma) => {
+                    let q = (0..3)
+                        .map(|a| (o[a] as f64 * size[a] / sigma[a]).powi(2))
+                        .sum::<f64>();
+                    (-0.5 * q).exp()
+                }
+                _ => 1.0,
+            })
+            .collect::<Vec<_>>();
+
+        self.par_indexed_iter()
+            .map(|(ind, _)| {
+                let at = [ind.i, ind.j, ind.k].map(|p| p as i64);
+                let mut found = window
+                    .element
+                    .offsets()
+                    .iter()
+                    .zip(weights.iter())
+                    .filter_map(|(o, w)| {
+                        let i = edge_position(at[0] + o[0], dims[0], window.edge)?;
+                        let j = edge_position(at[1] + o[1], dims[1], window.edge)?;
+                        let k = edge_position(at[2] + o[2], dims[2], window.edge)?;
+                        let v = values[[i, j, k]];
+                        (!v.is_nan()).then_some((v, *w))
+                    })
+                    .collect::<Vec<_>>();
+                (ind, window.evaluate(&mut found))
+            })
+            .collect()
+    }
+
+    //store the window statistic of every block through set
+    pub fn assign_moving_window<F, G>(&mut self, attr: F, window: &MovingWindow, set: G)
+    where
+        B: Send,
+        F: Fn(&B) -> f64,
+        G: Fn(&mut B, f64) + Sync,
+    {
+        let filtered = self.moving_window(attr, window);
+        self.par_indexed_iter_mut().for_each(|(ind, b)| {
+            if let Some(v) = filtered.get(&ind) {
+                set(b, *v);
+            }
+        });
+    }
+}
@@ -0,0 +1,174 @@
+use crate::block::{BlockCoordinates, BlockIndex, BlockSize};
+
+//orientation of the model axes in world space, angles in degrees
+//azimuth rotates the axes clockwise about the vertical so +j points at bearing azimuth
+//and +i at bearing azimuth + 90, dip then tilts the model about its i axis lowering +j
+#[derive(Debug, PartialEq, Copy, Clone, Default)]
+pub struct FrameRotation {
+    pub azimuth: f32,
+    pub dip: f32,
+}
+
+impl FrameRotation {
+    pub fn new(azimuth: f32, dip: f32) -> Self {
+        Self { azimuth, dip }
+    }
+
+    //world directions of the model i, j, k axes
+    pub(crate) fn axes(&self) -> [[f32; 3]; 3] {
+        let (sa, ca) = self.azimuth.to_radians().sin_cos();
+        let (sd, cd) = self.dip.to_radians().sin_cos();
+
+        [
+            [ca, -sa, 0.0],
+            [sa * cd, ca * cd, -sd],
+            [sa * sd, ca * sd, cd],
+        ]
+    }
+}
+
+//spatial definition of a blockmodel lattice
+//origin is the centroid of block (0, 0, 0)
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct ModelFrame {
+    pub origin: BlockCoordinates,
+    pub block_size: BlockSize,
+    pub dims: [usize; 3],
+    pub rotation: FrameRotation,
+    //coordinate reference system of the world coordinates, None for an unknown or local grid
+    pub epsg: Option<u32>,
+}
+
+impl ModelFrame {
+    pub fn new(origin: BlockCoordinates, block_size: BlockSize, dims: [usize; 3]) -> Self {
+        Self {
+            origin,
+            block_size,
+            dims,
+            rotation: FrameRotation::default(),
+            epsg: None,
+        }
+    }
+
+    pub fn with_rotation(mut self, rotation: FrameRotation) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    pub fn with_epsg(mut self, epsg: Option<u32>) -> Self {
+        self.epsg = epsg;
+        self
+    }
+
+    pub fn is_rotated(&self) -> bool {
+        self.rotation != FrameRotation::default()
+    }
+
+    //total number of cells in frame
+    pub fn num_cells(&self) -> usize {
+        self.dims[0] * self.dims[1] * self.dims[2]
+    }
+
+    pub fn contains(&self, ind: BlockIndex) -> bool {
+        ind.i < self.dims[0] && ind.j < self.dims[1] && ind.k < self.dims[2]
+    }
+
+    //world coordinates of a point offset from the origin along the model axes
+    pub fn to_world(&self, offset: [f32; 3]) -> BlockCoordinates {
+        if !self.is_rotated() {
+            return BlockCoordinates {
+                x: self.origin.x + offset[0],
+                y: self.origin.y + offset[1],
+                z: self.origin.z + offset[2],
+            };
+        }
+
+        let axes = self.rotation.axes();
+        let along = |d: usize| (0..3).map(|a| axes[a][d] * offset[a]).sum::<f32>();
+        BlockCoordinates {
+            x: self.origin.x + along(0),
+            y: self.origin.y + along(1),
+            z: self.origin.z + along(2),
+        }
+    }
+
+    //offset of world coordinates from the origin along the model axes
+    pub fn to_local(&self, coords: BlockCoordinates) -> [f32; 3] {
+        let d = [
+            coords.x - self.origin.x,
+            coords.y - self.origin.y,
+            coords.z - self.origin.z,
+        ];
+        if !self.is_rotated() {
+            return d;
+        }
+
+        self.rotation
+            .axes()
+            .map(|axis| axis[0] * d[0] + axis[1] * d[1] + axis[2] * d[2])
+    }
+
+    //centroid of block at index
+    pub fn index_to_coordinates(&self, ind: BlockIndex) -> BlockCoordinates {
+        self.to_world([
+            ind.i as f32 * self.block_size.x_size,
+            ind.j as f32 * self.block_size.y_size,
+            ind.k as f32 * self.block_size.z_size,
+        ])
+    }
+
+    //position of the origin of other on the lattice of self in blocks, None unless both share
+    //block size, rotation and a lattice, and neither has a different known crs
+    pub fn lattice_offset(&self, other: &ModelFrame) -> Option<[i64; 3]> {
+        if self.block_size != other.block_size || self.rotation != other.rotation {
+            return None;
+        }
+        if self.epsg.zip(other.epsg).is_some_and(|(a, b)| a != b) {
+            return None;
+        }
+        let local = self.to_local(other.origin);
+        let size = [
+            self.block_size.x_size,
+            self.block_size.y_size,
+            self.block_size.z_size,
+        ];
+        let mut offset = [0; 3];
+        for d in 0..3 {
+            let pos = local[d] / size[d];
+            if (pos - pos.round()).abs() > 1e-3 {
+                return None;
+            }
+            offset[d] = pos.round() as i64;
+        }
+        Some(offset)
+    }
+
+    //index of the cell holding the centroid of cell ind of other, None if outside frame
+    pub fn index_from(&self, other: &ModelFrame, ind: BlockIndex) -> Option<BlockIndex> {
+        if self == other {
+            return self.contains(ind).then_some(ind);
+        }
+        self.coordinates_to_index(other.index_to_coordinates(ind))
+    }
+
+    //index of the block containing coords, None if outside frame
+    pub fn coordinates_to_index(&self, coords: BlockCoordinates) -> Option<BlockIndex> {
+        let [x, y, z] = self.to_local(coords);
+        let i = (x / self.block_size.x_size).round();
+        let j = (y / self.block_size.y_size).round();
+        let k = (z / self.block_size.z_size).round();
+
+        //also rejects NaN
+        if !(i >= 0.0 && j >= 0.0 && k >= 0.0) {
+            return None;
+        }
+
+        let ind = BlockIndex {
+            i: i as usize,
+            j: j as usize,
+            k: k as usize,
+        };
+
+        self.contains(ind).then_some(ind)
+    }
+}
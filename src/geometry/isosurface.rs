@@ -0,0 +1,188 @@
+use ndarray::Array3;
+
+use crate::block::BlockInterface;
+use crate::block_model::BlockModel;
+use crate::geometry::mesh::TriangleMesh;
+use crate::storage::BlockStorage;
+
+use std::collections::HashMap;
+
+//cube corners as offsets along the model axes
+const CORNERS: [[usize; 3]; 8] = [
+    [0, 0, 0],
+    [1, 0, 0],
+    [1, 1, 0],
+    [0, 1, 0],
+    [0, 0, 1],
+    [1, 0, 1],
+    [1, 1, 1],
+    [0, 1, 1],
+];
+
+//six tetrahedra sharing the 0-6 diagonal, the same split in every cube so faces match
+const TETRAHEDRA: [[usize; 4]; 6] = [
+    [0, 5, 1, 6],
+    [0, 1, 2, 6],
+    [0, 2, 3, 6],
+    [0, 3, 7, 6],
+    [0, 7, 4, 6],
+    [0, 4, 5, 6],
+];
+
+fn sub(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+//builds the shell, vertices on shared lattice edges are created once so the mesh is watertight
+struct ShellBuilder<'a, B, S>
+where
+    B: BlockInterface,
+    S: BlockStorage<B>,
+{
+    mdl: &'a BlockModel<B, S>,
+    //values padded by one NaN layer on every side, so the shell closes at the model edge
+    values: Array3<f64>,
+    threshold: f64,
+    vertices: Vec<[f64; 3]>,
+    triangles: Vec<[usize; 3]>,
+    edges: HashMap<([usize; 3], [usize; 3]), usize>,
+}
+
+impl<B, S> ShellBuilder<'_, B, S>
+where
+    B: BlockInterface,
+    S: BlockStorage<B>,
+{
+    fn inside(&self, p: [usize; 3]) -> bool {
+        self.values[p] >= self.threshold
+    }
+
+    //world position of a padded lattice point, which may be fractional
+    fn world(&self, p: [f64; 3]) -> [f64; 3] {
+        let size = self.mdl.block_size();
+        let size = [size.x_size, size.y_size, size.z_size];
+        let c = self
+            .mdl
+            .frame()
+            .to_world([0, 1, 2].map(|a| ((p[a] - 1.0) * size[a] as f64) as f32));
+        [c.x as f64, c.y as f64, c.z as f64]
+    }
+
+    //vertex where the threshold crosses the edge from a to b, linear in the values of both
+    //ends or at the midpoint next to a missing block
+    fn vertex(&mut self, a: [usize; 3], b: [usize; 3]) -> usize {
+        let key = if a < b { (a, b) } else { (b, a) };
+        if let Some(v) = self.edges.get(&key) {
+            return *v;
+        }
+        let (va, vb) = (self.values[a], self.values[b]);
+        let t = if va.is_nan() || vb.is_nan() || va == vb {
+            0.5
+        } else {
+            ((self.threshold - va) / (vb - va)).clamp(0.0, 1.0)
+        };
+        let p = [0, 1, 2].map(|d| a[d] as f64 + t * (b[d] as f64 - a[d] as f64));
+        let v = self.vertices.len();
+        self.vertices.push(self.world(p));
+        self.edges.insert(key, v);
+        v
+    }
+
+    //adds a triangle facing from the inside point towards the outside point
+    fn triangle(&mut self, mut t: [usize; 3], inside: [usize; 3], outside: [usize; 3]) {
+        let [p0, p1, p2] = t.map(|v| self.vertices[v]);
+        let normal = cross(sub(p1, p0), sub(p2, p0));
+        let to_world = |p: [usize; 3]| self.world(p.map(|c| c as f64));
+        if dot(normal, sub(to_world(outside), to_world(inside))) < 0.0 {
+            t.swap(1, 2);
+        }
+        self.triangles.push(t);
+    }
+
+    fn tetrahedron(&mut self, corners: [[usize; 3]; 4]) {
+        let (inside, outside): (Vec<_>, Vec<_>) =
+            corners.into_iter().partition(|c| self.inside(*c));
+        match (inside.len(), outside.len()) {
+            (1, 3) => {
+                let a = inside[0];
+                let t = [0, 1, 2].map(|n| self.vertex(a, outside[n]));
+                self.triangle(t, a, outside[0]);
+            }
+            (3, 1) => {
+                let d = outside[0];
+                let t = [0, 1, 2].map(|n| self.vertex(inside[n], d));
+                self.triangle(t, inside[0], d);
+            }
+            (2, 2) => {
+                let (a, b, c, d) = (inside[0], inside[1], outside[0], outside[1]);
+                let (ac, ad, bd, bc) = (
+                    self.vertex(a, c),
+                    self.vertex(a, d),
+                    self.vertex(b, d),
+                    self.vertex(b, c),
+                );
+                self.triangle([ac, ad, bd], a, c);
+                self.triangle([ac, bd, bc], a, c);
+            }
+            _ => (),
+        }
+    }
+}
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface,
+    S: BlockStorage<B>,
+{
+    //grade shell enclosing the block centroids whose attribute is at or above threshold, by
+    //marching cubes over the centroid lattice with every cube split into six tetrahedra, which
+    //avoids the ambiguous cases of the cube table, missing blocks and NaN values are outside
+    //and shells are closed at the model edge, triangles face outwards
+    pub fn isosurface<F>(&self, attr: F, threshold: f64) -> TriangleMesh
+    where
+        F: Fn(&B) -> f64,
+    {
+        let dims = self.dims();
+        let mut values = Array3::from_elem([dims[0] + 2, dims[1] + 2, dims[2] + 2], f64::NAN);
+        for (ind, b) in self.indexed_iter() {
+            values[[ind.i + 1, ind.j + 1, ind.k + 1]] = attr(b);
+        }
+
+        let mut builder = ShellBuilder {
+            mdl: self,
+            values,
+            threshold,
+            vertices: Vec::new(),
+            triangles: Vec::new(),
+            edges: HashMap::new(),
+        };
+        for i in 0..=dims[0] {
+            for j in 0..=dims[1] {
+                for k in 0..=dims[2] {
+                    let cube = CORNERS.map(|c| [i + c[0], j + c[1], k + c[2]]);
+                    let inside = cube.iter().filter(|c| builder.inside(**c)).count();
+                    if inside == 0 || inside == 8 {
+                        continue;
+                    }
+                    for tet in TETRAHEDRA.iter() {
+                        builder.tetrahedron(tet.map(|c| cube[c]));
+                    }
+                }
+            }
+        }
+
+        TriangleMesh::new(builder.vertices, builder.triangles)
+    }
+}
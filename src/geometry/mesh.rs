@@ -0,0 +1,351 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+//triangulated surface or solid, triangles index into vertices
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TriangleMesh {
+    pub vertices: Vec<[f64; 3]>,
+    pub triangles: Vec<[usize; 3]>,
+}
+
+impl TriangleMesh {
+    pub fn new(vertices: Vec<[f64; 3]>, triangles: Vec<[usize; 3]>) -> Self {
+        assert!(
+            triangles.iter().flatten().all(|v| *v < vertices.len()),
+            "triangle references a missing vertex"
+        );
+        Self {
+            vertices,
+            triangles,
+        }
+    }
+
+    pub fn num_triangles(&self) -> usize {
+        self.triangles.len()
+    }
+
+    //vertex positions of triangle t
+    pub fn triangle(&self, t: usize) -> [[f64; 3]; 3] {
+        self.triangles[t].map(|v| self.vertices[v])
+    }
+
+    //min and max corners of the vertices
+    pub fn bounds(&self) -> Option<([f64; 3], [f64; 3])> {
+        let first = *self.vertices.first()?;
+        Some(
+            self.vertices
+                .iter()
+                .fold((first, first), |(mut lo, mut hi), v| {
+                    for d in 0..3 {
+                        lo[d] = lo[d].min(v[d]);
+                        hi[d] = hi[d].max(v[d]);
+                    }
+                    (lo, hi)
+                }),
+        )
+    }
+
+    //wavefront obj, polygons are fan triangulated
+    pub fn from_obj<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn Error>> {
+        let mut vertices = Vec::new();
+        let mut triangles = Vec::new();
+
+        for line in fs::read_to_string(path)?.lines() {
+            let mut tokens = line.split_whitespace();
+            match tokens.next() {
+                Some("v") => {
+                    let mut v = [0.0; 3];
+                    for c in v.iter_mut() {
+                        *c = tokens
+                            .next()
+                            .ok_or("vertex with fewer than 3 coordinates")?
+                            .parse()?;
+                    }
+                    vertices.push(v);
+                }
+                Some("f") => {
+                    //refs may be v, v/vt, v//vn or v/vt/vn and negative refs count from the end
+                    let face = tokens
+                        .map(|t| -> Result<usize, Box<dyn Error>> {
+                            let r = t.split('/').next().unwrap_or(t).parse::<isize>()?;
+                            let v = if r < 0 {
+                                vertices.len() as isize + r
+                            } else {
+                                r - 1
+                            };
+                            if v < 0 || v as usize >= vertices.len() {
+                                return Err(format!("face references missing vertex {}", r).into());
+                            }
+                            Ok(v as usize)
+                        })
+                        .collect::<Result<Vec<_>, _>>()?;
+
+                    for w in 1..face.len().saturating_sub(1) {
+                        triangles.push([face[0], face[w], face[w + 1]]);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(Self::new(vertices, triangles))
+    }
+
+    pub fn to_obj<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn Error>> {
+        let file = File::create(path)?;
+        self.to_obj_writer(BufWriter::new(file))
+    }
+
+    pub fn to_obj_writer<W: Write>(&self, mut w: W) -> Result<(), Box<dyn Error>> {
+        for v in self.vertices.iter() {
+            writeln!(w, "v {} {} {}", v[0], v[1], v[2])?;
+        }
+        for t in self.triangles.iter() {
+            writeln!(w, "f {} {} {}", t[0] + 1, t[1] + 1, t[2] + 1)?;
+        }
+        w.flush()?;
+        Ok(())
+    }
+
+    //ascii ply
+    pub fn to_ply<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn Error>> {
+        let file = File::create(path)?;
+        self.to_ply_writer(BufWriter::new(file))
+    }
+
+    pub fn to_ply_writer<W: Write>(&self, mut w: W) -> Result<(), Box<dyn Error>> {
+        writeln!(w, "ply")?;
+        writeln!(w, "format ascii 1.0")?;
+        writeln!(w, "element vertex {}", self.vertices.len())?;
+        for axis in ["x", "y", "z"] {
+            writeln!(w, "property double {}", axis)?;
+        }
+        writeln!(w, "element face {}", self.triangles.len())?;
+        writeln!(w, "property list uchar int vertex_indices")?;
+        writeln!(w, "end_header")?;
+        for v in self.vertices.iter() {
+            writeln!(w, "{} {} {}", v[0], v[1], v[2])?;
+        }
+        for t in self.triangles.iter() {
+            writeln!(w, "3 {} {} {}", t[0], t[1], t[2])?;
+        }
+        w.flush()?;
+        Ok(())
+    }
+
+    //laplacian smoothing, each pass moves vertices factor of the way to the mean of their
+    //neighbours, vertices on open edges stay fixed so boundaries do not shrink
+    pub fn smooth(&mut self, iterations: usize, factor: f64) {
+        let mut neighbours = vec![Vec::new(); self.vertices.len()];
+        let mut edges: HashMap<(usize, usize), usize> = HashMap::new();
+        for t in self.triangles.iter() {
+            for e in 0..3 {
+                let (a, b) = (t[e], t[(e + 1) % 3]);
+                neighbours[a].push(b);
+                neighbours[b].push(a);
+                *edges.entry((a.min(b), a.max(b))).or_insert(0) += 1;
+            }
+        }
+        let mut fixed = vec![false; self.vertices.len()];
+        for ((a, b), count) in edges {
+            if count == 1 {
+                fixed[a] = true;
+                fixed[b] = true;
+            }
+        }
+        for n in neighbours.iter_mut() {
+            n.sort();
+            n.dedup();
+        }
+
+        for _ in 0..iterations {
+            let previous = self.vertices.clone();
+            for (v, p) in self.vertices.iter_mut().enumerate() {
+                if fixed[v] || neighbours[v].is_empty() {
+                    continue;
+                }
+                let count = neighbours[v].len() as f64;
+                for d in 0..3 {
+                    let mean = neighbours[v].iter().map(|n| previous[*n][d]).sum::<f64>() / count;
+                    p[d] += factor * (mean - p[d]);
+                }
+            }
+        }
+    }
+
+    //3DFACE entities of an ascii dxf, quadrilateral faces are split in two
+    pub fn from_dxf<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn Error>> {
+        let text = fs::read_to_string(path)?;
+        let mut lines = text.lines().map(str::trim);
+
+        let mut faces = Vec::new();
+        let mut current: Option<[[f64; 3]; 4]> = None;
+        while let (Some(code), Some(value)) = (lines.next(), lines.next()) {
+            let code = code.parse::<u32>()?;
+
+            if code == 0 {
+                faces.extend(current.take());
+                if value == "3DFACE" {
+                    current = Some([[0.0; 3]; 4]);
+                }
+                continue;
+            }
+
+            //corner c coordinate d has group code 10 * (d + 1) + c
+            if let Some(face) = current.as_mut() {
+                if (10..=33).contains(&code) && code % 10 < 4 {
+                    face[(code % 10) as usize][(code / 10 - 1) as usize] = value.parse()?;
+                }
+            }
+        }
+        faces.extend(current);
+
+        let mut mesh = Self::default();
+        for face in faces {
+            let base = mesh.vertices.len();
+            mesh.vertices.extend(face);
+            mesh.triangles.push([base, base + 1, base + 2]);
+
+            //triangular faces repeat the third corner
+            if face[3] != face[2] {
+                mesh.triangles.push([base, base + 2, base + 3]);
+            }
+        }
+        Ok(mesh)
+    }
+
+    //x, y, z csv points on a regular xy grid, each complete grid cell becomes two triangles
+    pub fn from_csv_grid<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn Error>> {
+        let mut rdr = csv::Reader::from_path(path)?;
+        let mut vertices = Vec::new();
+        for result in rdr.deserialize() {
+            let (x, y, z): (f64, f64, f64) = result?;
+            vertices.push([x, y, z]);
+        }
+
+        //adding zero turns -0.0 into 0.0 so both land on the same grid line
+        let key = |v: f64| v + 0.0;
+        let axis = |d: usize| {
+            let mut values = vertices
+                .iter()
+                .map(|v: &[f64; 3]| key(v[d]))
+                .collect::<Vec<_>>();
+            values.sort_by(|a, b| a.total_cmp(b));
+            values.dedup_by(|a, b| a.total_cmp(b).is_eq());
+            values
+        };
+        let (xs, ys) = (axis(0), axis(1));
+
+        let mut grid = vec![None; xs.len() * ys.len()];
+        for (n, v) in vertices.iter().enumerate() {
+            let find = |values: &[f64], c: f64| {
+                values
+                    .binary_search_by(|a| a.total_cmp(&key(c)))
+                    .map_err(|_| format!("grid point {n} ({}, {}) is off the grid", v[0], v[1]))
+            };
+            let (i, j) = (find(&xs, v[0])?, find(&ys, v[1])?);
+            grid[i * ys.len() + j] = Some(n);
+        }
+
+        let mut triangles = Vec::new();
+        for i in 0..xs.len().saturating_sub(1) {
+            for j in 0..ys.len().saturating_sub(1) {
+                let corner = |di: usize, dj: usize| grid[(i + di) * ys.len() + j + dj];
+                if let (Some(a), Some(b), Some(c), Some(d)) =
+                    (corner(0, 0), corner(1, 0), corner(1, 1), corner(0, 1))
+                {
+                    triangles.push([a, b, c]);
+                    triangles.push([a, c, d]);
+                }
+            }
+        }
+
+        Ok(Self::new(vertices, triangles))
+    }
+}
+
+//uniform xy bucket grid over the triangles of a mesh for vertical line queries
+pub(crate) struct TriangleGrid<'a> {
+    mesh: &'a TriangleMesh,
+    min: [f64; 2],
+    cell: f64,
+    dims: [usize; 2],
+    buckets: Vec<Vec<usize>>,
+}
+
+impl<'a> TriangleGrid<'a> {
+    pub(crate) fn new(mesh: &'a TriangleMesh) -> Self {
+        let (lo, hi) = mesh.bounds().unwrap_or(([0.0; 3], [0.0; 3]));
+        let (w, h) = (hi[0] - lo[0], hi[1] - lo[1]);
+
+        //about one triangle per bucket
+        let cell = ((w * h) / mesh.num_triangles().max(1) as f64)
+            .sqrt()
+            .max(w.max(h) / 4096.0)
+            .max(f64::EPSILON);
+        let dims = [(w / cell) as usize + 1, (h / cell) as usize + 1];
+
+        let mut grid = Self {
+            mesh,
+            min: [lo[0], lo[1]],
+            cell,
+            dims,
+            buckets: vec![Vec::new(); dims[0] * dims[1]],
+        };
+
+        for t in 0..mesh.num_triangles() {
+            let [a, b, c] = mesh.triangle(t);
+            let (x0, x1) = (a[0].min(b[0]).min(c[0]), a[0].max(b[0]).max(c[0]));
+            let (y0, y1) = (a[1].min(b[1]).min(c[1]), a[1].max(b[1]).max(c[1]));
+
+            let [i0, j0] = grid.bucket(x0, y0);
+            let [i1, j1] = grid.bucket(x1, y1);
+            for i in i0..=i1 {
+                for j in j0..=j1 {
+                    grid.buckets[i * dims[1] + j].push(t);
+                }
+            }
+        }
+        grid
+    }
+
+    pub(crate) fn cell(&self) -> f64 {
+        self.cell
+    }
+
+    fn bucket(&self, x: f64, y: f64) -> [usize; 2] {
+        let i = ((x - self.min[0]) / self.cell).max(0.0) as usize;
+        let j = ((y - self.min[1]) / self.cell).max(0.0) as usize;
+        [i.min(self.dims[0] - 1), j.min(self.dims[1] - 1)]
+    }
+
+    //elevations where the vertical line through x, y crosses each triangle,
+    //barycentric weights down to -eps count as inside
+    pub(crate) fn heights(&self, x: f64, y: f64, eps: f64) -> impl Iterator<Item = f64> + '_ {
+        let triangles = if x < self.min[0] || y < self.min[1] {
+            &[][..]
+        } else {
+            let [i, j] = self.bucket(x, y);
+            &self.buckets[i * self.dims[1] + j][..]
+        };
+
+        triangles.iter().filter_map(move |t| {
+            let [a, b, c] = self.mesh.triangle(*t);
+            let det = (b[1] - c[1]) * (a[0] - c[0]) + (c[0] - b[0]) * (a[1] - c[1]);
+            if det == 0.0 {
+                return None;
+            }
+
+            //barycentric weights of x, y in the triangle
+            let wa = ((b[1] - c[1]) * (x - c[0]) + (c[0] - b[0]) * (y - c[1])) / det;
+            let wb = ((c[1] - a[1]) * (x - c[0]) + (a[0] - c[0]) * (y - c[1])) / det;
+            let wc = 1.0 - wa - wb;
+
+            (wa >= -eps && wb >= -eps && wc >= -eps)
+                .then(|| c[2] + wa * (a[2] - c[2]) + wb * (b[2] - c[2]))
+        })
+    }
+}
@@ -0,0 +1,5 @@
+pub mod isosurface;
+pub mod mesh;
+pub mod polygon;
+pub mod solid;
+pub mod surface;
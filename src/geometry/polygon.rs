@@ -0,0 +1,145 @@
+use crate::block::BlockInterface;
+use crate::block_model::BlockModel;
+use crate::selection::Selection;
+use crate::storage::BlockStorage;
+
+use std::ops::RangeInclusive;
+
+//even odd crossing test of a closed ring, the last point connects back to the first
+fn ring_contains(ring: &[[f64; 2]], x: f64, y: f64) -> bool {
+    let mut inside = false;
+    let mut prev = ring[ring.len() - 1];
+    for p in ring.iter() {
+        if (p[1] > y) != (prev[1] > y)
+            && x < prev[0] + (y - prev[1]) * (p[0] - prev[0]) / (p[1] - prev[1])
+        {
+            inside = !inside;
+        }
+        prev = *p;
+    }
+    inside
+}
+
+//signed area of a ring, positive when counter clockwise
+pub(crate) fn ring_area(ring: &[[f64; 2]]) -> f64 {
+    let mut prev = ring[ring.len() - 1];
+    let mut area = 0.0;
+    for p in ring.iter() {
+        area += prev[0] * p[1] - p[0] * prev[1];
+        prev = *p;
+    }
+    area / 2.0
+}
+
+//closed xy polygon with holes, rings need not repeat their first point
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polygon {
+    pub exterior: Vec<[f64; 2]>,
+    pub holes: Vec<Vec<[f64; 2]>>,
+}
+
+impl Polygon {
+    pub fn new(exterior: Vec<[f64; 2]>) -> Self {
+        assert!(exterior.len() >= 3, "a polygon needs at least 3 points");
+        Self {
+            exterior,
+            holes: Vec::new(),
+        }
+    }
+
+    pub fn with_hole(mut self, hole: Vec<[f64; 2]>) -> Self {
+        assert!(hole.len() >= 3, "a hole needs at least 3 points");
+        self.holes.push(hole);
+        self
+    }
+
+    //inside the exterior and outside every hole
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        ring_contains(&self.exterior, x, y) && !self.holes.iter().any(|h| ring_contains(h, x, y))
+    }
+
+    //area of the exterior less the holes
+    pub fn area(&self) -> f64 {
+        ring_area(&self.exterior).abs() - self.holes.iter().map(|h| ring_area(h).abs()).sum::<f64>()
+    }
+
+    //min and max corners of the exterior
+    pub fn bounds(&self) -> ([f64; 2], [f64; 2]) {
+        let mut min = [f64::INFINITY; 2];
+        let mut max = [f64::NEG_INFINITY; 2];
+        for p in self.exterior.iter() {
+            for a in 0..2 {
+                min[a] = min[a].min(p[a]);
+                max[a] = max[a].max(p[a]);
+            }
+        }
+        (min, max)
+    }
+}
+
+//polygons whose union is the selected area, e.g. a lease boundary of several parcels
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct MultiPolygon {
+    pub polygons: Vec<Polygon>,
+}
+
+impl MultiPolygon {
+    pub fn new(polygons: Vec<Polygon>) -> Self {
+        Self { polygons }
+    }
+
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        self.polygons.iter().any(|p| p.contains(x, y))
+    }
+
+    pub fn area(&self) -> f64 {
+        self.polygons.iter().map(|p| p.area()).sum()
+    }
+}
+
+impl From<Polygon> for MultiPolygon {
+    fn from(polygon: Polygon) -> Self {
+        Self::new(vec![polygon])
+    }
+}
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface,
+    S: BlockStorage<B>,
+{
+    //present blocks on benches whose centroid lies inside the polygons in plan, e.g. a pit
+    //design or lease boundary
+    pub fn select_in_polygons(
+        &self,
+        polygons: &MultiPolygon,
+        benches: RangeInclusive<usize>,
+    ) -> Selection {
+        let bounds = polygons
+            .polygons
+            .iter()
+            .map(|p| p.bounds())
+            .collect::<Vec<_>>();
+        Selection::from_inds(
+            self.dims(),
+            self.indexed_iter()
+                .filter(|(ind, _)| benches.contains(&ind.k))
+                .filter(|(ind, _)| {
+                    let c = self.index_to_coordinates(*ind);
+                    let (x, y) = (c.x as f64, c.y as f64);
+                    polygons
+                        .polygons
+                        .iter()
+                        .zip(bounds.iter())
+                        .any(|(p, (min, max))| {
+                            x >= min[0]
+                                && x <= max[0]
+                                && y >= min[1]
+                                && y <= max[1]
+                                && p.contains(x, y)
+                        })
+                })
+                .map(|(ind, _)| ind),
+        )
+    }
+}
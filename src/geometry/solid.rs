@@ -0,0 +1,85 @@
+use rayon::prelude::*;
+
+use crate::block::{BlockIndex, BlockInterface};
+use crate::block_model::BlockModel;
+use crate::geometry::mesh::{TriangleGrid, TriangleMesh};
+use crate::storage::BlockStorage;
+
+use std::collections::{HashMap, HashSet};
+
+//point containment for a closed triangulated solid by vertical ray casting
+pub struct Solid<'a> {
+    grid: TriangleGrid<'a>,
+}
+
+impl<'a> Solid<'a> {
+    pub fn new(mesh: &'a TriangleMesh) -> Self {
+        Self {
+            grid: TriangleGrid::new(mesh),
+        }
+    }
+
+    //true if the upward ray from the point crosses the solid boundary an odd number of times
+    pub fn contains(&self, x: f64, y: f64, z: f64) -> bool {
+        //nudge the ray off shared edges and vertices, which would otherwise count twice
+        let x = x + self.grid.cell() * 1.234_567e-7;
+        let y = y + self.grid.cell() * 7.654_321e-8;
+
+        self.grid.heights(x, y, 0.0).filter(|h| *h > z).count() % 2 == 1
+    }
+}
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface + Sync,
+    S: BlockStorage<B> + Sync,
+{
+    //blocks whose centroid lies inside the solid
+    pub fn blocks_inside_solid(&self, mesh: &TriangleMesh) -> HashSet<BlockIndex> {
+        let solid = Solid::new(mesh);
+
+        self.par_indexed_iter()
+            .filter_map(|(ind, _)| {
+                let c = self.index_to_coordinates(ind);
+                solid
+                    .contains(c.x as f64, c.y as f64, c.z as f64)
+                    .then_some(ind)
+            })
+            .collect()
+    }
+
+    //fraction of each block inside the solid from samples^3 regularly spaced points,
+    //blocks entirely outside are absent
+    pub fn fraction_inside_solid(
+        &self,
+        mesh: &TriangleMesh,
+        samples: usize,
+    ) -> HashMap<BlockIndex, f64> {
+        assert!(samples > 0, "at least one sample per axis is required");
+
+        let solid = Solid::new(mesh);
+        let size = self.block_size();
+        let frame = self.frame();
+        let offset = |s: usize| (s as f32 + 0.5) / samples as f32 - 0.5;
+
+        self.par_indexed_iter()
+            .filter_map(|(ind, _)| {
+                let mut inside = 0;
+                for si in 0..samples {
+                    for sj in 0..samples {
+                        for sk in 0..samples {
+                            let p = frame.to_world([
+                                (ind.i as f32 + offset(si)) * size.x_size,
+                                (ind.j as f32 + offset(sj)) * size.y_size,
+                                (ind.k as f32 + offset(sk)) * size.z_size,
+                            ]);
+                            inside += solid.contains(p.x as f64, p.y as f64, p.z as f64) as usize;
+                        }
+                    }
+                }
+
+                (inside > 0).then(|| (ind, inside as f64 / samples.pow(3) as f64))
+            })
+            .collect()
+    }
+}
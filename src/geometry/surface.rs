@@ -0,0 +1,169 @@
+use rayon::prelude::*;
+
+use crate::block::{BlockIndex, BlockInterface};
+use crate::block_model::BlockModel;
+use crate::geometry::mesh::{TriangleGrid, TriangleMesh};
+use crate::storage::BlockStorage;
+
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::path::Path;
+
+//side of a surface relative to block centroids
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SurfaceSide {
+    Above,
+    Below,
+}
+
+//elevation lookup over a triangulated surface
+pub struct Surface<'a> {
+    grid: TriangleGrid<'a>,
+}
+
+impl<'a> Surface<'a> {
+    pub fn new(mesh: &'a TriangleMesh) -> Self {
+        Self {
+            grid: TriangleGrid::new(mesh),
+        }
+    }
+
+    //highest surface elevation above x, y, None outside the surface footprint
+    pub fn elevation(&self, x: f64, y: f64) -> Option<f64> {
+        self.grid.heights(x, y, 1e-9).reduce(f64::max)
+    }
+}
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface + Sync,
+    S: BlockStorage<B> + Sync,
+{
+    //blocks whose centroid lies on side of the surface
+    //blocks outside the surface footprint are never flagged
+    pub fn blocks_beyond_surface(
+        &self,
+        mesh: &TriangleMesh,
+        side: SurfaceSide,
+    ) -> HashSet<BlockIndex> {
+        let surface = Surface::new(mesh);
+
+        self.par_indexed_iter()
+            .filter_map(|(ind, _)| {
+                let c = self.index_to_coordinates(ind);
+                let z = surface.elevation(c.x as f64, c.y as f64)?;
+                let beyond = match side {
+                    SurfaceSide::Above => c.z as f64 > z,
+                    SurfaceSide::Below => (c.z as f64) < z,
+                };
+                beyond.then_some(ind)
+            })
+            .collect()
+    }
+
+    //fraction of each block volume below the surface, sampling samples x samples
+    //columns across the block footprint, blocks outside the surface footprint are absent
+    pub fn fraction_below_surface(
+        &self,
+        mesh: &TriangleMesh,
+        samples: usize,
+    ) -> HashMap<BlockIndex, f64> {
+        assert!(samples > 0, "at least one sample per axis is required");
+
+        let surface = Surface::new(mesh);
+        let size = self.block_size();
+        let frame = self.frame();
+
+        self.par_indexed_iter()
+            .filter_map(|(ind, _)| {
+                let mut total = 0.0;
+                let mut count = 0;
+                for si in 0..samples {
+                    for sj in 0..samples {
+                        let u = (si as f32 + 0.5) / samples as f32 - 0.5;
+                        let v = (sj as f32 + 0.5) / samples as f32 - 0.5;
+                        let p = frame.to_world([
+                            (ind.i as f32 + u) * size.x_size,
+                            (ind.j as f32 + v) * size.y_size,
+                            ind.k as f32 * size.z_size,
+                        ]);
+
+                        if let Some(z) = surface.elevation(p.x as f64, p.y as f64) {
+                            let bottom = p.z as f64 - size.z_size as f64 / 2.0;
+                            total += ((z - bottom) / size.z_size as f64).clamp(0.0, 1.0);
+                            count += 1;
+                        }
+                    }
+                }
+
+                (count > 0).then(|| (ind, total / count as f64))
+            })
+            .collect()
+    }
+
+    //remove blocks whose centroid lies on side of the surface, returning the number removed
+    pub fn clip_to_surface(&mut self, mesh: &TriangleMesh, side: SurfaceSide) -> usize {
+        let clipped = self.blocks_beyond_surface(mesh, side);
+        clipped.iter().for_each(|ind| {
+            self.blocks.remove(*ind);
+        });
+        clipped.len()
+    }
+}
+
+//elevation of a surface at the centre of every model column, None where a column holds no
+//blocks, points are world coordinates and column (i, j) is points[i * dims[1] + j]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ElevationGrid {
+    pub dims: [usize; 2],
+    pub points: Vec<Option<[f64; 3]>>,
+}
+
+impl ElevationGrid {
+    pub fn point(&self, i: usize, j: usize) -> Option<[f64; 3]> {
+        self.points[i * self.dims[1] + j]
+    }
+
+    pub fn elevation(&self, i: usize, j: usize) -> Option<f64> {
+        self.point(i, j).map(|p| p[2])
+    }
+
+    //two triangles between every four neighbouring columns with an elevation
+    pub fn to_mesh(&self) -> TriangleMesh {
+        let mut vertices = Vec::new();
+        let mut vertex = vec![None; self.points.len()];
+        for (n, p) in self.points.iter().enumerate() {
+            if let Some(p) = p {
+                vertex[n] = Some(vertices.len());
+                vertices.push(*p);
+            }
+        }
+
+        let [ni, nj] = self.dims;
+        let mut triangles = Vec::new();
+        for i in 0..ni.saturating_sub(1) {
+            for j in 0..nj.saturating_sub(1) {
+                let corner = |di: usize, dj: usize| vertex[(i + di) * nj + j + dj];
+                if let (Some(a), Some(b), Some(c), Some(d)) =
+                    (corner(0, 0), corner(1, 0), corner(1, 1), corner(0, 1))
+                {
+                    triangles.push([a, b, c]);
+                    triangles.push([a, c, d]);
+                }
+            }
+        }
+        TriangleMesh::new(vertices, triangles)
+    }
+
+    //x, y, z rows of the columns with an elevation, readable by TriangleMesh::from_csv_grid
+    //for unrotated models
+    pub fn to_csv<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn Error>> {
+        let mut w = csv::Writer::from_path(path)?;
+        w.write_record(["x", "y", "z"])?;
+        for p in self.points.iter().flatten() {
+            w.serialize(p)?;
+        }
+        w.flush()?;
+        Ok(())
+    }
+}
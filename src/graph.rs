@@ -0,0 +1,195 @@
+use rayon::prelude::*;
+
+use crate::block::{BlockIndex, BlockInterface};
+use crate::block_model::{BlockDependenceInterface, BlockModel};
+use crate::storage::BlockStorage;
+
+//precedence arcs between the present blocks of a model in compressed sparse row form
+//nodes are numbered in index order, missing blocks impose no precedence
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrecedenceGraph {
+    inds: Vec<BlockIndex>,
+    //arcs of node n are targets[offsets[n]..offsets[n + 1]]
+    pred_offsets: Vec<usize>,
+    pred_targets: Vec<usize>,
+    succ_offsets: Vec<usize>,
+    succ_targets: Vec<usize>,
+}
+
+impl PrecedenceGraph {
+    pub fn new<B, S, D>(mdl: &BlockModel<B, S>, dep: &D) -> Self
+    where
+        B: BlockInterface + Sync,
+        S: BlockStorage<B> + Sync,
+        D: BlockDependenceInterface + Sync,
+    {
+        let mut inds = mdl
+            .par_indexed_iter()
+            .map(|(ind, _)| ind)
+            .collect::<Vec<_>>();
+        inds.par_sort_unstable();
+
+        let preds = inds
+            .par_iter()
+            .map(|ind| {
+                let mut preds = dep
+                    .inds(mdl, *ind)
+                    .iter()
+                    .filter(|p| *p != ind)
+                    .filter_map(|p| inds.binary_search(p).ok())
+                    .collect::<Vec<_>>();
+                preds.sort_unstable();
+                preds.dedup();
+                preds
+            })
+            .collect::<Vec<_>>();
+
+        Self::from_preds(inds, preds)
+    }
+
+    //graph over sorted block indices from the predecessor nodes of each node
+    pub fn from_preds(inds: Vec<BlockIndex>, preds: Vec<Vec<usize>>) -> Self {
+        assert_eq!(
+            inds.len(),
+            preds.len(),
+            "every node needs a predecessor list"
+        );
+        assert!(
+            inds.windows(2).all(|w| w[0] < w[1]),
+            "block indices must be sorted and unique"
+        );
+        assert!(
+            preds.iter().flatten().all(|p| *p < inds.len()),
+            "predecessor outside graph"
+        );
+
+        let mut pred_offsets = Vec::with_capacity(inds.len() + 1);
+        pred_offsets.push(0);
+        for p in preds.iter() {
+            pred_offsets.push(pred_offsets.last().unwrap() + p.len());
+        }
+        let pred_targets = preds.into_iter().flatten().collect::<Vec<_>>();
+
+        //transpose by counting arcs into each node
+        let mut succ_offsets = vec![0; inds.len() + 1];
+        pred_targets.iter().for_each(|p| succ_offsets[p + 1] += 1);
+        for n in 0..inds.len() {
+            succ_offsets[n + 1] += succ_offsets[n];
+        }
+
+        let mut fill = succ_offsets.clone();
+        let mut succ_targets = vec![0; pred_targets.len()];
+        for n in 0..inds.len() {
+            for &p in pred_targets[pred_offsets[n]..pred_offsets[n + 1]].iter() {
+                succ_targets[fill[p]] = n;
+                fill[p] += 1;
+            }
+        }
+
+        Self {
+            inds,
+            pred_offsets,
+            pred_targets,
+            succ_offsets,
+            succ_targets,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.inds.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inds.is_empty()
+    }
+
+    pub fn num_arcs(&self) -> usize {
+        self.pred_targets.len()
+    }
+
+    pub fn inds(&self) -> &[BlockIndex] {
+        &self.inds
+    }
+
+    //block index of node n
+    pub fn ind(&self, n: usize) -> BlockIndex {
+        self.inds[n]
+    }
+
+    //node of block index, None if the block is not present
+    pub fn node(&self, ind: BlockIndex) -> Option<usize> {
+        self.inds.binary_search(&ind).ok()
+    }
+
+    //nodes that must be removed before node n
+    pub fn preds(&self, n: usize) -> &[usize] {
+        &self.pred_targets[self.pred_offsets[n]..self.pred_offsets[n + 1]]
+    }
+
+    //nodes that require node n to be removed first
+    pub fn succs(&self, n: usize) -> &[usize] {
+        &self.succ_targets[self.succ_offsets[n]..self.succ_offsets[n + 1]]
+    }
+
+    pub fn num_preds(&self, n: usize) -> usize {
+        self.pred_offsets[n + 1] - self.pred_offsets[n]
+    }
+
+    pub fn num_succs(&self, n: usize) -> usize {
+        self.succ_offsets[n + 1] - self.succ_offsets[n]
+    }
+
+    //n and every node that must be removed before it
+    pub fn cone(&self, n: usize) -> Vec<usize> {
+        self.reach(n, |m| self.preds(m))
+    }
+
+    //n and every node whose cone contains n
+    pub fn inverse_cone(&self, n: usize) -> Vec<usize> {
+        self.reach(n, |m| self.succs(m))
+    }
+
+    fn reach<'a, F>(&'a self, n: usize, next: F) -> Vec<usize>
+    where
+        F: Fn(usize) -> &'a [usize],
+    {
+        let mut seen = vec![false; self.len()];
+        let mut reached = vec![n];
+        seen[n] = true;
+
+        let mut head = 0;
+        while head < reached.len() {
+            for &m in next(reached[head]) {
+                if !seen[m] {
+                    seen[m] = true;
+                    reached.push(m);
+                }
+            }
+            head += 1;
+        }
+        reached
+    }
+}
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface + Sync,
+    S: BlockStorage<B> + Sync,
+{
+    pub fn precedence_graph<D: BlockDependenceInterface + Sync>(&self, dep: &D) -> PrecedenceGraph {
+        PrecedenceGraph::new(self, dep)
+    }
+}
+
+//explicit arcs as a precedence rule, blocks outside the graph have no predecessors
+impl BlockDependenceInterface for PrecedenceGraph {
+    fn inds<B: BlockInterface, S: BlockStorage<B>>(
+        &self,
+        _mdl: &BlockModel<B, S>,
+        ind: BlockIndex,
+    ) -> Vec<BlockIndex> {
+        self.node(ind)
+            .map(|n| self.preds(n).iter().map(|p| self.inds[*p]).collect())
+            .unwrap_or_default()
+    }
+}
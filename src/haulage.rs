@@ -0,0 +1,183 @@
+use rayon::prelude::*;
+
+use crate::block::{BlockCoordinates, BlockIndex, BlockInterface};
+use crate::block_model::BlockModel;
+use crate::economics::EconomicModel;
+use crate::storage::BlockStorage;
+
+use std::collections::HashMap;
+
+//approximate haul from a block to the cheapest exit, distances in metres
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Haul {
+    //index of the exit in Haulage::exits
+    pub exit: usize,
+    //rise from the block to the exit, negative when hauling down
+    pub lift: f64,
+    //length along the ramp and along flat roads
+    pub ramp_distance: f64,
+    pub flat_distance: f64,
+    //cost per tonne
+    pub cost: f64,
+}
+
+impl Haul {
+    pub fn distance(&self) -> f64 {
+        self.ramp_distance + self.flat_distance
+    }
+}
+
+//haulage to surface exits such as ramp portals, the bench elevation difference is climbed on
+//a ramp at ramp_grade, rise over run, which also covers that much horizontal distance, the
+//rest of the plan distance is flat road, costs are per tonne km
+#[derive(Debug, Clone, PartialEq)]
+pub struct Haulage {
+    pub exits: Vec<[f64; 3]>,
+    pub ramp_grade: f64,
+    pub flat_cost: f64,
+    pub ramp_cost: f64,
+    //fixed cost per tonne per metre of lift, e.g. the fuel of climbing loaded
+    pub lift_cost: f64,
+}
+
+impl Haulage {
+    pub fn new(exits: Vec<[f64; 3]>, ramp_grade: f64, flat_cost: f64, ramp_cost: f64) -> Self {
+        assert!(!exits.is_empty(), "haulage needs at least one exit");
+        assert!(ramp_grade > 0.0, "ramp grade must be positive");
+        Self {
+            exits,
+            ramp_grade,
+            flat_cost,
+            ramp_cost,
+            lift_cost: 0.0,
+        }
+    }
+
+    pub fn with_lift_cost(mut self, lift_cost: f64) -> Self {
+        self.lift_cost = lift_cost;
+        self
+    }
+
+    //haul from a point to one exit
+    pub fn haul_to(&self, at: BlockCoordinates, exit: usize) -> Haul {
+        let [x, y, z] = self.exits[exit];
+        let plan = ((x - at.x as f64).powi(2) + (y - at.y as f64).powi(2)).sqrt();
+        let lift = z - at.z as f64;
+        let run = lift.abs() / self.ramp_grade;
+        let ramp_distance = (run * run + lift * lift).sqrt();
+        let flat_distance = (plan - run).max(0.0);
+        let cost = (self.ramp_cost * ramp_distance + self.flat_cost * flat_distance) / 1000.0
+            + self.lift_cost * lift.max(0.0);
+        Haul {
+            exit,
+            lift,
+            ramp_distance,
+            flat_distance,
+            cost,
+        }
+    }
+
+    //haul to the cheapest exit
+    pub fn haul(&self, at: BlockCoordinates) -> Haul {
+        (0..self.exits.len())
+            .map(|e| self.haul_to(at, e))
+            .min_by(|a, b| a.cost.total_cmp(&b.cost))
+            .unwrap()
+    }
+
+    //economics with the haul cost of each block added to its mining cost
+    pub fn adjusted<E>(&self, econ: E) -> HaulageAdjusted<'_, E> {
+        HaulageAdjusted {
+            econ,
+            haulage: self,
+        }
+    }
+}
+
+//economic model whose mining cost includes haulage from the block centroid
+#[derive(Debug, Clone)]
+pub struct HaulageAdjusted<'a, E> {
+    pub econ: E,
+    pub haulage: &'a Haulage,
+}
+
+impl<B, E> EconomicModel<B> for HaulageAdjusted<'_, E>
+where
+    B: BlockInterface,
+    E: EconomicModel<B>,
+{
+    fn tonnage(&self, block: &B) -> f64 {
+        self.econ.tonnage(block)
+    }
+
+    fn grade(&self, block: &B) -> f64 {
+        self.econ.grade(block)
+    }
+
+    fn price(&self, block: &B) -> f64 {
+        self.econ.price(block)
+    }
+
+    fn selling_cost(&self, block: &B) -> f64 {
+        self.econ.selling_cost(block)
+    }
+
+    fn recovery(&self, block: &B) -> f64 {
+        self.econ.recovery(block)
+    }
+
+    fn mining_cost(&self, block: &B) -> f64 {
+        self.econ.mining_cost(block) + self.haulage.haul(block.coordinates()).cost
+    }
+
+    fn processing_cost(&self, block: &B) -> f64 {
+        self.econ.processing_cost(block)
+    }
+}
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface + Sync,
+    S: BlockStorage<B> + Sync,
+{
+    //haul of every present block from its centroid, computed in parallel
+    pub fn haulage(&self, haulage: &Haulage) -> HashMap<BlockIndex, Haul> {
+        self.par_indexed_iter()
+            .map(|(ind, _)| (ind, haulage.haul(self.index_to_coordinates(ind))))
+            .collect()
+    }
+
+    //mining cost adjustment factor of every present block, the mining cost with haulage over
+    //the mining cost of econ, NaN where econ has no mining cost
+    pub fn haulage_factors<E>(&self, econ: &E, haulage: &Haulage) -> HashMap<BlockIndex, f64>
+    where
+        E: EconomicModel<B> + Sync,
+    {
+        self.par_indexed_iter()
+            .map(|(ind, b)| {
+                let base = econ.mining_cost(b);
+                let haul = haulage.haul(self.index_to_coordinates(ind)).cost;
+                let factor = if base == 0.0 {
+                    f64::NAN
+                } else {
+                    (base + haul) / base
+                };
+                (ind, factor)
+            })
+            .collect()
+    }
+
+    //store the haul of every present block through set
+    pub fn assign_haulage<F>(&mut self, haulage: &Haulage, set: F)
+    where
+        B: Send,
+        F: Fn(&mut B, Haul) + Sync,
+    {
+        let hauls = self.haulage(haulage);
+        self.par_indexed_iter_mut().for_each(|(ind, b)| {
+            if let Some(h) = hauls.get(&ind) {
+                set(b, *h);
+            }
+        });
+    }
+}
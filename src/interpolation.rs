@@ -0,0 +1,65 @@
+use crate::block::{BlockCoordinates, BlockIndex, BlockInterface};
+use crate::block_model::BlockModel;
+use crate::storage::BlockStorage;
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface,
+    S: BlockStorage<B>,
+{
+    //value of attr at a world point, trilinear between the 8 surrounding block centroids,
+    //the nearest block is used where a corner is outside the model, missing or NaN, None
+    //outside the model extent or when the nearest block is missing or NaN
+    pub fn sample_at<F>(&self, x: f64, y: f64, z: f64, attr: F) -> Option<f64>
+    where
+        F: Fn(&B) -> f64,
+    {
+        let frame = self.frame();
+        let local = frame.to_local(BlockCoordinates {
+            x: x as f32,
+            y: y as f32,
+            z: z as f32,
+        });
+        let size = self.block_size();
+        let size = [size.x_size, size.y_size, size.z_size];
+        let dims = self.dims();
+
+        //position on the centroid lattice
+        let u = [0, 1, 2].map(|a| local[a] as f64 / size[a] as f64);
+        if (0..3).any(|a| !(u[a] >= -0.5 && u[a] <= dims[a] as f64 - 0.5)) {
+            return None;
+        }
+
+        let value = |i: i64, j: i64, k: i64| {
+            if i < 0 || j < 0 || k < 0 {
+                return None;
+            }
+            let ind = BlockIndex {
+                i: i as usize,
+                j: j as usize,
+                k: k as usize,
+            };
+            if !frame.contains(ind) {
+                return None;
+            }
+            self.block(ind).map(&attr).filter(|v| !v.is_nan())
+        };
+
+        let base = u.map(|c| c.floor());
+        let t = [0, 1, 2].map(|a| u[a] - base[a]);
+        let base = base.map(|c| c as i64);
+        let mut sum = 0.0;
+        for corner in 0..8 {
+            let o = [corner & 1, (corner >> 1) & 1, (corner >> 2) & 1];
+            let Some(v) = value(base[0] + o[0], base[1] + o[1], base[2] + o[2]) else {
+                let nearest = [0, 1, 2].map(|a| (u[a].round() as i64).clamp(0, dims[a] as i64 - 1));
+                return value(nearest[0], nearest[1], nearest[2]);
+            };
+            let w = (0..3)
+                .map(|a| if o[a] == 1 { t[a] } else { 1.0 - t[a] })
+                .product::<f64>();
+            sum += w * v;
+        }
+        Some(sum)
+    }
+}
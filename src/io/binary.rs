@@ -0,0 +1,167 @@
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::block::{BlockCoordinates, BlockIndex, BlockInterface, BlockSize};
+use crate::block_model::BlockModel;
+use crate::frame::{FrameRotation, ModelFrame};
+use crate::storage::BlockStorage;
+
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+type SendError = Box<dyn Error + Send + Sync>;
+
+const MAGIC: &[u8; 4] = b"BMUB";
+//bumped whenever the layout below changes, older files are rejected
+const VERSION: u32 = 2;
+//blocks per independently compressed chunk
+const CHUNK_SIZE: usize = 1 << 16;
+
+//options controlling binary export
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BinaryWriteOptions {
+    //zstd level, higher is smaller and slower
+    pub level: i32,
+}
+
+impl Default for BinaryWriteOptions {
+    fn default() -> Self {
+        Self { level: 3 }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Header {
+    origin: [f32; 3],
+    block_size: [f32; 3],
+    dims: [u64; 3],
+    rotation: [f32; 2],
+    epsg: Option<u32>,
+    num_blocks: u64,
+    num_chunks: u64,
+}
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface + Serialize + Sync,
+    S: BlockStorage<B>,
+{
+    //magic and version, an uncompressed frame header, then length prefixed chunks of
+    //bincode encoded (index, block) pairs each compressed with zstd in parallel
+    pub fn save<P: AsRef<Path>>(
+        &self,
+        path: P,
+        options: BinaryWriteOptions,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut blocks = self.indexed_iter().collect::<Vec<_>>();
+        blocks.sort_by_key(|(ind, _)| *ind);
+
+        let chunks = blocks
+            .par_chunks(CHUNK_SIZE)
+            .map(|chunk| -> Result<Vec<u8>, SendError> {
+                let raw = bincode::serialize(chunk)?;
+                Ok(zstd::bulk::compress(&raw, options.level)?)
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e as Box<dyn Error>)?;
+
+        let frame = self.frame();
+        let header = Header {
+            origin: [frame.origin.x, frame.origin.y, frame.origin.z],
+            block_size: [
+                frame.block_size.x_size,
+                frame.block_size.y_size,
+                frame.block_size.z_size,
+            ],
+            dims: frame.dims.map(|d| d as u64),
+            rotation: [frame.rotation.azimuth, frame.rotation.dip],
+            epsg: frame.epsg,
+            num_blocks: blocks.len() as u64,
+            num_chunks: chunks.len() as u64,
+        };
+
+        let mut w = BufWriter::new(File::create(path)?);
+        w.write_all(MAGIC)?;
+        w.write_all(&VERSION.to_le_bytes())?;
+        bincode::serialize_into(&mut w, &header)?;
+        for chunk in chunks.iter() {
+            w.write_all(&(chunk.len() as u64).to_le_bytes())?;
+            w.write_all(chunk)?;
+        }
+
+        w.flush()?;
+        Ok(())
+    }
+}
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface + Send,
+    S: BlockStorage<B>,
+{
+    //read a model written by save, blocks must not rely on self describing serde features
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn Error>> {
+        let mut r = BufReader::new(File::open(path)?);
+
+        let mut magic = [0; 4];
+        r.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err("not a block model binary file".into());
+        }
+
+        let mut version = [0; 4];
+        r.read_exact(&mut version)?;
+        let version = u32::from_le_bytes(version);
+        if version != VERSION {
+            return Err(format!(
+                "unsupported binary version {}, expected {}",
+                version, VERSION
+            )
+            .into());
+        }
+
+        let header: Header = bincode::deserialize_from(&mut r)?;
+
+        let mut chunks = Vec::with_capacity(header.num_chunks as usize);
+        for _ in 0..header.num_chunks {
+            let mut len = [0; 8];
+            r.read_exact(&mut len)?;
+            let mut chunk = vec![0; u64::from_le_bytes(len) as usize];
+            r.read_exact(&mut chunk)?;
+            chunks.push(chunk);
+        }
+
+        let decoded = chunks
+            .into_par_iter()
+            .map(|chunk| -> Result<Vec<(BlockIndex, B)>, SendError> {
+                let raw = zstd::stream::decode_all(chunk.as_slice())?;
+                Ok(bincode::deserialize(&raw)?)
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e as Box<dyn Error>)?;
+
+        let (inds, blocks): (Vec<_>, Vec<_>) = decoded.into_iter().flatten().unzip();
+        if blocks.len() as u64 != header.num_blocks {
+            return Err("binary file is truncated".into());
+        }
+
+        let [x, y, z] = header.origin;
+        let [x_size, y_size, z_size] = header.block_size;
+        let [azimuth, dip] = header.rotation;
+        let frame = ModelFrame::new(
+            BlockCoordinates { x, y, z },
+            BlockSize {
+                x_size,
+                y_size,
+                z_size,
+            },
+            header.dims.map(|d| d as usize),
+        )
+        .with_rotation(FrameRotation::new(azimuth, dip))
+        .with_epsg(header.epsg);
+
+        Ok(Self::from_parts(blocks, inds, frame)?)
+    }
+}
@@ -0,0 +1,199 @@
+use serde::Serialize;
+
+use crate::block::{BlockIndex, BlockInterface};
+use crate::block_model::BlockModel;
+use crate::io::NullPolicy;
+use crate::storage::BlockStorage;
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::io::{self, BufRead};
+use std::path::Path;
+
+//options controlling csv export
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CsvWriteOptions {
+    //prepend i, j, k columns to every record
+    pub include_index: bool,
+    pub delimiter: u8,
+}
+
+impl Default for CsvWriteOptions {
+    fn default() -> Self {
+        Self {
+            include_index: false,
+            delimiter: b',',
+        }
+    }
+}
+
+//options controlling csv import into an existing block struct
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvImportConfig {
+    pub delimiter: u8,
+    //lines skipped before the header row
+    pub skip_rows: usize,
+    //file header name to block field name, unmapped headers are used as is
+    pub columns: HashMap<String, String>,
+    //values treated as missing, read as empty fields so optional fields become None
+    pub missing: Vec<String>,
+    //missing values recognised by value, cleared like missing
+    pub nulls: NullPolicy,
+}
+
+impl Default for CsvImportConfig {
+    fn default() -> Self {
+        Self {
+            delimiter: b',',
+            skip_rows: 0,
+            columns: HashMap::new(),
+            missing: Vec::new(),
+            nulls: NullPolicy::default(),
+        }
+    }
+}
+
+impl CsvImportConfig {
+    //read header as field
+    pub fn with_column(mut self, header: &str, field: &str) -> Self {
+        self.columns.insert(header.to_string(), field.to_string());
+        self
+    }
+
+    pub fn with_missing(mut self, sentinel: &str) -> Self {
+        self.missing.push(sentinel.to_string());
+        self
+    }
+
+    pub fn with_null_policy(mut self, nulls: NullPolicy) -> Self {
+        self.nulls = nulls;
+        self
+    }
+
+    //deserialize every record of the file with headers renamed and sentinels cleared
+    pub(crate) fn read<B, P>(&self, path: P) -> Result<Vec<B>, Box<dyn Error>>
+    where
+        B: BlockInterface,
+        P: AsRef<Path>,
+    {
+        let mut reader = io::BufReader::new(std::fs::File::open(path)?);
+        let mut line = String::new();
+        for _ in 0..self.skip_rows {
+            line.clear();
+            reader.read_line(&mut line)?;
+        }
+
+        let mut rdr = csv::ReaderBuilder::new()
+            .delimiter(self.delimiter)
+            .trim(csv::Trim::All)
+            .from_reader(reader);
+
+        let headers = rdr
+            .headers()?
+            .iter()
+            .map(|h| self.columns.get(h).map_or(h, |f| f.as_str()))
+            .collect::<csv::StringRecord>();
+
+        let mut blocks = Vec::new();
+        for result in rdr.records() {
+            let record = result?;
+            let record = if self.missing.is_empty() && self.nulls == NullPolicy::default() {
+                record
+            } else {
+                record
+                    .iter()
+                    .map(|v| {
+                        if self.missing.iter().any(|m| m == v) || self.nulls.is_null_text(v) {
+                            ""
+                        } else {
+                            v
+                        }
+                    })
+                    .collect()
+            };
+            blocks.push(record.deserialize(Some(&headers))?);
+        }
+
+        Ok(blocks)
+    }
+}
+
+#[derive(Serialize)]
+struct IndexColumns {
+    i: usize,
+    j: usize,
+    k: usize,
+}
+
+impl From<BlockIndex> for IndexColumns {
+    fn from(ind: BlockIndex) -> Self {
+        Self {
+            i: ind.i,
+            j: ind.j,
+            k: ind.k,
+        }
+    }
+}
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface + Serialize,
+    S: BlockStorage<B>,
+{
+    //write every present block as a csv record, ordered by index
+    pub fn to_csv<P: AsRef<Path>>(
+        &self,
+        path: P,
+        options: CsvWriteOptions,
+    ) -> Result<(), Box<dyn Error>> {
+        let file = std::fs::File::create(path)?;
+        self.to_csv_writer(io::BufWriter::new(file), options)
+    }
+
+    pub fn to_csv_writer<W: io::Write>(
+        &self,
+        writer: W,
+        options: CsvWriteOptions,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut wtr = csv::WriterBuilder::new()
+            .delimiter(options.delimiter)
+            .from_writer(writer);
+
+        let mut blocks = self.indexed_iter().collect::<Vec<_>>();
+        blocks.sort_by_key(|(ind, _)| *ind);
+
+        for (ind, b) in blocks {
+            if options.include_index {
+                wtr.serialize((IndexColumns::from(ind), b))?;
+            } else {
+                wtr.serialize(b)?;
+            }
+        }
+
+        wtr.flush()?;
+        Ok(())
+    }
+}
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface,
+    S: BlockStorage<B>,
+{
+    pub fn from_unindexed_csv_with<P: AsRef<Path>>(
+        path: P,
+        config: &CsvImportConfig,
+    ) -> Result<Self, Box<dyn Error>> {
+        Ok(Self::from_unindexed(config.read(path)?)?)
+    }
+
+    pub fn from_indexed_csv_with<P: AsRef<Path>>(
+        path: P,
+        config: &CsvImportConfig,
+    ) -> Result<Self, Box<dyn Error>> {
+        let blocks = config.read::<B, _>(path)?;
+        let inds = blocks.iter().map(|b| b.index()).collect();
+
+        Ok(Self::from_indexed(blocks, inds)?)
+    }
+}
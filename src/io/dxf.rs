@@ -0,0 +1,238 @@
+use crate::geometry::mesh::TriangleMesh;
+use crate::geometry::polygon::{MultiPolygon, Polygon};
+
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+//entity of the ENTITIES section with its group codes in file order
+struct Entity {
+    kind: String,
+    pairs: Vec<(u32, String)>,
+}
+
+impl Entity {
+    fn value(&self, code: u32) -> Option<&str> {
+        self.pairs
+            .iter()
+            .find(|(c, _)| *c == code)
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn number(&self, code: u32) -> Result<f64, Box<dyn Error>> {
+        Ok(self.value(code).map(str::parse).transpose()?.unwrap_or(0.0))
+    }
+
+    fn flags(&self) -> Result<u32, Box<dyn Error>> {
+        Ok(self.value(70).map(str::parse).transpose()?.unwrap_or(0))
+    }
+
+    fn layer(&self) -> &str {
+        self.value(8).unwrap_or("0")
+    }
+}
+
+fn entities(text: &str) -> Result<Vec<Entity>, Box<dyn Error>> {
+    let mut lines = text.lines().map(str::trim);
+    let mut entities = Vec::new();
+    let mut current: Option<Entity> = None;
+    let mut section = String::new();
+    while let (Some(code), Some(value)) = (lines.next(), lines.next()) {
+        let code = code.parse::<u32>()?;
+        if code == 0 {
+            if let Some(e) = current.take() {
+                if e.kind == "SECTION" {
+                    section = e.value(2).unwrap_or_default().to_string();
+                } else if section == "ENTITIES" {
+                    entities.push(e);
+                }
+            }
+            if value == "ENDSEC" {
+                section.clear();
+            }
+            current = Some(Entity {
+                kind: value.to_string(),
+                pairs: Vec::new(),
+            });
+        } else if let Some(e) = current.as_mut() {
+            e.pairs.push((code, value.to_string()));
+        }
+    }
+    Ok(entities)
+}
+
+//LWPOLYLINE or POLYLINE string
+#[derive(Debug, Clone, PartialEq)]
+pub struct DxfPolyline {
+    pub layer: String,
+    pub closed: bool,
+    pub points: Vec<[f64; 3]>,
+}
+
+impl DxfPolyline {
+    //plan outline, z is dropped
+    pub fn to_polygon(&self) -> Polygon {
+        Polygon::new(self.points.iter().map(|p| [p[0], p[1]]).collect())
+    }
+}
+
+//design strings and faces of an ascii dxf, blocks are not expanded
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DxfDrawing {
+    pub polylines: Vec<DxfPolyline>,
+    //3DFACE entities and polyface meshes, quadrilateral faces are split in two
+    pub mesh: TriangleMesh,
+}
+
+impl DxfDrawing {
+    //entities on layer, or on every layer if None
+    pub fn from_path<P: AsRef<Path>>(path: P, layer: Option<&str>) -> Result<Self, Box<dyn Error>> {
+        let text = fs::read_to_string(path)?;
+        let mut drawing = Self::default();
+
+        let mut entities = entities(&text)?.into_iter().peekable();
+        while let Some(e) = entities.next() {
+            //vertices belong to the preceding POLYLINE until SEQEND
+            let mut vertices = Vec::new();
+            if e.kind == "POLYLINE" {
+                while let Some(v) = entities.next_if(|v| v.kind == "VERTEX") {
+                    vertices.push(v);
+                }
+                entities.next_if(|v| v.kind == "SEQEND");
+            }
+            if layer.is_some_and(|l| l != e.layer()) {
+                continue;
+            }
+
+            match e.kind.as_str() {
+                "LWPOLYLINE" => {
+                    let elevation = e.number(38)?;
+                    let mut points: Vec<[f64; 3]> = Vec::new();
+                    for (code, value) in e.pairs.iter() {
+                        match code {
+                            10 => points.push([value.parse()?, 0.0, elevation]),
+                            20 => {
+                                if let Some(p) = points.last_mut() {
+                                    p[1] = value.parse()?;
+                                }
+                            }
+                            _ => (),
+                        }
+                    }
+                    drawing.polylines.push(DxfPolyline {
+                        layer: e.layer().to_string(),
+                        closed: e.flags()? & 1 != 0,
+                        points,
+                    });
+                }
+                "POLYLINE" if e.flags()? & 64 != 0 => {
+                    //polyface mesh, face records hold 1 based vertex indices, negative for
+                    //invisible edges
+                    let base = drawing.mesh.vertices.len();
+                    for v in vertices.iter() {
+                        let flags = v.flags()?;
+                        if flags & 128 != 0 && flags & 64 == 0 {
+                            let mut face = Vec::new();
+                            for code in 71..=74 {
+                                let n = v.number(code)?.abs() as usize;
+                                if n > 0 {
+                                    face.push(base + n - 1);
+                                }
+                            }
+                            if face.len() >= 3 {
+                                drawing.mesh.triangles.push([face[0], face[1], face[2]]);
+                            }
+                            if face.len() == 4 {
+                                drawing.mesh.triangles.push([face[0], face[2], face[3]]);
+                            }
+                        } else {
+                            drawing.mesh.vertices.push([
+                                v.number(10)?,
+                                v.number(20)?,
+                                v.number(30)?,
+                            ]);
+                        }
+                    }
+                }
+                "POLYLINE" => {
+                    let points = vertices
+                        .iter()
+                        .map(|v| Ok([v.number(10)?, v.number(20)?, v.number(30)?]))
+                        .collect::<Result<Vec<_>, Box<dyn Error>>>()?;
+                    drawing.polylines.push(DxfPolyline {
+                        layer: e.layer().to_string(),
+                        closed: e.flags()? & 1 != 0,
+                        points,
+                    });
+                }
+                "3DFACE" => {
+                    //corner c coordinate d has group code 10 * (d + 1) + c, triangular faces
+                    //repeat the third corner
+                    let mut face = [[0.0; 3]; 4];
+                    for (c, corner) in face.iter_mut().enumerate() {
+                        for (d, x) in corner.iter_mut().enumerate() {
+                            *x = e.number(10 * (d as u32 + 1) + c as u32)?;
+                        }
+                    }
+                    let base = drawing.mesh.vertices.len();
+                    drawing.mesh.vertices.extend(face);
+                    drawing.mesh.triangles.push([base, base + 1, base + 2]);
+                    if face[3] != face[2] {
+                        drawing.mesh.triangles.push([base, base + 2, base + 3]);
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        if let Some(bad) = drawing
+            .mesh
+            .triangles
+            .iter()
+            .flatten()
+            .find(|v| **v >= drawing.mesh.vertices.len())
+        {
+            return Err(format!("polyface references missing vertex {bad}").into());
+        }
+        Ok(drawing)
+    }
+
+    pub fn closed_polylines(&self) -> impl Iterator<Item = &DxfPolyline> {
+        self.polylines
+            .iter()
+            .filter(|p| p.closed && p.points.len() >= 3)
+    }
+
+    //plan area of the closed polylines, a ring inside an odd number of others is a hole of
+    //the smallest ring around it, so nested strings give islands in holes
+    pub fn footprint(&self) -> MultiPolygon {
+        let rings = self
+            .closed_polylines()
+            .map(|p| p.to_polygon())
+            .collect::<Vec<_>>();
+        let parents = (0..rings.len())
+            .map(|n| {
+                let [x, y] = rings[n].exterior[0];
+                (0..rings.len())
+                    .filter(|m| *m != n && rings[*m].contains(x, y))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let depth = parents.iter().map(|p| p.len()).collect::<Vec<_>>();
+
+        let mut polygons = Vec::new();
+        let mut index = vec![None; rings.len()];
+        for n in (0..rings.len()).filter(|n| depth[*n] % 2 == 0) {
+            index[n] = Some(polygons.len());
+            polygons.push(rings[n].clone());
+        }
+        for n in (0..rings.len()).filter(|n| depth[*n] % 2 == 1) {
+            if let Some(parent) = parents[n].iter().find(|m| depth[**m] + 1 == depth[n]) {
+                polygons[index[*parent].unwrap()]
+                    .holes
+                    .push(rings[n].exterior.clone());
+            }
+        }
+        MultiPolygon::new(polygons)
+    }
+}
@@ -0,0 +1,301 @@
+use crate::block::{BlockCoordinates, BlockIndex, BlockInterface, BlockSize, NamedAttribute};
+use crate::block_model::BlockModel;
+use crate::frame::ModelFrame;
+use crate::io::{GridBlock, NullPolicy};
+use crate::storage::BlockStorage;
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+//options controlling gslib grid import
+#[derive(Debug, Clone, PartialEq)]
+pub struct GslibOptions {
+    //missing values, at or below -999 by default
+    pub nulls: NullPolicy,
+    //realization to read from files holding several stacked grids
+    pub realization: usize,
+}
+
+impl Default for GslibOptions {
+    fn default() -> Self {
+        Self {
+            nulls: NullPolicy::at_or_below(-999.0),
+            realization: 0,
+        }
+    }
+}
+
+//gslib / geo-eas file, a title line, the number of variables, one name per line
+//and whitespace separated records
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct GslibFile {
+    pub title: String,
+    pub names: Vec<String>,
+    pub columns: Vec<Vec<f64>>,
+}
+
+impl GslibFile {
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn Error>> {
+        Self::from_reader(BufReader::new(File::open(path)?))
+    }
+
+    pub fn from_reader<R: BufRead>(reader: R) -> Result<Self, Box<dyn Error>> {
+        let mut lines = reader.lines();
+        let mut next = || lines.next().ok_or("unexpected end of gslib header");
+
+        let title = next()??.trim().to_string();
+        let num_vars = next()??
+            .split_whitespace()
+            .next()
+            .ok_or("missing gslib variable count")?
+            .parse::<usize>()?;
+
+        let mut names = Vec::with_capacity(num_vars);
+        for _ in 0..num_vars {
+            names.push(next()??.trim().to_string());
+        }
+
+        let mut columns = vec![Vec::new(); num_vars];
+        for line in lines {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let mut n = 0;
+            for value in line.split_whitespace() {
+                if n == num_vars {
+                    break;
+                }
+                columns[n].push(value.parse::<f64>()?);
+                n += 1;
+            }
+            if n != num_vars {
+                return Err(format!("gslib record has {} of {} values", n, num_vars).into());
+            }
+        }
+
+        Ok(Self {
+            title,
+            names,
+            columns,
+        })
+    }
+
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn Error>> {
+        self.to_writer(BufWriter::new(File::create(path)?))
+    }
+
+    pub fn to_writer<W: Write>(&self, mut w: W) -> Result<(), Box<dyn Error>> {
+        assert_eq!(
+            self.names.len(),
+            self.columns.len(),
+            "every column needs a name"
+        );
+
+        writeln!(w, "{}", self.title)?;
+        writeln!(w, "{}", self.names.len())?;
+        for name in self.names.iter() {
+            writeln!(w, "{}", name)?;
+        }
+
+        for row in 0..self.len() {
+            let record = self
+                .columns
+                .iter()
+                .map(|c| c[row].to_string())
+                .collect::<Vec<_>>();
+            writeln!(w, "{}", record.join(" "))?;
+        }
+
+        w.flush()?;
+        Ok(())
+    }
+
+    //number of records
+    pub fn len(&self) -> usize {
+        self.columns.first().map_or(0, |c| c.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn column(&self, name: &str) -> Option<&[f64]> {
+        let n = self.names.iter().position(|c| c == name)?;
+        Some(&self.columns[n])
+    }
+
+    //grid definition trailing the title as nx ny nz xmn ymn zmn xsiz ysiz zsiz [nreal],
+    //returned with the number of realizations
+    pub fn grid(&self) -> Option<(ModelFrame, usize)> {
+        let tokens = self.title.split_whitespace().collect::<Vec<_>>();
+
+        for len in [10, 9] {
+            let Some(start) = tokens.len().checked_sub(len) else {
+                continue;
+            };
+            let params = &tokens[start..];
+            let counts = params[..3]
+                .iter()
+                .map(|t| t.parse::<usize>().ok())
+                .collect::<Option<Vec<_>>>();
+            let values = params[3..9]
+                .iter()
+                .map(|t| t.parse::<f32>().ok())
+                .collect::<Option<Vec<_>>>();
+            let nreal = match params.get(9) {
+                Some(t) => t.parse::<usize>().ok(),
+                None => Some(1),
+            };
+
+            if let (Some(c), Some(v), Some(nreal)) = (counts, values, nreal) {
+                let frame = ModelFrame::new(
+                    BlockCoordinates {
+                        x: v[0],
+                        y: v[1],
+                        z: v[2],
+                    },
+                    BlockSize {
+                        x_size: v[3],
+                        y_size: v[4],
+                        z_size: v[5],
+                    },
+                    [c[0], c[1], c[2]],
+                );
+                return Some((frame, nreal));
+            }
+        }
+
+        None
+    }
+}
+
+//grid definition in gslib parameter order
+fn grid_title(frame: &ModelFrame) -> String {
+    let [nx, ny, nz] = frame.dims;
+    let (o, s) = (frame.origin, frame.block_size);
+    format!(
+        "{} {} {} {} {} {} {} {} {}",
+        nx, ny, nz, o.x, o.y, o.z, s.x_size, s.y_size, s.z_size
+    )
+}
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface,
+    S: BlockStorage<B>,
+{
+    //read a gslib grid, values run x fastest then y then z starting at the first cell centroid
+    //frame gives the grid definition, otherwise it is parsed from the title
+    //a block is built for every cell with at least one non-missing value
+    pub fn from_gslib_grid<P, F>(
+        path: P,
+        frame: Option<ModelFrame>,
+        options: GslibOptions,
+        build: F,
+    ) -> Result<Self, Box<dyn Error>>
+    where
+        P: AsRef<Path>,
+        F: Fn(&GridBlock) -> B,
+    {
+        let file = GslibFile::read(path)?;
+        let frame = match frame {
+            Some(frame) => frame,
+            None => file.grid().ok_or("gslib title has no grid definition")?.0,
+        };
+
+        let num_cells = frame.num_cells();
+        if num_cells == 0 || file.len() % num_cells != 0 {
+            return Err(format!(
+                "gslib file has {} records, not a multiple of {} cells",
+                file.len(),
+                num_cells
+            )
+            .into());
+        }
+        if options.realization >= file.len() / num_cells {
+            return Err(format!("gslib file has no realization {}", options.realization).into());
+        }
+
+        let [nx, ny, _] = frame.dims;
+        let start = options.realization * num_cells;
+
+        let mut blocks = Vec::new();
+        let mut inds = Vec::new();
+        for n in 0..num_cells {
+            let attributes = file
+                .names
+                .iter()
+                .zip(file.columns.iter())
+                .map(|(name, c)| (name, c[start + n]))
+                .filter(|(_, v)| !options.nulls.is_null(*v) && v.is_finite())
+                .map(|(name, v)| (name.clone(), v))
+                .collect::<HashMap<_, _>>();
+            if attributes.is_empty() {
+                continue;
+            }
+
+            let index = BlockIndex {
+                i: n % nx,
+                j: (n / nx) % ny,
+                k: n / (nx * ny),
+            };
+            let cell = GridBlock {
+                index,
+                coordinates: frame.index_to_coordinates(index),
+                size: frame.block_size,
+                attributes,
+            };
+
+            let mut block = build(&cell);
+            block.set_index(index);
+            blocks.push(block);
+            inds.push(index);
+        }
+
+        Ok(Self::from_parts(blocks, inds, frame)?)
+    }
+
+    //write every cell of the frame x fastest, empty cells hold missing
+    //the grid definition is appended to the title, the epsg code has no place in it and is lost
+    pub fn to_gslib_grid<P: AsRef<Path>>(
+        &self,
+        path: P,
+        title: &str,
+        attributes: &[NamedAttribute<B>],
+        missing: f64,
+    ) -> Result<(), Box<dyn Error>> {
+        let frame = self.frame();
+        if frame.is_rotated() {
+            return Err("gslib grids cannot represent rotated models".into());
+        }
+
+        let [ni, nj, nk] = self.dims();
+        let cells = (0..nk)
+            .flat_map(|k| (0..nj).flat_map(move |j| (0..ni).map(move |i| BlockIndex { i, j, k })))
+            .collect::<Vec<_>>();
+
+        let file = GslibFile {
+            title: format!("{} {}", title, grid_title(frame)),
+            names: attributes
+                .iter()
+                .map(|(name, _)| name.to_string())
+                .collect(),
+            columns: attributes
+                .iter()
+                .map(|(_, attr)| {
+                    cells
+                        .iter()
+                        .map(|ind| self.block(*ind).map_or(missing, attr))
+                        .collect()
+                })
+                .collect(),
+        };
+
+        file.write(path)
+    }
+}
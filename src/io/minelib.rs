@@ -0,0 +1,335 @@
+use ndarray::Array3;
+use serde::{Deserialize, Serialize};
+
+use crate::block::{BlockCoordinates, BlockIndex, BlockInterface, BlockSize};
+use crate::block_model::BlockModel;
+use crate::frame::ModelFrame;
+use crate::graph::PrecedenceGraph;
+use crate::storage::BlockStorage;
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+//minelib block, coordinates are the integer grid position of the block with unit size
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MineLibBlock {
+    pub id: usize,
+    pub x: i64,
+    pub y: i64,
+    pub z: i64,
+    //trailing columns of the block file
+    pub attributes: Vec<f64>,
+    //objective coefficient from the problem file
+    pub value: f64,
+    //resource constraint coefficients from the problem file, zero if unlisted
+    pub resources: Vec<f64>,
+    i: usize,
+    j: usize,
+    k: usize,
+}
+
+impl BlockInterface for MineLibBlock {
+    fn coordinates(&self) -> BlockCoordinates {
+        BlockCoordinates {
+            x: self.x as f32,
+            y: self.y as f32,
+            z: self.z as f32,
+        }
+    }
+
+    fn size(&self) -> BlockSize {
+        unit()
+    }
+
+    fn index(&self) -> BlockIndex {
+        BlockIndex {
+            i: self.i,
+            j: self.j,
+            k: self.k,
+        }
+    }
+
+    fn set_index(&mut self, ind: BlockIndex) {
+        self.i = ind.i;
+        self.j = ind.j;
+        self.k = ind.k;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MineLibProblemType {
+    //ultimate pit limit
+    Upit,
+    //constrained pit limit, a precedence and resource constrained schedule
+    Cpit,
+}
+
+//bounds on the use of a resource in a period, unbounded sides are infinite
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ResourceLimit {
+    pub resource: usize,
+    pub period: usize,
+    pub min: f64,
+    pub max: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MineLibProblem {
+    pub name: String,
+    pub kind: MineLibProblemType,
+    pub num_periods: usize,
+    pub num_resources: usize,
+    pub discount_rate: f64,
+    pub limits: Vec<ResourceLimit>,
+    //objective coefficient by block id
+    pub values: HashMap<usize, f64>,
+    //resource coefficients by block id and resource
+    pub coefficients: HashMap<(usize, usize), f64>,
+}
+
+//minelib instance, blocks are indexed from the minimum grid position and the graph
+//holds the arcs of the precedence file
+pub struct MineLibInstance<S: BlockStorage<MineLibBlock> = Array3<Option<MineLibBlock>>> {
+    pub model: BlockModel<MineLibBlock, S>,
+    pub graph: PrecedenceGraph,
+    pub problem: MineLibProblem,
+}
+
+fn unit() -> BlockSize {
+    BlockSize {
+        x_size: 1.0,
+        y_size: 1.0,
+        z_size: 1.0,
+    }
+}
+
+//data lines of a minelib file, skipping blank and % comment lines
+fn data_lines(text: &str) -> impl Iterator<Item = (usize, Vec<&str>)> {
+    text.lines()
+        .enumerate()
+        .map(|(n, line)| (n + 1, line.split('%').next().unwrap_or("").trim()))
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(n, line)| (n, line.split_whitespace().collect()))
+}
+
+fn parse<T: std::str::FromStr>(token: Option<&&str>, line: usize) -> Result<T, Box<dyn Error>> {
+    token
+        .and_then(|t| t.parse().ok())
+        .ok_or_else(|| format!("invalid minelib value on line {}", line).into())
+}
+
+impl MineLibProblem {
+    //upit or cpit problem file, pcpsp destinations are not supported
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn Error>> {
+        let text = fs::read_to_string(path)?;
+
+        let mut problem = Self {
+            name: String::new(),
+            kind: MineLibProblemType::Upit,
+            num_periods: 1,
+            num_resources: 0,
+            discount_rate: 0.0,
+            limits: Vec::new(),
+            values: HashMap::new(),
+            coefficients: HashMap::new(),
+        };
+
+        let mut section = "";
+        for (n, tokens) in data_lines(&text) {
+            let line = tokens.join(" ");
+            if line == "EOF" {
+                break;
+            }
+
+            if let Some((key, value)) = line.split_once(':') {
+                let value = value.trim();
+                let value_token = value.split_whitespace().next();
+                match key.trim() {
+                    "NAME" => problem.name = value.to_string(),
+                    "TYPE" => {
+                        problem.kind = match value {
+                            "UPIT" => MineLibProblemType::Upit,
+                            "CPIT" => MineLibProblemType::Cpit,
+                            other => {
+                                return Err(format!("unsupported minelib problem {}", other).into())
+                            }
+                        }
+                    }
+                    "NPERIODS" => problem.num_periods = parse(value_token.as_ref(), n)?,
+                    "NRESOURCE_SIDE_CONSTRAINTS" => {
+                        problem.num_resources = parse(value_token.as_ref(), n)?
+                    }
+                    "DISCOUNT_RATE" => problem.discount_rate = parse(value_token.as_ref(), n)?,
+                    _ => {}
+                }
+                section = match key.trim() {
+                    "OBJECTIVE_FUNCTION" => "objective",
+                    "RESOURCE_CONSTRAINT_LIMITS" => "limits",
+                    "RESOURCE_CONSTRAINT_COEFFICIENTS" => "coefficients",
+                    _ => "",
+                };
+                continue;
+            }
+
+            match section {
+                "objective" => {
+                    problem
+                        .values
+                        .insert(parse(tokens.first(), n)?, parse(tokens.get(1), n)?);
+                }
+                "limits" => {
+                    let resource = parse(tokens.first(), n)?;
+                    //a period of * applies the limit to every period
+                    let periods = match tokens.get(1) {
+                        Some(&"*") => (0..problem.num_periods).collect(),
+                        t => vec![parse(t, n)?],
+                    };
+                    let value = parse::<f64>(tokens.get(3), n)?;
+                    let (min, max) = match tokens.get(2) {
+                        Some(&"L") => (f64::NEG_INFINITY, value),
+                        Some(&"G") => (value, f64::INFINITY),
+                        Some(&"I") => (value, parse(tokens.get(4), n)?),
+                        _ => return Err(format!("invalid constraint type on line {}", n).into()),
+                    };
+                    problem
+                        .limits
+                        .extend(periods.into_iter().map(|period| ResourceLimit {
+                            resource,
+                            period,
+                            min,
+                            max,
+                        }));
+                }
+                "coefficients" => {
+                    problem.coefficients.insert(
+                        (parse(tokens.first(), n)?, parse(tokens.get(1), n)?),
+                        parse(tokens.get(2), n)?,
+                    );
+                }
+                _ => return Err(format!("unexpected minelib data on line {}", n).into()),
+            }
+        }
+
+        Ok(problem)
+    }
+}
+
+impl<S: BlockStorage<MineLibBlock>> MineLibInstance<S> {
+    //read the block, precedence and problem files of an instance
+    pub fn read<P: AsRef<Path>>(
+        blocks: P,
+        precedence: P,
+        problem: P,
+    ) -> Result<Self, Box<dyn Error>> {
+        let problem = MineLibProblem::read(problem)?;
+
+        let text = fs::read_to_string(blocks)?;
+        let mut blocks = Vec::new();
+        for (n, tokens) in data_lines(&text) {
+            let id = parse(tokens.first(), n)?;
+            let attributes = tokens
+                .get(4..)
+                .unwrap_or_default()
+                .iter()
+                .map(|t| parse(Some(t), n))
+                .collect::<Result<Vec<f64>, _>>()?;
+
+            blocks.push(MineLibBlock {
+                id,
+                x: parse(tokens.get(1), n)?,
+                y: parse(tokens.get(2), n)?,
+                z: parse(tokens.get(3), n)?,
+                attributes,
+                value: problem.values.get(&id).copied().unwrap_or(0.0),
+                resources: (0..problem.num_resources)
+                    .map(|r| problem.coefficients.get(&(id, r)).copied().unwrap_or(0.0))
+                    .collect(),
+                i: 0,
+                j: 0,
+                k: 0,
+            });
+        }
+
+        let min = |c: fn(&MineLibBlock) -> i64| blocks.iter().map(c).min().unwrap_or(0);
+        let max = |c: fn(&MineLibBlock) -> i64| blocks.iter().map(c).max().unwrap_or(-1);
+        let (x0, y0, z0) = (min(|b| b.x), min(|b| b.y), min(|b| b.z));
+        let dims = [
+            (max(|b| b.x) - x0 + 1) as usize,
+            (max(|b| b.y) - y0 + 1) as usize,
+            (max(|b| b.z) - z0 + 1) as usize,
+        ];
+
+        let mut inds = Vec::with_capacity(blocks.len());
+        let mut ind_of = HashMap::with_capacity(blocks.len());
+        for b in blocks.iter_mut() {
+            let ind = BlockIndex {
+                i: (b.x - x0) as usize,
+                j: (b.y - y0) as usize,
+                k: (b.z - z0) as usize,
+            };
+            b.set_index(ind);
+            inds.push(ind);
+            if ind_of.insert(b.id, ind).is_some() {
+                return Err(format!("duplicate minelib block id {}", b.id).into());
+            }
+        }
+
+        let origin = BlockCoordinates {
+            x: x0 as f32,
+            y: y0 as f32,
+            z: z0 as f32,
+        };
+        let frame = ModelFrame::new(origin, unit(), dims);
+        let model = BlockModel::from_parts(blocks, inds, frame)?;
+
+        //precedence lines are id, number of predecessors, predecessor ids
+        let text = fs::read_to_string(precedence)?;
+        let mut arcs = HashMap::new();
+        for (n, tokens) in data_lines(&text) {
+            let id: usize = parse(tokens.first(), n)?;
+            let count: usize = parse(tokens.get(1), n)?;
+            if tokens.len() != count + 2 {
+                return Err(format!("predecessor count mismatch on line {}", n).into());
+            }
+
+            let ind = *ind_of.get(&id).ok_or(format!("unknown block {}", id))?;
+            let preds = tokens[2..]
+                .iter()
+                .map(|t| {
+                    let p: usize = parse(Some(t), n)?;
+                    ind_of
+                        .get(&p)
+                        .copied()
+                        .ok_or_else(|| format!("unknown block {}", p).into())
+                })
+                .collect::<Result<Vec<_>, Box<dyn Error>>>()?;
+            arcs.insert(ind, preds);
+        }
+
+        let mut graph_inds = ind_of.into_values().collect::<Vec<_>>();
+        graph_inds.sort_unstable();
+        let preds = graph_inds
+            .iter()
+            .map(|ind| {
+                let mut preds = arcs
+                    .get(ind)
+                    .into_iter()
+                    .flatten()
+                    .filter(|p| *p != ind)
+                    .map(|p| graph_inds.binary_search(p).unwrap())
+                    .collect::<Vec<_>>();
+                preds.sort_unstable();
+                preds.dedup();
+                preds
+            })
+            .collect();
+
+        Ok(Self {
+            model,
+            graph: PrecedenceGraph::from_preds(graph_inds, preds),
+            problem,
+        })
+    }
+}
@@ -0,0 +1,104 @@
+use crate::block::{BlockCoordinates, BlockIndex, BlockSize};
+
+use serde::{Deserialize, Deserializer};
+
+use std::collections::HashMap;
+
+#[cfg(feature = "binary")]
+pub mod binary;
+pub mod csv;
+#[cfg(feature = "dxf")]
+pub mod dxf;
+pub mod gslib;
+pub mod minelib;
+#[cfg(feature = "omf")]
+pub mod omf;
+#[cfg(feature = "parquet")]
+pub mod parquet;
+pub mod raster;
+pub mod vtk;
+
+//values read as missing, imports map them to None for optional fields and NaN for numbers
+//so statistics and estimation skip them, NaN and empty fields are always missing
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct NullPolicy {
+    //numbers matched exactly, e.g. -99 and -999
+    pub sentinels: Vec<f64>,
+    //numbers at or below are missing, e.g. the gslib convention
+    pub at_or_below: Option<f64>,
+    //text matched after trimming, e.g. NA
+    pub text: Vec<String>,
+}
+
+impl NullPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //-99, -999 and -9999 with NA, N/A and null
+    pub fn common() -> Self {
+        Self {
+            sentinels: vec![-99.0, -999.0, -9999.0],
+            at_or_below: None,
+            text: ["NA", "N/A", "null"].map(String::from).to_vec(),
+        }
+    }
+
+    pub fn at_or_below(limit: f64) -> Self {
+        Self {
+            at_or_below: Some(limit),
+            ..Self::default()
+        }
+    }
+
+    pub fn with_sentinel(mut self, value: f64) -> Self {
+        self.sentinels.push(value);
+        self
+    }
+
+    pub fn with_text(mut self, text: &str) -> Self {
+        self.text.push(text.to_string());
+        self
+    }
+
+    pub fn is_null(&self, value: f64) -> bool {
+        value.is_nan()
+            || self.sentinels.contains(&value)
+            || self.at_or_below.is_some_and(|l| value <= l)
+    }
+
+    //whether a raw field is missing, numeric fields are checked as numbers
+    pub fn is_null_text(&self, field: &str) -> bool {
+        let field = field.trim();
+        field.is_empty()
+            || self.text.iter().any(|t| t == field)
+            || field.parse::<f64>().is_ok_and(|v| self.is_null(v))
+    }
+
+    //NaN for missing values
+    pub fn clean(&self, value: f64) -> f64 {
+        if self.is_null(value) {
+            f64::NAN
+        } else {
+            value
+        }
+    }
+
+    pub fn to_option(&self, value: f64) -> Option<f64> {
+        (!self.is_null(value)).then_some(value)
+    }
+}
+
+//serde helper reading missing fields of a plain float as NaN, use with
+//#[serde(deserialize_with = "nan_if_missing")]
+pub fn nan_if_missing<'de, D: Deserializer<'de>>(de: D) -> Result<f64, D::Error> {
+    Ok(Option::<f64>::deserialize(de)?.unwrap_or(f64::NAN))
+}
+
+//cell read from a gridded file, attributes hold the non-missing values
+pub struct GridBlock {
+    pub index: BlockIndex,
+    pub coordinates: BlockCoordinates,
+    pub size: BlockSize,
+    pub attributes: HashMap<String, f64>,
+}
@@ -0,0 +1,380 @@
+use arrow::array::{Array, Float64Array};
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::record_batch::RecordBatch;
+use bytes::Bytes;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::arrow::ArrowWriter;
+use serde::{Deserialize, Serialize};
+use zip::write::SimpleFileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+use crate::block::{BlockCoordinates, BlockIndex, BlockInterface, BlockSize, NamedAttribute};
+use crate::block_model::BlockModel;
+use crate::frame::{FrameRotation, ModelFrame};
+use crate::io::GridBlock;
+use crate::storage::BlockStorage;
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::File;
+use std::io::{Read, Seek, Write};
+use std::path::Path;
+
+//omf v2 container, a zip archive holding index.json and one parquet file per array
+const INDEX: &str = "index.json";
+
+#[derive(Serialize, Deserialize)]
+struct Project {
+    name: String,
+    #[serde(default)]
+    description: String,
+    elements: Vec<Element>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Element {
+    name: String,
+    #[serde(default)]
+    description: String,
+    geometry: Geometry,
+    #[serde(default)]
+    attributes: Vec<Attribute>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type")]
+#[allow(clippy::large_enum_variant)]
+enum Geometry {
+    BlockModel {
+        //minimum corner of the grid
+        origin: [f64; 3],
+        orientation: Orientation,
+        grid: Grid,
+        #[serde(default)]
+        subblocks: Option<serde_json::Value>,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Orientation {
+    u: [f64; 3],
+    v: [f64; 3],
+    w: [f64; 3],
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type")]
+enum Grid {
+    Regular {
+        size: [f64; 3],
+        count: [u32; 3],
+    },
+    Tensor {
+        u: ArrayRef,
+        v: ArrayRef,
+        w: ArrayRef,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+struct Attribute {
+    name: String,
+    #[serde(default)]
+    description: String,
+    location: String,
+    data: AttributeData,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type")]
+enum AttributeData {
+    Number {
+        values: ArrayRef,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ArrayRef {
+    filename: String,
+    item_count: u64,
+}
+
+fn write_array<W: Write + Seek>(
+    zip: &mut ZipWriter<W>,
+    filename: &str,
+    values: Vec<Option<f64>>,
+) -> Result<ArrayRef, Box<dyn Error>> {
+    let schema = Schema::new(vec![Field::new("number", DataType::Float64, true)]);
+    let item_count = values.len() as u64;
+    let batch = RecordBatch::try_new(
+        schema.clone().into(),
+        vec![std::sync::Arc::new(Float64Array::from(values))],
+    )?;
+
+    let mut buf = Vec::new();
+    let mut writer = ArrowWriter::try_new(&mut buf, schema.into(), None)?;
+    writer.write(&batch)?;
+    writer.close()?;
+
+    zip.start_file(filename, SimpleFileOptions::default())?;
+    zip.write_all(&buf)?;
+
+    Ok(ArrayRef {
+        filename: filename.to_string(),
+        item_count,
+    })
+}
+
+fn read_array<R: Read + Seek>(
+    zip: &mut ZipArchive<R>,
+    array: &ArrayRef,
+) -> Result<Vec<Option<f64>>, Box<dyn Error>> {
+    let mut buf = Vec::new();
+    zip.by_name(&array.filename)?.read_to_end(&mut buf)?;
+
+    let mut values = Vec::with_capacity(array.item_count as usize);
+    for batch in ParquetRecordBatchReaderBuilder::try_new(Bytes::from(buf))?.build()? {
+        let batch = batch?;
+        let column = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .ok_or("omf array is not a float64 column")?;
+        values.extend((0..column.len()).map(|n| column.is_valid(n).then(|| column.value(n))));
+    }
+
+    if values.len() as u64 != array.item_count {
+        return Err(format!("omf array {} has the wrong length", array.filename).into());
+    }
+    Ok(values)
+}
+
+//common spacing of a tensor axis
+fn uniform_spacing(spacing: &[Option<f64>]) -> Result<f64, Box<dyn Error>> {
+    let first = spacing
+        .first()
+        .copied()
+        .flatten()
+        .ok_or("empty tensor axis")?;
+    if spacing
+        .iter()
+        .all(|s| s.is_some_and(|s| (s - first).abs() <= 1e-9 * first.abs()))
+    {
+        Ok(first)
+    } else {
+        Err("tensor grids with varying block sizes are not supported".into())
+    }
+}
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface,
+    S: BlockStorage<B>,
+{
+    //world directions of the model axes
+    fn omf_orientation(&self) -> Orientation {
+        let frame = self.frame();
+        let zero = frame.to_world([0.0; 3]);
+        let axis = |offset: [f32; 3]| {
+            let p = frame.to_world(offset);
+            [
+                (p.x - zero.x) as f64,
+                (p.y - zero.y) as f64,
+                (p.z - zero.z) as f64,
+            ]
+        };
+
+        Orientation {
+            u: axis([1.0, 0.0, 0.0]),
+            v: axis([0.0, 1.0, 0.0]),
+            w: axis([0.0, 0.0, 1.0]),
+        }
+    }
+
+    //write the model as a single regular block model element, cell arrays run i fastest
+    //and empty cells are null, omf has no coordinate system field so the epsg code is lost
+    pub fn to_omf<P: AsRef<Path>>(
+        &self,
+        path: P,
+        name: &str,
+        attributes: &[NamedAttribute<B>],
+    ) -> Result<(), Box<dyn Error>> {
+        let mut zip = ZipWriter::new(File::create(path)?);
+
+        let [ni, nj, nk] = self.dims();
+        let cells = (0..nk)
+            .flat_map(|k| (0..nj).flat_map(move |j| (0..ni).map(move |i| BlockIndex { i, j, k })))
+            .collect::<Vec<_>>();
+
+        let mut omf_attributes = Vec::new();
+        for (n, (attr_name, attr)) in attributes.iter().enumerate() {
+            let values = cells.iter().map(|ind| self.block(*ind).map(attr)).collect();
+            omf_attributes.push(Attribute {
+                name: attr_name.to_string(),
+                description: String::new(),
+                location: "Primitives".to_string(),
+                data: AttributeData::Number {
+                    values: write_array(&mut zip, &format!("{}.parquet", n), values)?,
+                },
+            });
+        }
+
+        let size = self.block_size();
+        let corner =
+            self.frame()
+                .to_world([-size.x_size / 2.0, -size.y_size / 2.0, -size.z_size / 2.0]);
+        let element = Element {
+            name: name.to_string(),
+            description: String::new(),
+            geometry: Geometry::BlockModel {
+                origin: [corner.x as f64, corner.y as f64, corner.z as f64],
+                orientation: self.omf_orientation(),
+                grid: Grid::Regular {
+                    size: [size.x_size as f64, size.y_size as f64, size.z_size as f64],
+                    count: [ni as u32, nj as u32, nk as u32],
+                },
+                subblocks: None,
+            },
+            attributes: omf_attributes,
+        };
+
+        let project = Project {
+            name: name.to_string(),
+            description: String::new(),
+            elements: vec![element],
+        };
+
+        zip.start_file(INDEX, SimpleFileOptions::default())?;
+        serde_json::to_writer(&mut zip, &project)?;
+        zip.finish()?;
+        Ok(())
+    }
+
+    //read the block model element called element, or the first one, building a block for
+    //every cell with at least one non-null attribute (every cell if there are none)
+    //sub-blocked models are not supported
+    pub fn from_omf<P, F>(path: P, element: Option<&str>, build: F) -> Result<Self, Box<dyn Error>>
+    where
+        P: AsRef<Path>,
+        F: Fn(&GridBlock) -> B,
+    {
+        let mut zip = ZipArchive::new(File::open(path)?)?;
+        let project: Project = serde_json::from_reader(zip.by_name(INDEX)?)?;
+
+        let found = project
+            .elements
+            .into_iter()
+            .filter(|e| matches!(e.geometry, Geometry::BlockModel { .. }))
+            .find(|e| element.is_none_or(|name| e.name == name))
+            .ok_or("no matching block model element")?;
+
+        let Geometry::BlockModel {
+            origin,
+            orientation,
+            grid,
+            subblocks,
+        } = found.geometry
+        else {
+            unreachable!()
+        };
+        if subblocks.is_some_and(|s| !s.is_null()) {
+            return Err("sub-blocked omf models are not supported".into());
+        }
+
+        let (size, count) = match grid {
+            Grid::Regular { size, count } => (size, count.map(|c| c as usize)),
+            Grid::Tensor { u, v, w } => {
+                let axes = [u, v, w];
+                let mut size = [0.0; 3];
+                let mut count = [0; 3];
+                for d in 0..3 {
+                    let spacing = read_array(&mut zip, &axes[d])?;
+                    size[d] = uniform_spacing(&spacing)?;
+                    count[d] = spacing.len();
+                }
+                (size, count)
+            }
+        };
+
+        //model axes must be an unscaled rotation expressible as azimuth then dip
+        let [u, v, w] = [orientation.u, orientation.v, orientation.w];
+        let rotation = FrameRotation::new(
+            (-u[1]).atan2(u[0]).to_degrees() as f32,
+            (-v[2]).asin().to_degrees() as f32,
+        );
+        let block_size = BlockSize {
+            x_size: size[0] as f32,
+            y_size: size[1] as f32,
+            z_size: size[2] as f32,
+        };
+        let corner = BlockCoordinates {
+            x: origin[0] as f32,
+            y: origin[1] as f32,
+            z: origin[2] as f32,
+        };
+        let corner_frame = ModelFrame::new(corner, block_size, count).with_rotation(rotation);
+        let frame = ModelFrame::new(
+            corner_frame.to_world([
+                block_size.x_size / 2.0,
+                block_size.y_size / 2.0,
+                block_size.z_size / 2.0,
+            ]),
+            block_size,
+            count,
+        )
+        .with_rotation(rotation);
+
+        let check = rotation.axes();
+        let close = |a: [f64; 3], b: [f32; 3]| (0..3).all(|d| (a[d] - b[d] as f64).abs() < 1e-4);
+        if !(close(u, check[0]) && close(v, check[1]) && close(w, check[2])) {
+            return Err("omf orientation is not a supported rotation".into());
+        }
+
+        let num_cells = count.iter().product::<usize>();
+        let mut columns = Vec::new();
+        for attr in found.attributes {
+            if let AttributeData::Number { values } = attr.data {
+                let values = read_array(&mut zip, &values)?;
+                if values.len() != num_cells {
+                    return Err(format!("attribute {} is not defined on blocks", attr.name).into());
+                }
+                columns.push((attr.name, values));
+            }
+        }
+
+        let mut blocks = Vec::new();
+        let mut inds = Vec::new();
+        for n in 0..num_cells {
+            let attributes = columns
+                .iter()
+                .filter_map(|(name, values)| values[n].map(|v| (name.clone(), v)))
+                .collect::<HashMap<_, _>>();
+            if !columns.is_empty() && attributes.is_empty() {
+                continue;
+            }
+
+            let index = BlockIndex {
+                i: n % count[0],
+                j: (n / count[0]) % count[1],
+                k: n / (count[0] * count[1]),
+            };
+            let cell = GridBlock {
+                index,
+                coordinates: frame.index_to_coordinates(index),
+                size: block_size,
+                attributes,
+            };
+
+            let mut block = build(&cell);
+            block.set_index(index);
+            blocks.push(block);
+            inds.push(index);
+        }
+
+        Ok(Self::from_parts(blocks, inds, frame)?)
+    }
+}
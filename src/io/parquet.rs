@@ -0,0 +1,133 @@
+use arrow::datatypes::FieldRef;
+use arrow::record_batch::RecordBatch;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use rayon::prelude::*;
+use serde::Serialize;
+use serde_arrow::schema::{SchemaLike, TracingOptions};
+
+use crate::block::BlockInterface;
+use crate::block_model::BlockModel;
+use crate::storage::BlockStorage;
+
+use std::error::Error;
+use std::fs::File;
+use std::path::Path;
+
+type SendError = Box<dyn Error + Send + Sync>;
+
+//options controlling parquet export
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParquetWriteOptions {
+    //rows per row group, row groups are encoded in parallel
+    pub row_group_size: usize,
+}
+
+impl Default for ParquetWriteOptions {
+    fn default() -> Self {
+        Self {
+            row_group_size: 1 << 20,
+        }
+    }
+}
+
+//decode every row group of a parquet file in parallel, in file order
+fn read_blocks<B, P>(path: P) -> Result<Vec<B>, Box<dyn Error>>
+where
+    B: BlockInterface + Send,
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let num_row_groups = ParquetRecordBatchReaderBuilder::try_new(File::open(path)?)?
+        .metadata()
+        .num_row_groups();
+
+    let groups = (0..num_row_groups)
+        .into_par_iter()
+        .map(|rg| -> Result<Vec<B>, SendError> {
+            let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(path)?)?
+                .with_row_groups(vec![rg])
+                .build()?;
+
+            let mut blocks = Vec::new();
+            for batch in reader {
+                blocks.extend(serde_arrow::from_record_batch::<Vec<B>>(&batch?)?);
+            }
+            Ok(blocks)
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e as Box<dyn Error>)?;
+
+    Ok(groups.into_iter().flatten().collect())
+}
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface + Send,
+    S: BlockStorage<B>,
+{
+    //block fields map to columns, indices are computed from block coordinates
+    pub fn from_parquet<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn Error>> {
+        Ok(Self::from_unindexed(read_blocks(path)?)?)
+    }
+
+    //indices are taken from the stored blocks
+    pub fn from_indexed_parquet<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn Error>> {
+        let blocks = read_blocks::<B, _>(path)?;
+        let inds = blocks.iter().map(|b| b.index()).collect();
+
+        Ok(Self::from_indexed(blocks, inds)?)
+    }
+}
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface + Serialize + Sync,
+    S: BlockStorage<B>,
+{
+    //write every present block as a parquet row, ordered by index
+    pub fn to_parquet<P: AsRef<Path>>(
+        &self,
+        path: P,
+        options: ParquetWriteOptions,
+    ) -> Result<(), Box<dyn Error>> {
+        assert!(
+            options.row_group_size > 0,
+            "row group size must be positive"
+        );
+
+        let mut blocks = self.indexed_iter().collect::<Vec<_>>();
+        blocks.sort_by_key(|(ind, _)| *ind);
+        let blocks = blocks.into_iter().map(|(_, b)| b).collect::<Vec<_>>();
+
+        //schema traced from the blocks themselves so optional and enum fields resolve
+        let fields = Vec::<FieldRef>::from_samples(&blocks, TracingOptions::default())?;
+
+        let batches = blocks
+            .par_chunks(options.row_group_size)
+            .map(|chunk| -> Result<RecordBatch, SendError> {
+                Ok(serde_arrow::to_record_batch(&fields, &chunk)?)
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e as Box<dyn Error>)?;
+
+        let props = WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .set_max_row_group_size(options.row_group_size)
+            .build();
+
+        let schema = batches.first().ok_or("no blocks to write")?.schema();
+        let mut writer = ArrowWriter::try_new(File::create(path)?, schema, Some(props))?;
+
+        //one row group per encoded chunk
+        for batch in batches.iter() {
+            writer.write(batch)?;
+            writer.flush()?;
+        }
+
+        writer.close()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,261 @@
+use ndarray::Array2;
+
+use crate::block::{BlockIndex, BlockInterface};
+use crate::block_model::BlockModel;
+use crate::storage::BlockStorage;
+
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+//plan grid of one value per i, j column of an undipped model, NaN cells have no data
+#[derive(Debug, Clone, PartialEq)]
+pub struct Raster {
+    //outer corner of cell 0, 0, the lower left corner of unrotated grids
+    pub corner: [f64; 2],
+    pub cell_size: [f64; 2],
+    //bearing of the columns of the grid in degrees, the grid turns clockwise about corner
+    pub azimuth: f64,
+    pub values: Array2<f64>,
+    //projected coordinate reference system of the corner, None for a local grid
+    pub epsg: Option<u32>,
+}
+
+//tiff field types
+const SHORT: u16 = 3;
+const LONG: u16 = 4;
+const ASCII: u16 = 2;
+const DOUBLE: u16 = 12;
+
+impl Raster {
+    pub fn dims(&self) -> [usize; 2] {
+        let shape = self.values.shape();
+        [shape[0], shape[1]]
+    }
+
+    //value of the cell at row r counted from the high j edge, north on unrotated grids, as
+    //rasters are stored
+    fn row_value(&self, r: usize, i: usize) -> f64 {
+        self.values[[i, self.dims()[1] - 1 - r]]
+    }
+
+    //affine map from pixel column and row, counted from the high j edge, to world xy as
+    //[x0, x per column, x per row, y0, y per column, y per row]
+    fn pixel_transform(&self) -> [f64; 6] {
+        let [sx, sy] = self.cell_size;
+        let ny = self.dims()[1] as f64;
+        let (sa, ca) = self.azimuth.to_radians().sin_cos();
+        [
+            self.corner[0] + ny * sy * sa,
+            sx * ca,
+            -sy * sa,
+            self.corner[1] + ny * sy * ca,
+            -sx * sa,
+            -sy * ca,
+        ]
+    }
+
+    //esri ascii grid, dx and dy replace cellsize for rectangular cells, the format has no
+    //rotation so rotated grids are rejected
+    pub fn to_ascii_grid<P: AsRef<Path>>(
+        &self,
+        path: P,
+        nodata: f64,
+    ) -> Result<(), Box<dyn Error>> {
+        if self.azimuth != 0.0 {
+            return Err("ascii grids cannot represent rotated rasters".into());
+        }
+        let mut w = BufWriter::new(File::create(path)?);
+        let [nx, ny] = self.dims();
+        writeln!(w, "ncols {nx}")?;
+        writeln!(w, "nrows {ny}")?;
+        writeln!(w, "xllcorner {}", self.corner[0])?;
+        writeln!(w, "yllcorner {}", self.corner[1])?;
+        if self.cell_size[0] == self.cell_size[1] {
+            writeln!(w, "cellsize {}", self.cell_size[0])?;
+        } else {
+            writeln!(w, "dx {}", self.cell_size[0])?;
+            writeln!(w, "dy {}", self.cell_size[1])?;
+        }
+        writeln!(w, "NODATA_value {nodata}")?;
+        for r in 0..ny {
+            let row = (0..nx)
+                .map(|i| {
+                    let v = self.row_value(r, i);
+                    if v.is_nan() { nodata } else { v }.to_string()
+                })
+                .collect::<Vec<_>>();
+            writeln!(w, "{}", row.join(" "))?;
+        }
+        w.flush()?;
+        Ok(())
+    }
+
+    //single band float32 geotiff georeferenced by tie point and pixel scale, or by the model
+    //transformation for rotated grids, no data cells hold nodata as written to the gdal nodata
+    //tag, the epsg code is written as the projected coordinate system, undefined without one
+    pub fn to_geotiff<P: AsRef<Path>>(&self, path: P, nodata: f64) -> Result<(), Box<dyn Error>> {
+        let [nx, ny] = self.dims();
+        let epsg = self
+            .epsg
+            .map(|code| {
+                u16::try_from(code).map_err(|_| format!("epsg code {code} does not fit a geokey"))
+            })
+            .transpose()?;
+
+        let shorts = |v: &[u16]| v.iter().flat_map(|x| x.to_le_bytes()).collect::<Vec<_>>();
+        let longs = |v: &[u32]| v.iter().flat_map(|x| x.to_le_bytes()).collect::<Vec<_>>();
+        let doubles = |v: &[f64]| v.iter().flat_map(|x| x.to_le_bytes()).collect::<Vec<_>>();
+        let mut nodata_text = nodata.to_string().into_bytes();
+        nodata_text.push(0);
+
+        //version, key count, then model type projected, raster type pixel is area and the
+        //projected coordinate system
+        let mut geokeys = vec![1, 1, 0, 2, 1024, 0, 1, 1, 1025, 0, 1, 1];
+        if let Some(code) = epsg {
+            geokeys[3] += 1;
+            geokeys.extend([3072, 0, 1, code]);
+        }
+
+        //(tag, type, count, value bytes), sorted by tag, the strip offset is set below
+        let mut fields: Vec<(u16, u16, u32, Vec<u8>)> = vec![
+            (256, LONG, 1, longs(&[nx as u32])),
+            (257, LONG, 1, longs(&[ny as u32])),
+            (258, SHORT, 1, shorts(&[32])),
+            (259, SHORT, 1, shorts(&[1])),
+            (262, SHORT, 1, shorts(&[1])),
+            (273, LONG, 1, longs(&[0])),
+            (277, SHORT, 1, shorts(&[1])),
+            (278, LONG, 1, longs(&[ny as u32])),
+            (279, LONG, 1, longs(&[(nx * ny * 4) as u32])),
+            (284, SHORT, 1, shorts(&[1])),
+            (339, SHORT, 1, shorts(&[3])),
+        ];
+        let [x0, xc, xr, y0, yc, yr] = self.pixel_transform();
+        if self.azimuth == 0.0 {
+            let scale = doubles(&[self.cell_size[0], self.cell_size[1], 0.0]);
+            fields.push((33550, DOUBLE, 3, scale));
+            fields.push((33922, DOUBLE, 6, doubles(&[0.0, 0.0, 0.0, x0, y0, 0.0])));
+        } else {
+            //row major 4 by 4 matrix from pixel column, row and height to world
+            let matrix = [
+                xc, xr, 0.0, x0, yc, yr, 0.0, y0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0,
+            ];
+            fields.push((34264, DOUBLE, 16, doubles(&matrix)));
+        }
+        fields.push((34735, SHORT, geokeys.len() as u32, shorts(&geokeys)));
+        fields.push((42113, ASCII, nodata_text.len() as u32, nodata_text));
+
+        //header, directory, then values too large for their entry, then the image
+        let directory = 8 + 2 + 12 * fields.len() + 4;
+        let mut extra = Vec::new();
+        let mut offsets = Vec::new();
+        for (_, _, _, bytes) in fields.iter() {
+            if bytes.len() > 4 {
+                offsets.push(Some(directory + extra.len()));
+                extra.extend(bytes);
+                if extra.len() % 2 == 1 {
+                    extra.push(0);
+                }
+            } else {
+                offsets.push(None);
+            }
+        }
+        let image = directory + extra.len();
+        fields[5].3 = longs(&[image as u32]);
+
+        let mut w = BufWriter::new(File::create(path)?);
+        w.write_all(b"II")?;
+        w.write_all(&42u16.to_le_bytes())?;
+        w.write_all(&8u32.to_le_bytes())?;
+        w.write_all(&(fields.len() as u16).to_le_bytes())?;
+        for ((tag, kind, count, bytes), offset) in fields.iter().zip(offsets.iter()) {
+            w.write_all(&tag.to_le_bytes())?;
+            w.write_all(&kind.to_le_bytes())?;
+            w.write_all(&count.to_le_bytes())?;
+            match offset {
+                Some(o) => w.write_all(&(*o as u32).to_le_bytes())?,
+                None => {
+                    let mut value = [0u8; 4];
+                    value[..bytes.len()].copy_from_slice(bytes);
+                    w.write_all(&value)?;
+                }
+            }
+        }
+        w.write_all(&0u32.to_le_bytes())?;
+        w.write_all(&extra)?;
+        for r in 0..ny {
+            for i in 0..nx {
+                let v = self.row_value(r, i);
+                let v = if v.is_nan() { nodata } else { v };
+                w.write_all(&(v as f32).to_le_bytes())?;
+            }
+        }
+        w.flush()?;
+        Ok(())
+    }
+}
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface,
+    S: BlockStorage<B>,
+{
+    //plan grid on the i, j lattice, rotated with the azimuth of the frame, a dipped frame has
+    //no plan grid
+    pub(crate) fn raster(&self, values: Array2<f64>) -> Result<Raster, Box<dyn Error>> {
+        let frame = self.frame();
+        if frame.rotation.dip != 0.0 {
+            return Err("rasters cannot represent dipped models".into());
+        }
+        let size = self.block_size();
+        let corner = frame.to_world([-size.x_size / 2.0, -size.y_size / 2.0, 0.0]);
+        Ok(Raster {
+            corner: [corner.x as f64, corner.y as f64],
+            cell_size: [size.x_size as f64, size.y_size as f64],
+            azimuth: frame.rotation.azimuth as f64,
+            values,
+            epsg: self.epsg(),
+        })
+    }
+
+    //attribute of the blocks on bench k, e.g. a grade heatmap, missing blocks have no data
+    pub fn bench_raster<F>(&self, k: usize, attr: F) -> Result<Raster, Box<dyn Error>>
+    where
+        F: Fn(&B) -> f64,
+    {
+        let dims = self.dims();
+        assert!(
+            k < dims[2],
+            "bench {k} outside model with {} benches",
+            dims[2]
+        );
+        self.raster(Array2::from_shape_fn((dims[0], dims[1]), |(i, j)| {
+            self.block(BlockIndex { i, j, k }).map_or(f64::NAN, &attr)
+        }))
+    }
+
+    //elevation of the surface left by mining, the floor of the lowest mined block of each
+    //column or the top of the highest block where none is mined, empty columns have no data
+    pub fn mined_surface_raster<F>(&self, mined: F) -> Result<Raster, Box<dyn Error>>
+    where
+        F: Fn(&B) -> bool,
+    {
+        let dims = self.dims();
+        let half = self.block_size().z_size as f64 / 2.0;
+        self.raster(Array2::from_shape_fn((dims[0], dims[1]), |(i, j)| {
+            let z = |k: usize| self.index_to_coordinates(BlockIndex { i, j, k }).z as f64;
+            let column = (0..dims[2])
+                .filter_map(|k| self.block(BlockIndex { i, j, k }).map(|b| (k, b)))
+                .collect::<Vec<_>>();
+            if let Some((k, _)) = column.iter().find(|(_, b)| mined(b)) {
+                z(*k) - half
+            } else if let Some((k, _)) = column.last() {
+                z(*k) + half
+            } else {
+                f64::NAN
+            }
+        }))
+    }
+}
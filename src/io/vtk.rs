@@ -0,0 +1,151 @@
+use crate::block::{BlockCoordinates, BlockIndex, BlockInterface, NamedAttribute};
+use crate::block_model::BlockModel;
+use crate::storage::BlockStorage;
+
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+//cell layout of a vtk export
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub enum VtkLayout {
+    //every cell of the frame as a structured grid, empty cells hold NaN and present = 0
+    #[default]
+    Structured,
+    //one hexahedron per present block, suited to sparse models
+    Hexahedra,
+}
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface,
+    S: BlockStorage<B>,
+{
+    //legacy ascii vtk with each attribute written as cell data, the epsg code is not written
+    pub fn to_vtk<P: AsRef<Path>>(
+        &self,
+        path: P,
+        layout: VtkLayout,
+        attributes: &[NamedAttribute<B>],
+    ) -> Result<(), Box<dyn Error>> {
+        let file = File::create(path)?;
+        self.to_vtk_writer(BufWriter::new(file), layout, attributes)
+    }
+
+    pub fn to_vtk_writer<W: Write>(
+        &self,
+        mut w: W,
+        layout: VtkLayout,
+        attributes: &[NamedAttribute<B>],
+    ) -> Result<(), Box<dyn Error>> {
+        writeln!(w, "# vtk DataFile Version 3.0")?;
+        writeln!(w, "block model")?;
+        writeln!(w, "ASCII")?;
+
+        let cells = match layout {
+            VtkLayout::Structured => self.vtk_structured(&mut w)?,
+            VtkLayout::Hexahedra => self.vtk_hexahedra(&mut w)?,
+        };
+
+        writeln!(w, "CELL_DATA {}", cells.len())?;
+        if layout == VtkLayout::Structured {
+            writeln!(w, "SCALARS present int 1")?;
+            writeln!(w, "LOOKUP_TABLE default")?;
+            for ind in cells.iter() {
+                writeln!(w, "{}", self.block(*ind).is_some() as u8)?;
+            }
+        }
+
+        for (name, attr) in attributes {
+            writeln!(
+                w,
+                "SCALARS {} double 1",
+                name.replace(char::is_whitespace, "_")
+            )?;
+            writeln!(w, "LOOKUP_TABLE default")?;
+            for ind in cells.iter() {
+                writeln!(w, "{}", self.block(*ind).map_or(f64::NAN, attr))?;
+            }
+        }
+
+        w.flush()?;
+        Ok(())
+    }
+
+    //point offset from the centroid of ind by corner blocks along each model axis
+    fn vtk_corner(&self, ind: BlockIndex, corner: [f32; 3]) -> BlockCoordinates {
+        let size = self.block_size();
+        self.frame().to_world([
+            (ind.i as f32 + corner[0]) * size.x_size,
+            (ind.j as f32 + corner[1]) * size.y_size,
+            (ind.k as f32 + corner[2]) * size.z_size,
+        ])
+    }
+
+    //writes the grid geometry, returning cells in vtk order with i varying fastest
+    fn vtk_structured<W: Write>(&self, w: &mut W) -> io::Result<Vec<BlockIndex>> {
+        let [ni, nj, nk] = self.dims();
+        writeln!(w, "DATASET STRUCTURED_GRID")?;
+        writeln!(w, "DIMENSIONS {} {} {}", ni + 1, nj + 1, nk + 1)?;
+        writeln!(w, "POINTS {} double", (ni + 1) * (nj + 1) * (nk + 1))?;
+
+        for k in 0..=nk {
+            for j in 0..=nj {
+                for i in 0..=ni {
+                    let p = self.vtk_corner(BlockIndex { i, j, k }, [-0.5; 3]);
+                    writeln!(w, "{} {} {}", p.x, p.y, p.z)?;
+                }
+            }
+        }
+
+        let mut cells = Vec::with_capacity(ni * nj * nk);
+        for k in 0..nk {
+            for j in 0..nj {
+                for i in 0..ni {
+                    cells.push(BlockIndex { i, j, k });
+                }
+            }
+        }
+        Ok(cells)
+    }
+
+    //writes 8 corners per present block, returning cells ordered by index
+    fn vtk_hexahedra<W: Write>(&self, w: &mut W) -> io::Result<Vec<BlockIndex>> {
+        //vtk hexahedron corner order
+        const CORNERS: [[f32; 3]; 8] = [
+            [-0.5, -0.5, -0.5],
+            [0.5, -0.5, -0.5],
+            [0.5, 0.5, -0.5],
+            [-0.5, 0.5, -0.5],
+            [-0.5, -0.5, 0.5],
+            [0.5, -0.5, 0.5],
+            [0.5, 0.5, 0.5],
+            [-0.5, 0.5, 0.5],
+        ];
+
+        let mut cells = self.indexed_iter().map(|(ind, _)| ind).collect::<Vec<_>>();
+        cells.sort();
+
+        writeln!(w, "DATASET UNSTRUCTURED_GRID")?;
+        writeln!(w, "POINTS {} double", cells.len() * 8)?;
+        for ind in cells.iter() {
+            for corner in CORNERS {
+                let p = self.vtk_corner(*ind, corner);
+                writeln!(w, "{} {} {}", p.x, p.y, p.z)?;
+            }
+        }
+
+        writeln!(w, "CELLS {} {}", cells.len(), cells.len() * 9)?;
+        for n in 0..cells.len() {
+            let p = (0..8).map(|c| (8 * n + c).to_string()).collect::<Vec<_>>();
+            writeln!(w, "8 {}", p.join(" "))?;
+        }
+
+        writeln!(w, "CELL_TYPES {}", cells.len())?;
+        for _ in 0..cells.len() {
+            writeln!(w, "12")?;
+        }
+        Ok(cells)
+    }
+}
@@ -1,2 +1,49 @@
+pub mod accumulation;
+pub mod arithmetic;
+pub mod bench;
 pub mod block;
 pub mod block_model;
+pub mod categorical;
+pub mod caving;
+pub mod columnar;
+pub mod cone;
+pub mod crs;
+pub mod cutoff;
+pub mod density;
+pub mod dilution;
+pub mod distance;
+pub mod drillhole;
+pub mod dynamic;
+pub mod economics;
+pub mod error;
+pub mod estimation;
+pub mod filter;
+pub mod frame;
+pub mod geometry;
+pub mod graph;
+pub mod haulage;
+pub mod interpolation;
+pub mod io;
+pub mod merge;
+pub mod morphology;
+pub mod path;
+pub mod pit;
+pub mod precedence;
+pub mod query;
+pub mod realizations;
+pub mod reblock;
+pub mod reconciliation;
+pub mod risk;
+pub mod scenario;
+pub mod schedule;
+pub mod section;
+pub mod selection;
+pub mod spatial;
+pub mod statistics;
+pub mod stope;
+pub mod storage;
+pub mod stripping;
+pub mod topological;
+pub mod underground;
+pub mod validation;
+pub mod zones;
@@ -0,0 +1,108 @@
+use crate::block::{BlockIndex, BlockInterface};
+use crate::block_model::BlockModel;
+use crate::error::BlockModelError;
+use crate::frame::ModelFrame;
+use crate::storage::BlockStorage;
+
+//block kept where both models have a block
+pub enum MergePolicy<'a, B> {
+    TakeSelf,
+    TakeOther,
+    //the block with the larger key, e.g. an update date, self on ties
+    Newest(&'a dyn Fn(&B) -> f64),
+    //block built from the self and other blocks, its index is reassigned
+    Callback(&'a dyn Fn(&B, &B) -> B),
+}
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface,
+    S: BlockStorage<B>,
+{
+    //blocks of both models on a frame covering both, other must share the block size,
+    //rotation and lattice of self but may be offset by whole blocks and is reindexed, shared
+    //categorical attributes must use the same dictionaries
+    pub fn merge<S2: BlockStorage<B>>(
+        &self,
+        other: &BlockModel<B, S2>,
+        policy: MergePolicy<B>,
+    ) -> Result<Self, BlockModelError> {
+        let offset = self
+            .frame()
+            .lattice_offset(other.frame())
+            .ok_or(BlockModelError::IncompatibleFrames)?;
+
+        //categorical codes must mean the same in both models
+        let mut categories = self.categories.clone();
+        for (attribute, dict) in other.categories.iter() {
+            match categories.get(attribute) {
+                Some(d) if d != dict => {
+                    return Err(BlockModelError::MismatchedDictionaries {
+                        attribute: attribute.clone(),
+                    })
+                }
+                Some(_) => {}
+                None => {
+                    categories.insert(attribute.clone(), dict.clone());
+                }
+            }
+        }
+
+        //union of both lattices in self indices
+        let (dims, other_dims) = (self.dims(), other.dims());
+        let mut min = [0i64; 3];
+        let mut new_dims = [0; 3];
+        for d in 0..3 {
+            min[d] = offset[d].min(0);
+            let max = (dims[d] as i64).max(offset[d] + other_dims[d] as i64);
+            new_dims[d] = (max - min[d]) as usize;
+        }
+        let size = self.block_size();
+        let frame = ModelFrame::new(
+            self.frame().to_world([
+                min[0] as f32 * size.x_size,
+                min[1] as f32 * size.y_size,
+                min[2] as f32 * size.z_size,
+            ]),
+            size,
+            new_dims,
+        )
+        .with_rotation(self.frame().rotation)
+        .with_epsg(self.frame().epsg);
+
+        let shift = |ind: BlockIndex, by: [i64; 3]| BlockIndex {
+            i: (ind.i as i64 + by[0] - min[0]) as usize,
+            j: (ind.j as i64 + by[1] - min[1]) as usize,
+            k: (ind.k as i64 + by[2] - min[2]) as usize,
+        };
+
+        let mut storage = S::with_dims(new_dims);
+        for (ind, b) in self.indexed_iter() {
+            let ind = shift(ind, [0; 3]);
+            let mut b = b.clone();
+            b.set_index(ind);
+            storage.insert(ind, b);
+        }
+        for (ind, b) in other.indexed_iter() {
+            let ind = shift(ind, offset);
+            let mut merged = match (storage.get(ind), &policy) {
+                (None, _) | (Some(_), MergePolicy::TakeOther) => b.clone(),
+                (Some(_), MergePolicy::TakeSelf) => continue,
+                (Some(current), MergePolicy::Newest(key)) => {
+                    if key(b) > key(current) {
+                        b.clone()
+                    } else {
+                        continue;
+                    }
+                }
+                (Some(current), MergePolicy::Callback(f)) => f(current, b),
+            };
+            merged.set_index(ind);
+            storage.insert(ind, merged);
+        }
+
+        let mut merged = Self::from_storage(storage, frame);
+        merged.categories = categories;
+        Ok(merged)
+    }
+}
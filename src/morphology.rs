@@ -0,0 +1,128 @@
+use rayon::prelude::*;
+
+use crate::block::BlockIndex;
+use crate::selection::Selection;
+
+//neighbourhood used by morphological operations, offsets in blocks
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StructuringElement {
+    offsets: Vec<[i64; 3]>,
+}
+
+impl StructuringElement {
+    //arbitrary offsets, the origin is always included
+    pub fn from_offsets(mut offsets: Vec<[i64; 3]>) -> Self {
+        offsets.push([0, 0, 0]);
+        offsets.sort_unstable();
+        offsets.dedup();
+        Self { offsets }
+    }
+
+    //every offset within ri, rj, rk blocks along each axis
+    pub fn cuboid(ri: usize, rj: usize, rk: usize) -> Self {
+        Self::within(ri, rj, rk, |_| true)
+    }
+
+    //offsets inside the ellipsoid with radii ri, rj, rk blocks
+    pub fn ellipsoid(ri: usize, rj: usize, rk: usize) -> Self {
+        let r = |d: i64, r: usize| if r == 0 { 0.0 } else { d as f64 / r as f64 };
+        Self::within(ri, rj, rk, |[di, dj, dk]| {
+            r(di, ri).powi(2) + r(dj, rj).powi(2) + r(dk, rk).powi(2) <= 1.0
+        })
+    }
+
+    //horizontal ellipse on a single bench, radii in blocks
+    pub fn disc(ri: usize, rj: usize) -> Self {
+        Self::ellipsoid(ri, rj, 0)
+    }
+
+    //ni by nj blocks on a single bench with the origin at a corner, so even widths are exact
+    pub fn rectangle(ni: usize, nj: usize) -> Self {
+        assert!(ni > 0 && nj > 0, "rectangle must span at least one block");
+        let mut offsets = Vec::with_capacity(ni * nj);
+        for di in 0..ni as i64 {
+            for dj in 0..nj as i64 {
+                offsets.push([di, dj, 0]);
+            }
+        }
+        Self::from_offsets(offsets)
+    }
+
+    fn within<F: Fn([i64; 3]) -> bool>(ri: usize, rj: usize, rk: usize, keep: F) -> Self {
+        let (ri, rj, rk) = (ri as i64, rj as i64, rk as i64);
+        let mut offsets = Vec::new();
+        for di in -ri..=ri {
+            for dj in -rj..=rj {
+                for dk in -rk..=rk {
+                    if keep([di, dj, dk]) {
+                        offsets.push([di, dj, dk]);
+                    }
+                }
+            }
+        }
+        Self::from_offsets(offsets)
+    }
+
+    pub fn offsets(&self) -> &[[i64; 3]] {
+        &self.offsets
+    }
+}
+
+//ind shifted by offset, None outside the lattice
+pub(crate) fn shift(ind: BlockIndex, offset: [i64; 3], dims: [usize; 3]) -> Option<BlockIndex> {
+    let i = ind.i as i64 + offset[0];
+    let j = ind.j as i64 + offset[1];
+    let k = ind.k as i64 + offset[2];
+    let inside = |v: i64, n: usize| v >= 0 && v < n as i64;
+
+    (inside(i, dims[0]) && inside(j, dims[1]) && inside(k, dims[2])).then_some(BlockIndex {
+        i: i as usize,
+        j: j as usize,
+        k: k as usize,
+    })
+}
+
+impl Selection {
+    //cells reached by shifting any selected cell by any offset
+    pub fn dilate(&self, element: &StructuringElement) -> Selection {
+        let dims = self.dims();
+        let mut out = Selection::new(dims);
+        for ind in self.iter() {
+            for offset in element.offsets() {
+                if let Some(n) = shift(ind, *offset, dims) {
+                    out.insert(n);
+                }
+            }
+        }
+        out
+    }
+
+    //cells whose every shift lies in the selection, cells beyond the lattice count as
+    //unselected
+    pub fn erode(&self, element: &StructuringElement) -> Selection {
+        let dims = self.dims();
+        let kept = self
+            .iter()
+            .collect::<Vec<_>>()
+            .into_par_iter()
+            .filter(|ind| {
+                element
+                    .offsets()
+                    .iter()
+                    .all(|offset| shift(*ind, *offset, dims).is_some_and(|n| self.contains(n)))
+            })
+            .collect::<Vec<_>>();
+        Selection::from_inds(dims, kept)
+    }
+
+    //erode then dilate, removes features smaller than the element
+    pub fn open(&self, element: &StructuringElement) -> Selection {
+        self.erode(element).dilate(element)
+    }
+
+    //dilate then erode, fills gaps smaller than the element, the original cells are kept
+    //since the lattice boundary clips the dilation
+    pub fn close(&self, element: &StructuringElement) -> Selection {
+        self.dilate(element).erode(element).union(self)
+    }
+}
@@ -0,0 +1,198 @@
+use crate::block::{BlockIndex, BlockInterface};
+use crate::block_model::BlockModel;
+use crate::selection::Selection;
+use crate::storage::BlockStorage;
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+//open cell ordered by estimated total length
+#[derive(PartialEq)]
+struct Open(f64, BlockIndex);
+
+impl Eq for Open {}
+
+impl PartialOrd for Open {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Open {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+//cells from start to end and the travelled length in world units, ramp moves skip the cells
+//they cross
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockPath {
+    pub cells: Vec<BlockIndex>,
+    pub length: f64,
+}
+
+//lattice move with its world length and the cells it passes through, relative to the start
+struct Move {
+    offset: [i64; 3],
+    length: f64,
+    crossed: Vec<[i64; 3]>,
+}
+
+fn step(ind: BlockIndex, offset: [i64; 3], dims: [usize; 3]) -> Option<BlockIndex> {
+    let p = [ind.i, ind.j, ind.k]
+        .iter()
+        .zip(offset.iter())
+        .map(|(c, o)| *c as i64 + o)
+        .collect::<Vec<_>>();
+    (0..3)
+        .all(|a| p[a] >= 0 && p[a] < dims[a] as i64)
+        .then(|| BlockIndex {
+            i: p[0] as usize,
+            j: p[1] as usize,
+            k: p[2] as usize,
+        })
+}
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface,
+    S: BlockStorage<B>,
+{
+    //world vector of a lattice offset split into plan and vertical length
+    fn plan_and_rise(&self, offset: [f64; 3]) -> (f64, f64) {
+        let size = self.block_size();
+        let zero = self.frame().to_world([0.0; 3]);
+        let p = self.frame().to_world([
+            offset[0] as f32 * size.x_size,
+            offset[1] as f32 * size.y_size,
+            offset[2] as f32 * size.z_size,
+        ]);
+        let (dx, dy, dz) = (
+            (p.x - zero.x) as f64,
+            (p.y - zero.y) as f64,
+            (p.z - zero.z) as f64,
+        );
+        ((dx * dx + dy * dy).sqrt(), dz)
+    }
+
+    //moves to the 8 neighbours on a bench and ramp moves to the benches above and below on
+    //the nearest ring of cells reachable within max_grade
+    fn path_moves(&self, max_grade: f64) -> Vec<Move> {
+        let size = self.block_size();
+        let (min_size, max_size) = (
+            size.x_size.min(size.y_size) as f64,
+            size.x_size.max(size.y_size) as f64,
+        );
+        let (_, bench) = self.plan_and_rise([0.0, 0.0, 1.0]);
+        let ring = bench.abs() / max_grade;
+        let reach = ((ring + max_size) / min_size).ceil() as i64;
+
+        let mut moves = Vec::new();
+        for dk in -1..=1i64 {
+            let r = if dk == 0 { 1 } else { reach };
+            for di in -r..=r {
+                for dj in -r..=r {
+                    if di == 0 && dj == 0 && dk == 0 {
+                        continue;
+                    }
+                    let (plan, rise) = self.plan_and_rise([di as f64, dj as f64, dk as f64]);
+                    if rise.abs() > max_grade * plan {
+                        continue;
+                    }
+                    if dk != 0 && plan >= ring + max_size {
+                        continue;
+                    }
+
+                    let n = 2 * di.abs().max(dj.abs()).max(1);
+                    let mut crossed = (1..=n)
+                        .map(|s| {
+                            let t = s as f64 / n as f64;
+                            [di, dj, dk].map(|o| (o as f64 * t).round() as i64)
+                        })
+                        .collect::<Vec<_>>();
+                    crossed.dedup();
+                    moves.push(Move {
+                        offset: [di, dj, dk],
+                        length: (plan * plan + rise * rise).sqrt(),
+                        crossed,
+                    });
+                }
+            }
+        }
+        moves
+    }
+
+    //shortest path between two cells through the open cells, e.g. mined out air blocks, by a*
+    //search where no move rises or falls steeper than max_grade, rise over run, so benches
+    //are changed along ramps, None if either end is closed or no path exists
+    pub fn shortest_path(
+        &self,
+        from: BlockIndex,
+        to: BlockIndex,
+        open: &Selection,
+        max_grade: f64,
+    ) -> Option<BlockPath> {
+        assert!(max_grade > 0.0, "max grade must be positive");
+        let dims = self.dims();
+        assert!(
+            open.dims() == dims,
+            "selection lattice does not match the model"
+        );
+        if !open.contains(from) || !open.contains(to) {
+            return None;
+        }
+
+        let moves = self.path_moves(max_grade);
+        let ramp = (1.0 + 1.0 / (max_grade * max_grade)).sqrt();
+        let target = self.index_to_coordinates(to);
+        let estimate = |ind: BlockIndex| {
+            let c = self.index_to_coordinates(ind);
+            let (dx, dy, dz) = (
+                (target.x - c.x) as f64,
+                (target.y - c.y) as f64,
+                (target.z - c.z) as f64,
+            );
+            (dx * dx + dy * dy + dz * dz).sqrt().max(dz.abs() * ramp)
+        };
+
+        let mut travelled = HashMap::from([(from, 0.0)]);
+        let mut previous = HashMap::new();
+        let mut heap = BinaryHeap::from([Reverse(Open(estimate(from), from))]);
+        while let Some(Reverse(Open(f, ind))) = heap.pop() {
+            //skip entries superseded by a shorter route
+            let g = travelled[&ind];
+            if f > g + estimate(ind) + 1e-9 {
+                continue;
+            }
+            if ind == to {
+                let mut cells = vec![to];
+                while let Some(p) = previous.get(cells.last().unwrap()) {
+                    cells.push(*p);
+                }
+                cells.reverse();
+                return Some(BlockPath { cells, length: g });
+            }
+
+            for m in moves.iter() {
+                let Some(next) = step(ind, m.offset, dims) else {
+                    continue;
+                };
+                let clear = m
+                    .crossed
+                    .iter()
+                    .all(|c| step(ind, *c, dims).is_some_and(|c| open.contains(c)));
+                if !clear {
+                    continue;
+                }
+                let length = g + m.length;
+                if travelled.get(&next).is_none_or(|best| length < *best) {
+                    travelled.insert(next, length);
+                    previous.insert(next, ind);
+                    heap.push(Reverse(Open(length + estimate(next), next)));
+                }
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,101 @@
+use crate::pit::{ClosureGraph, PitOptimizer};
+
+use std::collections::VecDeque;
+
+//floating cone variants
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub enum FloatingConeVariant {
+    //mine any cone whose total value is positive
+    #[default]
+    Simple,
+    //cones that cannot pay for themselves transfer their ore value to the waste they
+    //share with later cones, reducing the overlap errors of the simple method
+    Korobov,
+}
+
+//heuristic pit generator floating extraction cones below positive blocks
+//cones are floated bench by bench from the top of the model down, repeating
+//until a full pass mines nothing
+#[derive(Copy, Clone, Debug, Default)]
+pub struct FloatingCone {
+    pub variant: FloatingConeVariant,
+}
+
+impl FloatingCone {
+    pub fn new(variant: FloatingConeVariant) -> Self {
+        Self { variant }
+    }
+
+    //unmined nodes that must be removed to mine base, including base
+    fn cone(graph: &ClosureGraph, mined: &[bool], base: usize, seen: &mut [bool]) -> Vec<usize> {
+        let mut cone = vec![base];
+        let mut queue = VecDeque::from([base]);
+        seen[base] = true;
+
+        while let Some(n) = queue.pop_front() {
+            for &p in graph.preds[n].iter() {
+                if !mined[p] && !seen[p] {
+                    seen[p] = true;
+                    cone.push(p);
+                    queue.push_back(p);
+                }
+            }
+        }
+
+        //reset scratch flags for the next cone
+        cone.iter().for_each(|n| seen[*n] = false);
+        cone
+    }
+}
+
+impl PitOptimizer for FloatingCone {
+    fn solve(&self, graph: &ClosureGraph) -> Vec<bool> {
+        let mut values = graph.weights.clone();
+        let mut mined = vec![false; graph.len()];
+        let mut seen = vec![false; graph.len()];
+
+        //top bench first
+        let mut order = (0..graph.len()).collect::<Vec<_>>();
+        order.sort_by_key(|n| std::cmp::Reverse(graph.inds[*n].k));
+
+        loop {
+            let mut changed = false;
+            for &base in order.iter() {
+                if mined[base] || values[base] <= 0.0 {
+                    continue;
+                }
+
+                let cone = Self::cone(graph, &mined, base, &mut seen);
+                let total = cone.iter().map(|n| values[*n]).sum::<f64>();
+
+                if total > 0.0 {
+                    cone.iter().for_each(|n| mined[*n] = true);
+                    changed = true;
+                    continue;
+                }
+
+                if self.variant == FloatingConeVariant::Korobov {
+                    //ore in the cone pays down its waste proportionally
+                    let ore = cone.iter().map(|n| values[*n].max(0.0)).sum::<f64>();
+                    let waste = ore - total;
+                    let scale = (waste - ore) / waste;
+
+                    for &n in cone.iter() {
+                        if values[n] > 0.0 {
+                            values[n] = 0.0;
+                        } else {
+                            values[n] *= scale;
+                        }
+                    }
+                    changed = true;
+                }
+            }
+
+            if !changed {
+                break;
+            }
+        }
+
+        mined
+    }
+}
@@ -0,0 +1,163 @@
+use crate::pit::{ClosureGraph, PitOptimizer};
+
+//dummy root of the LG forest
+const ROOT: usize = usize::MAX;
+
+//orientation of a tree edge relative to the root
+//plus edges point away from the root, minus edges towards it
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Edge {
+    Plus,
+    Minus,
+}
+
+//ultimate pit limit via the Lerchs–Grossmann tree normalization algorithm
+#[derive(Copy, Clone, Debug, Default)]
+pub struct LerchsGrossmann;
+
+impl PitOptimizer for LerchsGrossmann {
+    fn solve(&self, graph: &ClosureGraph) -> Vec<bool> {
+        let mut tree = Tree::new(&graph.weights);
+
+        //merge strong nodes into weak branches until no precedence arc leaves the strong set
+        loop {
+            let mut merged = false;
+            for k in 0..graph.len() {
+                if !tree.is_strong(k) {
+                    continue;
+                }
+
+                if let Some(&l) = graph.preds[k].iter().find(|&&l| !tree.is_strong(l)) {
+                    tree.merge(k, l);
+                    merged = true;
+                }
+            }
+
+            if !merged {
+                break;
+            }
+        }
+
+        (0..graph.len()).map(|n| tree.is_strong(n)).collect()
+    }
+}
+
+//normalized LG forest, each node stores the edge to its parent
+struct Tree {
+    parent: Vec<usize>,
+    edge: Vec<Edge>,
+    //total weight of the subtree rooted at node
+    mass: Vec<f64>,
+}
+
+impl Tree {
+    //every node starts as its own branch hanging off the root
+    fn new(weights: &[f64]) -> Self {
+        Self {
+            parent: vec![ROOT; weights.len()],
+            edge: vec![Edge::Plus; weights.len()],
+            mass: weights.to_vec(),
+        }
+    }
+
+    fn is_strong_edge(&self, n: usize) -> bool {
+        match self.edge[n] {
+            Edge::Plus => self.mass[n] > 0.0,
+            Edge::Minus => self.mass[n] <= 0.0,
+        }
+    }
+
+    //child of the root on the path from n
+    fn branch_root(&self, mut n: usize) -> usize {
+        while self.parent[n] != ROOT {
+            n = self.parent[n];
+        }
+        n
+    }
+
+    fn is_strong(&self, n: usize) -> bool {
+        self.mass[self.branch_root(n)] > 0.0
+    }
+
+    //hang the strong branch containing k below weak node l through arc k -> l
+    fn merge(&mut self, k: usize, l: usize) {
+        let m = self.branch_root(k);
+        let branch_mass = self.mass[m];
+
+        //path from k up to the branch root
+        let mut path = vec![k];
+        while *path.last().unwrap() != m {
+            path.push(self.parent[*path.last().unwrap()]);
+        }
+
+        //re-root the strong branch at k, reversing the path edges
+        for w in path.windows(2).rev() {
+            let (child, parent) = (w[0], w[1]);
+            self.parent[parent] = child;
+            self.edge[parent] = match self.edge[child] {
+                Edge::Plus => Edge::Minus,
+                Edge::Minus => Edge::Plus,
+            };
+            self.mass[parent] = branch_mass - self.mass[child];
+        }
+
+        self.parent[k] = l;
+        self.edge[k] = Edge::Minus;
+        self.mass[k] = branch_mass;
+
+        //weak branch gains the strong mass along the path from l to its root
+        let mut chain = path.into_iter().rev().collect::<Vec<_>>();
+        let mut n = l;
+        loop {
+            self.mass[n] += branch_mass;
+            chain.push(n);
+            if self.parent[n] == ROOT {
+                break;
+            }
+            n = self.parent[n];
+        }
+
+        self.normalize(&chain);
+    }
+
+    //cut strong edges along a chain ordered from deepest node to the branch root,
+    //each cut subtree becomes a new branch of the root
+    fn normalize(&mut self, chain: &[usize]) {
+        let mut removed = 0.0;
+        for &n in chain {
+            self.mass[n] -= removed;
+
+            if self.parent[n] != ROOT && self.is_strong_edge(n) {
+                removed += self.mass[n];
+                self.parent[n] = ROOT;
+                self.edge[n] = Edge::Plus;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LerchsGrossmann;
+    use crate::block::BlockIndex;
+    use crate::pit::tests::{brute_force, closure_value, toy_pit};
+    use crate::pit::PitOptimizer;
+
+    #[test]
+    fn matches_brute_force() {
+        let graph = toy_pit();
+        let in_pit = LerchsGrossmann.solve(&graph);
+        assert_eq!(closure_value(&graph, &in_pit), Some(brute_force(&graph)));
+        assert_eq!(in_pit.iter().filter(|s| **s).count(), 11);
+        assert!(graph
+            .selected(&in_pit)
+            .contains(&BlockIndex { i: 4, j: 0, k: 0 }));
+    }
+
+    #[test]
+    fn waste_only_pit_is_empty() {
+        let mut graph = toy_pit();
+        graph.weights.iter_mut().for_each(|w| *w = -w.abs());
+        assert!(LerchsGrossmann.solve(&graph).iter().all(|s| !s));
+    }
+}
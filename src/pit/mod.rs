@@ -0,0 +1,200 @@
+pub mod floating_cone;
+pub mod lerchs_grossmann;
+pub mod nested;
+pub mod pseudoflow;
+pub mod pushback;
+pub mod slope;
+pub mod surface;
+pub mod width;
+
+use crate::block::{BlockIndex, BlockInterface};
+use crate::block_model::{BlockDependenceInterface, BlockModel};
+use crate::selection::Selection;
+use crate::storage::BlockStorage;
+
+use std::collections::{HashMap, HashSet};
+
+//maximum closure problem over the present blocks of a model
+//missing blocks are treated as air and impose no precedence
+#[derive(Debug, Clone)]
+pub struct ClosureGraph {
+    pub inds: Vec<BlockIndex>,
+    pub weights: Vec<f64>,
+    //nodes that must be mined before each node
+    pub preds: Vec<Vec<usize>>,
+}
+
+impl ClosureGraph {
+    pub fn new<B, S, D, F>(mdl: &BlockModel<B, S>, dep: &D, value: F) -> Self
+    where
+        B: BlockInterface,
+        S: BlockStorage<B>,
+        D: BlockDependenceInterface,
+        F: Fn(&B) -> f64,
+    {
+        let mut inds = mdl.indexed_iter().map(|(ind, _)| ind).collect::<Vec<_>>();
+        inds.sort();
+
+        let node_of = inds
+            .iter()
+            .enumerate()
+            .map(|(n, ind)| (*ind, n))
+            .collect::<HashMap<_, _>>();
+
+        let weights = inds
+            .iter()
+            .map(|ind| value(mdl.block(*ind).unwrap()))
+            .collect();
+
+        let preds = inds
+            .iter()
+            .map(|ind| {
+                dep.inds(mdl, *ind)
+                    .iter()
+                    .filter(|p| *p != ind)
+                    .filter_map(|p| node_of.get(p).copied())
+                    .collect()
+            })
+            .collect();
+
+        Self {
+            inds,
+            weights,
+            preds,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.inds.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inds.is_empty()
+    }
+
+    //prevent blocks outside the selection from being mined, along with every block above
+    //them, by pricing them below the value of all positive blocks combined
+    pub fn restrict(&mut self, selection: &Selection) {
+        let penalty = 1.0 + self.weights.iter().filter(|w| **w > 0.0).sum::<f64>();
+        for (w, ind) in self.weights.iter_mut().zip(self.inds.iter()) {
+            if !selection.contains(*ind) {
+                *w = -penalty;
+            }
+        }
+    }
+
+    //block indices of the selected nodes
+    pub fn selected(&self, in_pit: &[bool]) -> HashSet<BlockIndex> {
+        self.inds
+            .iter()
+            .zip(in_pit)
+            .filter(|(_, sel)| **sel)
+            .map(|(ind, _)| *ind)
+            .collect()
+    }
+}
+
+//solver for the ultimate pit (maximum closure) problem
+pub trait PitOptimizer {
+    //membership of each graph node in the maximum value closure
+    fn solve(&self, graph: &ClosureGraph) -> Vec<bool>;
+
+    //indices of the blocks inside the maximum value pit
+    fn optimize<B, S, D, F>(&self, mdl: &BlockModel<B, S>, dep: &D, value: F) -> HashSet<BlockIndex>
+    where
+        B: BlockInterface,
+        S: BlockStorage<B>,
+        D: BlockDependenceInterface,
+        F: Fn(&B) -> f64,
+    {
+        let graph = ClosureGraph::new(mdl, dep, value);
+        graph.selected(&self.solve(&graph))
+    }
+
+    //maximum value pit mining only blocks inside the selection
+    fn optimize_within<B, S, D, F>(
+        &self,
+        mdl: &BlockModel<B, S>,
+        dep: &D,
+        value: F,
+        selection: &Selection,
+    ) -> HashSet<BlockIndex>
+    where
+        B: BlockInterface,
+        S: BlockStorage<B>,
+        D: BlockDependenceInterface,
+        F: Fn(&B) -> f64,
+    {
+        let mut graph = ClosureGraph::new(mdl, dep, value);
+        graph.restrict(selection);
+        graph.selected(&self.solve(&graph))
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::ClosureGraph;
+    use crate::block::BlockIndex;
+
+    //2d section five blocks wide and three deep, every block waits for the three blocks above
+    //it, the ore at (2, 0) pays for its cone and the ore at (4, 0) only for the one waste
+    //block its cone adds
+    pub(crate) fn toy_pit() -> ClosureGraph {
+        let bottom = [-1.0, -1.0, 10.0, -1.0, 3.0];
+        let (ni, nk) = (bottom.len(), 3);
+        let node = |i: usize, k: usize| i * nk + k;
+
+        let mut inds = Vec::new();
+        let mut weights = Vec::new();
+        let mut preds = Vec::new();
+        for (i, ore) in bottom.iter().enumerate() {
+            for k in 0..nk {
+                inds.push(BlockIndex { i, j: 0, k });
+                weights.push(if k == 0 { *ore } else { -1.0 });
+                preds.push(if k + 1 < nk {
+                    (i.saturating_sub(1)..(i + 2).min(ni))
+                        .map(|p| node(p, k + 1))
+                        .collect()
+                } else {
+                    Vec::new()
+                });
+            }
+        }
+        ClosureGraph {
+            inds,
+            weights,
+            preds,
+        }
+    }
+
+    //value of a selection, None unless it is closed under the precedence
+    pub(crate) fn closure_value(graph: &ClosureGraph, in_pit: &[bool]) -> Option<f64> {
+        (0..graph.len())
+            .filter(|n| in_pit[*n])
+            .all(|n| graph.preds[n].iter().all(|p| in_pit[*p]))
+            .then(|| {
+                (0..graph.len())
+                    .filter(|n| in_pit[*n])
+                    .map(|n| graph.weights[n])
+                    .sum()
+            })
+    }
+
+    //best closure over every subset of nodes
+    pub(crate) fn brute_force(graph: &ClosureGraph) -> f64 {
+        (0..1u32 << graph.len())
+            .filter_map(|mask| {
+                let in_pit = (0..graph.len())
+                    .map(|n| mask & (1 << n) != 0)
+                    .collect::<Vec<_>>();
+                closure_value(graph, &in_pit)
+            })
+            .fold(f64::NEG_INFINITY, f64::max)
+    }
+
+    #[test]
+    fn toy_pit_optimum() {
+        //both ore blocks, their cones and nothing else
+        assert_eq!(brute_force(&toy_pit()), 4.0);
+    }
+}
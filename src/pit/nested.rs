@@ -0,0 +1,86 @@
+use crate::block::{BlockIndex, BlockInterface};
+use crate::block_model::{BlockDependenceInterface, BlockModel};
+use crate::pit::{ClosureGraph, PitOptimizer};
+use crate::storage::BlockStorage;
+
+use std::collections::{HashMap, HashSet};
+
+//nested pit shells generated by scaling block revenue
+#[derive(Debug, Clone, PartialEq)]
+pub struct NestedShells {
+    //ascending revenue factors, shell n was optimized at revenue_factors[n]
+    pub revenue_factors: Vec<f64>,
+    //first shell each mined block enters, blocks outside every shell are absent
+    pub shells: HashMap<BlockIndex, usize>,
+}
+
+impl NestedShells {
+    //optimize a pit for every revenue factor with block value rf * revenue - cost
+    pub fn generate<O, B, S, D, R, C>(
+        optimizer: &O,
+        mdl: &BlockModel<B, S>,
+        dep: &D,
+        revenue_factors: &[f64],
+        revenue: R,
+        cost: C,
+    ) -> Self
+    where
+        O: PitOptimizer,
+        B: BlockInterface,
+        S: BlockStorage<B>,
+        D: BlockDependenceInterface,
+        R: Fn(&B) -> f64,
+        C: Fn(&B) -> f64,
+    {
+        let mut revenue_factors = revenue_factors.to_vec();
+        revenue_factors.sort_by(|a, b| a.total_cmp(b));
+
+        //arcs are shared by every shell, only weights change
+        let mut graph = ClosureGraph::new(mdl, dep, |_| 0.0);
+        let (revenues, costs): (Vec<_>, Vec<_>) = graph
+            .inds
+            .iter()
+            .map(|ind| {
+                let b = mdl.block(*ind).unwrap();
+                (revenue(b), cost(b))
+            })
+            .unzip();
+
+        let mut shells = HashMap::new();
+        for (shell, rf) in revenue_factors.iter().enumerate() {
+            graph.weights = revenues
+                .iter()
+                .zip(costs.iter())
+                .map(|(r, c)| rf * r - c)
+                .collect();
+
+            //keep the first shell so results stay nested under ties
+            for ind in graph.selected(&optimizer.solve(&graph)) {
+                shells.entry(ind).or_insert(shell);
+            }
+        }
+
+        Self {
+            revenue_factors,
+            shells,
+        }
+    }
+
+    pub fn num_shells(&self) -> usize {
+        self.revenue_factors.len()
+    }
+
+    //first shell containing block
+    pub fn shell(&self, ind: BlockIndex) -> Option<usize> {
+        self.shells.get(&ind).copied()
+    }
+
+    //blocks inside shell n, including all smaller shells
+    pub fn pit(&self, n: usize) -> HashSet<BlockIndex> {
+        self.shells
+            .iter()
+            .filter(|(_, s)| **s <= n)
+            .map(|(ind, _)| *ind)
+            .collect()
+    }
+}
@@ -0,0 +1,348 @@
+use crate::pit::{ClosureGraph, PitOptimizer};
+
+//null node/arc marker
+const NONE: usize = usize::MAX;
+
+//ultimate pit limit via Hochbaum's highest label pseudoflow algorithm
+//source and sink arcs are saturated up front so excess lives on the block nodes,
+//precedence arcs have infinite capacity
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Pseudoflow;
+
+impl PitOptimizer for Pseudoflow {
+    fn solve(&self, graph: &ClosureGraph) -> Vec<bool> {
+        let mut solver = Solver::new(graph);
+        while let Some(root) = solver.highest_strong_root() {
+            solver.process_root(root);
+        }
+
+        solver.label.iter().map(|l| *l == solver.lifted).collect()
+    }
+}
+
+struct Solver {
+    //precedence arcs, from must be mined after to
+    arc_from: Vec<usize>,
+    arc_to: Vec<usize>,
+    flow: Vec<f64>,
+
+    //residual arcs leaving each node that are not part of the forest
+    out_of_tree: Vec<Vec<usize>>,
+    next_arc: Vec<usize>,
+
+    //forest, upward is set when the node is the tail of the arc to its parent
+    parent: Vec<usize>,
+    arc_to_parent: Vec<usize>,
+    upward: Vec<bool>,
+    first_child: Vec<usize>,
+    next_sibling: Vec<usize>,
+    prev_sibling: Vec<usize>,
+    next_scan: Vec<usize>,
+
+    excess: Vec<f64>,
+    label: Vec<usize>,
+    label_count: Vec<usize>,
+    //label assigned to nodes known to be in the source set
+    lifted: usize,
+
+    strong_roots: Vec<Vec<usize>>,
+    highest_label: usize,
+}
+
+impl Solver {
+    fn new(graph: &ClosureGraph) -> Self {
+        let n = graph.len();
+
+        let mut arc_from = Vec::new();
+        let mut arc_to = Vec::new();
+        let mut out_of_tree = vec![Vec::new(); n];
+        for (from, preds) in graph.preds.iter().enumerate() {
+            for &to in preds {
+                out_of_tree[from].push(arc_from.len());
+                arc_from.push(from);
+                arc_to.push(to);
+            }
+        }
+        let flow = vec![0.0; arc_from.len()];
+
+        //positive blocks start strong with label 1, the rest weak with label 0
+        let lifted = n + 1;
+        let mut label = vec![0; n];
+        let mut label_count = vec![0; lifted + 1];
+        let mut strong_roots = vec![Vec::new(); lifted + 1];
+        for (node, w) in graph.weights.iter().enumerate() {
+            if *w > 0.0 {
+                label[node] = 1;
+                strong_roots[1].push(node);
+            }
+            label_count[label[node]] += 1;
+        }
+
+        Self {
+            arc_from,
+            arc_to,
+            flow,
+            out_of_tree,
+            next_arc: vec![0; n],
+            parent: vec![NONE; n],
+            arc_to_parent: vec![NONE; n],
+            upward: vec![false; n],
+            first_child: vec![NONE; n],
+            next_sibling: vec![NONE; n],
+            prev_sibling: vec![NONE; n],
+            next_scan: vec![NONE; n],
+            excess: graph.weights.clone(),
+            label,
+            label_count,
+            lifted,
+            strong_roots,
+            highest_label: 1,
+        }
+    }
+
+    fn add_strong_root(&mut self, node: usize) {
+        self.strong_roots[self.label[node]].push(node);
+        self.highest_label = self.highest_label.max(self.label[node]);
+    }
+
+    fn add_child(&mut self, parent: usize, child: usize) {
+        self.parent[child] = parent;
+        self.prev_sibling[child] = NONE;
+        self.next_sibling[child] = self.first_child[parent];
+        if self.first_child[parent] != NONE {
+            self.prev_sibling[self.first_child[parent]] = child;
+        }
+        self.first_child[parent] = child;
+    }
+
+    fn remove_child(&mut self, parent: usize, child: usize) {
+        let (prev, next) = (self.prev_sibling[child], self.next_sibling[child]);
+        if prev == NONE {
+            self.first_child[parent] = next;
+        } else {
+            self.next_sibling[prev] = next;
+        }
+        if next != NONE {
+            self.prev_sibling[next] = prev;
+        }
+
+        self.parent[child] = NONE;
+        self.prev_sibling[child] = NONE;
+        self.next_sibling[child] = NONE;
+    }
+
+    //highest labelled strong root with a non-empty label below it,
+    //trees above a label gap cannot reach a weak node and are lifted into the source set
+    fn highest_strong_root(&mut self) -> Option<usize> {
+        for l in (1..=self.highest_label).rev() {
+            if self.strong_roots[l].is_empty() {
+                continue;
+            }
+
+            self.highest_label = l;
+            if self.label_count[l - 1] > 0 {
+                return self.strong_roots[l].pop();
+            }
+
+            while let Some(root) = self.strong_roots[l].pop() {
+                self.lift_tree(root);
+            }
+        }
+
+        //roots split off weak trees start scanning from label 1
+        if self.strong_roots[0].is_empty() {
+            return None;
+        }
+
+        for root in std::mem::take(&mut self.strong_roots[0]) {
+            self.label_count[0] -= 1;
+            self.label[root] = 1;
+            self.label_count[1] += 1;
+            self.strong_roots[1].push(root);
+        }
+
+        self.highest_label = 1;
+        self.strong_roots[1].pop()
+    }
+
+    fn lift_tree(&mut self, root: usize) {
+        let mut stack = vec![root];
+        while let Some(node) = stack.pop() {
+            self.label_count[self.label[node]] -= 1;
+            self.label[node] = self.lifted;
+
+            let mut child = self.first_child[node];
+            while child != NONE {
+                stack.push(child);
+                child = self.next_sibling[child];
+            }
+        }
+    }
+
+    //depth first search of the nodes sharing the root label for a merger arc,
+    //relabelling nodes whose subtree is exhausted
+    fn process_root(&mut self, root: usize) {
+        let mut strong = root;
+        self.next_scan[root] = self.first_child[root];
+
+        if let Some(arc) = self.find_weak_arc(root) {
+            self.merge(root, arc);
+            self.push_excess(root);
+            return;
+        }
+        self.check_children(root);
+
+        while strong != NONE {
+            while self.next_scan[strong] != NONE {
+                let child = self.next_scan[strong];
+                self.next_scan[strong] = self.next_sibling[child];
+                strong = child;
+                self.next_scan[strong] = self.first_child[strong];
+
+                if let Some(arc) = self.find_weak_arc(strong) {
+                    self.merge(strong, arc);
+                    self.push_excess(root);
+                    return;
+                }
+                self.check_children(strong);
+            }
+
+            strong = self.parent[strong];
+            if strong != NONE {
+                self.check_children(strong);
+            }
+        }
+
+        self.add_strong_root(root);
+    }
+
+    //residual arc from node to a node one label below
+    fn find_weak_arc(&mut self, node: usize) -> Option<usize> {
+        let target = self.label[node].checked_sub(1)?;
+
+        let mut i = self.next_arc[node];
+        while i < self.out_of_tree[node].len() {
+            let arc = self.out_of_tree[node][i];
+            if self.label[self.arc_to[arc]] == target {
+                self.next_arc[node] = i;
+                self.out_of_tree[node].swap_remove(i);
+                return Some(arc);
+            }
+            i += 1;
+        }
+
+        self.next_arc[node] = self.out_of_tree[node].len();
+        None
+    }
+
+    //advance the child scan to the next child sharing the node label,
+    //otherwise relabel the node
+    fn check_children(&mut self, node: usize) {
+        while self.next_scan[node] != NONE {
+            if self.label[self.next_scan[node]] == self.label[node] {
+                return;
+            }
+            self.next_scan[node] = self.next_sibling[self.next_scan[node]];
+        }
+
+        self.label_count[self.label[node]] -= 1;
+        self.label[node] += 1;
+        self.label_count[self.label[node]] += 1;
+        self.next_arc[node] = 0;
+    }
+
+    //re-root the strong tree at node and hang it below the head of arc
+    fn merge(&mut self, node: usize, arc: usize) {
+        let mut current = node;
+        let mut new_parent = self.arc_to[arc];
+        let mut new_arc = arc;
+        let mut new_upward = true;
+
+        while self.parent[current] != NONE {
+            let old_parent = self.parent[current];
+            let old_arc = self.arc_to_parent[current];
+            let old_upward = self.upward[current];
+
+            self.remove_child(old_parent, current);
+            self.arc_to_parent[current] = new_arc;
+            self.upward[current] = new_upward;
+            self.add_child(new_parent, current);
+
+            new_parent = current;
+            current = old_parent;
+            new_arc = old_arc;
+            new_upward = !old_upward;
+        }
+
+        self.arc_to_parent[current] = new_arc;
+        self.upward[current] = new_upward;
+        self.add_child(new_parent, current);
+    }
+
+    //push the old root excess towards the new root, splitting the tree at arcs
+    //without enough residual capacity
+    fn push_excess(&mut self, root: usize) {
+        let mut current = root;
+        let mut prev_excess = 1.0;
+
+        while self.excess[current] > 0.0 && self.parent[current] != NONE {
+            let parent = self.parent[current];
+            let arc = self.arc_to_parent[current];
+            prev_excess = self.excess[parent];
+
+            if self.upward[current] {
+                //precedence arcs are uncapacitated
+                self.flow[arc] += self.excess[current];
+                self.excess[parent] += self.excess[current];
+                self.excess[current] = 0.0;
+            } else if self.flow[arc] >= self.excess[current] {
+                self.flow[arc] -= self.excess[current];
+                self.excess[parent] += self.excess[current];
+                self.excess[current] = 0.0;
+            } else {
+                self.excess[current] -= self.flow[arc];
+                self.excess[parent] += self.flow[arc];
+                self.flow[arc] = 0.0;
+
+                //arc is residual again from its tail, the parent
+                debug_assert_eq!(self.arc_from[arc], parent);
+                self.out_of_tree[parent].push(arc);
+                self.remove_child(parent, current);
+                self.add_strong_root(current);
+            }
+
+            current = parent;
+        }
+
+        if self.excess[current] > 0.0 && prev_excess <= 0.0 {
+            self.add_strong_root(current);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Pseudoflow;
+    use crate::pit::lerchs_grossmann::LerchsGrossmann;
+    use crate::pit::tests::{brute_force, closure_value, toy_pit};
+    use crate::pit::PitOptimizer;
+
+    #[test]
+    fn matches_brute_force() {
+        let graph = toy_pit();
+        let in_pit = Pseudoflow.solve(&graph);
+        assert_eq!(closure_value(&graph, &in_pit), Some(brute_force(&graph)));
+    }
+
+    #[test]
+    fn matches_lerchs_grossmann() {
+        let mut graph = toy_pit();
+        for value in [-1.0, 4.0, 8.0] {
+            graph.weights[2 * 3 + 1] = value;
+            let lg = LerchsGrossmann.solve(&graph);
+            let pf = Pseudoflow.solve(&graph);
+            assert_eq!(closure_value(&graph, &pf), closure_value(&graph, &lg));
+            assert_eq!(closure_value(&graph, &pf), Some(brute_force(&graph)));
+        }
+    }
+}
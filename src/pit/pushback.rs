@@ -0,0 +1,192 @@
+use rayon::prelude::*;
+
+use crate::block::{BlockIndex, BlockInterface};
+use crate::block_model::BlockModel;
+use crate::pit::nested::NestedShells;
+use crate::selection::Selection;
+use crate::storage::BlockStorage;
+
+use std::collections::{HashMap, HashSet};
+
+//narrow benches then sum of squared tonnages, which for a fixed number of pushbacks is
+//the squared deviation from the mean up to a constant
+type Cost = (usize, f64);
+
+//consecutive nested shells grouped into mining phases
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pushbacks {
+    //last shell of every pushback, ascending
+    pub last_shells: Vec<usize>,
+    //pushback of every block inside the largest shell
+    pub phases: HashMap<BlockIndex, usize>,
+    pub tonnages: Vec<f64>,
+    //benches of each pushback too narrow to hold a footprint of the minimum width
+    pub narrow_benches: Vec<usize>,
+}
+
+impl Pushbacks {
+    pub fn len(&self) -> usize {
+        self.last_shells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.last_shells.is_empty()
+    }
+
+    pub fn phase(&self, ind: BlockIndex) -> Option<usize> {
+        self.phases.get(&ind).copied()
+    }
+}
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface,
+    S: BlockStorage<B>,
+{
+    //group the shells into up to num_pushbacks phases of consecutive shells balancing
+    //tonnage, every bench of a pushback must fit a square footprint min_width across and
+    //fewer pushbacks are used when that needs it, if no grouping fits the fewest narrow
+    //benches win
+    pub fn pushbacks<T>(
+        &self,
+        shells: &NestedShells,
+        num_pushbacks: usize,
+        min_width: f64,
+        tonnage: T,
+    ) -> Pushbacks
+    where
+        T: Fn(&B) -> f64,
+    {
+        assert!(num_pushbacks > 0, "at least one pushback is required");
+
+        let num_shells = shells.num_shells();
+        let mut increments = vec![Vec::new(); num_shells];
+        let mut shell_tonnages = vec![0.0; num_shells];
+        for (ind, shell) in shells.shells.iter() {
+            increments[*shell].push(*ind);
+            shell_tonnages[*shell] += tonnage(self.block(*ind).unwrap());
+        }
+
+        let element = self.footprint(min_width);
+        let dims = self.dims();
+
+        //narrow benches and tonnage of a pushback of shells a..=b
+        let costs = (0..num_shells)
+            .into_par_iter()
+            .map(|a| {
+                let mut ring = Selection::new(dims);
+                let mut t = 0.0;
+                (a..num_shells)
+                    .map(|b| {
+                        increments[b].iter().for_each(|ind| {
+                            ring.insert(*ind);
+                        });
+                        t += shell_tonnages[b];
+                        let benches = ring.iter().map(|ind| ind.k).collect::<HashSet<_>>();
+                        let wide = ring
+                            .erode(&element)
+                            .iter()
+                            .map(|ind| ind.k)
+                            .collect::<HashSet<_>>();
+                        (benches.difference(&wide).count(), t)
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let cost = |a: usize, b: usize| {
+            let (narrow, t) = costs[a][b - a];
+            (narrow, t * t)
+        };
+        let add = |(n0, d0): Cost, (n1, d1): Cost| (n0 + n1, d0 + d1);
+        let better = |x: Cost, y: Cost| x.0 < y.0 || (x.0 == y.0 && x.1 < y.1);
+
+        //best[p][s], cheapest split of shells 0..s into p + 1 pushbacks, with the split point
+        let num_pushbacks = num_pushbacks.min(num_shells);
+        let mut best: Vec<Vec<Option<(Cost, usize)>>> =
+            vec![vec![None; num_shells + 1]; num_pushbacks];
+        if let Some(first) = best.first_mut() {
+            for (s, b) in first.iter_mut().enumerate().skip(1) {
+                *b = Some((cost(0, s - 1), 0));
+            }
+        }
+        for p in 1..num_pushbacks {
+            for s in p + 1..=num_shells {
+                for split in p..s {
+                    let Some((prev, _)) = best[p - 1][split] else {
+                        continue;
+                    };
+                    let c = add(prev, cost(split, s - 1));
+                    if best[p][s].is_none_or(|(b, _)| better(c, b)) {
+                        best[p][s] = Some((c, split));
+                    }
+                }
+            }
+        }
+
+        //most pushbacks with the fewest narrow benches
+        let narrowest = |p: usize| best[p][num_shells].map_or(usize::MAX, |(c, _)| c.0);
+        let fewest = (0..num_pushbacks).map(narrowest).min().unwrap_or(0);
+        let mut p = (0..num_pushbacks)
+            .rev()
+            .find(|p| narrowest(*p) == fewest)
+            .map_or(0, |p| p + 1);
+
+        let mut last_shells = Vec::with_capacity(p);
+        let mut s = num_shells;
+        while p > 0 && s > 0 {
+            let (_, split) = best[p - 1][s].unwrap();
+            last_shells.push(s - 1);
+            s = split;
+            p -= 1;
+        }
+        last_shells.reverse();
+
+        let mut phase_of = vec![0; num_shells];
+        let mut tonnages = Vec::with_capacity(last_shells.len());
+        let mut narrow_benches = Vec::with_capacity(last_shells.len());
+        let mut first = 0;
+        for (phase, last) in last_shells.iter().enumerate() {
+            phase_of[first..=*last].fill(phase);
+            let (narrow, t) = costs[first][last - first];
+            narrow_benches.push(narrow);
+            tonnages.push(t);
+            first = last + 1;
+        }
+
+        let phases = shells
+            .shells
+            .iter()
+            .map(|(ind, shell)| (*ind, phase_of[*shell]))
+            .collect();
+
+        Pushbacks {
+            last_shells,
+            phases,
+            tonnages,
+            narrow_benches,
+        }
+    }
+
+    //group shells into pushbacks and write each block's pushback
+    pub fn assign_pushbacks<T, F>(
+        &mut self,
+        shells: &NestedShells,
+        num_pushbacks: usize,
+        min_width: f64,
+        tonnage: T,
+        set: F,
+    ) -> Pushbacks
+    where
+        B: Send,
+        T: Fn(&B) -> f64,
+        F: Fn(&mut B, usize) + Sync,
+    {
+        let pushbacks = self.pushbacks(shells, num_pushbacks, min_width, tonnage);
+        self.par_indexed_iter_mut().for_each(|(ind, b)| {
+            if let Some(phase) = pushbacks.phases.get(&ind) {
+                set(b, *phase);
+            }
+        });
+        pushbacks
+    }
+}
@@ -0,0 +1,205 @@
+use serde::Serialize;
+
+use crate::block::{BlockIndex, BlockInterface};
+use crate::block_model::BlockModel;
+use crate::precedence::{AzimuthSlopePrecedence, SlopePrecedence};
+use crate::selection::Selection;
+use crate::spatial::KdTree;
+use crate::storage::BlockStorage;
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::Path;
+
+//design slope angle in degrees from horizontal at a world azimuth, degrees clockwise from +y
+pub trait SlopeTarget {
+    fn target_angle(&self, azimuth: f32) -> f32;
+}
+
+impl SlopeTarget for SlopePrecedence {
+    fn target_angle(&self, _azimuth: f32) -> f32 {
+        self.slope_angle()
+    }
+}
+
+impl SlopeTarget for AzimuthSlopePrecedence {
+    fn target_angle(&self, azimuth: f32) -> f32 {
+        self.slope_angle(azimuth)
+    }
+}
+
+//angle from a wall toe block to the nearest unmined block a stack of benches above it,
+//measured between centroids as the precedence templates are built
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SlopeMeasurement {
+    pub toe: BlockIndex,
+    pub crest: BlockIndex,
+    //bearing from toe to crest
+    pub azimuth: f32,
+    pub angle: f32,
+    pub target: f32,
+}
+
+//measurements of one azimuth sector and bench stack, stacks count up from bench 0
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct SlopeSector {
+    pub sector: usize,
+    pub stack: usize,
+    pub count: usize,
+    pub mean_angle: f32,
+    pub min_angle: f32,
+    pub max_angle: f32,
+    //target at the sector centre
+    pub target: f32,
+    pub violations: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SlopeAudit {
+    pub benches_per_stack: usize,
+    pub num_sectors: usize,
+    //degrees a measurement may exceed its target by
+    pub tolerance: f32,
+    pub measurements: Vec<SlopeMeasurement>,
+    //sorted by sector then stack
+    pub sectors: Vec<SlopeSector>,
+}
+
+impl SlopeAudit {
+    //measurements steeper than the target
+    pub fn violations(&self) -> impl Iterator<Item = &SlopeMeasurement> {
+        self.measurements
+            .iter()
+            .filter(|m| m.angle > m.target + self.tolerance)
+    }
+
+    //one row per sector and stack
+    pub fn to_csv<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn Error>> {
+        let mut w = csv::Writer::from_path(path)?;
+        for s in self.sectors.iter() {
+            w.serialize(s)?;
+        }
+        w.flush()?;
+        Ok(())
+    }
+}
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface,
+    S: BlockStorage<B>,
+{
+    //realized inter ramp slope angles of a pit shell over stacks of benches_per_stack benches,
+    //toes are pit blocks next to an unmined present block on their bench and are measured
+    //to the nearest unmined present block benches_per_stack benches above, sectors are
+    //centred on evenly spaced azimuths starting at north as in AzimuthSlopePrecedence
+    pub fn slope_audit<T: SlopeTarget>(
+        &self,
+        pit: &Selection,
+        target: &T,
+        benches_per_stack: usize,
+        num_sectors: usize,
+        tolerance: f32,
+    ) -> SlopeAudit {
+        assert!(
+            pit.dims() == self.dims(),
+            "selection dims do not match the model"
+        );
+        assert!(benches_per_stack > 0, "a stack needs at least one bench");
+        assert!(num_sectors > 0, "at least one sector is required");
+
+        let dims = self.dims();
+        let unmined = |ind: BlockIndex| !pit.contains(ind) && self.block(ind).is_some();
+        let plan = |ind: BlockIndex| {
+            let c = self.index_to_coordinates(ind);
+            [c.x as f64, c.y as f64, 0.0]
+        };
+
+        //unmined blocks of every bench in plan
+        let benches = (0..dims[2])
+            .map(|k| {
+                let inds = (0..dims[0])
+                    .flat_map(|i| (0..dims[1]).map(move |j| BlockIndex { i, j, k }))
+                    .filter(|ind| unmined(*ind))
+                    .collect::<Vec<_>>();
+                let tree = KdTree::new(&inds.iter().map(|ind| plan(*ind)).collect::<Vec<_>>());
+                (inds, tree)
+            })
+            .collect::<Vec<_>>();
+
+        let mut measurements = Vec::new();
+        for toe in pit.iter() {
+            let crest_k = toe.k + benches_per_stack;
+            if crest_k >= dims[2] || self.block(toe).is_none() {
+                continue;
+            }
+            let wall = (-1..=1i64).any(|di| {
+                (-1..=1i64).any(|dj| {
+                    let (i, j) = (toe.i as i64 + di, toe.j as i64 + dj);
+                    i >= 0
+                        && j >= 0
+                        && (i as usize) < dims[0]
+                        && (j as usize) < dims[1]
+                        && unmined(BlockIndex {
+                            i: i as usize,
+                            j: j as usize,
+                            k: toe.k,
+                        })
+                })
+            });
+            if !wall {
+                continue;
+            }
+
+            let (inds, tree) = &benches[crest_k];
+            let Some(&(n, distance)) = tree.nearest(plan(toe), 1).first() else {
+                continue;
+            };
+            let crest = inds[n];
+            let (a, b) = (
+                self.index_to_coordinates(toe),
+                self.index_to_coordinates(crest),
+            );
+            let azimuth = (b.x - a.x).atan2(b.y - a.y).to_degrees().rem_euclid(360.0);
+            let rise = (b.z - a.z) as f64;
+            measurements.push(SlopeMeasurement {
+                toe,
+                crest,
+                azimuth,
+                angle: rise.atan2(distance).to_degrees() as f32,
+                target: target.target_angle(azimuth),
+            });
+        }
+
+        let width = 360.0 / num_sectors as f32;
+        let sector_of = |azimuth: f32| ((azimuth / width).round() as usize) % num_sectors;
+        let mut groups: BTreeMap<(usize, usize), Vec<&SlopeMeasurement>> = BTreeMap::new();
+        for m in measurements.iter() {
+            groups
+                .entry((sector_of(m.azimuth), m.toe.k / benches_per_stack))
+                .or_default()
+                .push(m);
+        }
+        let sectors = groups
+            .into_iter()
+            .map(|((sector, stack), ms)| SlopeSector {
+                sector,
+                stack,
+                count: ms.len(),
+                mean_angle: ms.iter().map(|m| m.angle).sum::<f32>() / ms.len() as f32,
+                min_angle: ms.iter().map(|m| m.angle).fold(f32::INFINITY, f32::min),
+                max_angle: ms.iter().map(|m| m.angle).fold(f32::NEG_INFINITY, f32::max),
+                target: target.target_angle(sector as f32 * width),
+                violations: ms.iter().filter(|m| m.angle > m.target + tolerance).count(),
+            })
+            .collect();
+
+        SlopeAudit {
+            benches_per_stack,
+            num_sectors,
+            tolerance,
+            measurements,
+            sectors,
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use crate::block::{BlockIndex, BlockInterface};
+use crate::block_model::BlockModel;
+use crate::geometry::mesh::TriangleMesh;
+use crate::selection::Selection;
+use crate::storage::BlockStorage;
+
+use std::collections::HashMap;
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface,
+    S: BlockStorage<B>,
+{
+    //faces between pit blocks and the unmined blocks around them as outward facing triangles
+    //sharing lattice corner vertices, include_air adds the faces against absent blocks and
+    //the model edge, closing the pit into a solid
+    pub fn pit_surface(&self, pit: &Selection, include_air: bool) -> TriangleMesh {
+        assert!(
+            pit.dims() == self.dims(),
+            "selection dims do not match the model"
+        );
+
+        let dims = self.dims();
+        let size = self.block_size();
+        let mut vertices = Vec::new();
+        let mut corners: HashMap<[usize; 3], usize> = HashMap::new();
+        let mut vertex = |c: [usize; 3]| {
+            *corners.entry(c).or_insert_with(|| {
+                let p = self.frame().to_world([
+                    (c[0] as f32 - 0.5) * size.x_size,
+                    (c[1] as f32 - 0.5) * size.y_size,
+                    (c[2] as f32 - 0.5) * size.z_size,
+                ]);
+                vertices.push([p.x as f64, p.y as f64, p.z as f64]);
+                vertices.len() - 1
+            })
+        };
+
+        let mut triangles = Vec::new();
+        for ind in pit.iter() {
+            let cell = [ind.i, ind.j, ind.k];
+            for axis in 0..3 {
+                for up in [false, true] {
+                    let neighbour = match up {
+                        true if cell[axis] + 1 < dims[axis] => Some(cell[axis] + 1),
+                        false if cell[axis] > 0 => Some(cell[axis] - 1),
+                        _ => None,
+                    }
+                    .map(|n| {
+                        let mut c = cell;
+                        c[axis] = n;
+                        BlockIndex {
+                            i: c[0],
+                            j: c[1],
+                            k: c[2],
+                        }
+                    });
+                    let exposed = match neighbour {
+                        Some(n) if pit.contains(n) => false,
+                        Some(n) if self.block(n).is_some() => true,
+                        _ => include_air,
+                    };
+                    if !exposed {
+                        continue;
+                    }
+
+                    //quad corners counter clockwise about the axis, reversed on the low side
+                    let (b, c) = ((axis + 1) % 3, (axis + 2) % 3);
+                    let mut base = cell;
+                    base[axis] += up as usize;
+                    let offset = |db: usize, dc: usize| {
+                        let mut p = base;
+                        p[b] += db;
+                        p[c] += dc;
+                        p
+                    };
+                    let mut quad =
+                        [offset(0, 0), offset(1, 0), offset(1, 1), offset(0, 1)].map(&mut vertex);
+                    if !up {
+                        quad.reverse();
+                    }
+                    triangles.push([quad[0], quad[1], quad[2]]);
+                    triangles.push([quad[0], quad[2], quad[3]]);
+                }
+            }
+        }
+
+        TriangleMesh::new(vertices, triangles)
+    }
+}
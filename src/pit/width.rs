@@ -0,0 +1,83 @@
+use crate::block::{BlockIndex, BlockInterface};
+use crate::block_model::BlockModel;
+use crate::morphology::StructuringElement;
+use crate::selection::Selection;
+use crate::storage::BlockStorage;
+
+use std::collections::BTreeMap;
+
+//how regions narrower than the minimum width are corrected
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum WidthCorrection {
+    //mine gaps between mined areas that are too narrow to leave
+    Fill,
+    //leave mined areas that are too narrow to mine
+    Remove,
+}
+
+//blocks changed by a minimum width pass, sorted by index
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct WidthReport {
+    pub added: Vec<BlockIndex>,
+    pub removed: Vec<BlockIndex>,
+}
+
+impl WidthReport {
+    pub fn num_affected(&self) -> usize {
+        self.added.len() + self.removed.len()
+    }
+
+    //(added, removed) block counts on every affected bench
+    pub fn by_bench(&self) -> BTreeMap<usize, (usize, usize)> {
+        let mut benches = BTreeMap::new();
+        for ind in self.added.iter() {
+            benches.entry(ind.k).or_insert((0, 0)).0 += 1;
+        }
+        for ind in self.removed.iter() {
+            benches.entry(ind.k).or_insert((0, 0)).1 += 1;
+        }
+        benches
+    }
+}
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface,
+    S: BlockStorage<B>,
+{
+    //square bench footprint at least width world units across
+    pub(crate) fn footprint(&self, width: f64) -> StructuringElement {
+        let size = self.block_size();
+        let blocks = |s: f32| ((width / s as f64) - 1e-9).ceil().max(1.0) as usize;
+        StructuringElement::rectangle(blocks(size.x_size), blocks(size.y_size))
+    }
+
+    //correct every bench of the pit so each mined block lies in a square mining footprint
+    //at least width world units across, filled blocks are limited to present blocks and
+    //slope precedence is not re-checked
+    pub fn enforce_minimum_width(
+        &self,
+        pit: &Selection,
+        width: f64,
+        correction: WidthCorrection,
+    ) -> (Selection, WidthReport) {
+        assert_eq!(pit.dims(), self.dims(), "pit spans a different lattice");
+        assert!(width > 0.0, "minimum width must be positive");
+
+        let element = self.footprint(width);
+
+        let corrected = match correction {
+            WidthCorrection::Fill => pit
+                .close(&element)
+                .intersection(&self.select_all())
+                .union(pit),
+            WidthCorrection::Remove => pit.open(&element),
+        };
+
+        let report = WidthReport {
+            added: corrected.difference(pit).iter().collect(),
+            removed: pit.difference(&corrected).iter().collect(),
+        };
+        (corrected, report)
+    }
+}
@@ -0,0 +1,218 @@
+use crate::block::{BlockIndex, BlockInterface, BlockSize};
+use crate::block_model::{BlockDependenceInterface, BlockModel};
+use crate::frame::ModelFrame;
+use crate::storage::BlockStorage;
+
+//relative (di, dj, dk) offsets from a block to the blocks above it that must be removed first
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrecedenceTemplate {
+    offsets: Vec<[isize; 3]>,
+}
+
+impl PrecedenceTemplate {
+    pub fn new(offsets: Vec<[isize; 3]>) -> Self {
+        Self { offsets }
+    }
+
+    pub fn offsets(&self) -> &[[isize; 3]] {
+        &self.offsets
+    }
+
+    //build the minimal template for a cone whose wall angle (degrees from horizontal)
+    //depends on the xy direction of the offset, azimuth in degrees clockwise from +y
+    pub(crate) fn from_slope_fn<F>(block_size: BlockSize, max_benches: usize, slope_at: F) -> Self
+    where
+        F: Fn(f32) -> f32,
+    {
+        //full cone of offsets reachable at each bench above the block
+        let cone = (0..=max_benches)
+            .map(|dk| Self::cone_level(block_size, dk, &slope_at))
+            .collect::<Vec<_>>();
+
+        //drop offsets implied transitively by a shallower offset
+        let mut offsets = Vec::new();
+        for dk in 1..=max_benches {
+            for &[di, dj] in cone[dk].iter() {
+                let implied = (1..dk).any(|d1| {
+                    cone[d1]
+                        .iter()
+                        .any(|&[ai, aj]| cone[dk - d1].contains(&[di - ai, dj - aj]))
+                });
+
+                if !implied {
+                    offsets.push([di, dj, dk as isize]);
+                }
+            }
+        }
+
+        Self { offsets }
+    }
+
+    fn cone_level<F>(block_size: BlockSize, dk: usize, slope_at: &F) -> Vec<[isize; 2]>
+    where
+        F: Fn(f32) -> f32,
+    {
+        if dk == 0 {
+            return vec![[0, 0]];
+        }
+
+        let height = dk as f32 * block_size.z_size;
+
+        //widest reach over all directions bounds the search window
+        let min_angle = (0..360)
+            .map(|az| slope_at(az as f32))
+            .fold(f32::MAX, f32::min);
+        let max_reach = height / min_angle.to_radians().tan();
+        let ri = (max_reach / block_size.x_size).ceil() as isize;
+        let rj = (max_reach / block_size.y_size).ceil() as isize;
+
+        let mut level = Vec::new();
+        for di in -ri..=ri {
+            for dj in -rj..=rj {
+                let dx = di as f32 * block_size.x_size;
+                let dy = dj as f32 * block_size.y_size;
+                let dist = (dx * dx + dy * dy).sqrt();
+
+                let azimuth = dx.atan2(dy).to_degrees().rem_euclid(360.0);
+                let reach = height / slope_at(azimuth).to_radians().tan();
+
+                if dist <= reach + 1e-4 * block_size.x_size.min(block_size.y_size) {
+                    level.push([di, dj]);
+                }
+            }
+        }
+        level
+    }
+
+    //apply template to a block, clipping to the model dimensions
+    pub fn apply(&self, ind: BlockIndex, dims: [usize; 3]) -> Vec<BlockIndex> {
+        self.offsets
+            .iter()
+            .filter_map(|&[di, dj, dk]| {
+                let i = ind.i as isize + di;
+                let j = ind.j as isize + dj;
+                let k = ind.k as isize + dk;
+                let in_bounds = i >= 0
+                    && j >= 0
+                    && k >= 0
+                    && (i as usize) < dims[0]
+                    && (j as usize) < dims[1]
+                    && (k as usize) < dims[2];
+
+                in_bounds.then_some(BlockIndex {
+                    i: i as usize,
+                    j: j as usize,
+                    k: k as usize,
+                })
+            })
+            .collect()
+    }
+}
+
+//predecessors approximating a cone with constant wall angle
+#[derive(Debug, Clone, PartialEq)]
+pub struct SlopePrecedence {
+    slope_angle: f32,
+    template: PrecedenceTemplate,
+}
+
+impl SlopePrecedence {
+    //slope angle in degrees from horizontal, max_benches is the number of benches above
+    //a block the template spans, deeper templates approximate the cone more closely
+    pub fn new(slope_angle: f32, max_benches: usize, frame: &ModelFrame) -> Self {
+        assert!(
+            slope_angle > 0.0 && slope_angle < 90.0,
+            "slope angle must be in (0, 90) degrees"
+        );
+
+        let template =
+            PrecedenceTemplate::from_slope_fn(frame.block_size, max_benches, |_| slope_angle);
+
+        Self {
+            slope_angle,
+            template,
+        }
+    }
+
+    pub fn slope_angle(&self) -> f32 {
+        self.slope_angle
+    }
+
+    pub fn template(&self) -> &PrecedenceTemplate {
+        &self.template
+    }
+}
+
+impl BlockDependenceInterface for SlopePrecedence {
+    fn inds<B: BlockInterface, S: BlockStorage<B>>(
+        &self,
+        mdl: &BlockModel<B, S>,
+        ind: BlockIndex,
+    ) -> Vec<BlockIndex> {
+        self.template.apply(ind, mdl.dims())
+    }
+}
+
+//predecessors approximating a cone whose wall angle varies with azimuth
+//sector angles are centred on evenly spaced azimuths starting at north (+y) and
+//proceeding clockwise, angles between sector centres are linearly interpolated
+#[derive(Debug, Clone, PartialEq)]
+pub struct AzimuthSlopePrecedence {
+    sector_angles: Vec<f32>,
+    template: PrecedenceTemplate,
+}
+
+impl AzimuthSlopePrecedence {
+    pub fn new(sector_angles: Vec<f32>, max_benches: usize, frame: &ModelFrame) -> Self {
+        assert!(!sector_angles.is_empty(), "at least one sector is required");
+        assert!(
+            sector_angles.iter().all(|&a| a > 0.0 && a < 90.0),
+            "slope angles must be in (0, 90) degrees"
+        );
+
+        //template offsets are measured in model axes, sectors in world bearings
+        let template = PrecedenceTemplate::from_slope_fn(frame.block_size, max_benches, |az| {
+            Self::interpolate(&sector_angles, az + frame.rotation.azimuth)
+        });
+
+        Self {
+            sector_angles,
+            template,
+        }
+    }
+
+    fn interpolate(sector_angles: &[f32], azimuth: f32) -> f32 {
+        let n = sector_angles.len();
+        let width = 360.0 / n as f32;
+        let pos = azimuth.rem_euclid(360.0) / width;
+
+        let low = pos.floor() as usize % n;
+        let high = (low + 1) % n;
+        let t = pos.fract();
+
+        sector_angles[low] * (1.0 - t) + sector_angles[high] * t
+    }
+
+    //interpolated slope angle at azimuth (degrees clockwise from +y)
+    pub fn slope_angle(&self, azimuth: f32) -> f32 {
+        Self::interpolate(&self.sector_angles, azimuth)
+    }
+
+    pub fn sector_angles(&self) -> &[f32] {
+        &self.sector_angles
+    }
+
+    pub fn template(&self) -> &PrecedenceTemplate {
+        &self.template
+    }
+}
+
+impl BlockDependenceInterface for AzimuthSlopePrecedence {
+    fn inds<B: BlockInterface, S: BlockStorage<B>>(
+        &self,
+        mdl: &BlockModel<B, S>,
+        ind: BlockIndex,
+    ) -> Vec<BlockIndex> {
+        self.template.apply(ind, mdl.dims())
+    }
+}
@@ -0,0 +1,473 @@
+use crate::block::{BlockIndex, BlockInterface};
+use crate::block_model::BlockModel;
+use crate::categorical::Dictionary;
+use crate::dynamic::{AttributeValue, DynamicBlock};
+use crate::error::QueryError;
+use crate::selection::Selection;
+use crate::storage::BlockStorage;
+
+use std::collections::BTreeMap;
+
+//named attribute access for filter expressions
+pub trait Queryable {
+    fn attribute(&self, name: &str) -> Option<AttributeValue>;
+}
+
+impl Queryable for DynamicBlock {
+    fn attribute(&self, name: &str) -> Option<AttributeValue> {
+        self.get(name).cloned()
+    }
+}
+
+//value of a sub expression, missing attributes are null and compare false
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    Text(String),
+}
+
+impl From<AttributeValue> for Value {
+    fn from(v: AttributeValue) -> Self {
+        match v {
+            AttributeValue::Bool(b) => Self::Bool(b),
+            AttributeValue::Int(v) => Self::Number(v as f64),
+            AttributeValue::Float(v) => Self::Number(v),
+            AttributeValue::Text(t) => Self::Text(t),
+            AttributeValue::Category(c) => Self::Number(c.0 as f64),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+//parsed filter expression
+//operators by increasing precedence are ||, &&, comparisons (== != < <= > >=), + -, * /,
+//and unary ! -, identifiers i, j, k, x, y, z refer to the block index and centroid
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Number(f64),
+    Text(String),
+    Bool(bool),
+    Attribute(String),
+    Not(Box<Expr>),
+    Neg(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Text(String),
+    Ident(String),
+    Op(&'static str),
+    Open,
+    Close,
+}
+
+const OPS: [&str; 15] = [
+    "||", "&&", "==", "!=", "<=", ">=", "<", ">", "+", "-", "*", "/", "!", "=", "|",
+];
+
+fn tokenize(text: &str) -> Result<Vec<(usize, Token)>, QueryError> {
+    let bytes = text.as_bytes();
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+
+    while pos < bytes.len() {
+        let c = bytes[pos] as char;
+        let start = pos;
+
+        if c.is_whitespace() {
+            pos += 1;
+            continue;
+        }
+
+        let token = if c == '(' {
+            pos += 1;
+            Token::Open
+        } else if c == ')' {
+            pos += 1;
+            Token::Close
+        } else if c.is_ascii_digit() || c == '.' {
+            while pos < bytes.len()
+                && ((bytes[pos] as char).is_ascii_alphanumeric() || bytes[pos] == b'.')
+            {
+                //exponent sign
+                if matches!(bytes[pos], b'e' | b'E')
+                    && matches!(bytes.get(pos + 1), Some(b'+' | b'-'))
+                {
+                    pos += 1;
+                }
+                pos += 1;
+            }
+            let number = text[start..pos].parse().map_err(|_| QueryError::Parse {
+                pos: start,
+                message: format!("invalid number {}", &text[start..pos]),
+            })?;
+            Token::Number(number)
+        } else if c == '"' || c == '\'' {
+            pos += 1;
+            while pos < bytes.len() && bytes[pos] as char != c {
+                pos += 1;
+            }
+            if pos == bytes.len() {
+                return Err(QueryError::Parse {
+                    pos: start,
+                    message: "unterminated string".to_string(),
+                });
+            }
+            pos += 1;
+            Token::Text(text[start + 1..pos - 1].to_string())
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            while pos < bytes.len()
+                && ((bytes[pos] as char).is_ascii_alphanumeric() || bytes[pos] == b'_')
+            {
+                pos += 1;
+            }
+            Token::Ident(text[start..pos].to_string())
+        } else {
+            let op = OPS
+                .iter()
+                .find(|op| text[pos..].starts_with(*op))
+                .ok_or_else(|| QueryError::Parse {
+                    pos,
+                    message: format!("unexpected character {}", c),
+                })?;
+            pos += op.len();
+            //single = and | are accepted as == and ||
+            Token::Op(match *op {
+                "=" => "==",
+                "|" => "||",
+                op => op,
+            })
+        };
+
+        tokens.push((start, token));
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    next: usize,
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.next).map(|(_, t)| t)
+    }
+
+    fn pos(&self) -> usize {
+        self.tokens.get(self.next).map_or(self.end, |(p, _)| *p)
+    }
+
+    fn error(&self, message: &str) -> QueryError {
+        QueryError::Parse {
+            pos: self.pos(),
+            message: message.to_string(),
+        }
+    }
+
+    //consume the next token if it is one of ops
+    fn op(&mut self, ops: &[(&str, BinaryOp)]) -> Option<BinaryOp> {
+        let Some(Token::Op(t)) = self.peek() else {
+            return None;
+        };
+        let (_, op) = ops.iter().find(|(o, _)| o == t)?;
+        self.next += 1;
+        Some(*op)
+    }
+
+    fn binary<F>(&mut self, ops: &[(&str, BinaryOp)], operand: F) -> Result<Expr, QueryError>
+    where
+        F: Fn(&mut Self) -> Result<Expr, QueryError>,
+    {
+        let mut lhs = operand(self)?;
+        while let Some(op) = self.op(ops) {
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(operand(self)?));
+        }
+        Ok(lhs)
+    }
+
+    fn or(&mut self) -> Result<Expr, QueryError> {
+        self.binary(&[("||", BinaryOp::Or)], Self::and)
+    }
+
+    fn and(&mut self) -> Result<Expr, QueryError> {
+        self.binary(&[("&&", BinaryOp::And)], Self::comparison)
+    }
+
+    fn comparison(&mut self) -> Result<Expr, QueryError> {
+        let ops = [
+            ("==", BinaryOp::Eq),
+            ("!=", BinaryOp::Ne),
+            ("<", BinaryOp::Lt),
+            ("<=", BinaryOp::Le),
+            (">", BinaryOp::Gt),
+            (">=", BinaryOp::Ge),
+        ];
+
+        let lhs = self.sum()?;
+        match self.op(&ops) {
+            Some(op) => Ok(Expr::Binary(op, Box::new(lhs), Box::new(self.sum()?))),
+            None => Ok(lhs),
+        }
+    }
+
+    fn sum(&mut self) -> Result<Expr, QueryError> {
+        self.binary(&[("+", BinaryOp::Add), ("-", BinaryOp::Sub)], Self::product)
+    }
+
+    fn product(&mut self) -> Result<Expr, QueryError> {
+        self.binary(&[("*", BinaryOp::Mul), ("/", BinaryOp::Div)], Self::unary)
+    }
+
+    fn unary(&mut self) -> Result<Expr, QueryError> {
+        match self.peek() {
+            Some(Token::Op("!")) => {
+                self.next += 1;
+                Ok(Expr::Not(Box::new(self.unary()?)))
+            }
+            Some(Token::Op("-")) => {
+                self.next += 1;
+                Ok(Expr::Neg(Box::new(self.unary()?)))
+            }
+            _ => self.primary(),
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, QueryError> {
+        let token = self
+            .peek()
+            .cloned()
+            .ok_or_else(|| self.error("unexpected end of expression"))?;
+
+        let expr = match token {
+            Token::Number(v) => Expr::Number(v),
+            Token::Text(t) => Expr::Text(t),
+            Token::Ident(name) => match name.as_str() {
+                "true" => Expr::Bool(true),
+                "false" => Expr::Bool(false),
+                _ => Expr::Attribute(name),
+            },
+            Token::Open => {
+                self.next += 1;
+                let inner = self.or()?;
+                if self.peek() != Some(&Token::Close) {
+                    return Err(self.error("expected )"));
+                }
+                inner
+            }
+            _ => return Err(self.error("expected a value")),
+        };
+
+        self.next += 1;
+        Ok(expr)
+    }
+}
+
+fn type_error(op: BinaryOp, lhs: &Value, rhs: &Value) -> QueryError {
+    QueryError::Type {
+        message: format!("cannot apply {:?} to {:?} and {:?}", op, lhs, rhs),
+    }
+}
+
+impl Expr {
+    pub fn parse(text: &str) -> Result<Self, QueryError> {
+        let mut parser = Parser {
+            tokens: tokenize(text)?,
+            next: 0,
+            end: text.len(),
+        };
+
+        let expr = parser.or()?;
+        if parser.peek().is_some() {
+            return Err(parser.error("unexpected trailing input"));
+        }
+        Ok(expr)
+    }
+
+    //text compared with a categorical attribute replaced by its code, names missing from the
+    //dictionary become NaN so they match nothing
+    fn resolve_categories(self, dictionaries: &BTreeMap<String, Dictionary>) -> Self {
+        let code = |attribute: &Expr, text: &Expr| match (attribute, text) {
+            (Self::Attribute(a), Self::Text(t)) => dictionaries
+                .get(a)
+                .map(|d| Self::Number(d.code(t).map_or(f64::NAN, |c| c.0 as f64))),
+            _ => None,
+        };
+        match self {
+            Self::Not(e) => Self::Not(Box::new(e.resolve_categories(dictionaries))),
+            Self::Neg(e) => Self::Neg(Box::new(e.resolve_categories(dictionaries))),
+            Self::Binary(op, lhs, rhs) => {
+                let (lhs, rhs) = match (code(&lhs, &rhs), code(&rhs, &lhs)) {
+                    (Some(r), _) => (*lhs, r),
+                    (_, Some(l)) => (l, *rhs),
+                    _ => (
+                        lhs.resolve_categories(dictionaries),
+                        rhs.resolve_categories(dictionaries),
+                    ),
+                };
+                Self::Binary(op, Box::new(lhs), Box::new(rhs))
+            }
+            e => e,
+        }
+    }
+
+    fn eval<B: BlockInterface + Queryable>(
+        &self,
+        ind: BlockIndex,
+        b: &B,
+    ) -> Result<Value, QueryError> {
+        Ok(match self {
+            Self::Number(v) => Value::Number(*v),
+            Self::Text(t) => Value::Text(t.clone()),
+            Self::Bool(v) => Value::Bool(*v),
+            Self::Attribute(name) => match name.as_str() {
+                "i" => Value::Number(ind.i as f64),
+                "j" => Value::Number(ind.j as f64),
+                "k" => Value::Number(ind.k as f64),
+                "x" => Value::Number(b.coordinates().x as f64),
+                "y" => Value::Number(b.coordinates().y as f64),
+                "z" => Value::Number(b.coordinates().z as f64),
+                _ => b.attribute(name).map_or(Value::Null, Value::from),
+            },
+            Self::Not(e) => match e.eval(ind, b)? {
+                Value::Bool(v) => Value::Bool(!v),
+                Value::Null => Value::Null,
+                v => {
+                    return Err(QueryError::Type {
+                        message: format!("cannot negate {:?}", v),
+                    })
+                }
+            },
+            Self::Neg(e) => match e.eval(ind, b)? {
+                Value::Number(v) => Value::Number(-v),
+                Value::Null => Value::Null,
+                v => {
+                    return Err(QueryError::Type {
+                        message: format!("cannot negate {:?}", v),
+                    })
+                }
+            },
+            Self::Binary(op, lhs, rhs) => {
+                let lhs = lhs.eval(ind, b)?;
+
+                //short circuit, null counts as false
+                match (op, &lhs) {
+                    (BinaryOp::Or, Value::Bool(true)) => return Ok(Value::Bool(true)),
+                    (BinaryOp::And, Value::Bool(false) | Value::Null) => {
+                        return Ok(Value::Bool(false))
+                    }
+                    _ => {}
+                }
+
+                let rhs = rhs.eval(ind, b)?;
+                Self::apply(*op, lhs, rhs)?
+            }
+        })
+    }
+
+    fn apply(op: BinaryOp, lhs: Value, rhs: Value) -> Result<Value, QueryError> {
+        use BinaryOp::*;
+
+        if matches!(op, Or | And) {
+            let truth = |v: &Value| match v {
+                Value::Bool(b) => Ok(*b),
+                Value::Null => Ok(false),
+                _ => Err(type_error(op, &lhs, &rhs)),
+            };
+            let (l, r) = (truth(&lhs)?, truth(&rhs)?);
+            return Ok(Value::Bool(if op == Or { l || r } else { l && r }));
+        }
+
+        Ok(match (&lhs, &rhs) {
+            //comparisons involving a missing attribute never hold
+            (Value::Null, _) | (_, Value::Null) => match op {
+                Eq | Ne | Lt | Le | Gt | Ge => Value::Bool(false),
+                _ => Value::Null,
+            },
+            (Value::Number(l), Value::Number(r)) => match op {
+                Eq => Value::Bool(l == r),
+                Ne => Value::Bool(l != r),
+                Lt => Value::Bool(l < r),
+                Le => Value::Bool(l <= r),
+                Gt => Value::Bool(l > r),
+                Ge => Value::Bool(l >= r),
+                Add => Value::Number(l + r),
+                Sub => Value::Number(l - r),
+                Mul => Value::Number(l * r),
+                Div => Value::Number(l / r),
+                Or | And => unreachable!(),
+            },
+            (Value::Text(l), Value::Text(r)) => match op {
+                Eq => Value::Bool(l == r),
+                Ne => Value::Bool(l != r),
+                Lt => Value::Bool(l < r),
+                Le => Value::Bool(l <= r),
+                Gt => Value::Bool(l > r),
+                Ge => Value::Bool(l >= r),
+                _ => return Err(type_error(op, &lhs, &rhs)),
+            },
+            (Value::Bool(l), Value::Bool(r)) => match op {
+                Eq => Value::Bool(l == r),
+                Ne => Value::Bool(l != r),
+                _ => return Err(type_error(op, &lhs, &rhs)),
+            },
+            _ => return Err(type_error(op, &lhs, &rhs)),
+        })
+    }
+
+    //whether the expression holds for block, non boolean results are a type error
+    pub fn matches<B: BlockInterface + Queryable>(
+        &self,
+        ind: BlockIndex,
+        b: &B,
+    ) -> Result<bool, QueryError> {
+        match self.eval(ind, b)? {
+            Value::Bool(v) => Ok(v),
+            Value::Null => Ok(false),
+            v => Err(QueryError::Type {
+                message: format!("expression evaluates to {:?}, not a boolean", v),
+            }),
+        }
+    }
+}
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface + Queryable,
+    S: BlockStorage<B>,
+{
+    //present blocks matching a filter expression such as "au > 0.5 && rock == 'ox'",
+    //categorical attributes compare with names through the model dictionaries
+    pub fn query(&self, expression: &str) -> Result<Selection, QueryError> {
+        let expr = Expr::parse(expression)?.resolve_categories(&self.categories);
+
+        let mut selection = Selection::new(self.dims());
+        for (ind, b) in self.indexed_iter() {
+            if expr.matches(ind, b)? {
+                selection.insert(ind);
+            }
+        }
+        Ok(selection)
+    }
+}
@@ -0,0 +1,189 @@
+use crate::block::{BlockIndex, BlockInterface, NamedAttribute};
+use crate::block_model::BlockModel;
+use crate::columnar::ColumnarModel;
+use crate::error::BlockModelError;
+use crate::frame::ModelFrame;
+use crate::statistics::interpolated_quantile;
+use crate::storage::BlockStorage;
+
+//per block statistic over the realizations of one attribute, NaN values are skipped
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum RealizationSummary {
+    //e-type estimate
+    Mean,
+    Variance,
+    StdDev,
+    //fraction of realizations strictly above the cutoff
+    ProbabilityAbove(f64),
+    //fraction of realizations at or below the cutoff
+    ProbabilityBelow(f64),
+    //quantile in 0 to 1, interpolated between realizations
+    Quantile(f64),
+    //difference between the 0.75 and 0.25 quantiles
+    InterquartileRange,
+}
+
+impl RealizationSummary {
+    //summary column name for attribute, e.g. grade_mean or grade_above_0.5
+    pub fn column_name(&self, attribute: &str) -> String {
+        match self {
+            Self::Mean => format!("{attribute}_mean"),
+            Self::Variance => format!("{attribute}_variance"),
+            Self::StdDev => format!("{attribute}_std_dev"),
+            Self::ProbabilityAbove(c) => format!("{attribute}_above_{c}"),
+            Self::ProbabilityBelow(c) => format!("{attribute}_below_{c}"),
+            Self::Quantile(q) => format!("{attribute}_q{q}"),
+            Self::InterquartileRange => format!("{attribute}_iqr"),
+        }
+    }
+
+    //statistic of the values of one block, NaN when none are valid
+    pub fn evaluate(&self, values: &[f64]) -> f64 {
+        let mut values = values
+            .iter()
+            .filter(|v| !v.is_nan())
+            .copied()
+            .collect::<Vec<_>>();
+        if values.is_empty() {
+            return f64::NAN;
+        }
+        let n = values.len() as f64;
+        let mean = values.iter().sum::<f64>() / n;
+        let variance = || values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n;
+        match self {
+            Self::Mean => mean,
+            Self::Variance => variance(),
+            Self::StdDev => variance().sqrt(),
+            Self::ProbabilityAbove(c) => values.iter().filter(|v| *v > c).count() as f64 / n,
+            Self::ProbabilityBelow(c) => values.iter().filter(|v| *v <= c).count() as f64 / n,
+            Self::Quantile(q) => {
+                values.sort_by(|a, b| a.total_cmp(b));
+                interpolated_quantile(&values, *q)
+            }
+            Self::InterquartileRange => {
+                values.sort_by(|a, b| a.total_cmp(b));
+                interpolated_quantile(&values, 0.75) - interpolated_quantile(&values, 0.25)
+            }
+        }
+    }
+}
+
+//equally probable realizations, e.g. conditional simulations, of the same attributes over
+//one frame, each stored as a columnar model
+#[derive(Debug, Clone, PartialEq)]
+pub struct RealizationSet {
+    frame: ModelFrame,
+    names: Vec<String>,
+    realizations: Vec<ColumnarModel>,
+}
+
+impl RealizationSet {
+    //empty set of the named attributes
+    pub fn new(frame: ModelFrame, names: &[&str]) -> Self {
+        Self {
+            frame,
+            names: names.iter().map(|n| n.to_string()).collect(),
+            realizations: Vec::new(),
+        }
+    }
+
+    //one realization per model, every model must share the frame of the first
+    pub fn from_models<B, S>(
+        mdls: &[BlockModel<B, S>],
+        attributes: &[NamedAttribute<B>],
+    ) -> Result<Self, BlockModelError>
+    where
+        B: BlockInterface,
+        S: BlockStorage<B>,
+    {
+        let first = mdls.first().ok_or(BlockModelError::Empty)?;
+        let names = attributes.iter().map(|(n, _)| *n).collect::<Vec<_>>();
+        let mut set = Self::new(*first.frame(), &names);
+        for mdl in mdls.iter() {
+            set.push(mdl.to_columnar(attributes))?;
+        }
+        Ok(set)
+    }
+
+    //add a realization with the frame and columns of the set
+    pub fn push(&mut self, realization: ColumnarModel) -> Result<(), BlockModelError> {
+        if *realization.frame() != self.frame {
+            return Err(BlockModelError::IncompatibleFrames);
+        }
+        if realization.names() != self.names.as_slice() {
+            return Err(BlockModelError::MismatchedAttributes);
+        }
+        self.realizations.push(realization);
+        Ok(())
+    }
+
+    pub fn frame(&self) -> &ModelFrame {
+        &self.frame
+    }
+
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn len(&self) -> usize {
+        self.realizations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.realizations.is_empty()
+    }
+
+    pub fn realization(&self, n: usize) -> &ColumnarModel {
+        &self.realizations[n]
+    }
+
+    pub fn realizations(&self) -> &[ColumnarModel] {
+        &self.realizations
+    }
+
+    //value of attribute at ind in every realization, NaN where a realization lacks the
+    //block, None if attribute is not in the set
+    pub fn values(&self, attribute: &str, ind: BlockIndex) -> Option<Vec<f64>> {
+        let c = self.names.iter().position(|n| n == attribute)?;
+        Some(
+            self.realizations
+                .iter()
+                .map(|r| r.get(ind).map_or(f64::NAN, |b| b.value(c)))
+                .collect(),
+        )
+    }
+
+    //one column per (attribute, summary) named by RealizationSummary::column_name over the
+    //cells present in any realization, panics if an attribute is not in the set
+    pub fn summary(&self, columns: &[(&str, RealizationSummary)]) -> ColumnarModel {
+        let mut model = ColumnarModel::new(self.frame);
+        let targets = columns
+            .iter()
+            .map(|(attribute, summary)| {
+                assert!(
+                    self.names.iter().any(|n| n == attribute),
+                    "attribute {attribute} is not in the realization set"
+                );
+                model.add_column(&summary.column_name(attribute))
+            })
+            .collect::<Vec<_>>();
+
+        let dims = self.frame.dims;
+        let mut row = vec![0.0; model.names().len()];
+        for i in 0..dims[0] {
+            for j in 0..dims[1] {
+                for k in 0..dims[2] {
+                    let ind = BlockIndex { i, j, k };
+                    if self.realizations.iter().all(|r| r.get(ind).is_none()) {
+                        continue;
+                    }
+                    for (c, (attribute, summary)) in targets.iter().zip(columns.iter()) {
+                        row[*c] = summary.evaluate(&self.values(attribute, ind).unwrap());
+                    }
+                    model.insert(ind, &row);
+                }
+            }
+        }
+        model
+    }
+}
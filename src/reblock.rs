@@ -0,0 +1,155 @@
+use crate::block::{BlockCoordinates, BlockIndex, BlockInterface, BlockSize};
+use crate::block_model::BlockModel;
+use crate::error::BlockModelError;
+use crate::frame::ModelFrame;
+use crate::storage::BlockStorage;
+
+use std::collections::HashMap;
+
+//parent cell of a reblocked model and the present child blocks it covers
+pub struct ReblockCell<'a, B> {
+    pub index: BlockIndex,
+    pub coordinates: BlockCoordinates,
+    pub size: BlockSize,
+    //children ordered by their index in the source model
+    pub children: Vec<&'a B>,
+    //number of child cells covered by the parent, including empty ones
+    pub capacity: usize,
+}
+
+impl<B> ReblockCell<'_, B> {
+    //fraction of the parent volume occupied by present children
+    pub fn fill_fraction(&self) -> f64 {
+        self.children.len() as f64 / self.capacity as f64
+    }
+
+    //volume weighted average, children share a common size
+    pub fn mean<A: Fn(&B) -> f64>(&self, attr: A) -> f64 {
+        self.weighted_mean(attr, |_| 1.0)
+    }
+
+    //average weighted by e.g. tonnage, NaN if all weights are zero
+    pub fn weighted_mean<A, W>(&self, attr: A, weight: W) -> f64
+    where
+        A: Fn(&B) -> f64,
+        W: Fn(&B) -> f64,
+    {
+        let (sum, total) = self.children.iter().fold((0.0, 0.0), |(s, t), b| {
+            let w = weight(b);
+            (s + w * attr(b), t + w)
+        });
+        sum / total
+    }
+
+    pub fn sum<A: Fn(&B) -> f64>(&self, attr: A) -> f64 {
+        self.children.iter().map(|b| attr(b)).sum()
+    }
+
+    //category with the largest total weight, ties go to the first child encountered
+    pub fn majority<K, A, W>(&self, attr: A, weight: W) -> Option<K>
+    where
+        K: PartialEq,
+        A: Fn(&B) -> K,
+        W: Fn(&B) -> f64,
+    {
+        let mut votes: Vec<(K, f64)> = Vec::new();
+        for b in self.children.iter() {
+            let key = attr(b);
+            match votes.iter_mut().find(|(k, _)| *k == key) {
+                Some((_, w)) => *w += weight(b),
+                None => votes.push((key, weight(b))),
+            }
+        }
+
+        votes
+            .into_iter()
+            .fold(None, |best: Option<(K, f64)>, (k, w)| match best {
+                Some((_, bw)) if bw >= w => best,
+                _ => Some((k, w)),
+            })
+            .map(|(k, _)| k)
+    }
+}
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface,
+    S: BlockStorage<B>,
+{
+    //aggregate factor_i x factor_j x factor_k groups of blocks into parent blocks,
+    //merge builds each parent from its cell, parents without children are left empty
+    pub fn reblock<F>(
+        &self,
+        factor_i: usize,
+        factor_j: usize,
+        factor_k: usize,
+        merge: F,
+    ) -> Result<Self, BlockModelError>
+    where
+        F: Fn(&ReblockCell<B>) -> B,
+    {
+        assert!(
+            factor_i > 0 && factor_j > 0 && factor_k > 0,
+            "reblock factors must be positive"
+        );
+
+        let size = self.block_size();
+        let dims = self.dims();
+
+        let parent_size = BlockSize {
+            x_size: size.x_size * factor_i as f32,
+            y_size: size.y_size * factor_j as f32,
+            z_size: size.z_size * factor_k as f32,
+        };
+        //centroid of parent (0, 0, 0)
+        let parent_origin = self.frame().to_world([
+            size.x_size * (factor_i - 1) as f32 / 2.0,
+            size.y_size * (factor_j - 1) as f32 / 2.0,
+            size.z_size * (factor_k - 1) as f32 / 2.0,
+        ]);
+        let frame = ModelFrame::new(
+            parent_origin,
+            parent_size,
+            [
+                dims[0].div_ceil(factor_i),
+                dims[1].div_ceil(factor_j),
+                dims[2].div_ceil(factor_k),
+            ],
+        )
+        .with_rotation(self.frame().rotation)
+        .with_epsg(self.frame().epsg);
+
+        let mut groups = HashMap::<BlockIndex, Vec<(BlockIndex, &B)>>::new();
+        for (ind, b) in self.indexed_iter() {
+            let parent = BlockIndex {
+                i: ind.i / factor_i,
+                j: ind.j / factor_j,
+                k: ind.k / factor_k,
+            };
+            groups.entry(parent).or_default().push((ind, b));
+        }
+
+        let mut parents = groups.into_iter().collect::<Vec<_>>();
+        parents.sort_by_key(|(ind, _)| *ind);
+
+        let (blocks, inds) = parents
+            .into_iter()
+            .map(|(index, mut children)| {
+                children.sort_by_key(|(ind, _)| *ind);
+                let cell = ReblockCell {
+                    index,
+                    coordinates: frame.index_to_coordinates(index),
+                    size: parent_size,
+                    children: children.into_iter().map(|(_, b)| b).collect(),
+                    capacity: factor_i * factor_j * factor_k,
+                };
+
+                let mut parent = merge(&cell);
+                parent.set_index(index);
+                (parent, index)
+            })
+            .unzip();
+
+        Self::from_parts(blocks, inds, frame)
+    }
+}
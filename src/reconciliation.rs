@@ -0,0 +1,208 @@
+use serde::Serialize;
+
+use crate::block::{BlockIndex, BlockInterface};
+use crate::block_model::BlockModel;
+use crate::storage::BlockStorage;
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::Path;
+
+//tonnage, tonnage weighted grade and contained metal of a block or a whole model, grade is
+//NaN without tonnage
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quantities {
+    pub tonnage: f64,
+    pub grade: f64,
+    pub metal: f64,
+}
+
+impl Default for Quantities {
+    fn default() -> Self {
+        Self {
+            tonnage: 0.0,
+            grade: f64::NAN,
+            metal: 0.0,
+        }
+    }
+}
+
+impl Quantities {
+    fn add(&mut self, tonnage: f64, grade: f64) {
+        self.tonnage += tonnage;
+        self.metal += tonnage * grade;
+        self.grade = self.metal / self.tonnage;
+    }
+}
+
+//largest differences of a block that are not flagged
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DiffTolerance {
+    pub tonnage: f64,
+    pub grade: f64,
+}
+
+//quantities of one base cell in both models, None where a model has no blocks
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockDiff {
+    pub ind: BlockIndex,
+    pub base: Option<Quantities>,
+    pub other: Option<Quantities>,
+    pub flagged: bool,
+}
+
+impl BlockDiff {
+    //other less base, missing blocks count as zero
+    pub fn tonnage_difference(&self) -> f64 {
+        self.other.unwrap_or_default().tonnage - self.base.unwrap_or_default().tonnage
+    }
+
+    //NaN unless both models have the block
+    pub fn grade_difference(&self) -> f64 {
+        self.other.unwrap_or_default().grade - self.base.unwrap_or_default().grade
+    }
+
+    pub fn metal_difference(&self) -> f64 {
+        self.other.unwrap_or_default().metal - self.base.unwrap_or_default().metal
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct DiffRow {
+    i: usize,
+    j: usize,
+    k: usize,
+    base_tonnage: f64,
+    base_grade: f64,
+    base_metal: f64,
+    other_tonnage: f64,
+    other_grade: f64,
+    other_metal: f64,
+    flagged: bool,
+}
+
+//block by block comparison of a model against a base model on the base lattice
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelDiff {
+    //every cell present in either model, sorted by index
+    pub blocks: Vec<BlockDiff>,
+    pub base: Quantities,
+    //of the other blocks inside the base frame
+    pub other: Quantities,
+    //other blocks whose centroid lies outside the base frame
+    pub outside: usize,
+}
+
+impl ModelDiff {
+    pub fn flagged(&self) -> impl Iterator<Item = &BlockDiff> + '_ {
+        self.blocks.iter().filter(|b| b.flagged)
+    }
+
+    pub fn num_flagged(&self) -> usize {
+        self.flagged().count()
+    }
+
+    //one row per cell, missing sides have zero tonnage and NaN grade
+    pub fn to_csv<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn Error>> {
+        let mut w = csv::Writer::from_path(path)?;
+        for b in self.blocks.iter() {
+            let (base, other) = (b.base.unwrap_or_default(), b.other.unwrap_or_default());
+            w.serialize(DiffRow {
+                i: b.ind.i,
+                j: b.ind.j,
+                k: b.ind.k,
+                base_tonnage: base.tonnage,
+                base_grade: base.grade,
+                base_metal: base.metal,
+                other_tonnage: other.tonnage,
+                other_grade: other.grade,
+                other_metal: other.metal,
+                flagged: b.flagged,
+            })?;
+        }
+        w.flush()?;
+        Ok(())
+    }
+}
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface,
+    S: BlockStorage<B>,
+{
+    //compare other, e.g. a grade control model, against self, e.g. the resource model,
+    //other blocks are assigned to the base cell holding their centroid and combined there so
+    //models on a finer or shifted lattice are resampled, blocks are flagged when tonnage or
+    //grade differ by more than the tolerance or when only one model has them, tonnage must
+    //scale with block volume, e.g. DensityModel::tonnage, when the block sizes differ
+    pub fn diff<S2, T, G>(
+        &self,
+        other: &BlockModel<B, S2>,
+        tonnage: T,
+        grade: G,
+        tolerance: DiffTolerance,
+    ) -> ModelDiff
+    where
+        S2: BlockStorage<B>,
+        T: Fn(&B) -> f64,
+        G: Fn(&B) -> f64,
+    {
+        let mut cells: BTreeMap<BlockIndex, (Option<Quantities>, Option<Quantities>)> =
+            BTreeMap::new();
+        let mut base = Quantities::default();
+        for (ind, b) in self.indexed_iter() {
+            let (t, g) = (tonnage(b), grade(b));
+            cells
+                .entry(ind)
+                .or_default()
+                .0
+                .get_or_insert_with(Quantities::default)
+                .add(t, g);
+            base.add(t, g);
+        }
+
+        let mut totals = Quantities::default();
+        let mut outside = 0;
+        for (ind, b) in other.indexed_iter() {
+            let Some(cell) = self.frame().index_from(other.frame(), ind) else {
+                outside += 1;
+                continue;
+            };
+            let (t, g) = (tonnage(b), grade(b));
+            cells
+                .entry(cell)
+                .or_default()
+                .1
+                .get_or_insert_with(Quantities::default)
+                .add(t, g);
+            totals.add(t, g);
+        }
+
+        let blocks = cells
+            .into_iter()
+            .map(|(ind, (base, other))| {
+                let mut diff = BlockDiff {
+                    ind,
+                    base,
+                    other,
+                    flagged: false,
+                };
+                diff.flagged = match (base, other) {
+                    (Some(_), Some(_)) => {
+                        diff.tonnage_difference().abs() > tolerance.tonnage
+                            || diff.grade_difference().abs() > tolerance.grade
+                    }
+                    _ => true,
+                };
+                diff
+            })
+            .collect();
+
+        ModelDiff {
+            blocks,
+            base,
+            other: totals,
+            outside,
+        }
+    }
+}
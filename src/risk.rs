@@ -0,0 +1,311 @@
+use serde::Serialize;
+
+use crate::block::BlockIndex;
+use crate::columnar::{ColumnarBlock, ColumnarModel};
+use crate::cutoff::GradeTonnage;
+use crate::realizations::RealizationSet;
+use crate::statistics::interpolated_quantile;
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt::Display;
+use std::path::Path;
+
+//spread of a quantity across realizations, p10 is the value 10% of realizations fall below,
+//NaN values are skipped
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Percentiles {
+    pub p10: f64,
+    pub p50: f64,
+    pub p90: f64,
+}
+
+impl Percentiles {
+    pub fn new(values: &[f64]) -> Self {
+        let mut sorted = values
+            .iter()
+            .filter(|v| !v.is_nan())
+            .copied()
+            .collect::<Vec<_>>();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        Self {
+            p10: interpolated_quantile(&sorted, 0.1),
+            p50: interpolated_quantile(&sorted, 0.5),
+            p90: interpolated_quantile(&sorted, 0.9),
+        }
+    }
+}
+
+//tonnage and grade a group must reach, e.g. a bench or phase budget
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RiskTargets {
+    pub tonnage: f64,
+    pub grade: f64,
+}
+
+//ore above cutoff in one group of blocks over every realization
+#[derive(Debug, Clone, PartialEq)]
+pub struct GroupRisk<G> {
+    pub group: G,
+    //tonnage and mean grade above cutoff per realization, grade is NaN without ore
+    pub tonnages: Vec<f64>,
+    pub grades: Vec<f64>,
+    pub tonnage: Percentiles,
+    pub grade: Percentiles,
+    //fraction of realizations reaching the targets, no ore never reaches the grade target
+    pub probability_tonnage: f64,
+    pub probability_grade: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct GroupRiskRow {
+    group: String,
+    tonnage_p10: f64,
+    tonnage_p50: f64,
+    tonnage_p90: f64,
+    grade_p10: f64,
+    grade_p50: f64,
+    grade_p90: f64,
+    probability_tonnage: f64,
+    probability_grade: f64,
+}
+
+//groups sorted by key
+#[derive(Debug, Clone, PartialEq)]
+pub struct RiskReport<G> {
+    pub cutoff: f64,
+    pub targets: RiskTargets,
+    pub groups: Vec<GroupRisk<G>>,
+}
+
+impl<G: Display> RiskReport<G> {
+    //one row per group
+    pub fn to_csv<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn Error>> {
+        let mut w = csv::Writer::from_path(path)?;
+        for g in self.groups.iter() {
+            w.serialize(GroupRiskRow {
+                group: g.group.to_string(),
+                tonnage_p10: g.tonnage.p10,
+                tonnage_p50: g.tonnage.p50,
+                tonnage_p90: g.tonnage.p90,
+                grade_p10: g.grade.p10,
+                grade_p50: g.grade.p50,
+                grade_p90: g.grade.p90,
+                probability_tonnage: g.probability_tonnage,
+                probability_grade: g.probability_grade,
+            })?;
+        }
+        w.flush()?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct GradeTonnageRiskRow {
+    cutoff: f64,
+    tonnage_p10: f64,
+    tonnage_p50: f64,
+    tonnage_p90: f64,
+    grade_p10: f64,
+    grade_p50: f64,
+    grade_p90: f64,
+    metal_p10: f64,
+    metal_p50: f64,
+    metal_p90: f64,
+}
+
+//spread of the grade tonnage curve across realizations, one entry per cutoff
+#[derive(Debug, Clone, PartialEq)]
+pub struct GradeTonnageRisk {
+    pub cutoffs: Vec<f64>,
+    pub tonnage: Vec<Percentiles>,
+    pub grade: Vec<Percentiles>,
+    pub metal: Vec<Percentiles>,
+}
+
+impl GradeTonnageRisk {
+    pub fn to_csv<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn Error>> {
+        let mut w = csv::Writer::from_path(path)?;
+        for (n, cutoff) in self.cutoffs.iter().enumerate() {
+            let (t, g, m) = (self.tonnage[n], self.grade[n], self.metal[n]);
+            w.serialize(GradeTonnageRiskRow {
+                cutoff: *cutoff,
+                tonnage_p10: t.p10,
+                tonnage_p50: t.p50,
+                tonnage_p90: t.p90,
+                grade_p10: g.p10,
+                grade_p50: g.p50,
+                grade_p90: g.p90,
+                metal_p10: m.p10,
+                metal_p50: m.p50,
+                metal_p90: m.p90,
+            })?;
+        }
+        w.flush()?;
+        Ok(())
+    }
+}
+
+impl RealizationSet {
+    fn column(&self, attribute: &str) -> usize {
+        self.names()
+            .iter()
+            .position(|n| n == attribute)
+            .unwrap_or_else(|| panic!("attribute {attribute} is not in the realization set"))
+    }
+
+    //tonnage and grade above cutoff of every group in every realization, blocks whose group
+    //is None are skipped, tonnage may read simulated attributes such as density
+    pub fn risk_by<G, K, T>(
+        &self,
+        grade: &str,
+        cutoff: f64,
+        tonnage: T,
+        group: K,
+        targets: RiskTargets,
+    ) -> RiskReport<G>
+    where
+        G: Ord,
+        K: Fn(BlockIndex) -> Option<G>,
+        T: Fn(ColumnarBlock) -> f64,
+    {
+        let c = self.column(grade);
+        let n = self.len();
+
+        //(tonnage, metal) above cutoff per group and realization
+        let mut sums: BTreeMap<G, Vec<(f64, f64)>> = BTreeMap::new();
+        for (r, real) in self.realizations().iter().enumerate() {
+            for (ind, b) in real.indexed_iter() {
+                let Some(key) = group(ind) else {
+                    continue;
+                };
+                let sum = sums.entry(key).or_insert_with(|| vec![(0.0, 0.0); n]);
+                let g = b.value(c);
+                if g >= cutoff {
+                    let t = tonnage(b);
+                    sum[r].0 += t;
+                    sum[r].1 += t * g;
+                }
+            }
+        }
+
+        let groups = sums
+            .into_iter()
+            .map(|(group, sums)| {
+                let tonnages = sums.iter().map(|(t, _)| *t).collect::<Vec<_>>();
+                let grades = sums.iter().map(|(t, m)| m / t).collect::<Vec<_>>();
+                let share = |hits: usize| hits as f64 / n as f64;
+                GroupRisk {
+                    group,
+                    tonnage: Percentiles::new(&tonnages),
+                    grade: Percentiles::new(&grades),
+                    probability_tonnage: share(
+                        tonnages.iter().filter(|t| **t >= targets.tonnage).count(),
+                    ),
+                    probability_grade: share(
+                        grades.iter().filter(|g| **g >= targets.grade).count(),
+                    ),
+                    tonnages,
+                    grades,
+                }
+            })
+            .collect();
+
+        RiskReport {
+            cutoff,
+            targets,
+            groups,
+        }
+    }
+
+    //p10, p50 and p90 tonnage, grade and metal above each cutoff across realizations
+    pub fn grade_tonnage_risk<T>(
+        &self,
+        grade: &str,
+        tonnage: T,
+        cutoffs: &[f64],
+    ) -> GradeTonnageRisk
+    where
+        T: Fn(ColumnarBlock) -> f64,
+    {
+        let c = self.column(grade);
+        let curves = self
+            .realizations()
+            .iter()
+            .map(|real| {
+                GradeTonnage::new(
+                    real.indexed_iter()
+                        .map(|(_, b)| (b.value(c), tonnage(b)))
+                        .filter(|(g, _)| !g.is_nan())
+                        .collect(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let mut risk = GradeTonnageRisk {
+            cutoffs: cutoffs.to_vec(),
+            tonnage: Vec::with_capacity(cutoffs.len()),
+            grade: Vec::with_capacity(cutoffs.len()),
+            metal: Vec::with_capacity(cutoffs.len()),
+        };
+        for cutoff in cutoffs.iter() {
+            let tonnages = curves
+                .iter()
+                .map(|gt| gt.tonnage_above(*cutoff))
+                .collect::<Vec<_>>();
+            let grades = curves
+                .iter()
+                .map(|gt| gt.mean_grade_above(*cutoff).unwrap_or(f64::NAN))
+                .collect::<Vec<_>>();
+            let metals = tonnages
+                .iter()
+                .zip(grades.iter())
+                .map(|(t, g)| if *t > 0.0 { t * g } else { 0.0 })
+                .collect::<Vec<_>>();
+            risk.tonnage.push(Percentiles::new(&tonnages));
+            risk.grade.push(Percentiles::new(&grades));
+            risk.metal.push(Percentiles::new(&metals));
+        }
+        risk
+    }
+
+    //central interval holding level of the realizations of attribute at every block, with
+    //columns <attribute>_lower, <attribute>_upper and <attribute>_relative_width, the width
+    //over the mean, which drives risk based classification
+    pub fn confidence_intervals(&self, attribute: &str, level: f64) -> ColumnarModel {
+        assert!(
+            level > 0.0 && level < 1.0,
+            "confidence level must be between 0 and 1"
+        );
+        let c = self.column(attribute);
+
+        let mut model = ColumnarModel::new(*self.frame());
+        for suffix in ["lower", "upper", "relative_width"] {
+            model.add_column(&format!("{attribute}_{suffix}"));
+        }
+
+        let dims = self.frame().dims;
+        for i in 0..dims[0] {
+            for j in 0..dims[1] {
+                for k in 0..dims[2] {
+                    let ind = BlockIndex { i, j, k };
+                    if self.realizations().iter().all(|r| r.get(ind).is_none()) {
+                        continue;
+                    }
+                    let mut values = self
+                        .realizations()
+                        .iter()
+                        .filter_map(|r| r.get(ind).map(|b| b.value(c)))
+                        .filter(|v| !v.is_nan())
+                        .collect::<Vec<_>>();
+                    values.sort_by(|a, b| a.total_cmp(b));
+                    let lower = interpolated_quantile(&values, (1.0 - level) / 2.0);
+                    let upper = interpolated_quantile(&values, (1.0 + level) / 2.0);
+                    let mean = values.iter().sum::<f64>() / values.len() as f64;
+                    model.insert(ind, &[lower, upper, (upper - lower) / mean]);
+                }
+            }
+        }
+        model
+    }
+}
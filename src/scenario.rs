@@ -0,0 +1,253 @@
+use rayon::prelude::*;
+use serde::Serialize;
+
+use crate::block::{BlockIndex, BlockInterface};
+use crate::block_model::{BlockDependenceInterface, BlockModel};
+use crate::economics::EconomicModel;
+use crate::pit::{ClosureGraph, PitOptimizer};
+use crate::schedule::greedy::GreedyScheduler;
+use crate::storage::BlockStorage;
+
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::path::Path;
+
+//factors applied to the base price and costs
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Scenario {
+    pub price_factor: f64,
+    pub mining_cost_factor: f64,
+    pub processing_cost_factor: f64,
+}
+
+impl Scenario {
+    pub fn new(price_factor: f64, mining_cost_factor: f64, processing_cost_factor: f64) -> Self {
+        Self {
+            price_factor,
+            mining_cost_factor,
+            processing_cost_factor,
+        }
+    }
+
+    //economics with the factors applied
+    pub fn adjusted<'a, E>(&self, econ: &'a E) -> ScenarioAdjusted<'a, E> {
+        ScenarioAdjusted {
+            econ,
+            scenario: *self,
+        }
+    }
+}
+
+//economic model with price and costs scaled by a scenario, selling cost is unchanged
+#[derive(Debug, Clone)]
+pub struct ScenarioAdjusted<'a, E> {
+    pub econ: &'a E,
+    pub scenario: Scenario,
+}
+
+impl<B, E> EconomicModel<B> for ScenarioAdjusted<'_, E>
+where
+    B: BlockInterface,
+    E: EconomicModel<B>,
+{
+    fn tonnage(&self, block: &B) -> f64 {
+        self.econ.tonnage(block)
+    }
+
+    fn grade(&self, block: &B) -> f64 {
+        self.econ.grade(block)
+    }
+
+    fn price(&self, block: &B) -> f64 {
+        self.scenario.price_factor * self.econ.price(block)
+    }
+
+    fn selling_cost(&self, block: &B) -> f64 {
+        self.econ.selling_cost(block)
+    }
+
+    fn recovery(&self, block: &B) -> f64 {
+        self.econ.recovery(block)
+    }
+
+    fn mining_cost(&self, block: &B) -> f64 {
+        self.scenario.mining_cost_factor * self.econ.mining_cost(block)
+    }
+
+    fn processing_cost(&self, block: &B) -> f64 {
+        self.scenario.processing_cost_factor * self.econ.processing_cost(block)
+    }
+}
+
+//pit of one scenario, ore is the pit blocks worth processing and npv is NaN without a
+//schedule
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct ScenarioResult {
+    pub price_factor: f64,
+    pub mining_cost_factor: f64,
+    pub processing_cost_factor: f64,
+    pub num_blocks: usize,
+    pub tonnage: f64,
+    pub ore_tonnage: f64,
+    pub waste_tonnage: f64,
+    //recovered metal units
+    pub metal: f64,
+    //undiscounted value of the pit
+    pub value: f64,
+    pub npv: f64,
+}
+
+//one row per scenario in the order of ScenarioRunner::scenarios
+#[derive(Debug, Clone, PartialEq)]
+pub struct SensitivityTable {
+    pub rows: Vec<ScenarioResult>,
+}
+
+impl SensitivityTable {
+    //scenario with the highest npv, or value when unscheduled
+    pub fn best(&self) -> Option<&ScenarioResult> {
+        let key = |r: &ScenarioResult| if r.npv.is_nan() { r.value } else { r.npv };
+        self.rows.iter().max_by(|a, b| key(a).total_cmp(&key(b)))
+    }
+
+    pub fn to_csv<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn Error>> {
+        let mut w = csv::Writer::from_path(path)?;
+        for row in self.rows.iter() {
+            w.serialize(row)?;
+        }
+        w.flush()?;
+        Ok(())
+    }
+}
+
+//hill of value analysis, the pit is optimized for every combination of the factors and
+//optionally scheduled as a single phase for its npv
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScenarioRunner {
+    pub price_factors: Vec<f64>,
+    pub mining_cost_factors: Vec<f64>,
+    pub processing_cost_factors: Vec<f64>,
+    pub scheduler: Option<GreedyScheduler>,
+    pub discount_rate: f64,
+}
+
+impl ScenarioRunner {
+    pub fn new(
+        price_factors: Vec<f64>,
+        mining_cost_factors: Vec<f64>,
+        processing_cost_factors: Vec<f64>,
+    ) -> Self {
+        assert!(
+            !price_factors.is_empty()
+                && !mining_cost_factors.is_empty()
+                && !processing_cost_factors.is_empty(),
+            "every factor needs at least one value"
+        );
+        Self {
+            price_factors,
+            mining_cost_factors,
+            processing_cost_factors,
+            scheduler: None,
+            discount_rate: 0.0,
+        }
+    }
+
+    pub fn with_schedule(mut self, scheduler: GreedyScheduler, discount_rate: f64) -> Self {
+        self.scheduler = Some(scheduler);
+        self.discount_rate = discount_rate;
+        self
+    }
+
+    //every combination of the factors, price varying slowest
+    pub fn scenarios(&self) -> Vec<Scenario> {
+        let mut scenarios = Vec::new();
+        for p in self.price_factors.iter() {
+            for m in self.mining_cost_factors.iter() {
+                for c in self.processing_cost_factors.iter() {
+                    scenarios.push(Scenario::new(*p, *m, *c));
+                }
+            }
+        }
+        scenarios
+    }
+
+    //optimize and summarize every scenario in parallel, arcs are built once and shared
+    pub fn run<O, B, S, D, E>(
+        &self,
+        optimizer: &O,
+        mdl: &BlockModel<B, S>,
+        dep: &D,
+        econ: &E,
+    ) -> SensitivityTable
+    where
+        O: PitOptimizer + Sync,
+        B: BlockInterface + Sync,
+        S: BlockStorage<B> + Sync,
+        D: BlockDependenceInterface,
+        E: EconomicModel<B> + Sync,
+    {
+        let base = ClosureGraph::new(mdl, dep, |_| 0.0);
+        let rows = self
+            .scenarios()
+            .into_par_iter()
+            .map(|scenario| {
+                let econ = scenario.adjusted(econ);
+                let mut graph = base.clone();
+                graph.weights = graph
+                    .inds
+                    .iter()
+                    .map(|ind| econ.value(mdl.block(*ind).unwrap()))
+                    .collect();
+                let pit = graph.selected(&optimizer.solve(&graph));
+                self.summarize(mdl, &econ, &pit)
+            })
+            .collect();
+        SensitivityTable { rows }
+    }
+
+    fn summarize<B, S, E>(
+        &self,
+        mdl: &BlockModel<B, S>,
+        econ: &ScenarioAdjusted<'_, E>,
+        pit: &HashSet<BlockIndex>,
+    ) -> ScenarioResult
+    where
+        B: BlockInterface,
+        S: BlockStorage<B>,
+        E: EconomicModel<B>,
+    {
+        let scenario = econ.scenario;
+        let mut row = ScenarioResult {
+            price_factor: scenario.price_factor,
+            mining_cost_factor: scenario.mining_cost_factor,
+            processing_cost_factor: scenario.processing_cost_factor,
+            num_blocks: pit.len(),
+            tonnage: 0.0,
+            ore_tonnage: 0.0,
+            waste_tonnage: 0.0,
+            metal: 0.0,
+            value: 0.0,
+            npv: f64::NAN,
+        };
+        for ind in pit.iter() {
+            let b = mdl.block(*ind).unwrap();
+            let t = econ.tonnage(b);
+            row.tonnage += t;
+            row.value += econ.value(b);
+            if econ.is_ore(b) {
+                row.ore_tonnage += t;
+                row.metal += t * econ.grade(b) * econ.recovery(b);
+            } else {
+                row.waste_tonnage += t;
+            }
+        }
+
+        if let Some(scheduler) = self.scheduler.as_ref() {
+            let phases = pit.iter().map(|ind| (*ind, 0)).collect::<HashMap<_, _>>();
+            row.npv = scheduler
+                .schedule(mdl, &phases, econ)
+                .npv(mdl, self.discount_rate, econ);
+        }
+        row
+    }
+}
@@ -0,0 +1,441 @@
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+
+use crate::block::{BlockIndex, BlockInterface};
+use crate::block_model::BlockModel;
+use crate::economics::EconomicModel;
+use crate::graph::PrecedenceGraph;
+use crate::schedule::{discount_factor, Schedule};
+use crate::storage::BlockStorage;
+
+use std::collections::{HashMap, HashSet};
+
+//how the temperature falls over the iterations
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Cooling {
+    //multiplied by the factor after every iteration
+    Geometric(f64),
+    //falls to zero at the last iteration
+    Linear,
+    //initial temperature / ln(e + iteration)
+    Logarithmic,
+}
+
+impl Cooling {
+    fn temperature(&self, initial: f64, iteration: usize, iterations: usize) -> f64 {
+        match self {
+            Cooling::Geometric(factor) => initial * factor.powi(iteration as i32),
+            Cooling::Linear => initial * (1.0 - iteration as f64 / iterations.max(1) as f64),
+            Cooling::Logarithmic => initial / (std::f64::consts::E + iteration as f64).ln(),
+        }
+    }
+}
+
+//state of an annealing run passed to the progress callback
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnnealingProgress {
+    pub iteration: usize,
+    pub temperature: f64,
+    //of the graph blocks
+    pub npv: f64,
+    pub best_npv: f64,
+    //moves accepted so far
+    pub accepted: usize,
+}
+
+//improves a schedule by moving blocks between periods, swapping the periods of two blocks
+//and switching blocks between the plant and the dump, every move keeps precedence and the
+//capacities satisfied, worse moves are accepted with probability exp(delta / temperature)
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulatedAnnealing {
+    //tonnes mined per period
+    pub mining_capacity: f64,
+    //tonnes processed per period
+    pub processing_capacity: f64,
+    pub discount_rate: f64,
+    pub initial_temperature: f64,
+    pub cooling: Cooling,
+    pub iterations: usize,
+    //iterations between progress callbacks, 0 reports only at the end
+    pub report_interval: usize,
+    pub seed: u64,
+}
+
+//move of one block to a period, num_periods leaves it unmined
+#[derive(Debug, Clone, Copy)]
+struct Move {
+    node: usize,
+    period: usize,
+    processed: bool,
+}
+
+//schedule of the graph blocks during annealing
+struct State {
+    num_periods: usize,
+    periods: Vec<usize>,
+    processed: Vec<bool>,
+    mined: Vec<f64>,
+    plant: Vec<f64>,
+}
+
+impl SimulatedAnnealing {
+    //best schedule found starting from a feasible schedule without stockpiles, blocks outside
+    //the graph keep their periods and count against the capacities, unscheduled graph blocks
+    //may be brought into the schedule
+    pub fn improve<B, S, E, F>(
+        &self,
+        mdl: &BlockModel<B, S>,
+        graph: &PrecedenceGraph,
+        econ: &E,
+        initial: &Schedule,
+        mut progress: F,
+    ) -> Schedule
+    where
+        B: BlockInterface,
+        S: BlockStorage<B>,
+        E: EconomicModel<B>,
+        F: FnMut(&AnnealingProgress),
+    {
+        assert!(
+            initial.stockpiled.is_empty(),
+            "schedules with stockpiles cannot be annealed"
+        );
+
+        let num_periods = initial.num_periods;
+        let block = |ind: BlockIndex| mdl.block(ind).expect("block is missing from the model");
+        //tonnage, value if processed and value if dumped of every node
+        let values = graph
+            .inds()
+            .iter()
+            .map(|ind| {
+                let b = block(*ind);
+                (econ.tonnage(b), econ.process_value(b), econ.waste_value(b))
+            })
+            .collect::<Vec<_>>();
+        let discount = (0..=num_periods)
+            .map(|t| {
+                if t < num_periods {
+                    discount_factor(self.discount_rate, t)
+                } else {
+                    0.0
+                }
+            })
+            .collect::<Vec<_>>();
+        let value = |node: usize, period: usize, processed: bool| {
+            let (_, process, waste) = values[node];
+            discount[period] * if processed { process } else { waste }
+        };
+
+        let mut state = State {
+            num_periods,
+            periods: graph
+                .inds()
+                .iter()
+                .map(|ind| initial.period(*ind).unwrap_or(num_periods))
+                .collect(),
+            processed: graph
+                .inds()
+                .iter()
+                .map(|ind| initial.is_processed(*ind))
+                .collect(),
+            mined: vec![0.0; num_periods],
+            plant: vec![0.0; num_periods],
+        };
+        for (ind, period) in initial.periods.iter() {
+            let t = econ.tonnage(block(*ind));
+            state.mined[*period] += t;
+            if initial.is_processed(*ind) {
+                state.plant[*period] += t;
+            }
+        }
+
+        let mut npv = (0..graph.len())
+            .map(|n| value(n, state.periods[n], state.processed[n]))
+            .sum::<f64>();
+        let mut best = (npv, state.periods.clone(), state.processed.clone());
+        let mut accepted = 0;
+        let mut rng = SmallRng::seed_from_u64(self.seed);
+
+        for iteration in 0..self.iterations {
+            let temperature =
+                self.cooling
+                    .temperature(self.initial_temperature, iteration, self.iterations);
+            if self.report_interval > 0 && iteration % self.report_interval == 0 {
+                progress(&AnnealingProgress {
+                    iteration,
+                    temperature,
+                    npv,
+                    best_npv: best.0,
+                    accepted,
+                });
+            }
+            if graph.is_empty() {
+                break;
+            }
+
+            let moves = match rng.gen_range(0..3) {
+                0 => self.shift(graph, &state, &values, &mut rng),
+                1 => self.swap(graph, &state, &values, &mut rng),
+                _ => self.switch(&state, &values, &mut rng),
+            };
+            if moves.is_empty() {
+                continue;
+            }
+
+            let delta = moves
+                .iter()
+                .map(|m| {
+                    value(m.node, m.period, m.processed)
+                        - value(m.node, state.periods[m.node], state.processed[m.node])
+                })
+                .sum::<f64>();
+            if delta < 0.0 && rng.gen::<f64>() >= (delta / temperature).exp() {
+                continue;
+            }
+
+            for m in moves.iter() {
+                state.apply(*m, values[m.node].0);
+            }
+            npv += delta;
+            accepted += 1;
+            if npv > best.0 {
+                best = (npv, state.periods.clone(), state.processed.clone());
+            }
+        }
+
+        progress(&AnnealingProgress {
+            iteration: self.iterations,
+            temperature: self.cooling.temperature(
+                self.initial_temperature,
+                self.iterations,
+                self.iterations,
+            ),
+            npv,
+            best_npv: best.0,
+            accepted,
+        });
+
+        let (_, best_periods, best_processed) = best;
+        let mut periods = initial
+            .periods
+            .iter()
+            .filter(|(ind, _)| graph.node(**ind).is_none())
+            .map(|(ind, period)| (*ind, *period))
+            .collect::<HashMap<_, _>>();
+        let mut processed = initial
+            .processed
+            .iter()
+            .filter(|ind| graph.node(**ind).is_none())
+            .copied()
+            .collect::<HashSet<_>>();
+        for (n, ind) in graph.inds().iter().enumerate() {
+            if best_periods[n] < num_periods {
+                periods.insert(*ind, best_periods[n]);
+                if best_processed[n] {
+                    processed.insert(*ind);
+                }
+            }
+        }
+        Schedule::new(mdl, econ, num_periods, periods, processed)
+    }
+
+    //periods node can move to given the periods of its neighbours
+    fn window(&self, graph: &PrecedenceGraph, state: &State, node: usize) -> (usize, usize) {
+        let lo = graph
+            .preds(node)
+            .iter()
+            .map(|p| state.periods[*p])
+            .max()
+            .unwrap_or(0);
+        let hi = graph
+            .succs(node)
+            .iter()
+            .map(|s| state.periods[*s])
+            .min()
+            .unwrap_or(state.num_periods);
+        (lo, hi)
+    }
+
+    //whether the moves, applied together, keep every capacity
+    fn fits(&self, state: &State, values: &[(f64, f64, f64)], moves: &[Move]) -> bool {
+        let mut mined = HashMap::new();
+        let mut plant = HashMap::new();
+        for m in moves.iter() {
+            let t = values[m.node].0;
+            let (from, was) = (state.periods[m.node], state.processed[m.node]);
+            *mined.entry(from).or_insert(0.0) -= t;
+            *mined.entry(m.period).or_insert(0.0) += t;
+            if was {
+                *plant.entry(from).or_insert(0.0) -= t;
+            }
+            if m.processed {
+                *plant.entry(m.period).or_insert(0.0) += t;
+            }
+        }
+        let within = |extra: &HashMap<usize, f64>, used: &[f64], capacity: f64| {
+            extra
+                .iter()
+                .all(|(t, d)| *t >= state.num_periods || *d <= 0.0 || used[*t] + d <= capacity)
+        };
+        within(&mined, &state.mined, self.mining_capacity)
+            && within(&plant, &state.plant, self.processing_capacity)
+    }
+
+    //one block to another period within its precedence window, ore keeps going to the plant
+    //when it has room
+    fn shift(
+        &self,
+        graph: &PrecedenceGraph,
+        state: &State,
+        values: &[(f64, f64, f64)],
+        rng: &mut SmallRng,
+    ) -> Vec<Move> {
+        let node = rng.gen_range(0..graph.len());
+        let (lo, hi) = self.window(graph, state, node);
+        let hi = hi.min(state.num_periods);
+        if lo >= hi {
+            return vec![];
+        }
+        let period = rng.gen_range(lo..=hi);
+        if period == state.periods[node] {
+            return vec![];
+        }
+
+        let (_, process, waste) = values[node];
+        let mined = period < state.num_periods;
+        let to_plant = Move {
+            node,
+            period,
+            processed: mined && process > waste,
+        };
+        if to_plant.processed && self.fits(state, values, &[to_plant]) {
+            return vec![to_plant];
+        }
+        let to_dump = Move {
+            processed: false,
+            ..to_plant
+        };
+        if self.fits(state, values, &[to_dump]) {
+            vec![to_dump]
+        } else {
+            vec![]
+        }
+    }
+
+    //exchange the periods of two mined blocks not directly linked by precedence
+    fn swap(
+        &self,
+        graph: &PrecedenceGraph,
+        state: &State,
+        values: &[(f64, f64, f64)],
+        rng: &mut SmallRng,
+    ) -> Vec<Move> {
+        let (a, b) = (rng.gen_range(0..graph.len()), rng.gen_range(0..graph.len()));
+        let (t_a, t_b) = (state.periods[a], state.periods[b]);
+        if t_a == t_b || t_a >= state.num_periods || t_b >= state.num_periods {
+            return vec![];
+        }
+        if graph.preds(a).contains(&b) || graph.preds(b).contains(&a) {
+            return vec![];
+        }
+        let inside = |node: usize, t: usize| {
+            let (lo, hi) = self.window(graph, state, node);
+            lo <= t && t <= hi
+        };
+        if !inside(a, t_b) || !inside(b, t_a) {
+            return vec![];
+        }
+
+        let moves = [
+            Move {
+                node: a,
+                period: t_b,
+                processed: state.processed[a],
+            },
+            Move {
+                node: b,
+                period: t_a,
+                processed: state.processed[b],
+            },
+        ];
+        if self.fits(state, values, &moves) {
+            moves.to_vec()
+        } else {
+            vec![]
+        }
+    }
+
+    //send a mined block to the other destination
+    fn switch(&self, state: &State, values: &[(f64, f64, f64)], rng: &mut SmallRng) -> Vec<Move> {
+        let node = rng.gen_range(0..state.periods.len());
+        if state.periods[node] >= state.num_periods {
+            return vec![];
+        }
+        let m = Move {
+            node,
+            period: state.periods[node],
+            processed: !state.processed[node],
+        };
+        if self.fits(state, values, &[m]) {
+            vec![m]
+        } else {
+            vec![]
+        }
+    }
+}
+
+impl State {
+    fn apply(&mut self, m: Move, tonnage: f64) {
+        let (from, was) = (self.periods[m.node], self.processed[m.node]);
+        if from < self.num_periods {
+            self.mined[from] -= tonnage;
+            if was {
+                self.plant[from] -= tonnage;
+            }
+        }
+        if m.period < self.num_periods {
+            self.mined[m.period] += tonnage;
+            if m.processed {
+                self.plant[m.period] += tonnage;
+            }
+        }
+        self.periods[m.node] = m.period;
+        self.processed[m.node] = m.processed;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Cooling, SimulatedAnnealing};
+    use crate::schedule::tests::{
+        assert_feasible, brute_force_npv, toy_model, CAPACITY, PERIODS, RATE,
+    };
+    use crate::schedule::Schedule;
+
+    use std::collections::{HashMap, HashSet};
+
+    #[test]
+    fn reaches_the_optimum_feasibly() {
+        let (mdl, graph, econ) = toy_model();
+        let annealing = SimulatedAnnealing {
+            mining_capacity: CAPACITY,
+            processing_capacity: f64::INFINITY,
+            discount_rate: RATE,
+            initial_temperature: 1.0,
+            cooling: Cooling::Geometric(0.999),
+            iterations: 20_000,
+            report_interval: 0,
+            seed: 7,
+        };
+        let initial = Schedule::new(&mdl, &econ, PERIODS, HashMap::new(), HashSet::new());
+        let mut reported = 0.0;
+        let schedule = annealing.improve(&mdl, &graph, &econ, &initial, |p| reported = p.best_npv);
+        assert_feasible(&schedule, &graph);
+
+        let npv = schedule.npv(&mdl, RATE, &econ);
+        let optimum = brute_force_npv(&mdl, &graph, &econ);
+        assert!((npv - reported).abs() < 1e-9);
+        //small enough for the seeded run to find the optimum from an empty schedule
+        assert!((npv - optimum).abs() < 1e-9);
+    }
+}
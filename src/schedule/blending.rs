@@ -0,0 +1,104 @@
+use crate::block::{BlockIndex, BlockInterface};
+use crate::block_model::BlockModel;
+use crate::economics::EconomicModel;
+use crate::schedule::Schedule;
+use crate::storage::BlockStorage;
+
+use std::collections::HashMap;
+
+//bounds on the tonnage weighted average of an attribute over the material processed each
+//period, e.g. a contaminant, unbounded sides are infinite
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlendingConstraint {
+    pub name: String,
+    pub values: HashMap<BlockIndex, f64>,
+    pub min: f64,
+    pub max: f64,
+}
+
+impl BlendingConstraint {
+    //attribute of every present block, whitespace in name is replaced so it can name rows
+    pub fn new<B, S, A>(mdl: &BlockModel<B, S>, name: &str, attr: A, min: f64, max: f64) -> Self
+    where
+        B: BlockInterface,
+        S: BlockStorage<B>,
+        A: Fn(&B) -> f64,
+    {
+        assert!(min <= max, "blending bounds are reversed");
+        Self {
+            name: name.split_whitespace().collect::<Vec<_>>().join("_"),
+            values: mdl.indexed_iter().map(|(ind, b)| (ind, attr(b))).collect(),
+            min,
+            max,
+        }
+    }
+
+    pub fn at_most<B, S, A>(mdl: &BlockModel<B, S>, name: &str, attr: A, max: f64) -> Self
+    where
+        B: BlockInterface,
+        S: BlockStorage<B>,
+        A: Fn(&B) -> f64,
+    {
+        Self::new(mdl, name, attr, f64::NEG_INFINITY, max)
+    }
+
+    pub fn at_least<B, S, A>(mdl: &BlockModel<B, S>, name: &str, attr: A, min: f64) -> Self
+    where
+        B: BlockInterface,
+        S: BlockStorage<B>,
+        A: Fn(&B) -> f64,
+    {
+        Self::new(mdl, name, attr, min, f64::INFINITY)
+    }
+
+    pub(crate) fn value(&self, ind: BlockIndex) -> f64 {
+        *self
+            .values
+            .get(&ind)
+            .expect("blending attribute is missing for a block")
+    }
+
+    //tonnage weighted amount by which a blend of tonnage and sum of tonnage * value breaks
+    //the bounds
+    pub(crate) fn violation(&self, tonnage: f64, sum: f64) -> f64 {
+        let over = if self.max.is_finite() {
+            sum - self.max * tonnage
+        } else {
+            0.0
+        };
+        let under = if self.min.is_finite() {
+            self.min * tonnage - sum
+        } else {
+            0.0
+        };
+        over.max(under).max(0.0)
+    }
+}
+
+impl Schedule {
+    //tonnage weighted average of the constrained attribute over the blocks processed directly
+    //each period, NaN for periods processing nothing, reclaimed stockpile material is not
+    //tracked by attribute
+    pub fn blend<B, S, E>(
+        &self,
+        mdl: &BlockModel<B, S>,
+        econ: &E,
+        constraint: &BlendingConstraint,
+    ) -> Vec<f64>
+    where
+        B: BlockInterface,
+        S: BlockStorage<B>,
+        E: EconomicModel<B>,
+    {
+        let mut sums = vec![(0.0, 0.0); self.num_periods];
+        for ind in self.processed.iter() {
+            let t = econ.tonnage(mdl.block(*ind).expect("processed block is missing"));
+            let (tonnage, sum) = &mut sums[self.periods[ind]];
+            *tonnage += t;
+            *sum += t * constraint.value(*ind);
+        }
+        sums.into_iter()
+            .map(|(t, s)| if t > 0.0 { s / t } else { f64::NAN })
+            .collect()
+    }
+}
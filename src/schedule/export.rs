@@ -0,0 +1,137 @@
+use serde::Serialize;
+
+use crate::block::{BlockIndex, BlockInterface, NamedAttribute};
+use crate::block_model::BlockModel;
+use crate::economics::Routing;
+use crate::io::vtk::VtkLayout;
+use crate::schedule::Schedule;
+use crate::storage::BlockStorage;
+
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+//destinations of a schedule without routing, in the order of their vtk codes
+const DESTINATIONS: [&str; 3] = ["waste", "plant", "stockpile"];
+
+//one scheduled block of a schedule csv
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ScheduledBlock {
+    pub i: usize,
+    pub j: usize,
+    pub k: usize,
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub period: usize,
+    pub destination: String,
+}
+
+impl Schedule {
+    //position of the destination of a scheduled block, in routing.names when routed and in
+    //waste, plant, stockpile otherwise
+    pub fn destination(&self, ind: BlockIndex, routing: Option<&Routing>) -> Option<usize> {
+        self.periods.get(&ind)?;
+        match routing {
+            Some(routing) => routing.destination(ind),
+            None if self.processed.contains(&ind) => Some(1),
+            None if self.stockpiled.contains(&ind) => Some(2),
+            None => Some(0),
+        }
+    }
+
+    pub fn destination_name<'a>(
+        &self,
+        ind: BlockIndex,
+        routing: Option<&'a Routing>,
+    ) -> Option<&'a str> {
+        let d = self.destination(ind, routing)?;
+        match routing {
+            Some(routing) => Some(routing.names[d].as_str()),
+            None => Some(DESTINATIONS[d]),
+        }
+    }
+
+    //model as legacy vtk with period and destination code cell data ahead of attributes,
+    //unscheduled blocks hold NaN, for animating the mining sequence by thresholding period
+    pub fn to_vtk<B, S, P>(
+        &self,
+        path: P,
+        mdl: &BlockModel<B, S>,
+        layout: VtkLayout,
+        routing: Option<&Routing>,
+        attributes: &[NamedAttribute<B>],
+    ) -> Result<(), Box<dyn Error>>
+    where
+        B: BlockInterface,
+        S: BlockStorage<B>,
+        P: AsRef<Path>,
+    {
+        let file = File::create(path)?;
+        self.to_vtk_writer(BufWriter::new(file), mdl, layout, routing, attributes)
+    }
+
+    pub fn to_vtk_writer<B, S, W>(
+        &self,
+        w: W,
+        mdl: &BlockModel<B, S>,
+        layout: VtkLayout,
+        routing: Option<&Routing>,
+        attributes: &[NamedAttribute<B>],
+    ) -> Result<(), Box<dyn Error>>
+    where
+        B: BlockInterface,
+        S: BlockStorage<B>,
+        W: Write,
+    {
+        let period = |b: &B| self.period(b.index()).map_or(f64::NAN, |p| p as f64);
+        let destination = |b: &B| {
+            self.destination(b.index(), routing)
+                .map_or(f64::NAN, |d| d as f64)
+        };
+        let mut all: Vec<NamedAttribute<B>> =
+            vec![("period", &period), ("destination", &destination)];
+        all.extend_from_slice(attributes);
+        mdl.to_vtk_writer(w, layout, &all)
+    }
+
+    //one row per scheduled block ordered by period then index
+    pub fn to_csv<B, S, P>(
+        &self,
+        path: P,
+        mdl: &BlockModel<B, S>,
+        routing: Option<&Routing>,
+    ) -> Result<(), Box<dyn Error>>
+    where
+        B: BlockInterface,
+        S: BlockStorage<B>,
+        P: AsRef<Path>,
+    {
+        let mut rows = self.periods.iter().collect::<Vec<_>>();
+        rows.sort_by_key(|(ind, period)| (**period, **ind));
+
+        let mut w = csv::Writer::from_path(path)?;
+        for (ind, period) in rows {
+            let c = mdl
+                .block(*ind)
+                .expect("scheduled block is missing from the model")
+                .coordinates();
+            w.serialize(ScheduledBlock {
+                i: ind.i,
+                j: ind.j,
+                k: ind.k,
+                x: c.x,
+                y: c.y,
+                z: c.z,
+                period: *period,
+                destination: self
+                    .destination_name(*ind, routing)
+                    .unwrap_or_default()
+                    .to_string(),
+            })?;
+        }
+        w.flush()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,247 @@
+use crate::block::{BlockIndex, BlockInterface};
+use crate::block_model::BlockModel;
+use crate::economics::EconomicModel;
+use crate::schedule::blending::BlendingConstraint;
+use crate::schedule::stockpile::{Parcel, Pile, Stockpile};
+use crate::schedule::{Schedule, VerticalAdvance};
+use crate::storage::BlockStorage;
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+//block of a bench-phase waiting to be mined
+#[derive(Debug, Clone, Copy)]
+struct Pending {
+    ind: BlockIndex,
+    tonnage: f64,
+    value: f64,
+    ore: bool,
+    //value gained by processing rather than dumping
+    margin: f64,
+    parcel: Parcel,
+}
+
+//bench-phase scheduler filling each period with the most valuable available bench-phases
+//a bench-phase becomes available once the bench above in the same phase is mined out and
+//the previous phase has mined out every bench down to phase_lag benches below it
+#[derive(Debug, Clone, PartialEq)]
+pub struct GreedyScheduler {
+    pub num_periods: usize,
+    //tonnes mined per period
+    pub mining_capacity: f64,
+    //tonnes processed per period
+    pub processing_capacity: f64,
+    //benches a phase must lead the next one by
+    pub phase_lag: usize,
+    //ore that would wait for the plant is stockpiled while the pile has room and reclaimed
+    //into spare plant capacity at the end of each period
+    pub stockpile: Option<Stockpile>,
+    //bounds on the blend of ore fed directly to the plant each period
+    pub blending: Vec<BlendingConstraint>,
+    //cost per unit of tonnage weighted blend violation, ore is dumped rather than processed
+    //when its violation costs more than processing gains
+    pub blending_penalty: f64,
+    //sink rate and active bench limits, a phase starts above its top bench
+    pub advance: VerticalAdvance,
+}
+
+impl GreedyScheduler {
+    //schedule every block with a phase, ore goes to the plant while it has capacity unless
+    //the blend penalty outweighs its margin, then to the stockpile when processing it later
+    //still pays for rehandling, and is left in place otherwise, blocks heavier than a
+    //capacity are never mined
+    pub fn schedule<B, S, E>(
+        &self,
+        mdl: &BlockModel<B, S>,
+        phases: &HashMap<BlockIndex, usize>,
+        econ: &E,
+    ) -> Schedule
+    where
+        B: BlockInterface,
+        S: BlockStorage<B>,
+        E: EconomicModel<B>,
+    {
+        //benches of each phase from the top down, blocks in descending value
+        let mut units: BTreeMap<usize, BTreeMap<usize, Vec<Pending>>> = BTreeMap::new();
+        for (ind, phase) in phases.iter() {
+            let b = mdl
+                .block(*ind)
+                .expect("phased block is missing from the model");
+            units
+                .entry(*phase)
+                .or_default()
+                .entry(ind.k)
+                .or_default()
+                .push(Pending {
+                    ind: *ind,
+                    tonnage: econ.tonnage(b),
+                    value: econ.value(b),
+                    ore: econ.is_ore(b),
+                    margin: econ.process_value(b) - econ.waste_value(b),
+                    parcel: Parcel::new(econ, b),
+                });
+        }
+        let mut units = units
+            .into_values()
+            .map(|benches| {
+                benches
+                    .into_iter()
+                    .rev()
+                    .map(|(k, mut blocks)| {
+                        blocks.sort_by(|a, b| b.value.total_cmp(&a.value).then(a.ind.cmp(&b.ind)));
+                        (k, blocks)
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let mut current = vec![0; units.len()];
+        //deepest bench each phase has mined from
+        let mut deepest: Vec<Option<usize>> = vec![None; units.len()];
+
+        let mut periods = HashMap::with_capacity(phases.len());
+        let mut processed = HashSet::new();
+        let mut stockpiled = HashSet::new();
+        let mut pile = self.stockpile.clone().map(Pile::new);
+        let mut reports = Vec::new();
+        for period in 0..self.num_periods {
+            let mut mined_tonnage = 0.0;
+            let mut processed_tonnage = 0.0;
+            let mut blend_sums = vec![0.0; self.blending.len()];
+            //lowest bench each phase may work this period
+            let floor = units
+                .iter()
+                .zip(deepest.iter())
+                .map(|(benches, d)| {
+                    let top = benches.first().map_or(0, |(k, _)| *k as i64);
+                    let sink = self.advance.sink_rate.map_or(i64::MAX / 2, |s| s as i64);
+                    d.map_or(top + 1, |d| d as i64) - sink
+                })
+                .collect::<Vec<_>>();
+            let mut active: HashSet<(usize, usize)> = HashSet::new();
+
+            'period: loop {
+                let mut available = (0..units.len())
+                    .filter(|q| self.is_available(&units, &current, *q))
+                    .filter(|q| units[*q][current[*q]].0 as i64 >= floor[*q])
+                    .filter(|q| {
+                        let unit = (*q, units[*q][current[*q]].0);
+                        active.contains(&unit)
+                            || self
+                                .advance
+                                .max_active_benches
+                                .is_none_or(|max| active.len() < max)
+                    })
+                    .map(|q| {
+                        let blocks = &units[q][current[q]].1;
+                        (q, blocks.iter().map(|p| p.value).sum::<f64>())
+                    })
+                    .collect::<Vec<_>>();
+                available.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+
+                for (q, _) in available {
+                    let unit = (q, units[q][current[q]].0);
+                    if !active.contains(&unit)
+                        && self
+                            .advance
+                            .max_active_benches
+                            .is_some_and(|max| active.len() >= max)
+                    {
+                        continue;
+                    }
+                    let (k, blocks) = &mut units[q][current[q]];
+                    let k = *k;
+                    let before = blocks.len();
+                    blocks.retain(|p| {
+                        if mined_tonnage + p.tonnage > self.mining_capacity {
+                            return true;
+                        }
+                        if p.ore {
+                            if processed_tonnage + p.tonnage <= self.processing_capacity {
+                                let penalty = self.blend_penalty(p, processed_tonnage, &blend_sums);
+                                if p.margin > penalty {
+                                    processed_tonnage += p.tonnage;
+                                    processed.insert(p.ind);
+                                    for (sum, blend) in
+                                        blend_sums.iter_mut().zip(self.blending.iter())
+                                    {
+                                        *sum += p.tonnage * blend.value(p.ind);
+                                    }
+                                }
+                            } else {
+                                match pile.as_mut() {
+                                    Some(pile)
+                                        if pile.has_room(p.tonnage)
+                                            && p.margin > p.tonnage * pile.rehandle_cost() =>
+                                    {
+                                        pile.add(p.parcel);
+                                        stockpiled.insert(p.ind);
+                                    }
+                                    _ => return true,
+                                }
+                            }
+                        }
+                        mined_tonnage += p.tonnage;
+                        periods.insert(p.ind, period);
+                        false
+                    });
+                    if blocks.len() < before {
+                        active.insert(unit);
+                        deepest[q] = Some(deepest[q].map_or(k, |d| d.min(k)));
+                    }
+
+                    //a mined out bench can release new bench-phases
+                    if blocks.is_empty() {
+                        current[q] += 1;
+                        continue 'period;
+                    }
+                }
+                break;
+            }
+
+            if let Some(pile) = pile.as_mut() {
+                pile.reclaim((self.processing_capacity - processed_tonnage).max(0.0));
+                reports.push(pile.report());
+            }
+        }
+
+        let schedule = Schedule::new(mdl, econ, self.num_periods, periods, processed);
+        match pile {
+            Some(_) => schedule.with_stockpile(stockpiled, reports),
+            None => schedule,
+        }
+    }
+
+    //cost of the extra blend violation from processing p
+    fn blend_penalty(&self, p: &Pending, tonnage: f64, sums: &[f64]) -> f64 {
+        let extra = self
+            .blending
+            .iter()
+            .zip(sums)
+            .map(|(blend, sum)| {
+                let after =
+                    blend.violation(tonnage + p.tonnage, sum + p.tonnage * blend.value(p.ind));
+                after - blend.violation(tonnage, *sum)
+            })
+            .sum::<f64>();
+        self.blending_penalty * extra.max(0.0)
+    }
+
+    fn is_available(
+        &self,
+        units: &[Vec<(usize, Vec<Pending>)>],
+        current: &[usize],
+        q: usize,
+    ) -> bool {
+        let Some((k, _)) = units[q].get(current[q]) else {
+            return false;
+        };
+        if q == 0 {
+            return true;
+        }
+
+        //bench the previous phase is working, None once it is mined out
+        match units[q - 1].get(current[q - 1]) {
+            None => true,
+            Some((prev_k, _)) => (*prev_k as i64) < *k as i64 - self.phase_lag as i64,
+        }
+    }
+}
@@ -0,0 +1,392 @@
+use crate::block::{BlockIndex, BlockInterface};
+use crate::block_model::BlockModel;
+use crate::economics::EconomicModel;
+use crate::graph::PrecedenceGraph;
+use crate::pit::{ClosureGraph, PitOptimizer};
+use crate::schedule::{discount_factor, Schedule};
+use crate::storage::BlockStorage;
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+//per period limit on the sum of a block attribute over the blocks mined, e.g. tonnage
+#[derive(Debug, Clone, PartialEq)]
+pub struct Resource {
+    pub name: String,
+    pub usage: HashMap<BlockIndex, f64>,
+    pub capacity: f64,
+}
+
+impl Resource {
+    pub fn new<B, S, U>(mdl: &BlockModel<B, S>, name: &str, usage: U, capacity: f64) -> Self
+    where
+        B: BlockInterface,
+        S: BlockStorage<B>,
+        U: Fn(&B) -> f64,
+    {
+        Self {
+            name: name.to_string(),
+            usage: mdl.indexed_iter().map(|(ind, b)| (ind, usage(b))).collect(),
+            capacity,
+        }
+    }
+
+    //tonnage of every block
+    pub fn mining<B, S, E>(mdl: &BlockModel<B, S>, econ: &E, capacity: f64) -> Self
+    where
+        B: BlockInterface,
+        S: BlockStorage<B>,
+        E: EconomicModel<B>,
+    {
+        Self::new(mdl, "mining", |b| econ.tonnage(b), capacity)
+    }
+
+    //tonnage of the blocks worth processing
+    pub fn processing<B, S, E>(mdl: &BlockModel<B, S>, econ: &E, capacity: f64) -> Self
+    where
+        B: BlockInterface,
+        S: BlockStorage<B>,
+        E: EconomicModel<B>,
+    {
+        let usage = |b: &B| {
+            if econ.process_value(b) > econ.waste_value(b) {
+                econ.tonnage(b)
+            } else {
+                0.0
+            }
+        };
+        Self::new(mdl, "processing", usage, capacity)
+    }
+
+    fn usage(&self, ind: BlockIndex) -> f64 {
+        *self
+            .usage
+            .get(&ind)
+            .expect("resource usage is missing for a block")
+    }
+}
+
+//best feasible schedule found with the bounds on the optimal npv
+#[derive(Debug, Clone, PartialEq)]
+pub struct LagrangianSolution {
+    pub schedule: Schedule,
+    //smallest lagrangian dual value
+    pub upper_bound: f64,
+    //npv of the schedule
+    pub lower_bound: f64,
+    //final multipliers per resource and period
+    pub multipliers: Vec<Vec<f64>>,
+    pub iterations: usize,
+}
+
+//constrained pit scheduling with fixed destinations, the resource constraints are priced
+//into the objective and the remaining precedence problem is a maximum closure over blocks
+//and periods, multipliers follow subgradient steps of step * gap / |subgradient|^2 with the
+//step halved whenever the bound stops improving
+#[derive(Debug, Clone, PartialEq)]
+pub struct LagrangianRelaxation {
+    pub num_periods: usize,
+    pub discount_rate: f64,
+    pub resources: Vec<Resource>,
+    pub iterations: usize,
+    //initial step scale, between 0 and 2
+    pub step: f64,
+    //relative gap at which to stop
+    pub tolerance: f64,
+}
+
+//iterations without a better upper bound before the step is halved
+const PATIENCE: usize = 5;
+
+impl LagrangianRelaxation {
+    //blocks are processed when that beats dumping them, the feasible schedule delays blocks
+    //of each relaxed solution in precedence order until every resource has room
+    pub fn solve<B, S, E, P>(
+        &self,
+        mdl: &BlockModel<B, S>,
+        graph: &PrecedenceGraph,
+        econ: &E,
+        solver: &P,
+    ) -> LagrangianSolution
+    where
+        B: BlockInterface,
+        S: BlockStorage<B>,
+        E: EconomicModel<B>,
+        P: PitOptimizer,
+    {
+        assert!(self.num_periods > 0, "at least one period is required");
+
+        let periods = self.num_periods;
+        let block = |ind: BlockIndex| {
+            mdl.block(ind)
+                .expect("graph block is missing from the model")
+        };
+        let values = graph
+            .inds()
+            .iter()
+            .map(|ind| econ.value(block(*ind)))
+            .collect::<Vec<_>>();
+        let usage = self
+            .resources
+            .iter()
+            .map(|r| graph.inds().iter().map(|ind| r.usage(*ind)).collect())
+            .collect::<Vec<Vec<f64>>>();
+        let discount = (0..periods)
+            .map(|t| discount_factor(self.discount_rate, t))
+            .collect::<Vec<_>>();
+        let rank = topological_rank(graph);
+
+        //node n * periods + t is selected when block n is mined by period t
+        let node = |n: usize, t: usize| n * periods + t;
+        let mut closure = ClosureGraph {
+            inds: graph
+                .inds()
+                .iter()
+                .flat_map(|ind| std::iter::repeat_n(*ind, periods))
+                .collect(),
+            weights: vec![0.0; graph.len() * periods],
+            preds: (0..graph.len())
+                .flat_map(|n| {
+                    (0..periods).map(move |t| {
+                        let mut preds = graph
+                            .preds(n)
+                            .iter()
+                            .map(|p| node(*p, t))
+                            .collect::<Vec<_>>();
+                        if t + 1 < periods {
+                            preds.push(node(n, t + 1));
+                        }
+                        preds
+                    })
+                })
+                .collect(),
+        };
+
+        let mut multipliers = vec![vec![0.0; periods]; self.resources.len()];
+        let mut upper_bound = f64::INFINITY;
+        let mut best: Option<(f64, Vec<usize>)> = None;
+        let mut step = self.step;
+        let mut stalled = 0;
+        let mut iterations = 0;
+        while iterations < self.iterations {
+            iterations += 1;
+
+            //value of mining block n in period t priced by the multipliers
+            let priced = |n: usize, t: usize| {
+                if t >= periods {
+                    return 0.0;
+                }
+                discount[t] * values[n]
+                    - multipliers
+                        .iter()
+                        .zip(usage.iter())
+                        .map(|(m, u)| m[t] * u[n])
+                        .sum::<f64>()
+            };
+            for n in 0..graph.len() {
+                for t in 0..periods {
+                    closure.weights[node(n, t)] = priced(n, t) - priced(n, t + 1);
+                }
+            }
+
+            let selected = solver.solve(&closure);
+            let relaxed = (0..graph.len())
+                .map(|n| {
+                    (0..periods)
+                        .find(|t| selected[node(n, *t)])
+                        .unwrap_or(periods)
+                })
+                .collect::<Vec<_>>();
+
+            let dual = closure
+                .weights
+                .iter()
+                .zip(selected.iter())
+                .filter(|(_, s)| **s)
+                .map(|(w, _)| w)
+                .sum::<f64>()
+                + multipliers
+                    .iter()
+                    .zip(self.resources.iter())
+                    .map(|(m, r)| m.iter().sum::<f64>() * r.capacity)
+                    .sum::<f64>();
+            if dual < upper_bound {
+                upper_bound = dual;
+                stalled = 0;
+            } else {
+                stalled += 1;
+                if stalled >= PATIENCE {
+                    step *= 0.5;
+                    stalled = 0;
+                }
+            }
+
+            let feasible = self.repair(graph, &relaxed, &rank, &usage);
+            let npv = feasible
+                .iter()
+                .enumerate()
+                .filter(|(_, t)| **t < periods)
+                .map(|(n, t)| discount[*t] * values[n])
+                .sum::<f64>();
+            if best.as_ref().is_none_or(|(v, _)| npv > *v) {
+                best = Some((npv, feasible));
+            }
+
+            let lower_bound = best.as_ref().map_or(0.0, |(v, _)| *v);
+            if upper_bound - lower_bound <= self.tolerance * upper_bound.abs() {
+                break;
+            }
+
+            //capacity left per resource and period by the relaxed solution
+            let mut slack = self
+                .resources
+                .iter()
+                .map(|r| vec![r.capacity; periods])
+                .collect::<Vec<_>>();
+            for (n, t) in relaxed.iter().enumerate() {
+                if *t < periods {
+                    for (s, u) in slack.iter_mut().zip(usage.iter()) {
+                        s[*t] -= u[n];
+                    }
+                }
+            }
+            //subgradient with components that cannot move the multipliers dropped
+            let norm = slack
+                .iter()
+                .zip(multipliers.iter())
+                .flat_map(|(s, m)| s.iter().zip(m.iter()))
+                .filter(|(s, m)| **s < 0.0 || **m > 0.0)
+                .map(|(s, _)| s * s)
+                .sum::<f64>();
+            if norm == 0.0 {
+                break;
+            }
+            let size = step * (upper_bound - lower_bound).max(0.0) / norm;
+            for (m, s) in multipliers.iter_mut().zip(slack.iter()) {
+                for (m, s) in m.iter_mut().zip(s.iter()) {
+                    *m = (*m - size * s).max(0.0);
+                }
+            }
+        }
+
+        let (lower_bound, feasible) = best.unwrap_or((0.0, vec![periods; graph.len()]));
+        let mut scheduled = HashMap::new();
+        let mut processed = HashSet::new();
+        for (n, t) in feasible.iter().enumerate() {
+            if *t < periods {
+                let ind = graph.ind(n);
+                scheduled.insert(ind, *t);
+                let b = block(ind);
+                if econ.process_value(b) > econ.waste_value(b) {
+                    processed.insert(ind);
+                }
+            }
+        }
+
+        LagrangianSolution {
+            schedule: Schedule::new(mdl, econ, periods, scheduled, processed),
+            upper_bound,
+            lower_bound,
+            multipliers,
+            iterations,
+        }
+    }
+
+    //earliest periods no sooner than the relaxed ones with room for every resource, taken in
+    //precedence order, blocks that fit no period are left unmined with their successors
+    fn repair(
+        &self,
+        graph: &PrecedenceGraph,
+        relaxed: &[usize],
+        rank: &[usize],
+        usage: &[Vec<f64>],
+    ) -> Vec<usize> {
+        let periods = self.num_periods;
+        let mut order = (0..graph.len())
+            .filter(|n| relaxed[*n] < periods)
+            .collect::<Vec<_>>();
+        order.sort_by_key(|n| (relaxed[*n], rank[*n]));
+
+        let mut used = vec![vec![0.0; periods]; self.resources.len()];
+        let mut feasible = vec![periods; graph.len()];
+        for n in order {
+            let earliest = graph
+                .preds(n)
+                .iter()
+                .map(|p| feasible[*p])
+                .max()
+                .unwrap_or(0)
+                .max(relaxed[n]);
+            let fits = |t: &usize| {
+                self.resources
+                    .iter()
+                    .zip(used.iter())
+                    .zip(usage.iter())
+                    .all(|((r, used), u)| used[*t] + u[n] <= r.capacity)
+            };
+            if let Some(t) = (earliest..periods).find(fits) {
+                for (used, u) in used.iter_mut().zip(usage.iter()) {
+                    used[t] += u[n];
+                }
+                feasible[n] = t;
+            }
+        }
+        feasible
+    }
+}
+
+//position of every node in an order where each node follows its predecessors
+fn topological_rank(graph: &PrecedenceGraph) -> Vec<usize> {
+    let mut remaining = (0..graph.len())
+        .map(|n| graph.num_preds(n))
+        .collect::<Vec<_>>();
+    let mut queue = (0..graph.len())
+        .filter(|n| remaining[*n] == 0)
+        .collect::<VecDeque<_>>();
+    let mut rank = vec![usize::MAX; graph.len()];
+    let mut next = 0;
+    while let Some(n) = queue.pop_front() {
+        rank[n] = next;
+        next += 1;
+        for s in graph.succs(n).iter() {
+            remaining[*s] -= 1;
+            if remaining[*s] == 0 {
+                queue.push_back(*s);
+            }
+        }
+    }
+    rank
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LagrangianRelaxation, Resource};
+    use crate::pit::pseudoflow::Pseudoflow;
+    use crate::schedule::discount_factor;
+    use crate::schedule::tests::{
+        assert_feasible, brute_force_npv, toy_model, CAPACITY, PERIODS, RATE,
+    };
+
+    #[test]
+    fn feasible_within_bounds() {
+        let (mdl, graph, econ) = toy_model();
+        let relaxation = LagrangianRelaxation {
+            num_periods: PERIODS,
+            discount_rate: RATE,
+            resources: vec![Resource::mining(&mdl, &econ, CAPACITY)],
+            iterations: 200,
+            step: 1.0,
+            tolerance: 1e-6,
+        };
+        let solution = relaxation.solve(&mdl, &graph, &econ, &Pseudoflow);
+        assert_feasible(&solution.schedule, &graph);
+
+        let npv = solution.schedule.npv(&mdl, RATE, &econ);
+        assert!((solution.lower_bound - npv).abs() < 1e-9);
+        let optimum = brute_force_npv(&mdl, &graph, &econ);
+        assert!(solution.lower_bound > 0.0);
+        assert!(solution.lower_bound <= optimum + 1e-9);
+        assert!(optimum <= solution.upper_bound + 1e-9);
+        //no worse than the bound without capacities, the whole pit, worth 3, in the first period
+        assert!(solution.upper_bound <= 3.0 * discount_factor(RATE, 0) + 1e-9);
+    }
+}
@@ -0,0 +1,608 @@
+use crate::block::{BlockIndex, BlockInterface};
+use crate::block_model::BlockModel;
+use crate::economics::EconomicModel;
+use crate::graph::PrecedenceGraph;
+use crate::schedule::blending::BlendingConstraint;
+use crate::schedule::{discount_factor, Schedule, VerticalAdvance};
+use crate::storage::BlockStorage;
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum VariableKind {
+    Continuous,
+    Integer,
+    Binary,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Variable {
+    pub name: String,
+    pub kind: VariableKind,
+    pub lower: f64,
+    pub upper: f64,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Sense {
+    Le,
+    Ge,
+    Eq,
+}
+
+//sum of coefficient * variable compared against rhs
+#[derive(Debug, Clone, PartialEq)]
+pub struct Constraint {
+    pub name: String,
+    pub terms: Vec<(usize, f64)>,
+    pub sense: Sense,
+    pub rhs: f64,
+}
+
+//mixed integer program maximizing objective, terms refer to variables by position
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct LinearProgram {
+    pub variables: Vec<Variable>,
+    pub objective: Vec<(usize, f64)>,
+    pub constraints: Vec<Constraint>,
+}
+
+//terms per line, keeps lines well below the lp format limit
+const TERMS_PER_LINE: usize = 8;
+
+impl LinearProgram {
+    pub fn add_variable(
+        &mut self,
+        name: String,
+        kind: VariableKind,
+        lower: f64,
+        upper: f64,
+    ) -> usize {
+        self.variables.push(Variable {
+            name,
+            kind,
+            lower,
+            upper,
+        });
+        self.variables.len() - 1
+    }
+
+    pub fn add_constraint(
+        &mut self,
+        name: String,
+        terms: Vec<(usize, f64)>,
+        sense: Sense,
+        rhs: f64,
+    ) {
+        self.constraints.push(Constraint {
+            name,
+            terms,
+            sense,
+            rhs,
+        });
+    }
+
+    pub fn num_variables(&self) -> usize {
+        self.variables.len()
+    }
+
+    pub fn num_constraints(&self) -> usize {
+        self.constraints.len()
+    }
+
+    fn write_terms<W: Write>(
+        &self,
+        w: &mut W,
+        terms: &[(usize, f64)],
+    ) -> Result<(), Box<dyn Error>> {
+        if terms.is_empty() {
+            write!(w, " 0 {}", self.variables[0].name)?;
+        }
+        for (n, (var, coef)) in terms.iter().enumerate() {
+            if n > 0 && n % TERMS_PER_LINE == 0 {
+                write!(w, "\n ")?;
+            }
+            let sign = if *coef < 0.0 { '-' } else { '+' };
+            write!(w, " {} {} {}", sign, coef.abs(), self.variables[*var].name)?;
+        }
+        Ok(())
+    }
+
+    //cplex lp format, read by gurobi, cbc, highs and glpk
+    pub fn to_lp_writer<W: Write>(&self, mut w: W) -> Result<(), Box<dyn Error>> {
+        assert!(!self.variables.is_empty(), "program has no variables");
+
+        writeln!(w, "Maximize")?;
+        write!(w, " obj:")?;
+        self.write_terms(&mut w, &self.objective)?;
+        writeln!(w)?;
+
+        writeln!(w, "Subject To")?;
+        for c in self.constraints.iter() {
+            write!(w, " {}:", c.name)?;
+            self.write_terms(&mut w, &c.terms)?;
+            let sense = match c.sense {
+                Sense::Le => "<=",
+                Sense::Ge => ">=",
+                Sense::Eq => "=",
+            };
+            writeln!(w, " {} {}", sense, c.rhs)?;
+        }
+
+        writeln!(w, "Bounds")?;
+        for v in self
+            .variables
+            .iter()
+            .filter(|v| v.kind != VariableKind::Binary)
+        {
+            let bound = |b: f64| {
+                if b.is_infinite() {
+                    format!("{}inf", if b < 0.0 { "-" } else { "+" })
+                } else {
+                    b.to_string()
+                }
+            };
+            writeln!(w, " {} <= {} <= {}", bound(v.lower), v.name, bound(v.upper))?;
+        }
+
+        for (section, kind) in [
+            ("General", VariableKind::Integer),
+            ("Binary", VariableKind::Binary),
+        ] {
+            let vars = self
+                .variables
+                .iter()
+                .filter(|v| v.kind == kind)
+                .collect::<Vec<_>>();
+            if vars.is_empty() {
+                continue;
+            }
+            writeln!(w, "{}", section)?;
+            for v in vars {
+                writeln!(w, " {}", v.name)?;
+            }
+        }
+        writeln!(w, "End")?;
+        Ok(())
+    }
+
+    pub fn write_lp<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn Error>> {
+        let mut w = BufWriter::new(File::create(path)?);
+        self.to_lp_writer(&mut w)?;
+        w.flush()?;
+        Ok(())
+    }
+
+    //free mps format, the objective is negated and minimized since not every reader
+    //supports OBJSENSE
+    pub fn to_mps_writer<W: Write>(&self, mut w: W) -> Result<(), Box<dyn Error>> {
+        writeln!(w, "NAME block_model")?;
+        writeln!(w, "ROWS")?;
+        writeln!(w, " N obj")?;
+        for c in self.constraints.iter() {
+            let sense = match c.sense {
+                Sense::Le => 'L',
+                Sense::Ge => 'G',
+                Sense::Eq => 'E',
+            };
+            writeln!(w, " {} {}", sense, c.name)?;
+        }
+
+        //entries of every column
+        let mut columns = vec![Vec::new(); self.variables.len()];
+        for (var, coef) in self.objective.iter() {
+            columns[*var].push(("obj", -coef));
+        }
+        for c in self.constraints.iter() {
+            for (var, coef) in c.terms.iter() {
+                columns[*var].push((c.name.as_str(), *coef));
+            }
+        }
+
+        writeln!(w, "COLUMNS")?;
+        let mut integer = false;
+        for (n, (v, entries)) in self.variables.iter().zip(columns.iter()).enumerate() {
+            let is_integer = v.kind != VariableKind::Continuous;
+            if is_integer != integer {
+                let marker = if is_integer { "INTORG" } else { "INTEND" };
+                writeln!(w, "    MARKER{} 'MARKER' '{}'", n, marker)?;
+                integer = is_integer;
+            }
+            if entries.is_empty() {
+                writeln!(w, "    {} obj 0", v.name)?;
+            }
+            for (row, coef) in entries.iter() {
+                writeln!(w, "    {} {} {}", v.name, row, coef)?;
+            }
+        }
+        if integer {
+            writeln!(w, "    MARKEREND 'MARKER' 'INTEND'")?;
+        }
+
+        writeln!(w, "RHS")?;
+        for c in self.constraints.iter().filter(|c| c.rhs != 0.0) {
+            writeln!(w, "    rhs {} {}", c.name, c.rhs)?;
+        }
+
+        writeln!(w, "BOUNDS")?;
+        for v in self.variables.iter() {
+            if v.kind == VariableKind::Binary {
+                writeln!(w, " BV bnd {}", v.name)?;
+                continue;
+            }
+            match (v.lower.is_finite(), v.upper.is_finite()) {
+                (false, false) => writeln!(w, " FR bnd {}", v.name)?,
+                (false, true) => {
+                    writeln!(w, " MI bnd {}", v.name)?;
+                    writeln!(w, " UP bnd {} {}", v.name, v.upper)?;
+                }
+                (true, upper) => {
+                    if v.lower != 0.0 {
+                        writeln!(w, " LO bnd {} {}", v.name, v.lower)?;
+                    }
+                    if upper {
+                        writeln!(w, " UP bnd {} {}", v.name, v.upper)?;
+                    } else if v.kind == VariableKind::Integer {
+                        //some readers default marked integers to binary
+                        writeln!(w, " PL bnd {}", v.name)?;
+                    }
+                }
+            }
+        }
+        writeln!(w, "ENDATA")?;
+        Ok(())
+    }
+
+    pub fn write_mps<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn Error>> {
+        let mut w = BufWriter::new(File::create(path)?);
+        self.to_mps_writer(&mut w)?;
+        w.flush()?;
+        Ok(())
+    }
+
+    //variable values from a gurobi .sol, cbc or highs solution file, each line holding a
+    //variable name followed by its value, variables absent from the file are zero
+    pub fn read_solution<P: AsRef<Path>>(&self, path: P) -> Result<Vec<f64>, Box<dyn Error>> {
+        let position = self
+            .variables
+            .iter()
+            .enumerate()
+            .map(|(n, v)| (v.name.as_str(), n))
+            .collect::<HashMap<_, _>>();
+
+        let mut values = vec![0.0; self.variables.len()];
+        for line in fs::read_to_string(path)?.lines() {
+            let tokens = line.split_whitespace().collect::<Vec<_>>();
+            if tokens.first().is_some_and(|t| t.starts_with('#')) {
+                continue;
+            }
+            for (n, token) in tokens.iter().enumerate() {
+                let Some(var) = position.get(token) else {
+                    continue;
+                };
+                let value = tokens
+                    .get(n + 1)
+                    .ok_or_else(|| format!("missing value for {}", token))?;
+                values[*var] = value.parse()?;
+                break;
+            }
+        }
+        Ok(values)
+    }
+}
+
+//direct block scheduling over the nodes of a precedence graph, with one period this is
+//the constrained pit limit problem
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchedulingProblem {
+    pub num_periods: usize,
+    //tonnes mined per period
+    pub mining_capacity: f64,
+    //tonnes processed per period
+    pub processing_capacity: f64,
+    //per period, cash flows are discounted at the end of each period
+    pub discount_rate: f64,
+    //bounds on the processed blend of every period
+    pub blending: Vec<BlendingConstraint>,
+    //sink rate and active bench limits with the whole pit as a single phase
+    pub advance: VerticalAdvance,
+}
+
+//variables of one block, by[t] is 1 once the block is mined by period t and
+//processed[t] is 1 if it is mined in t and sent to the plant
+#[derive(Debug, Clone)]
+struct BlockVariables {
+    by: Vec<usize>,
+    processed: Vec<usize>,
+}
+
+fn name(prefix: char, ind: BlockIndex, period: usize) -> String {
+    format!("{}_{}_{}_{}_{}", prefix, ind.i, ind.j, ind.k, period)
+}
+
+impl SchedulingProblem {
+    //binary program with precedence enforced on cumulative mining variables, mining and
+    //processing capacities, blending bounds and vertical advance limits each period and a
+    //discounted objective
+    pub fn formulate<B, S, E>(
+        &self,
+        mdl: &BlockModel<B, S>,
+        graph: &PrecedenceGraph,
+        econ: &E,
+    ) -> LinearProgram
+    where
+        B: BlockInterface,
+        S: BlockStorage<B>,
+        E: EconomicModel<B>,
+    {
+        assert!(self.num_periods > 0, "at least one period is required");
+
+        let periods = self.num_periods;
+        let discount = (0..=periods)
+            .map(|t| {
+                if t < periods {
+                    discount_factor(self.discount_rate, t)
+                } else {
+                    0.0
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let mut lp = LinearProgram::default();
+        let vars = graph
+            .inds()
+            .iter()
+            .map(|ind| BlockVariables {
+                by: (0..periods)
+                    .map(|t| lp.add_variable(name('x', *ind, t), VariableKind::Binary, 0.0, 1.0))
+                    .collect(),
+                processed: (0..periods)
+                    .map(|t| lp.add_variable(name('y', *ind, t), VariableKind::Binary, 0.0, 1.0))
+                    .collect(),
+            })
+            .collect::<Vec<_>>();
+
+        //bench variables, reach[k][t] is 1 once bench k is mined into by period t and
+        //active[k][t] is 1 if bench k is worked in t
+        let benches = graph
+            .inds()
+            .iter()
+            .map(|ind| ind.k)
+            .collect::<BTreeSet<_>>();
+        let bench_vars = |prefix: &str, lp: &mut LinearProgram| {
+            benches
+                .iter()
+                .map(|k| {
+                    let v = (0..periods)
+                        .map(|t| {
+                            let name = format!("{}_{}_{}", prefix, k, t);
+                            lp.add_variable(name, VariableKind::Binary, 0.0, 1.0)
+                        })
+                        .collect::<Vec<_>>();
+                    (*k, v)
+                })
+                .collect::<BTreeMap<_, _>>()
+        };
+        let reach = match self.advance.sink_rate {
+            Some(_) => bench_vars("reach", &mut lp),
+            None => BTreeMap::new(),
+        };
+        let active = match self.advance.max_active_benches {
+            Some(_) => bench_vars("active", &mut lp),
+            None => BTreeMap::new(),
+        };
+
+        let mut mining = vec![Vec::new(); periods];
+        let mut processing = vec![Vec::new(); periods];
+        //blend over and under terms per constraint and period
+        let mut over = vec![vec![Vec::new(); periods]; self.blending.len()];
+        let mut under = vec![vec![Vec::new(); periods]; self.blending.len()];
+        for (n, ind) in graph.inds().iter().enumerate() {
+            let b = mdl
+                .block(*ind)
+                .expect("graph block is missing from the model");
+            let (t_b, waste, process) =
+                (econ.tonnage(b), econ.waste_value(b), econ.process_value(b));
+            let v = &vars[n];
+
+            for t in 0..periods {
+                //mined in t is by[t] - by[t - 1], so by[t] carries the waste value of t less
+                //that of t + 1
+                lp.objective
+                    .push((v.by[t], waste * (discount[t] - discount[t + 1])));
+                lp.objective
+                    .push((v.processed[t], (process - waste) * discount[t]));
+
+                mining[t].push((v.by[t], t_b));
+                if t > 0 {
+                    mining[t].push((v.by[t - 1], -t_b));
+                    lp.add_constraint(
+                        name('s', *ind, t),
+                        vec![(v.by[t - 1], 1.0), (v.by[t], -1.0)],
+                        Sense::Le,
+                        0.0,
+                    );
+                }
+                processing[t].push((v.processed[t], t_b));
+                for (c, blend) in self.blending.iter().enumerate() {
+                    let a = blend.value(*ind);
+                    if blend.max.is_finite() {
+                        over[c][t].push((v.processed[t], t_b * (a - blend.max)));
+                    }
+                    if blend.min.is_finite() {
+                        under[c][t].push((v.processed[t], t_b * (a - blend.min)));
+                    }
+                }
+
+                //processed only in the period it is mined
+                let mut terms = vec![(v.processed[t], 1.0), (v.by[t], -1.0)];
+                if t > 0 {
+                    terms.push((v.by[t - 1], 1.0));
+                }
+                lp.add_constraint(name('d', *ind, t), terms, Sense::Le, 0.0);
+
+                if let Some(r) = reach.get(&ind.k) {
+                    lp.add_constraint(
+                        name('h', *ind, t),
+                        vec![(v.by[t], 1.0), (r[t], -1.0)],
+                        Sense::Le,
+                        0.0,
+                    );
+                }
+                if let Some(w) = active.get(&ind.k) {
+                    let mut terms = vec![(v.by[t], 1.0), (w[t], -1.0)];
+                    if t > 0 {
+                        terms.push((v.by[t - 1], -1.0));
+                    }
+                    lp.add_constraint(name('w', *ind, t), terms, Sense::Le, 0.0);
+                }
+
+                for (a, p) in graph.preds(n).iter().enumerate() {
+                    lp.add_constraint(
+                        format!("{}_{}", name('p', *ind, t), a),
+                        vec![(v.by[t], 1.0), (vars[*p].by[t], -1.0)],
+                        Sense::Le,
+                        0.0,
+                    );
+                }
+            }
+        }
+
+        for (t, terms) in mining.into_iter().enumerate() {
+            lp.add_constraint(
+                format!("mine_{}", t),
+                terms,
+                Sense::Le,
+                self.mining_capacity,
+            );
+        }
+        for (t, terms) in processing.into_iter().enumerate() {
+            lp.add_constraint(
+                format!("plant_{}", t),
+                terms,
+                Sense::Le,
+                self.processing_capacity,
+            );
+        }
+        if let Some(rate) = self.advance.sink_rate {
+            //bench k may be mined into in t only once bench k + rate was by t - 1, benches
+            //within rate of the top are open from the first period
+            for (k, r) in reach.iter() {
+                let Some(above) = reach.get(&(k + rate)) else {
+                    continue;
+                };
+                for t in 0..periods {
+                    let mut terms = vec![(r[t], 1.0)];
+                    if t > 0 {
+                        terms.push((above[t - 1], -1.0));
+                    }
+                    lp.add_constraint(format!("sink_{}_{}", k, t), terms, Sense::Le, 0.0);
+                }
+            }
+        }
+        if let Some(max) = self.advance.max_active_benches {
+            for t in 0..periods {
+                lp.add_constraint(
+                    format!("benches_{}", t),
+                    active.values().map(|w| (w[t], 1.0)).collect(),
+                    Sense::Le,
+                    max as f64,
+                );
+            }
+        }
+        for (c, blend) in self.blending.iter().enumerate() {
+            for t in 0..periods {
+                if blend.max.is_finite() {
+                    let terms = std::mem::take(&mut over[c][t]);
+                    lp.add_constraint(
+                        format!("blend_{}_max_{}", blend.name, t),
+                        terms,
+                        Sense::Le,
+                        0.0,
+                    );
+                }
+                if blend.min.is_finite() {
+                    let terms = std::mem::take(&mut under[c][t]);
+                    lp.add_constraint(
+                        format!("blend_{}_min_{}", blend.name, t),
+                        terms,
+                        Sense::Ge,
+                        0.0,
+                    );
+                }
+            }
+        }
+        lp
+    }
+
+    //schedule from variable values of a program built by formulate
+    pub fn schedule_from_values<B, S, E>(
+        &self,
+        mdl: &BlockModel<B, S>,
+        lp: &LinearProgram,
+        values: &[f64],
+        econ: &E,
+    ) -> Schedule
+    where
+        B: BlockInterface,
+        S: BlockStorage<B>,
+        E: EconomicModel<B>,
+    {
+        //first period each block is mined by, and the periods it is processed in
+        let mut first: BTreeMap<BlockIndex, usize> = BTreeMap::new();
+        let mut processed = HashSet::new();
+        for (v, value) in lp.variables.iter().zip(values) {
+            if *value < 0.5 {
+                continue;
+            }
+            let Some((prefix, ind, period)) = parse_name(&v.name) else {
+                continue;
+            };
+            match prefix {
+                'x' => {
+                    let p = first.entry(ind).or_insert(period);
+                    *p = (*p).min(period);
+                }
+                'y' => {
+                    processed.insert(ind);
+                }
+                _ => {}
+            }
+        }
+
+        let periods = first.into_iter().collect::<HashMap<_, _>>();
+        processed.retain(|ind| periods.contains_key(ind));
+        Schedule::new(mdl, econ, self.num_periods, periods, processed)
+    }
+
+    //schedule from a solver solution file of a program built by formulate
+    pub fn read_solution<B, S, E, P>(
+        &self,
+        path: P,
+        mdl: &BlockModel<B, S>,
+        lp: &LinearProgram,
+        econ: &E,
+    ) -> Result<Schedule, Box<dyn Error>>
+    where
+        B: BlockInterface,
+        S: BlockStorage<B>,
+        E: EconomicModel<B>,
+        P: AsRef<Path>,
+    {
+        let values = lp.read_solution(path)?;
+        Ok(self.schedule_from_values(mdl, lp, &values, econ))
+    }
+}
+
+//prefix, block and period of a scheduling variable name
+fn parse_name(name: &str) -> Option<(char, BlockIndex, usize)> {
+    let mut parts = name.split('_');
+    let prefix = parts.next()?.chars().next()?;
+    let mut next = || parts.next()?.parse::<usize>().ok();
+    let (i, j, k, period) = (next()?, next()?, next()?, next()?);
+    Some((prefix, BlockIndex { i, j, k }, period))
+}
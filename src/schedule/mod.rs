@@ -0,0 +1,298 @@
+pub mod annealing;
+pub mod blending;
+pub mod export;
+pub mod greedy;
+pub mod lagrangian;
+pub mod milp;
+pub mod npv;
+#[cfg(feature = "good_lp")]
+pub mod solver;
+pub mod stockpile;
+pub mod surfaces;
+
+use crate::block::{BlockIndex, BlockInterface};
+use crate::block_model::BlockModel;
+use crate::economics::{Destination, EconomicModel, Routing};
+use crate::schedule::stockpile::StockpilePeriod;
+use crate::storage::BlockStorage;
+
+use std::collections::{HashMap, HashSet};
+
+//discount applied to cash flows of period n, which are received at its end
+pub fn discount_factor(rate: f64, period: usize) -> f64 {
+    (1.0 + rate).powi(-(period as i32 + 1))
+}
+
+//geometric limits on how fast pits deepen, benches are counted by k
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct VerticalAdvance {
+    //benches a phase may deepen per period, counted below the deepest bench it had reached
+    //before the period
+    pub sink_rate: Option<usize>,
+    //bench-phases worked per period across the mine
+    pub max_active_benches: Option<usize>,
+}
+
+//totals of the blocks mined in one period
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PeriodSummary {
+    pub num_blocks: usize,
+    pub mined_tonnage: f64,
+    //including material reclaimed from stockpiles
+    pub processed_tonnage: f64,
+    //undiscounted value of the mined blocks at their destinations and of any reclaimed
+    //material
+    pub value: f64,
+}
+
+//period each block is mined in and whether it is sent to the plant
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schedule {
+    pub num_periods: usize,
+    //unscheduled blocks are absent
+    pub periods: HashMap<BlockIndex, usize>,
+    pub processed: HashSet<BlockIndex>,
+    //blocks sent to the stockpile, charged their mining cost when mined
+    pub stockpiled: HashSet<BlockIndex>,
+    //stockpile movements per period, empty without a stockpile
+    pub stockpile: Vec<StockpilePeriod>,
+    pub summaries: Vec<PeriodSummary>,
+}
+
+impl Schedule {
+    //summarise a block to period assignment, processed blocks must be scheduled
+    pub fn new<B, S, E>(
+        mdl: &BlockModel<B, S>,
+        econ: &E,
+        num_periods: usize,
+        periods: HashMap<BlockIndex, usize>,
+        processed: HashSet<BlockIndex>,
+    ) -> Self
+    where
+        B: BlockInterface,
+        S: BlockStorage<B>,
+        E: EconomicModel<B>,
+    {
+        assert!(
+            processed.iter().all(|ind| periods.contains_key(ind)),
+            "processed blocks must be scheduled"
+        );
+
+        let mut summaries = vec![PeriodSummary::default(); num_periods];
+        for (ind, period) in periods.iter() {
+            assert!(
+                *period < num_periods,
+                "block scheduled after the last period"
+            );
+            let b = mdl
+                .block(*ind)
+                .expect("scheduled block is missing from the model");
+            let summary = &mut summaries[*period];
+            let t = econ.tonnage(b);
+            summary.num_blocks += 1;
+            summary.mined_tonnage += t;
+            if processed.contains(ind) {
+                summary.processed_tonnage += t;
+                summary.value += econ.process_value(b);
+            } else {
+                summary.value += econ.waste_value(b);
+            }
+        }
+
+        Self {
+            num_periods,
+            periods,
+            processed,
+            stockpiled: HashSet::new(),
+            stockpile: Vec::new(),
+            summaries,
+        }
+    }
+
+    //record stockpiled blocks, already scheduled as waste, and the stockpile reports
+    pub(crate) fn with_stockpile(
+        mut self,
+        stockpiled: HashSet<BlockIndex>,
+        stockpile: Vec<StockpilePeriod>,
+    ) -> Self {
+        assert_eq!(stockpile.len(), self.num_periods, "one report per period");
+        for (summary, report) in self.summaries.iter_mut().zip(stockpile.iter()) {
+            summary.processed_tonnage += report.reclaimed_tonnage;
+            summary.value += report.value();
+        }
+        self.stockpiled = stockpiled;
+        self.stockpile = stockpile;
+        self
+    }
+
+    //number of scheduled blocks
+    pub fn len(&self) -> usize {
+        self.periods.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.periods.is_empty()
+    }
+
+    pub fn period(&self, ind: BlockIndex) -> Option<usize> {
+        self.periods.get(&ind).copied()
+    }
+
+    pub fn is_processed(&self, ind: BlockIndex) -> bool {
+        self.processed.contains(&ind)
+    }
+
+    //blocks mined in period n, sorted by index
+    pub fn blocks(&self, n: usize) -> Vec<BlockIndex> {
+        let mut inds = self
+            .periods
+            .iter()
+            .filter(|(_, p)| **p == n)
+            .map(|(ind, _)| *ind)
+            .collect::<Vec<_>>();
+        inds.sort();
+        inds
+    }
+
+    //route the blocks of every period with each destination's capacity applying per period
+    pub fn route<B, S, E>(
+        &self,
+        mdl: &BlockModel<B, S>,
+        econ: &E,
+        destinations: &[Destination<B>],
+    ) -> Routing
+    where
+        B: BlockInterface,
+        S: BlockStorage<B>,
+        E: EconomicModel<B>,
+    {
+        let mut routing = Routing::empty(destinations);
+        for period in 0..self.num_periods {
+            let blocks = self
+                .blocks(period)
+                .into_iter()
+                .map(|ind| {
+                    (
+                        ind,
+                        mdl.block(ind)
+                            .expect("scheduled block is missing from the model"),
+                    )
+                })
+                .collect::<Vec<_>>();
+            routing.route(blocks.into_iter(), econ, destinations);
+        }
+        routing
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::{discount_factor, Schedule};
+    use crate::block::{BlockCoordinates, BlockSize};
+    use crate::block_model::{BlockModel, SquarePreds};
+    use crate::columnar::ColumnarRow;
+    use crate::economics::{EconomicModel, SimpleEconomicModel};
+    use crate::graph::PrecedenceGraph;
+
+    pub(crate) type ToyEconomics =
+        SimpleEconomicModel<fn(&ColumnarRow) -> f64, fn(&ColumnarRow) -> f64>;
+
+    pub(crate) const PERIODS: usize = 3;
+    pub(crate) const CAPACITY: f64 = 3.0;
+    pub(crate) const RATE: f64 = 0.1;
+
+    //section four blocks wide and two deep of unit tonnes, each block of the lower bench
+    //waits for the blocks above it, ore worth 3 and 4 lies below a row of waste and only
+    //three blocks fit a period
+    pub(crate) fn toy_model() -> (BlockModel<ColumnarRow>, PrecedenceGraph, ToyEconomics) {
+        let size = BlockSize {
+            x_size: 10.0,
+            y_size: 10.0,
+            z_size: 10.0,
+        };
+        let grades = [[0.0, 4.0, 5.0, 0.0], [0.0; 4]];
+        let rows = (0..2)
+            .flat_map(|k| (0..4).map(move |i| (i, k)))
+            .map(|(i, k)| {
+                let coords = BlockCoordinates {
+                    x: 10.0 * i as f32,
+                    y: 0.0,
+                    z: 10.0 * k as f32,
+                };
+                ColumnarRow::new(coords, size, vec![grades[k][i]])
+            })
+            .collect();
+        let mdl = BlockModel::from_unindexed(rows).unwrap();
+        let graph = mdl.precedence_graph(&SquarePreds);
+        let econ = SimpleEconomicModel {
+            price: 1.0,
+            selling_cost: 0.0,
+            recovery: 1.0,
+            processing_cost: 0.0,
+            mining_cost: 1.0,
+            reference_elevation: 100.0,
+            mining_cost_per_metre: 0.0,
+            grade: (|b: &ColumnarRow| b.value(0)) as fn(&ColumnarRow) -> f64,
+            tonnage: (|_: &ColumnarRow| 1.0) as fn(&ColumnarRow) -> f64,
+        };
+        (mdl, graph, econ)
+    }
+
+    //best npv over every assignment of the blocks to a period or to none, blocks go to their
+    //best destination
+    pub(crate) fn brute_force_npv(
+        mdl: &BlockModel<ColumnarRow>,
+        graph: &PrecedenceGraph,
+        econ: &ToyEconomics,
+    ) -> f64 {
+        let values = graph
+            .inds()
+            .iter()
+            .map(|ind| econ.value(mdl.block(*ind).unwrap()))
+            .collect::<Vec<_>>();
+        let n = graph.len();
+        let mut best = f64::NEG_INFINITY;
+        for code in 0..(PERIODS + 1).pow(n as u32) {
+            let periods = (0..n)
+                .map(|b| code / (PERIODS + 1).pow(b as u32) % (PERIODS + 1))
+                .collect::<Vec<_>>();
+            let ordered = (0..n).all(|b| graph.preds(b).iter().all(|p| periods[*p] <= periods[b]));
+            let fits = (0..PERIODS)
+                .all(|t| periods.iter().filter(|p| **p == t).count() as f64 <= CAPACITY);
+            if ordered && fits {
+                let npv = (0..n)
+                    .filter(|b| periods[*b] < PERIODS)
+                    .map(|b| discount_factor(RATE, periods[b]) * values[b])
+                    .sum::<f64>();
+                best = best.max(npv);
+            }
+        }
+        best
+    }
+
+    //every scheduled block follows its predecessors and every period is within capacity
+    pub(crate) fn assert_feasible(schedule: &Schedule, graph: &PrecedenceGraph) {
+        for (n, ind) in graph.inds().iter().enumerate() {
+            if let Some(t) = schedule.period(*ind) {
+                for p in graph.preds(n) {
+                    let pt = schedule.period(graph.ind(*p));
+                    assert!(pt.is_some_and(|pt| pt <= t), "precedence broken at {ind:?}");
+                }
+            }
+        }
+        for summary in schedule.summaries.iter() {
+            assert!(summary.mined_tonnage <= CAPACITY + 1e-9);
+        }
+    }
+
+    #[test]
+    fn toy_model_optimum() {
+        //one waste block, the richer ore with the rest of its cover, then the other ore with
+        //its last waste block
+        let (mdl, graph, econ) = toy_model();
+        let expected = -discount_factor(RATE, 0)
+            + 2.0 * discount_factor(RATE, 1)
+            + 2.0 * discount_factor(RATE, 2);
+        assert!((brute_force_npv(&mdl, &graph, &econ) - expected).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,104 @@
+use serde::Serialize;
+
+use crate::block::BlockInterface;
+use crate::block_model::BlockModel;
+use crate::economics::EconomicModel;
+use crate::schedule::{discount_factor, Schedule};
+use crate::storage::BlockStorage;
+
+use std::error::Error;
+use std::path::Path;
+
+//cash flow breakdown of one period, costs are positive
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct Cashflow {
+    pub period: usize,
+    pub mined_tonnage: f64,
+    pub processed_tonnage: f64,
+    //recovered metal units
+    pub metal: f64,
+    pub revenue: f64,
+    pub selling_cost: f64,
+    pub mining_cost: f64,
+    pub processing_cost: f64,
+    pub rehandle_cost: f64,
+    pub cash_flow: f64,
+    pub discounted_cash_flow: f64,
+}
+
+impl Schedule {
+    //cash flows of every period with end of period discounting at rate
+    pub fn cashflows<B, S, E>(&self, mdl: &BlockModel<B, S>, rate: f64, econ: &E) -> Vec<Cashflow>
+    where
+        B: BlockInterface,
+        S: BlockStorage<B>,
+        E: EconomicModel<B>,
+    {
+        let mut flows = (0..self.num_periods)
+            .map(|period| Cashflow {
+                period,
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+
+        for (ind, period) in self.periods.iter() {
+            let b = mdl
+                .block(*ind)
+                .expect("scheduled block is missing from the model");
+            let flow = &mut flows[*period];
+            let t = econ.tonnage(b);
+            flow.mined_tonnage += t;
+            flow.mining_cost += t * econ.mining_cost(b);
+            if self.processed.contains(ind) {
+                let metal = t * econ.grade(b) * econ.recovery(b);
+                flow.processed_tonnage += t;
+                flow.metal += metal;
+                flow.revenue += metal * econ.price(b);
+                flow.selling_cost += metal * econ.selling_cost(b);
+                flow.processing_cost += t * econ.processing_cost(b);
+            }
+        }
+
+        for (flow, pile) in flows.iter_mut().zip(self.stockpile.iter()) {
+            flow.processed_tonnage += pile.reclaimed_tonnage;
+            flow.metal += pile.metal;
+            flow.revenue += pile.revenue;
+            flow.selling_cost += pile.selling_cost;
+            flow.processing_cost += pile.processing_cost;
+            flow.rehandle_cost += pile.rehandle_cost;
+        }
+
+        for flow in flows.iter_mut() {
+            flow.cash_flow = flow.revenue
+                - flow.selling_cost
+                - flow.mining_cost
+                - flow.processing_cost
+                - flow.rehandle_cost;
+            flow.discounted_cash_flow = flow.cash_flow * discount_factor(rate, flow.period);
+        }
+        flows
+    }
+
+    //net present value of the schedule with end of period discounting at rate
+    pub fn npv<B, S, E>(&self, mdl: &BlockModel<B, S>, rate: f64, econ: &E) -> f64
+    where
+        B: BlockInterface,
+        S: BlockStorage<B>,
+        E: EconomicModel<B>,
+    {
+        self.cashflows(mdl, rate, econ)
+            .iter()
+            .map(|f| f.discounted_cash_flow)
+            .sum()
+    }
+}
+
+//one row per period
+pub fn write_cashflows<P: AsRef<Path>>(path: P, flows: &[Cashflow]) -> Result<(), Box<dyn Error>> {
+    let mut w = csv::Writer::from_path(path)?;
+    for flow in flows.iter() {
+        w.serialize(flow)?;
+    }
+    w.flush()?;
+    Ok(())
+}
@@ -0,0 +1,74 @@
+use good_lp::{default_solver, variable, Expression, ProblemVariables, Solution, SolverModel};
+
+use crate::block::BlockInterface;
+use crate::block_model::BlockModel;
+use crate::economics::EconomicModel;
+use crate::graph::PrecedenceGraph;
+use crate::schedule::milp::{LinearProgram, SchedulingProblem, Sense, VariableKind};
+use crate::schedule::Schedule;
+use crate::storage::BlockStorage;
+
+use std::error::Error;
+
+impl LinearProgram {
+    //optimal variable values from the default good_lp solver, cbc with the cbc feature and
+    //the pure rust microlp otherwise
+    pub fn solve(&self) -> Result<Vec<f64>, Box<dyn Error>> {
+        let mut problem = ProblemVariables::new();
+        let vars = self
+            .variables
+            .iter()
+            .map(|v| {
+                let def = match v.kind {
+                    VariableKind::Binary => variable().binary(),
+                    VariableKind::Integer => variable().integer().min(v.lower).max(v.upper),
+                    VariableKind::Continuous => variable().min(v.lower).max(v.upper),
+                };
+                problem.add(def.name(v.name.clone()))
+            })
+            .collect::<Vec<_>>();
+
+        let expression = |terms: &[(usize, f64)]| {
+            let mut e = Expression::from(0.0);
+            for (var, coef) in terms.iter() {
+                e.add_mul(*coef, vars[*var]);
+            }
+            e
+        };
+
+        let mut model = problem
+            .maximise(expression(&self.objective))
+            .using(default_solver);
+        for c in self.constraints.iter() {
+            let e = expression(&c.terms);
+            model = model.with(match c.sense {
+                Sense::Le => e.leq(c.rhs),
+                Sense::Ge => e.geq(c.rhs),
+                Sense::Eq => e.eq(c.rhs),
+            });
+        }
+
+        let solution = model.solve()?;
+        Ok(vars.iter().map(|v| solution.value(*v)).collect())
+    }
+}
+
+impl SchedulingProblem {
+    //formulate and solve the scheduling problem in process, practical for a few thousand
+    //blocks and periods combined
+    pub fn solve<B, S, E>(
+        &self,
+        mdl: &BlockModel<B, S>,
+        graph: &PrecedenceGraph,
+        econ: &E,
+    ) -> Result<Schedule, Box<dyn Error>>
+    where
+        B: BlockInterface,
+        S: BlockStorage<B>,
+        E: EconomicModel<B>,
+    {
+        let lp = self.formulate(mdl, graph, econ);
+        let values = lp.solve()?;
+        Ok(self.schedule_from_values(mdl, &lp, &values, econ))
+    }
+}
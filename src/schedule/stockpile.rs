@@ -0,0 +1,189 @@
+use crate::economics::EconomicModel;
+
+use std::collections::VecDeque;
+use std::ops::AddAssign;
+
+//order material leaves a stockpile in
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub enum BlendingPolicy {
+    //oldest material first
+    #[default]
+    Fifo,
+    //fully mixed, every tonne reclaimed has the average grade of the pile
+    AverageGrade,
+}
+
+//ore stored when the plant is full and reclaimed once it has spare capacity
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stockpile {
+    //tonnes held at any time
+    pub capacity: f64,
+    //cost per tonne reclaimed
+    pub rehandle_cost: f64,
+    pub policy: BlendingPolicy,
+}
+
+//stockpile movements of one period, grades are tonnage weighted and NaN without material
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct StockpilePeriod {
+    pub added_tonnage: f64,
+    pub added_grade: f64,
+    pub reclaimed_tonnage: f64,
+    pub reclaimed_grade: f64,
+    pub closing_tonnage: f64,
+    pub closing_grade: f64,
+    //of the reclaimed material
+    pub metal: f64,
+    pub revenue: f64,
+    pub selling_cost: f64,
+    pub processing_cost: f64,
+    pub rehandle_cost: f64,
+}
+
+impl StockpilePeriod {
+    //value realised by processing the reclaimed material
+    pub fn value(&self) -> f64 {
+        self.revenue - self.selling_cost - self.processing_cost - self.rehandle_cost
+    }
+}
+
+//quantities that scale with tonnage, so parcels can be mixed and split exactly
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub(crate) struct Parcel {
+    pub tonnage: f64,
+    //tonnage * grade
+    pub contained: f64,
+    //recovered metal once processed
+    pub metal: f64,
+    pub revenue: f64,
+    pub selling_cost: f64,
+    pub processing_cost: f64,
+}
+
+impl Parcel {
+    pub fn new<B, E: EconomicModel<B>>(econ: &E, block: &B) -> Self {
+        let t = econ.tonnage(block);
+        let metal = t * econ.grade(block) * econ.recovery(block);
+        Self {
+            tonnage: t,
+            contained: t * econ.grade(block),
+            metal,
+            revenue: metal * econ.price(block),
+            selling_cost: metal * econ.selling_cost(block),
+            processing_cost: t * econ.processing_cost(block),
+        }
+    }
+
+    fn scale(&self, f: f64) -> Self {
+        Self {
+            tonnage: self.tonnage * f,
+            contained: self.contained * f,
+            metal: self.metal * f,
+            revenue: self.revenue * f,
+            selling_cost: self.selling_cost * f,
+            processing_cost: self.processing_cost * f,
+        }
+    }
+
+    fn grade(&self) -> f64 {
+        if self.tonnage > 0.0 {
+            self.contained / self.tonnage
+        } else {
+            f64::NAN
+        }
+    }
+}
+
+impl AddAssign for Parcel {
+    fn add_assign(&mut self, other: Self) {
+        self.tonnage += other.tonnage;
+        self.contained += other.contained;
+        self.metal += other.metal;
+        self.revenue += other.revenue;
+        self.selling_cost += other.selling_cost;
+        self.processing_cost += other.processing_cost;
+    }
+}
+
+//material on a stockpile during scheduling
+#[derive(Debug, Clone)]
+pub(crate) struct Pile {
+    stockpile: Stockpile,
+    parcels: VecDeque<Parcel>,
+    tonnage: f64,
+    added: Parcel,
+    reclaimed: Parcel,
+}
+
+impl Pile {
+    pub fn new(stockpile: Stockpile) -> Self {
+        Self {
+            stockpile,
+            parcels: VecDeque::new(),
+            tonnage: 0.0,
+            added: Parcel::default(),
+            reclaimed: Parcel::default(),
+        }
+    }
+
+    pub fn rehandle_cost(&self) -> f64 {
+        self.stockpile.rehandle_cost
+    }
+
+    pub fn has_room(&self, tonnage: f64) -> bool {
+        self.tonnage + tonnage <= self.stockpile.capacity
+    }
+
+    pub fn add(&mut self, parcel: Parcel) {
+        self.tonnage += parcel.tonnage;
+        self.added += parcel;
+        match (self.stockpile.policy, self.parcels.front_mut()) {
+            (BlendingPolicy::AverageGrade, Some(pile)) => *pile += parcel,
+            _ => self.parcels.push_back(parcel),
+        }
+    }
+
+    //take up to tonnage from the pile following its blending policy
+    pub fn reclaim(&mut self, mut tonnage: f64) {
+        while tonnage > 0.0 {
+            let Some(front) = self.parcels.front_mut() else {
+                break;
+            };
+            if front.tonnage <= tonnage {
+                tonnage -= front.tonnage;
+                self.tonnage -= front.tonnage;
+                self.reclaimed += *front;
+                self.parcels.pop_front();
+            } else {
+                let part = front.scale(tonnage / front.tonnage);
+                *front = front.scale(1.0 - tonnage / front.tonnage);
+                self.tonnage -= part.tonnage;
+                self.reclaimed += part;
+                tonnage = 0.0;
+            }
+        }
+    }
+
+    //movements since the last report
+    pub fn report(&mut self) -> StockpilePeriod {
+        let mut closing = Parcel::default();
+        self.parcels.iter().for_each(|p| closing += *p);
+        let (added, reclaimed) = (self.added, self.reclaimed);
+        self.added = Parcel::default();
+        self.reclaimed = Parcel::default();
+
+        StockpilePeriod {
+            added_tonnage: added.tonnage,
+            added_grade: added.grade(),
+            reclaimed_tonnage: reclaimed.tonnage,
+            reclaimed_grade: reclaimed.grade(),
+            closing_tonnage: closing.tonnage,
+            closing_grade: closing.grade(),
+            metal: reclaimed.metal,
+            revenue: reclaimed.revenue,
+            selling_cost: reclaimed.selling_cost,
+            processing_cost: reclaimed.processing_cost,
+            rehandle_cost: reclaimed.tonnage * self.stockpile.rehandle_cost,
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use crate::block::{BlockIndex, BlockInterface};
+use crate::block_model::BlockModel;
+use crate::geometry::surface::ElevationGrid;
+use crate::schedule::Schedule;
+use crate::storage::BlockStorage;
+
+impl Schedule {
+    //top of the material left in each column at the end of period, the top face of the
+    //highest block not yet mined or the floor of a mined out column, for export through
+    //ElevationGrid::to_mesh and to_csv
+    pub fn surface<B, S>(&self, mdl: &BlockModel<B, S>, period: usize) -> ElevationGrid
+    where
+        B: BlockInterface,
+        S: BlockStorage<B>,
+    {
+        let [ni, nj, nk] = mdl.dims();
+        let size = mdl.block_size();
+        let mined = |ind: BlockIndex| self.period(ind).is_some_and(|p| p <= period);
+
+        let mut points = Vec::with_capacity(ni * nj);
+        for i in 0..ni {
+            for j in 0..nj {
+                let present = (0..nk)
+                    .rev()
+                    .map(|k| BlockIndex { i, j, k })
+                    .filter(|ind| mdl.block(*ind).is_some())
+                    .collect::<Vec<_>>();
+                //face offset in blocks from the centroid of the bench
+                let face = match present.iter().find(|ind| !mined(**ind)) {
+                    Some(ind) => Some((ind.k, 0.5)),
+                    None => present.last().map(|ind| (ind.k, -0.5)),
+                };
+
+                points.push(face.map(|(k, offset)| {
+                    let p = mdl.frame().to_world([
+                        i as f32 * size.x_size,
+                        j as f32 * size.y_size,
+                        (k as f32 + offset) * size.z_size,
+                    ]);
+                    [p.x as f64, p.y as f64, p.z as f64]
+                }));
+            }
+        }
+
+        ElevationGrid {
+            dims: [ni, nj],
+            points,
+        }
+    }
+
+    //surface at the end of every period
+    pub fn surfaces<B, S>(&self, mdl: &BlockModel<B, S>) -> Vec<ElevationGrid>
+    where
+        B: BlockInterface,
+        S: BlockStorage<B>,
+    {
+        (0..self.num_periods)
+            .map(|period| self.surface(mdl, period))
+            .collect()
+    }
+}
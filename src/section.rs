@@ -0,0 +1,73 @@
+use crate::block::{BlockCoordinates, BlockIndex, BlockInterface};
+use crate::block_model::BlockModel;
+use crate::storage::BlockStorage;
+
+//block intersecting a section corridor with its position in the section plane
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SectionBlock<'a, B> {
+    pub ind: BlockIndex,
+    pub block: &'a B,
+    //horizontal distance along the section line from the section origin
+    pub along: f64,
+    pub elevation: f64,
+    //signed horizontal distance of the centroid from the plane, positive to the right
+    pub offset: f64,
+}
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface,
+    S: BlockStorage<B>,
+{
+    //blocks overlapping the vertical corridor of total width centred on the plane through
+    //origin striking at azimuth (degrees clockwise from +y), ordered along the section then up
+    pub fn section(
+        &self,
+        origin: BlockCoordinates,
+        azimuth: f32,
+        width: f32,
+    ) -> Vec<SectionBlock<'_, B>> {
+        assert!(width >= 0.0, "section width must not be negative");
+
+        let (sa, ca) = (azimuth as f64).to_radians().sin_cos();
+        let dir = [sa, ca];
+        let normal = [ca, -sa];
+
+        //half footprint of a block measured across the plane
+        let frame = self.frame();
+        let size = self.block_size();
+        let zero = frame.to_world([0.0; 3]);
+        let axis = |offset: [f32; 3]| {
+            let p = frame.to_world(offset);
+            [(p.x - zero.x) as f64, (p.y - zero.y) as f64]
+        };
+        let [ai, aj] = [axis([size.x_size, 0.0, 0.0]), axis([0.0, size.y_size, 0.0])];
+        let reach = (ai[0] * normal[0] + ai[1] * normal[1]).abs() / 2.0
+            + (aj[0] * normal[0] + aj[1] * normal[1]).abs() / 2.0;
+        let half_width = width as f64 / 2.0 + reach;
+
+        let mut blocks = self
+            .indexed_iter()
+            .filter_map(|(ind, block)| {
+                let c = self.index_to_coordinates(ind);
+                let (dx, dy) = ((c.x - origin.x) as f64, (c.y - origin.y) as f64);
+
+                let offset = dx * normal[0] + dy * normal[1];
+                (offset.abs() <= half_width).then_some(SectionBlock {
+                    ind,
+                    block,
+                    along: dx * dir[0] + dy * dir[1],
+                    elevation: c.z as f64,
+                    offset,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        blocks.sort_by(|a, b| {
+            a.along
+                .total_cmp(&b.along)
+                .then(a.elevation.total_cmp(&b.elevation))
+        });
+        blocks
+    }
+}
@@ -0,0 +1,279 @@
+use crate::block::{BlockIndex, BlockInterface};
+use crate::block_model::BlockModel;
+use crate::storage::BlockStorage;
+
+use std::collections::HashSet;
+use std::error::Error;
+use std::fs;
+use std::ops::{BitAnd, BitOr, Sub};
+use std::path::Path;
+
+//set of cells of a model lattice stored as one bit per cell in index order
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Selection {
+    dims: [usize; 3],
+    bits: Vec<u64>,
+}
+
+impl Selection {
+    //empty selection over a lattice
+    pub fn new(dims: [usize; 3]) -> Self {
+        Self {
+            dims,
+            bits: vec![0; (dims.iter().product::<usize>()).div_ceil(64)],
+        }
+    }
+
+    //every cell of the lattice
+    pub fn full(dims: [usize; 3]) -> Self {
+        Self::new(dims).complement()
+    }
+
+    pub fn from_inds<I: IntoIterator<Item = BlockIndex>>(dims: [usize; 3], inds: I) -> Self {
+        let mut sel = Self::new(dims);
+        inds.into_iter().for_each(|ind| {
+            sel.insert(ind);
+        });
+        sel
+    }
+
+    pub fn dims(&self) -> [usize; 3] {
+        self.dims
+    }
+
+    fn num_cells(&self) -> usize {
+        self.dims.iter().product()
+    }
+
+    fn position(&self, ind: BlockIndex) -> usize {
+        let [ni, nj, nk] = self.dims;
+        assert!(
+            ind.i < ni && ind.j < nj && ind.k < nk,
+            "index outside selection"
+        );
+        (ind.i * nj + ind.j) * nk + ind.k
+    }
+
+    fn index(&self, pos: usize) -> BlockIndex {
+        let [_, nj, nk] = self.dims;
+        BlockIndex {
+            i: pos / (nj * nk),
+            j: (pos / nk) % nj,
+            k: pos % nk,
+        }
+    }
+
+    pub fn contains(&self, ind: BlockIndex) -> bool {
+        let [ni, nj, nk] = self.dims;
+        if ind.i >= ni || ind.j >= nj || ind.k >= nk {
+            return false;
+        }
+        let pos = self.position(ind);
+        self.bits[pos / 64] & (1 << (pos % 64)) != 0
+    }
+
+    //true if ind was not already selected, panics if ind is outside the lattice
+    pub fn insert(&mut self, ind: BlockIndex) -> bool {
+        let pos = self.position(ind);
+        let was = self.bits[pos / 64] & (1 << (pos % 64)) != 0;
+        self.bits[pos / 64] |= 1 << (pos % 64);
+        !was
+    }
+
+    //true if ind was selected
+    pub fn remove(&mut self, ind: BlockIndex) -> bool {
+        if !self.contains(ind) {
+            return false;
+        }
+        let pos = self.position(ind);
+        self.bits[pos / 64] &= !(1 << (pos % 64));
+        true
+    }
+
+    pub fn len(&self) -> usize {
+        self.bits.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bits.iter().all(|w| *w == 0)
+    }
+
+    //selected indices in ascending order
+    pub fn iter(&self) -> impl Iterator<Item = BlockIndex> + '_ {
+        self.bits.iter().enumerate().flat_map(move |(w, word)| {
+            let mut word = *word;
+            std::iter::from_fn(move || {
+                if word == 0 {
+                    return None;
+                }
+                let bit = word.trailing_zeros() as usize;
+                word &= word - 1;
+                Some(self.index(w * 64 + bit))
+            })
+        })
+    }
+
+    pub fn to_hash_set(&self) -> HashSet<BlockIndex> {
+        self.iter().collect()
+    }
+
+    fn combine<F: Fn(u64, u64) -> u64>(&self, other: &Self, op: F) -> Self {
+        assert_eq!(self.dims, other.dims, "selections span different lattices");
+        Self {
+            dims: self.dims,
+            bits: self
+                .bits
+                .iter()
+                .zip(other.bits.iter())
+                .map(|(a, b)| op(*a, *b))
+                .collect(),
+        }
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        self.combine(other, |a, b| a | b)
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        self.combine(other, |a, b| a & b)
+    }
+
+    pub fn difference(&self, other: &Self) -> Self {
+        self.combine(other, |a, b| a & !b)
+    }
+
+    pub fn symmetric_difference(&self, other: &Self) -> Self {
+        self.combine(other, |a, b| a ^ b)
+    }
+
+    //unselected cells of the lattice
+    pub fn complement(&self) -> Self {
+        let mut sel = Self {
+            dims: self.dims,
+            bits: self.bits.iter().map(|w| !w).collect(),
+        };
+        //clear the bits past the last cell
+        let tail = self.num_cells() % 64;
+        if let (Some(last), true) = (sel.bits.last_mut(), tail != 0) {
+            *last &= (1 << tail) - 1;
+        }
+        sel
+    }
+
+    //dims on the first line followed by the start and length of each run of selected cells
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn Error>> {
+        let [ni, nj, nk] = self.dims;
+        let mut out = format!("{} {} {}\n", ni, nj, nk);
+
+        let mut run: Option<(usize, usize)> = None;
+        for pos in self.iter().map(|ind| self.position(ind)) {
+            run = match run {
+                Some((start, len)) if start + len == pos => Some((start, len + 1)),
+                Some((start, len)) => {
+                    out.push_str(&format!("{} {}\n", start, len));
+                    Some((pos, 1))
+                }
+                None => Some((pos, 1)),
+            };
+        }
+        if let Some((start, len)) = run {
+            out.push_str(&format!("{} {}\n", start, len));
+        }
+
+        fs::write(path, out)?;
+        Ok(())
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn Error>> {
+        let text = fs::read_to_string(path)?;
+        let mut lines = text.lines();
+
+        let dims = lines
+            .next()
+            .ok_or("empty selection file")?
+            .split_whitespace()
+            .map(|t| t.parse::<usize>())
+            .collect::<Result<Vec<_>, _>>()?;
+        let dims: [usize; 3] = dims.try_into().map_err(|_| "invalid selection dims")?;
+
+        let mut sel = Self::new(dims);
+        for line in lines.filter(|l| !l.trim().is_empty()) {
+            let mut tokens = line.split_whitespace().map(|t| t.parse::<usize>());
+            let start = tokens.next().ok_or("missing run start")??;
+            let len = tokens.next().ok_or("missing run length")??;
+            if start + len > sel.num_cells() {
+                return Err("selection run outside lattice".into());
+            }
+            for pos in start..start + len {
+                sel.bits[pos / 64] |= 1 << (pos % 64);
+            }
+        }
+
+        Ok(sel)
+    }
+}
+
+impl BitOr for &Selection {
+    type Output = Selection;
+
+    fn bitor(self, other: Self) -> Selection {
+        self.union(other)
+    }
+}
+
+impl BitAnd for &Selection {
+    type Output = Selection;
+
+    fn bitand(self, other: Self) -> Selection {
+        self.intersection(other)
+    }
+}
+
+impl Sub for &Selection {
+    type Output = Selection;
+
+    fn sub(self, other: Self) -> Selection {
+        self.difference(other)
+    }
+}
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface,
+    S: BlockStorage<B>,
+{
+    //present blocks satisfying pred
+    pub fn select<F: Fn(&B) -> bool>(&self, pred: F) -> Selection {
+        Selection::from_inds(
+            self.dims(),
+            self.indexed_iter()
+                .filter(|(_, b)| pred(b))
+                .map(|(ind, _)| ind),
+        )
+    }
+
+    //every present block
+    pub fn select_all(&self) -> Selection {
+        self.select(|_| true)
+    }
+
+    //present blocks inside the selection
+    pub fn selected_iter<'a>(
+        &'a self,
+        selection: &'a Selection,
+    ) -> impl Iterator<Item = (BlockIndex, &'a B)> + 'a {
+        self.indexed_iter()
+            .filter(move |(ind, _)| selection.contains(*ind))
+    }
+
+    //copy of the model keeping only selected blocks, for reporting and export
+    pub fn filtered(&self, selection: &Selection) -> Self {
+        let mut storage = S::with_dims(self.dims());
+        for (ind, b) in self.selected_iter(selection) {
+            storage.insert(ind, b.clone());
+        }
+        let mut mdl = Self::from_storage(storage, *self.frame());
+        mdl.categories = self.categories.clone();
+        mdl
+    }
+}
@@ -0,0 +1,226 @@
+use crate::block::{BlockIndex, BlockInterface};
+use crate::block_model::BlockModel;
+use crate::estimation::search::SearchEllipsoid;
+use crate::estimation::Sample;
+use crate::storage::BlockStorage;
+
+use std::collections::BinaryHeap;
+
+//balanced kd-tree over 3d points with k-nearest and radius queries
+//anisotropic trees measure distance with an ellipsoid, in units of its major range
+#[derive(Debug, Clone)]
+pub struct KdTree {
+    //points in metric space, tree order
+    points: Vec<[f64; 3]>,
+    //input position of each tree point
+    ids: Vec<usize>,
+    //rows map world offsets into metric space
+    metric: [[f64; 3]; 3],
+}
+
+//max heap entry for the k nearest search
+#[derive(PartialEq)]
+struct Candidate(f64, usize);
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+impl KdTree {
+    pub fn new(points: &[[f64; 3]]) -> Self {
+        Self::with_metric(points, [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]])
+    }
+
+    //distances follow SearchEllipsoid::anisotropic_distance
+    pub fn anisotropic(points: &[[f64; 3]], ellipsoid: &SearchEllipsoid) -> Self {
+        let axes = ellipsoid.axes();
+        let metric =
+            [0, 1, 2].map(|a| axes[a].map(|c| c * ellipsoid.ranges[0] / ellipsoid.ranges[a]));
+        Self::with_metric(points, metric)
+    }
+
+    pub fn from_samples(samples: &[Sample], ellipsoid: Option<&SearchEllipsoid>) -> Self {
+        let points = samples.iter().map(|s| s.position()).collect::<Vec<_>>();
+        match ellipsoid {
+            Some(e) => Self::anisotropic(&points, e),
+            None => Self::new(&points),
+        }
+    }
+
+    fn with_metric(points: &[[f64; 3]], metric: [[f64; 3]; 3]) -> Self {
+        let mut tree = Self {
+            points: Vec::new(),
+            ids: Vec::new(),
+            metric,
+        };
+
+        let mut items = points
+            .iter()
+            .enumerate()
+            .map(|(n, p)| (tree.transform(*p), n))
+            .collect::<Vec<_>>();
+        Self::build(&mut items, 0);
+
+        (tree.points, tree.ids) = items.into_iter().unzip();
+        tree
+    }
+
+    //median split on axis depth % 3, the median sits at the middle of each range
+    fn build(items: &mut [([f64; 3], usize)], depth: usize) {
+        if items.len() <= 1 {
+            return;
+        }
+        let axis = depth % 3;
+        let mid = items.len() / 2;
+        items.select_nth_unstable_by(mid, |a, b| a.0[axis].total_cmp(&b.0[axis]));
+
+        let (left, right) = items.split_at_mut(mid);
+        Self::build(left, depth + 1);
+        Self::build(&mut right[1..], depth + 1);
+    }
+
+    fn transform(&self, p: [f64; 3]) -> [f64; 3] {
+        self.metric
+            .map(|row| row[0] * p[0] + row[1] * p[1] + row[2] * p[2])
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    fn dist2(a: [f64; 3], b: [f64; 3]) -> f64 {
+        (0..3).map(|d| (a[d] - b[d]).powi(2)).sum()
+    }
+
+    //k closest points as (input position, distance), nearest first
+    pub fn nearest(&self, at: [f64; 3], k: usize) -> Vec<(usize, f64)> {
+        if k == 0 {
+            return Vec::new();
+        }
+        let q = self.transform(at);
+        let mut heap = BinaryHeap::with_capacity(k + 1);
+        self.nearest_in(0, self.len(), 0, q, k, &mut heap);
+
+        let mut found = heap
+            .into_iter()
+            .map(|Candidate(d2, n)| (self.ids[n], d2.sqrt()))
+            .collect::<Vec<_>>();
+        found.sort_by(|a, b| a.1.total_cmp(&b.1));
+        found
+    }
+
+    fn nearest_in(
+        &self,
+        lo: usize,
+        hi: usize,
+        depth: usize,
+        q: [f64; 3],
+        k: usize,
+        heap: &mut BinaryHeap<Candidate>,
+    ) {
+        if lo >= hi {
+            return;
+        }
+        let mid = lo + (hi - lo) / 2;
+        let axis = depth % 3;
+
+        let d2 = Self::dist2(self.points[mid], q);
+        if heap.len() < k {
+            heap.push(Candidate(d2, mid));
+        } else if d2 < heap.peek().unwrap().0 {
+            heap.pop();
+            heap.push(Candidate(d2, mid));
+        }
+
+        let diff = q[axis] - self.points[mid][axis];
+        let (near, far) = if diff < 0.0 {
+            ((lo, mid), (mid + 1, hi))
+        } else {
+            ((mid + 1, hi), (lo, mid))
+        };
+
+        self.nearest_in(near.0, near.1, depth + 1, q, k, heap);
+        if heap.len() < k || diff * diff < heap.peek().unwrap().0 {
+            self.nearest_in(far.0, far.1, depth + 1, q, k, heap);
+        }
+    }
+
+    //points within radius as (input position, distance), nearest first
+    pub fn within(&self, at: [f64; 3], radius: f64) -> Vec<(usize, f64)> {
+        let q = self.transform(at);
+        let mut found = Vec::new();
+        self.within_in(0, self.len(), 0, q, radius * radius, &mut found);
+
+        found.sort_by(|a, b| a.1.total_cmp(&b.1));
+        found
+    }
+
+    fn within_in(
+        &self,
+        lo: usize,
+        hi: usize,
+        depth: usize,
+        q: [f64; 3],
+        r2: f64,
+        found: &mut Vec<(usize, f64)>,
+    ) {
+        if lo >= hi {
+            return;
+        }
+        let mid = lo + (hi - lo) / 2;
+        let axis = depth % 3;
+
+        let d2 = Self::dist2(self.points[mid], q);
+        if d2 <= r2 {
+            found.push((self.ids[mid], d2.sqrt()));
+        }
+
+        let diff = q[axis] - self.points[mid][axis];
+        if diff <= 0.0 || diff * diff <= r2 {
+            self.within_in(lo, mid, depth + 1, q, r2, found);
+        }
+        if diff >= 0.0 || diff * diff <= r2 {
+            self.within_in(mid + 1, hi, depth + 1, q, r2, found);
+        }
+    }
+}
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface,
+    S: BlockStorage<B>,
+{
+    //kd-tree over block centroids, tree point n is the block at inds[n]
+    pub fn centroid_tree(&self, ellipsoid: Option<&SearchEllipsoid>) -> (KdTree, Vec<BlockIndex>) {
+        let mut inds = self.indexed_iter().map(|(ind, _)| ind).collect::<Vec<_>>();
+        inds.sort();
+
+        let points = inds
+            .iter()
+            .map(|ind| {
+                let c = self.index_to_coordinates(*ind);
+                [c.x as f64, c.y as f64, c.z as f64]
+            })
+            .collect::<Vec<_>>();
+
+        let tree = match ellipsoid {
+            Some(e) => KdTree::anisotropic(&points, e),
+            None => KdTree::new(&points),
+        };
+        (tree, inds)
+    }
+}
@@ -0,0 +1,415 @@
+use serde::Serialize;
+
+use crate::block::{BlockInterface, NamedAttribute};
+use crate::block_model::BlockModel;
+use crate::estimation::Sample;
+use crate::selection::Selection;
+use crate::storage::BlockStorage;
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt::Display;
+use std::path::Path;
+
+//value at fraction q of sorted values, interpolated linearly between neighbours, NaN if empty
+pub(crate) fn interpolated_quantile(sorted: &[f64], q: f64) -> f64 {
+    if sorted.is_empty() {
+        return f64::NAN;
+    }
+    let at = q.clamp(0.0, 1.0) * (sorted.len() - 1) as f64;
+    let (lo, hi) = (at.floor() as usize, at.ceil() as usize);
+    sorted[lo] + (sorted[hi] - sorted[lo]) * (at - lo as f64)
+}
+
+//quantiles of weighted values, each value sits at the midpoint of its cumulative weight and
+//quantiles between them are interpolated linearly, NaN values and non positive weights are
+//skipped, q is in 0 to 1
+pub fn weighted_quantiles(values: &[(f64, f64)], q: &[f64]) -> Vec<f64> {
+    let mut values = values
+        .iter()
+        .filter(|(v, w)| !v.is_nan() && *w > 0.0)
+        .copied()
+        .collect::<Vec<_>>();
+    values.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let total = values.iter().map(|(_, w)| w).sum::<f64>();
+    let mut cumulative = 0.0;
+    let positions = values
+        .iter()
+        .map(|(_, w)| {
+            cumulative += w;
+            (cumulative - w / 2.0) / total
+        })
+        .collect::<Vec<_>>();
+
+    q.iter()
+        .map(|q| {
+            if values.is_empty() {
+                return f64::NAN;
+            }
+            let n = positions.partition_point(|p| p < q);
+            if n == 0 {
+                return values[0].0;
+            }
+            if n == values.len() {
+                return values[n - 1].0;
+            }
+            let f = (q - positions[n - 1]) / (positions[n] - positions[n - 1]);
+            values[n - 1].0 + f * (values[n].0 - values[n - 1].0)
+        })
+        .collect()
+}
+
+//bin layout of a histogram
+#[derive(Debug, Clone, PartialEq)]
+pub enum HistogramBins {
+    //equal width bins spanning the values
+    Count(usize),
+    //bins of this width aligned to multiples of it
+    Width(f64),
+    //increasing bin edges, values outside are not binned
+    Edges(Vec<f64>),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct HistogramRow {
+    from: f64,
+    to: f64,
+    count: usize,
+    weight: f64,
+    frequency: f64,
+    cumulative: f64,
+}
+
+//weighted histogram, every bin but the last excludes its upper edge
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    //one more than the number of bins
+    pub edges: Vec<f64>,
+    pub counts: Vec<usize>,
+    pub weights: Vec<f64>,
+    //values outside the edges
+    pub outside: usize,
+}
+
+impl Histogram {
+    //histogram of (value, weight) pairs, NaN values and non positive weights are skipped
+    pub fn new(values: &[(f64, f64)], bins: &HistogramBins) -> Self {
+        let values = values
+            .iter()
+            .filter(|(v, w)| !v.is_nan() && *w > 0.0)
+            .copied()
+            .collect::<Vec<_>>();
+        let (lo, hi) = values
+            .iter()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), (v, _)| {
+                (lo.min(*v), hi.max(*v))
+            });
+
+        let edges = match bins {
+            HistogramBins::Count(n) => {
+                assert!(*n > 0, "histogram needs at least one bin");
+                let (lo, hi) = if lo > hi { (0.0, 1.0) } else { (lo, hi) };
+                let width = if hi > lo { (hi - lo) / *n as f64 } else { 1.0 };
+                (0..=*n).map(|e| lo + e as f64 * width).collect()
+            }
+            HistogramBins::Width(width) => {
+                assert!(*width > 0.0, "histogram bin width must be positive");
+                let (lo, hi) = if lo > hi { (0.0, 0.0) } else { (lo, hi) };
+                let first = (lo / width).floor();
+                let n = ((hi / width).floor() - first) as usize + 1;
+                (0..=n).map(|e| (first + e as f64) * width).collect()
+            }
+            HistogramBins::Edges(edges) => {
+                assert!(
+                    edges.len() >= 2 && edges.windows(2).all(|e| e[0] < e[1]),
+                    "histogram edges must be increasing"
+                );
+                edges.clone()
+            }
+        };
+
+        let n = edges.len() - 1;
+        let mut counts = vec![0; n];
+        let mut weights = vec![0.0; n];
+        let mut outside = 0;
+        for (v, w) in values.iter() {
+            if *v < edges[0] || *v > edges[n] {
+                outside += 1;
+                continue;
+            }
+            let bin = (edges.partition_point(|e| e <= v) - 1).min(n - 1);
+            counts[bin] += 1;
+            weights[bin] += w;
+        }
+
+        Self {
+            edges,
+            counts,
+            weights,
+            outside,
+        }
+    }
+
+    //histogram of sample values, weights such as declustering weights default to one
+    pub fn from_samples(samples: &[Sample], weights: Option<&[f64]>, bins: &HistogramBins) -> Self {
+        Self::new(&weighted_sample_values(samples, weights), bins)
+    }
+
+    pub fn num_bins(&self) -> usize {
+        self.counts.len()
+    }
+
+    pub fn total_weight(&self) -> f64 {
+        self.weights.iter().sum()
+    }
+
+    //share of the binned weight in each bin
+    pub fn frequencies(&self) -> Vec<f64> {
+        let total = self.total_weight();
+        self.weights.iter().map(|w| w / total).collect()
+    }
+
+    //share of the binned weight up to the end of each bin
+    pub fn cumulative(&self) -> Vec<f64> {
+        let mut sum = 0.0;
+        self.frequencies()
+            .into_iter()
+            .map(|f| {
+                sum += f;
+                sum
+            })
+            .collect()
+    }
+
+    //one row per bin
+    pub fn to_csv<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn Error>> {
+        let mut w = csv::Writer::from_path(path)?;
+        let (frequencies, cumulative) = (self.frequencies(), self.cumulative());
+        for n in 0..self.num_bins() {
+            w.serialize(HistogramRow {
+                from: self.edges[n],
+                to: self.edges[n + 1],
+                count: self.counts[n],
+                weight: self.weights[n],
+                frequency: frequencies[n],
+                cumulative: cumulative[n],
+            })?;
+        }
+        w.flush()?;
+        Ok(())
+    }
+}
+
+fn weighted_sample_values(samples: &[Sample], weights: Option<&[f64]>) -> Vec<(f64, f64)> {
+    if let Some(weights) = weights {
+        assert!(
+            weights.len() == samples.len(),
+            "one weight is needed per sample"
+        );
+    }
+    samples
+        .iter()
+        .enumerate()
+        .map(|(n, s)| (s.value, weights.map_or(1.0, |w| w[n])))
+        .collect()
+}
+
+//weighted quantiles of sample values, weights default to one
+pub fn sample_quantiles(samples: &[Sample], weights: Option<&[f64]>, q: &[f64]) -> Vec<f64> {
+    weighted_quantiles(&weighted_sample_values(samples, weights), q)
+}
+
+//statistics of one attribute over the blocks of one domain, everything but count and
+//tonnes is NaN when the domain has no valid values
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DomainStats<D> {
+    pub domain: D,
+    pub attribute: String,
+    //blocks with a non NaN value
+    pub count: usize,
+    pub tonnes: f64,
+    pub mean: f64,
+    //tonnage weighted mean
+    pub weighted_mean: f64,
+    pub min: f64,
+    pub max: f64,
+    pub std_dev: f64,
+    //one value per requested percentile
+    pub percentiles: Vec<f64>,
+}
+
+//rows sorted by domain then in attribute order
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StatsTable<D> {
+    //requested percentiles, 0 to 100
+    pub percentiles: Vec<f64>,
+    pub rows: Vec<DomainStats<D>>,
+}
+
+impl<D: PartialEq> StatsTable<D> {
+    pub fn get(&self, domain: &D, attribute: &str) -> Option<&DomainStats<D>> {
+        self.rows
+            .iter()
+            .find(|r| r.domain == *domain && r.attribute == attribute)
+    }
+}
+
+impl<D: Display> StatsTable<D> {
+    //one row per domain and attribute, with a p<percentile> column per percentile
+    pub fn to_csv<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn Error>> {
+        let mut w = csv::Writer::from_path(path)?;
+        let mut header = [
+            "domain",
+            "attribute",
+            "count",
+            "tonnes",
+            "mean",
+            "weighted_mean",
+            "min",
+            "max",
+            "std_dev",
+        ]
+        .map(String::from)
+        .to_vec();
+        header.extend(self.percentiles.iter().map(|p| format!("p{p}")));
+        w.write_record(&header)?;
+
+        for r in self.rows.iter() {
+            let mut record = vec![r.domain.to_string(), r.attribute.clone()];
+            record.push(r.count.to_string());
+            record.extend(
+                [r.tonnes, r.mean, r.weighted_mean, r.min, r.max, r.std_dev]
+                    .iter()
+                    .chain(r.percentiles.iter())
+                    .map(|v| v.to_string()),
+            );
+            w.write_record(&record)?;
+        }
+        w.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "json")]
+impl<D: Serialize> StatsTable<D> {
+    pub fn to_json<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn Error>> {
+        let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+}
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface,
+    S: BlockStorage<B>,
+{
+    //(attribute, weight) of the selected blocks
+    fn weighted_values<F, W>(&self, selection: &Selection, attr: F, weight: W) -> Vec<(f64, f64)>
+    where
+        F: Fn(&B) -> f64,
+        W: Fn(&B) -> f64,
+    {
+        assert!(
+            selection.dims() == self.dims(),
+            "selection dims do not match the model"
+        );
+        self.selected_iter(selection)
+            .map(|(_, b)| (attr(b), weight(b)))
+            .collect()
+    }
+
+    //histogram of attr over the selected blocks, weight is e.g. tonnage or one per block
+    pub fn histogram<F, W>(
+        &self,
+        selection: &Selection,
+        attr: F,
+        bins: &HistogramBins,
+        weight: W,
+    ) -> Histogram
+    where
+        F: Fn(&B) -> f64,
+        W: Fn(&B) -> f64,
+    {
+        Histogram::new(&self.weighted_values(selection, attr, weight), bins)
+    }
+
+    //weighted quantiles of attr over the selected blocks, q is in 0 to 1
+    pub fn quantiles<F, W>(&self, selection: &Selection, attr: F, weight: W, q: &[f64]) -> Vec<f64>
+    where
+        F: Fn(&B) -> f64,
+        W: Fn(&B) -> f64,
+    {
+        weighted_quantiles(&self.weighted_values(selection, attr, weight), q)
+    }
+
+    //statistics of values in every domain, tonnage weights the weighted mean, e.g.
+    //DensityModel::tonnage, NaN values are skipped, percentiles are in 0 to 100
+    pub fn stats_by<D, K, T>(
+        &self,
+        domain: K,
+        values: &[NamedAttribute<B>],
+        tonnage: T,
+        percentiles: &[f64],
+    ) -> StatsTable<D>
+    where
+        D: Ord + Clone,
+        K: Fn(&B) -> D,
+        T: Fn(&B) -> f64,
+    {
+        assert!(
+            percentiles.iter().all(|p| (0.0..=100.0).contains(p)),
+            "percentiles must be between 0 and 100"
+        );
+
+        //(value, tonnes) per domain and attribute
+        let mut groups: BTreeMap<D, Vec<Vec<(f64, f64)>>> = BTreeMap::new();
+        for (_, b) in self.indexed_iter() {
+            let t = tonnage(b);
+            let group = groups
+                .entry(domain(b))
+                .or_insert_with(|| vec![Vec::new(); values.len()]);
+            for (g, (_, attr)) in group.iter_mut().zip(values.iter()) {
+                g.push((attr(b), t));
+            }
+        }
+
+        let mut rows = Vec::new();
+        for (d, group) in groups.into_iter() {
+            for (mut g, (name, _)) in group.into_iter().zip(values.iter()) {
+                let tonnes = g.iter().map(|(_, t)| t).sum::<f64>();
+                g.retain(|(v, _)| !v.is_nan());
+                g.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+                let n = g.len() as f64;
+                let mean = g.iter().map(|(v, _)| v).sum::<f64>() / n;
+                let weight = g.iter().map(|(_, t)| t).sum::<f64>();
+                let weighted_mean = g.iter().map(|(v, t)| v * t).sum::<f64>() / weight;
+                let std_dev = (g.iter().map(|(v, _)| (v - mean).powi(2)).sum::<f64>() / n).sqrt();
+                let sorted = g.iter().map(|(v, _)| *v).collect::<Vec<_>>();
+
+                rows.push(DomainStats {
+                    domain: d.clone(),
+                    attribute: name.to_string(),
+                    count: g.len(),
+                    tonnes,
+                    mean,
+                    weighted_mean,
+                    min: sorted.first().copied().unwrap_or(f64::NAN),
+                    max: sorted.last().copied().unwrap_or(f64::NAN),
+                    std_dev,
+                    percentiles: percentiles
+                        .iter()
+                        .map(|p| interpolated_quantile(&sorted, p / 100.0))
+                        .collect(),
+                });
+            }
+        }
+
+        StatsTable {
+            percentiles: percentiles.to_vec(),
+            rows,
+        }
+    }
+}
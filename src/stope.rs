@@ -0,0 +1,218 @@
+use ndarray::Array3;
+use rayon::prelude::*;
+
+use crate::block::{BlockIndex, BlockInterface};
+use crate::block_model::BlockModel;
+use crate::selection::Selection;
+use crate::storage::BlockStorage;
+
+//box of blocks mined as one stope
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stope {
+    //lowest corner of the box
+    pub min: BlockIndex,
+    //blocks along each axis
+    pub size: [usize; 3],
+    pub value: f64,
+}
+
+impl Stope {
+    pub fn contains(&self, ind: BlockIndex) -> bool {
+        let (p, lo) = ([ind.i, ind.j, ind.k], [self.min.i, self.min.j, self.min.k]);
+        (0..3).all(|a| p[a] >= lo[a] && p[a] < lo[a] + self.size[a])
+    }
+
+    pub fn inds(&self) -> impl Iterator<Item = BlockIndex> + '_ {
+        let [si, sj, sk] = self.size;
+        let m = self.min;
+        (0..si).flat_map(move |i| {
+            (0..sj).flat_map(move |j| {
+                (0..sk).map(move |k| BlockIndex {
+                    i: m.i + i,
+                    j: m.j + j,
+                    k: m.k + k,
+                })
+            })
+        })
+    }
+
+    pub fn selection(&self, dims: [usize; 3]) -> Selection {
+        Selection::from_inds(dims, self.inds())
+    }
+
+    //true if the boxes are closer than pillar blocks along every axis
+    pub(crate) fn conflicts(&self, other: &Stope, pillar: [usize; 3]) -> bool {
+        let (a, b) = (
+            [self.min.i, self.min.j, self.min.k],
+            [other.min.i, other.min.j, other.min.k],
+        );
+        (0..3).all(|d| {
+            a[d] < b[d] + other.size[d] + pillar[d] && b[d] < a[d] + self.size[d] + pillar[d]
+        })
+    }
+}
+
+//accepted stopes, by decreasing value
+#[derive(Debug, Clone, PartialEq)]
+pub struct StopeLayout {
+    pub dims: [usize; 3],
+    pub stopes: Vec<Stope>,
+}
+
+impl StopeLayout {
+    pub fn value(&self) -> f64 {
+        self.stopes.iter().map(|s| s.value).sum()
+    }
+
+    //one selection per stope
+    pub fn selections(&self) -> Vec<Selection> {
+        self.stopes.iter().map(|s| s.selection(self.dims)).collect()
+    }
+
+    //every stoped block
+    pub fn selection(&self) -> Selection {
+        Selection::from_inds(self.dims, self.stopes.iter().flat_map(|s| s.inds()))
+    }
+
+    //position of the stope holding ind
+    pub fn stope(&self, ind: BlockIndex) -> Option<usize> {
+        self.stopes.iter().position(|s| s.contains(ind))
+    }
+}
+
+//floating stope optimizer, the underground counterpart of the floating cone, every box of
+//blocks between the minimum and maximum stope dimensions is valued from each seed block, the
+//best shape of each seed is kept and seeds are accepted by decreasing value while they keep
+//the pillar spacing from accepted stopes, dimensions are in world units
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FloatingStope {
+    pub min_size: [f64; 3],
+    pub max_size: [f64; 3],
+    //solid rock left between stopes along each axis
+    pub pillar: [f64; 3],
+    //stopes must be worth more than this
+    pub min_value: f64,
+}
+
+impl FloatingStope {
+    pub fn new(min_size: [f64; 3], max_size: [f64; 3]) -> Self {
+        assert!(
+            (0..3).all(|a| min_size[a] > 0.0 && min_size[a] <= max_size[a]),
+            "stope sizes must be positive with min not above max"
+        );
+        Self {
+            min_size,
+            max_size,
+            pillar: [0.0; 3],
+            min_value: 0.0,
+        }
+    }
+
+    pub fn with_pillar(mut self, pillar: [f64; 3]) -> Self {
+        self.pillar = pillar;
+        self
+    }
+
+    pub fn with_min_value(mut self, min_value: f64) -> Self {
+        self.min_value = min_value;
+        self
+    }
+}
+
+//inclusive prefix sums with a zero border, the sum over a box is read from 8 corners
+fn prefix_sums(values: &Array3<f64>) -> Array3<f64> {
+    let d = values.dim();
+    let mut sums = Array3::zeros((d.0 + 1, d.1 + 1, d.2 + 1));
+    for i in 0..d.0 {
+        for j in 0..d.1 {
+            for k in 0..d.2 {
+                sums[[i + 1, j + 1, k + 1]] = values[[i, j, k]] + sums[[i, j + 1, k + 1]]
+                    - sums[[i, j, k + 1]]
+                    + sums[[i + 1, j, k + 1]]
+                    - sums[[i + 1, j, k]]
+                    + sums[[i + 1, j + 1, k]]
+                    - sums[[i, j + 1, k]]
+                    + sums[[i, j, k]];
+            }
+        }
+    }
+    sums
+}
+
+fn box_sum(sums: &Array3<f64>, lo: [usize; 3], size: [usize; 3]) -> f64 {
+    let hi = [lo[0] + size[0], lo[1] + size[1], lo[2] + size[2]];
+    let mut total = 0.0;
+    for corner in 0..8 {
+        let pick = |a: usize| if corner >> a & 1 == 1 { hi[a] } else { lo[a] };
+        let sign = if (corner as u32).count_ones() % 2 == 1 {
+            -1.0
+        } else {
+            1.0
+        };
+        total -= sign * sums[[pick(0), pick(1), pick(2)]];
+    }
+    total
+}
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface + Sync,
+    S: BlockStorage<B> + Sync,
+{
+    //stopes of blocks valued by value, stopes never include missing blocks
+    pub fn floating_stopes<F>(&self, optimizer: &FloatingStope, value: F) -> StopeLayout
+    where
+        F: Fn(&B) -> f64 + Sync,
+    {
+        let dims = self.dims();
+        let size = self.block_size();
+        let size = [size.x_size, size.y_size, size.z_size].map(|s| s as f64);
+        let blocks = |w: f64, s: f64| ((w / s) - 1e-9).ceil().max(0.0) as usize;
+        let min = [0, 1, 2].map(|a| blocks(optimizer.min_size[a], size[a]).max(1));
+        let max = [0, 1, 2].map(|a| ((optimizer.max_size[a] / size[a]) + 1e-9).floor() as usize);
+        let max = [0, 1, 2].map(|a| max[a].max(min[a]).min(dims[a]));
+        let pillar = [0, 1, 2].map(|a| blocks(optimizer.pillar[a], size[a]));
+
+        let mut values = Array3::zeros(dims);
+        let mut missing = Array3::from_elem(dims, 1.0);
+        for (ind, b) in self.indexed_iter() {
+            values[[ind.i, ind.j, ind.k]] = value(b);
+            missing[[ind.i, ind.j, ind.k]] = 0.0;
+        }
+        let (values, missing) = (prefix_sums(&values), prefix_sums(&missing));
+
+        let shapes = (min[0]..=max[0])
+            .flat_map(|i| {
+                (min[1]..=max[1]).flat_map(move |j| (min[2]..=max[2]).map(move |k| [i, j, k]))
+            })
+            .collect::<Vec<_>>();
+
+        //best shape floated from every seed corner
+        let mut candidates = self
+            .par_indexed_iter()
+            .filter_map(|(seed, _)| {
+                let lo = [seed.i, seed.j, seed.k];
+                shapes
+                    .iter()
+                    .filter(|s| (0..3).all(|a| lo[a] + s[a] <= dims[a]))
+                    .filter(|s| box_sum(&missing, lo, **s) < 0.5)
+                    .map(|s| Stope {
+                        min: seed,
+                        size: *s,
+                        value: box_sum(&values, lo, *s),
+                    })
+                    .filter(|s| s.value > optimizer.min_value)
+                    .max_by(|a, b| a.value.total_cmp(&b.value))
+            })
+            .collect::<Vec<_>>();
+        candidates.sort_by(|a, b| b.value.total_cmp(&a.value).then(a.min.cmp(&b.min)));
+
+        let mut stopes: Vec<Stope> = Vec::new();
+        for c in candidates {
+            if stopes.iter().all(|s| !s.conflicts(&c, pillar)) {
+                stopes.push(c);
+            }
+        }
+        StopeLayout { dims, stopes }
+    }
+}
@@ -0,0 +1,241 @@
+use ndarray::{Array3, ArrayBase, Zip};
+use rayon::prelude::*;
+
+use crate::block::BlockIndex;
+
+use std::collections::HashMap;
+
+//required interface for the container holding the blocks of a blockmodel
+pub trait BlockStorage<B> {
+    type IntoBlocks: Iterator<Item = (BlockIndex, B)>;
+
+    //empty storage spanning the given i, j, k dimensions
+    fn with_dims(dims: [usize; 3]) -> Self;
+
+    //i, j, k dimensions of the storage
+    fn dims(&self) -> [usize; 3];
+
+    //block at index, panics if index is outside dims (dense layouts)
+    fn get(&self, ind: BlockIndex) -> Option<&B>;
+    fn get_mut(&mut self, ind: BlockIndex) -> Option<&mut B>;
+
+    //place block at index, returning the block previously stored there
+    fn insert(&mut self, ind: BlockIndex, block: B) -> Option<B>;
+    fn remove(&mut self, ind: BlockIndex) -> Option<B>;
+
+    //present blocks with their indices
+    fn iter<'a>(&'a self) -> impl Iterator<Item = (BlockIndex, &'a B)>
+    where
+        B: 'a;
+
+    fn iter_mut<'a>(&'a mut self) -> impl Iterator<Item = (BlockIndex, &'a mut B)>
+    where
+        B: 'a;
+
+    //consume storage, yielding present blocks with their indices
+    fn into_blocks(self) -> Self::IntoBlocks;
+
+    //present blocks with their indices, in parallel
+    fn par_iter<'a>(&'a self) -> impl ParallelIterator<Item = (BlockIndex, &'a B)>
+    where
+        B: Sync + 'a;
+
+    fn par_iter_mut<'a>(&'a mut self) -> impl ParallelIterator<Item = (BlockIndex, &'a mut B)>
+    where
+        B: Send + 'a;
+
+    //number of present blocks
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+//dense layout, every cell of the bounding box is allocated
+impl<B: Clone> BlockStorage<B> for Array3<Option<B>> {
+    type IntoBlocks = DenseIntoBlocks<B>;
+
+    fn with_dims(dims: [usize; 3]) -> Self {
+        Array3::from_elem(dims, None)
+    }
+
+    fn dims(&self) -> [usize; 3] {
+        let shape = self.shape();
+        [shape[0], shape[1], shape[2]]
+    }
+
+    fn get(&self, ind: BlockIndex) -> Option<&B> {
+        self[[ind.i, ind.j, ind.k]].as_ref()
+    }
+
+    fn get_mut(&mut self, ind: BlockIndex) -> Option<&mut B> {
+        self[[ind.i, ind.j, ind.k]].as_mut()
+    }
+
+    fn insert(&mut self, ind: BlockIndex, block: B) -> Option<B> {
+        self[[ind.i, ind.j, ind.k]].replace(block)
+    }
+
+    fn remove(&mut self, ind: BlockIndex) -> Option<B> {
+        self[[ind.i, ind.j, ind.k]].take()
+    }
+
+    fn iter<'a>(&'a self) -> impl Iterator<Item = (BlockIndex, &'a B)>
+    where
+        B: 'a,
+    {
+        self.indexed_iter()
+            .filter_map(|((i, j, k), b)| b.as_ref().map(|b| (BlockIndex { i, j, k }, b)))
+    }
+
+    fn iter_mut<'a>(&'a mut self) -> impl Iterator<Item = (BlockIndex, &'a mut B)>
+    where
+        B: 'a,
+    {
+        self.indexed_iter_mut()
+            .filter_map(|((i, j, k), b)| b.as_mut().map(|b| (BlockIndex { i, j, k }, b)))
+    }
+
+    fn into_blocks(self) -> Self::IntoBlocks {
+        let dims = BlockStorage::dims(&self);
+
+        //raw storage is in logical order only for standard layout
+        let cells = if self.is_standard_layout() {
+            self.into_raw_vec()
+        } else {
+            self.as_standard_layout().into_owned().into_raw_vec()
+        };
+
+        DenseIntoBlocks {
+            dims,
+            pos: 0,
+            cells: cells.into_iter(),
+        }
+    }
+
+    fn par_iter<'a>(&'a self) -> impl ParallelIterator<Item = (BlockIndex, &'a B)>
+    where
+        B: Sync + 'a,
+    {
+        Zip::indexed(self)
+            .into_par_iter()
+            .filter_map(|((i, j, k), b)| b.as_ref().map(|b| (BlockIndex { i, j, k }, b)))
+    }
+
+    fn par_iter_mut<'a>(&'a mut self) -> impl ParallelIterator<Item = (BlockIndex, &'a mut B)>
+    where
+        B: Send + 'a,
+    {
+        Zip::indexed(self)
+            .into_par_iter()
+            .filter_map(|((i, j, k), b)| b.as_mut().map(|b| (BlockIndex { i, j, k }, b)))
+    }
+
+    fn len(&self) -> usize {
+        ArrayBase::iter(self).filter(|b| b.is_some()).count()
+    }
+}
+
+//by-value iterator over the present cells of a dense layout, in logical order
+pub struct DenseIntoBlocks<B> {
+    dims: [usize; 3],
+    pos: usize,
+    cells: std::vec::IntoIter<Option<B>>,
+}
+
+impl<B> Iterator for DenseIntoBlocks<B> {
+    type Item = (BlockIndex, B);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for cell in self.cells.by_ref() {
+            let pos = self.pos;
+            self.pos += 1;
+
+            if let Some(b) = cell {
+                let [_, nj, nk] = self.dims;
+                let ind = BlockIndex {
+                    i: pos / (nj * nk),
+                    j: (pos / nk) % nj,
+                    k: pos % nk,
+                };
+                return Some((ind, b));
+            }
+        }
+        None
+    }
+}
+
+//sparse layout, only present blocks are stored
+#[derive(Debug, Clone, PartialEq)]
+pub struct SparseStorage<B> {
+    dims: [usize; 3],
+    blocks: HashMap<BlockIndex, B>,
+}
+
+impl<B> BlockStorage<B> for SparseStorage<B> {
+    type IntoBlocks = std::collections::hash_map::IntoIter<BlockIndex, B>;
+
+    fn with_dims(dims: [usize; 3]) -> Self {
+        Self {
+            dims,
+            blocks: HashMap::new(),
+        }
+    }
+
+    fn dims(&self) -> [usize; 3] {
+        self.dims
+    }
+
+    fn get(&self, ind: BlockIndex) -> Option<&B> {
+        self.blocks.get(&ind)
+    }
+
+    fn get_mut(&mut self, ind: BlockIndex) -> Option<&mut B> {
+        self.blocks.get_mut(&ind)
+    }
+
+    fn insert(&mut self, ind: BlockIndex, block: B) -> Option<B> {
+        self.blocks.insert(ind, block)
+    }
+
+    fn remove(&mut self, ind: BlockIndex) -> Option<B> {
+        self.blocks.remove(&ind)
+    }
+
+    fn iter<'a>(&'a self) -> impl Iterator<Item = (BlockIndex, &'a B)>
+    where
+        B: 'a,
+    {
+        self.blocks.iter().map(|(ind, b)| (*ind, b))
+    }
+
+    fn iter_mut<'a>(&'a mut self) -> impl Iterator<Item = (BlockIndex, &'a mut B)>
+    where
+        B: 'a,
+    {
+        self.blocks.iter_mut().map(|(ind, b)| (*ind, b))
+    }
+
+    fn into_blocks(self) -> Self::IntoBlocks {
+        self.blocks.into_iter()
+    }
+
+    fn par_iter<'a>(&'a self) -> impl ParallelIterator<Item = (BlockIndex, &'a B)>
+    where
+        B: Sync + 'a,
+    {
+        self.blocks.par_iter().map(|(ind, b)| (*ind, b))
+    }
+
+    fn par_iter_mut<'a>(&'a mut self) -> impl ParallelIterator<Item = (BlockIndex, &'a mut B)>
+    where
+        B: Send + 'a,
+    {
+        self.blocks.par_iter_mut().map(|(ind, b)| (*ind, b))
+    }
+
+    fn len(&self) -> usize {
+        self.blocks.len()
+    }
+}
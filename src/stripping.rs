@@ -0,0 +1,204 @@
+use serde::Serialize;
+
+use crate::accumulation::strip_ratio;
+use crate::block::{BlockIndex, BlockInterface};
+use crate::block_model::BlockModel;
+use crate::estimation::classification::ResourceClass;
+use crate::pit::nested::NestedShells;
+use crate::pit::pushback::Pushbacks;
+use crate::storage::BlockStorage;
+
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::path::Path;
+
+//rule calling a block ore, everything else mined is waste
+pub enum OreDefinition<'a, B> {
+    //grade at or above the cutoff
+    Cutoff {
+        grade: &'a (dyn Fn(&B) -> f64 + Sync),
+        cutoff: f64,
+    },
+    //class at least as confident as min_class, blocks absent from classes are unclassified
+    Classification {
+        classes: &'a HashMap<BlockIndex, ResourceClass>,
+        min_class: ResourceClass,
+    },
+    //classed at least min_class and at or above the cutoff, as for reserves
+    ClassifiedCutoff {
+        classes: &'a HashMap<BlockIndex, ResourceClass>,
+        min_class: ResourceClass,
+        grade: &'a (dyn Fn(&B) -> f64 + Sync),
+        cutoff: f64,
+    },
+}
+
+impl<B> OreDefinition<'_, B> {
+    pub fn is_ore(&self, ind: BlockIndex, block: &B) -> bool {
+        let classed = |classes: &HashMap<BlockIndex, ResourceClass>, min: ResourceClass| {
+            classes
+                .get(&ind)
+                .copied()
+                .unwrap_or(ResourceClass::Unclassified)
+                <= min
+        };
+        match self {
+            OreDefinition::Cutoff { grade, cutoff } => grade(block) >= *cutoff,
+            OreDefinition::Classification { classes, min_class } => classed(classes, *min_class),
+            OreDefinition::ClassifiedCutoff {
+                classes,
+                min_class,
+                grade,
+                cutoff,
+            } => classed(classes, *min_class) && grade(block) >= *cutoff,
+        }
+    }
+}
+
+//one row of a strip ratio table, group is the pushback or shell increment
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct StripRow {
+    pub group: usize,
+    pub bench: usize,
+    pub num_blocks: usize,
+    pub ore_tonnes: f64,
+    pub waste_tonnes: f64,
+    pub strip_ratio: f64,
+}
+
+impl StripRow {
+    fn new(group: usize, bench: usize) -> Self {
+        Self {
+            group,
+            bench,
+            num_blocks: 0,
+            ore_tonnes: 0.0,
+            waste_tonnes: 0.0,
+            strip_ratio: f64::NAN,
+        }
+    }
+
+    fn add(&mut self, other: &StripRow) {
+        self.num_blocks += other.num_blocks;
+        self.ore_tonnes += other.ore_tonnes;
+        self.waste_tonnes += other.waste_tonnes;
+        self.strip_ratio = strip_ratio(self.ore_tonnes, self.waste_tonnes);
+    }
+}
+
+//ore and waste of every group and bench with blocks, sorted by group then bench
+#[derive(Debug, Clone, PartialEq)]
+pub struct StripReport {
+    pub rows: Vec<StripRow>,
+}
+
+impl StripReport {
+    //totals over rows keyed by key, the other key column of the result is 0
+    fn totals<K: Fn(&StripRow) -> (usize, usize)>(&self, key: K) -> Vec<StripRow> {
+        let mut totals: BTreeMap<(usize, usize), StripRow> = BTreeMap::new();
+        for row in self.rows.iter() {
+            let (group, bench) = key(row);
+            totals
+                .entry((group, bench))
+                .or_insert_with(|| StripRow::new(group, bench))
+                .add(row);
+        }
+        totals.into_values().collect()
+    }
+
+    //one row per group over all its benches
+    pub fn by_group(&self) -> Vec<StripRow> {
+        self.totals(|r| (r.group, 0))
+    }
+
+    //one row per bench over all groups
+    pub fn by_bench(&self) -> Vec<StripRow> {
+        self.totals(|r| (0, r.bench))
+    }
+
+    pub fn total(&self) -> StripRow {
+        let mut total = StripRow::new(0, 0);
+        for row in self.rows.iter() {
+            total.add(row);
+        }
+        total
+    }
+
+    pub fn to_csv<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn Error>> {
+        let mut w = csv::Writer::from_path(path)?;
+        for row in self.rows.iter() {
+            w.serialize(row)?;
+        }
+        w.flush()?;
+        Ok(())
+    }
+}
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface,
+    S: BlockStorage<B>,
+{
+    //ore and waste tonnes of the blocks in groups per group and bench, blocks absent from
+    //groups are not mined
+    pub fn strip_ratios<T>(
+        &self,
+        groups: &HashMap<BlockIndex, usize>,
+        ore: &OreDefinition<B>,
+        tonnage: T,
+    ) -> StripReport
+    where
+        T: Fn(&B) -> f64,
+    {
+        let mut rows: BTreeMap<(usize, usize), StripRow> = BTreeMap::new();
+        for (ind, group) in groups.iter() {
+            let Some(b) = self.block(*ind) else {
+                continue;
+            };
+            let row = rows
+                .entry((*group, ind.k))
+                .or_insert_with(|| StripRow::new(*group, ind.k));
+            row.num_blocks += 1;
+            if ore.is_ore(*ind, b) {
+                row.ore_tonnes += tonnage(b);
+            } else {
+                row.waste_tonnes += tonnage(b);
+            }
+        }
+        let rows = rows
+            .into_values()
+            .map(|mut r| {
+                r.strip_ratio = strip_ratio(r.ore_tonnes, r.waste_tonnes);
+                r
+            })
+            .collect();
+        StripReport { rows }
+    }
+
+    //strip ratios per pushback and bench
+    pub fn pushback_strip_ratios<T>(
+        &self,
+        pushbacks: &Pushbacks,
+        ore: &OreDefinition<B>,
+        tonnage: T,
+    ) -> StripReport
+    where
+        T: Fn(&B) -> f64,
+    {
+        self.strip_ratios(&pushbacks.phases, ore, tonnage)
+    }
+
+    //strip ratios per shell increment and bench, increment n holds the blocks first mined
+    //by shell n
+    pub fn shell_strip_ratios<T>(
+        &self,
+        shells: &NestedShells,
+        ore: &OreDefinition<B>,
+        tonnage: T,
+    ) -> StripReport
+    where
+        T: Fn(&B) -> f64,
+    {
+        self.strip_ratios(&shells.shells, ore, tonnage)
+    }
+}
@@ -0,0 +1,93 @@
+use crate::block::{BlockIndex, BlockInterface};
+use crate::block_model::{BlockDependenceInterface, BlockModel};
+use crate::error::BlockModelError;
+use crate::pit::ClosureGraph;
+use crate::storage::BlockStorage;
+
+use std::collections::VecDeque;
+
+//ordering of the present blocks in which every block follows all of its predecessors
+//computed once from a dependence and reused across traversals
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TopologicalOrder {
+    order: Vec<BlockIndex>,
+}
+
+impl TopologicalOrder {
+    //fails if the dependence contains a cycle among present blocks
+    pub fn new<B, S, D>(mdl: &BlockModel<B, S>, dep: &D) -> Result<Self, BlockModelError>
+    where
+        B: BlockInterface,
+        S: BlockStorage<B>,
+        D: BlockDependenceInterface,
+    {
+        let graph = ClosureGraph::new(mdl, dep, |_| 0.0);
+
+        let mut succs = vec![Vec::new(); graph.len()];
+        let mut remaining = vec![0; graph.len()];
+        for (n, preds) in graph.preds.iter().enumerate() {
+            remaining[n] = preds.len();
+            preds.iter().for_each(|p| succs[*p].push(n));
+        }
+
+        //kahn's algorithm, ties broken by index order
+        let mut queue = (0..graph.len())
+            .filter(|n| remaining[*n] == 0)
+            .collect::<VecDeque<_>>();
+        let mut order = Vec::with_capacity(graph.len());
+        while let Some(n) = queue.pop_front() {
+            order.push(graph.inds[n]);
+            for &s in succs[n].iter() {
+                remaining[s] -= 1;
+                if remaining[s] == 0 {
+                    queue.push_back(s);
+                }
+            }
+        }
+
+        if let Some(n) = remaining.iter().position(|r| *r > 0) {
+            return Err(BlockModelError::CyclicPrecedence { ind: graph.inds[n] });
+        }
+
+        Ok(Self { order })
+    }
+
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+
+    pub fn inds(&self) -> &[BlockIndex] {
+        &self.order
+    }
+
+    //blocks of mdl in order, blocks removed since the order was computed are skipped
+    pub fn iter<'a, B, S>(
+        &'a self,
+        mdl: &'a BlockModel<B, S>,
+    ) -> impl Iterator<Item = (BlockIndex, &'a B)> + 'a
+    where
+        B: BlockInterface,
+        S: BlockStorage<B>,
+    {
+        self.order
+            .iter()
+            .filter_map(|ind| mdl.get(*ind).map(|b| (*ind, b)))
+    }
+}
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface,
+    S: BlockStorage<B>,
+{
+    pub fn topological_order<D: BlockDependenceInterface>(
+        &self,
+        dep: &D,
+    ) -> Result<TopologicalOrder, BlockModelError> {
+        TopologicalOrder::new(self, dep)
+    }
+}
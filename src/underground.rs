@@ -0,0 +1,283 @@
+use crate::block::{BlockIndex, BlockInterface};
+use crate::block_model::{BlockDependenceInterface, BlockModel};
+use crate::schedule::Schedule;
+use crate::stope::StopeLayout;
+use crate::storage::BlockStorage;
+
+use std::collections::HashMap;
+
+//order in which levels are mined, levels count up with k
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub enum LevelDirection {
+    //levels below wait for the level above, e.g. sublevel caving or top down stoping
+    #[default]
+    TopDown,
+    //levels above wait for the level below, e.g. cut and fill
+    BottomUp,
+}
+
+//level by level mining, every block waits for the blocks of the previous level within
+//reach blocks along i and j, a reach covering the model orders whole levels
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct LevelPrecedence {
+    pub benches_per_level: usize,
+    pub direction: LevelDirection,
+    pub reach: [usize; 2],
+}
+
+impl LevelPrecedence {
+    pub fn new(benches_per_level: usize, direction: LevelDirection) -> Self {
+        assert!(benches_per_level > 0, "a level needs at least one bench");
+        Self {
+            benches_per_level,
+            direction,
+            reach: [0, 0],
+        }
+    }
+
+    pub fn with_reach(mut self, reach: [usize; 2]) -> Self {
+        self.reach = reach;
+        self
+    }
+}
+
+impl BlockDependenceInterface for LevelPrecedence {
+    fn inds<B: BlockInterface, S: BlockStorage<B>>(
+        &self,
+        mdl: &BlockModel<B, S>,
+        ind: BlockIndex,
+    ) -> Vec<BlockIndex> {
+        let dims = mdl.dims();
+        let n = self.benches_per_level;
+        let level = ind.k / n;
+        let previous = match self.direction {
+            LevelDirection::TopDown => level + 1,
+            LevelDirection::BottomUp if level > 0 => level - 1,
+            LevelDirection::BottomUp => return Vec::new(),
+        };
+
+        let window = |c: usize, r: usize, d: usize| c.saturating_sub(r)..(c + r + 1).min(d);
+        let mut inds = Vec::new();
+        for k in previous * n..((previous + 1) * n).min(dims[2]) {
+            for i in window(ind.i, self.reach[0], dims[0]) {
+                for j in window(ind.j, self.reach[1], dims[1]) {
+                    inds.push(BlockIndex { i, j, k });
+                }
+            }
+        }
+        inds
+    }
+}
+
+//stope by stope sequencing, every block of a stope waits for every block of the stopes
+//within gap blocks of it that rank lower, so a stope is mined out before a neighbour starts,
+//blocks outside the stopes have no predecessors. the arcs allow a neighbour to start in the
+//period its predecessor finishes, the schedulers have no time lags so the fill delay, the
+//periods a mined out stope needs to be filled before its neighbours start, is checked and
+//applied on finished schedules instead
+#[derive(Debug, Clone, PartialEq)]
+pub struct StopeSequence {
+    layout: StopeLayout,
+    ranks: Vec<usize>,
+    stope_of: HashMap<BlockIndex, usize>,
+    preds: Vec<Vec<usize>>,
+    fill_delay: usize,
+}
+
+impl StopeSequence {
+    pub fn new(layout: StopeLayout, ranks: Vec<usize>, gap: usize) -> Self {
+        assert!(
+            ranks.len() == layout.stopes.len(),
+            "one rank is needed per stope"
+        );
+        let stopes = &layout.stopes;
+        let stope_of = stopes
+            .iter()
+            .enumerate()
+            .flat_map(|(n, s)| s.inds().map(move |ind| (ind, n)))
+            .collect();
+        let preds = (0..stopes.len())
+            .map(|n| {
+                (0..stopes.len())
+                    .filter(|m| ranks[*m] < ranks[n])
+                    .filter(|m| stopes[*m].conflicts(&stopes[n], [gap + 1; 3]))
+                    .collect()
+            })
+            .collect();
+        Self {
+            layout,
+            ranks,
+            stope_of,
+            preds,
+            fill_delay: 0,
+        }
+    }
+
+    //periods between the last period of a stope and the first period of a neighbour waiting
+    //on it, 0 lets a neighbour start in the period the stope is mined out
+    pub fn with_fill_delay(mut self, periods: usize) -> Self {
+        self.fill_delay = periods;
+        self
+    }
+
+    pub fn fill_delay(&self) -> usize {
+        self.fill_delay
+    }
+
+    //secondary stopes wait for the primary stopes next to them
+    pub fn primary_secondary(layout: StopeLayout, secondary: &[bool], gap: usize) -> Self {
+        let ranks = secondary.iter().map(|s| *s as usize).collect();
+        Self::new(layout, ranks, gap)
+    }
+
+    //retreat along a model axis, stopes nearer the start of the axis first, or the end if
+    //reverse, neighbours on the same position are unordered
+    pub fn retreat(layout: StopeLayout, axis: usize, reverse: bool, gap: usize) -> Self {
+        assert!(axis < 3, "axis must be 0, 1 or 2");
+        let ranks = layout
+            .stopes
+            .iter()
+            .map(|s| {
+                let p = [s.min.i, s.min.j, s.min.k][axis];
+                if reverse {
+                    layout.dims[axis] - p
+                } else {
+                    p
+                }
+            })
+            .collect();
+        Self::new(layout, ranks, gap)
+    }
+
+    pub fn layout(&self) -> &StopeLayout {
+        &self.layout
+    }
+
+    pub fn rank(&self, stope: usize) -> usize {
+        self.ranks[stope]
+    }
+
+    //stopes that must be complete before stope starts
+    pub fn preds(&self, stope: usize) -> &[usize] {
+        &self.preds[stope]
+    }
+
+    //first and last period of a stope, none unless every block of it is scheduled
+    fn span(&self, stope: usize, periods: &HashMap<BlockIndex, usize>) -> Option<(usize, usize)> {
+        self.layout.stopes[stope]
+            .inds()
+            .try_fold(None, |span, ind| {
+                let t = *periods.get(&ind)?;
+                Some(Some(match span {
+                    Some((first, last)) => (t.min(first), t.max(last)),
+                    None => (t, t),
+                }))
+            })
+            .flatten()
+    }
+
+    //(predecessor, stope) pairs where a started stope begins before its predecessor is
+    //mined out and filled
+    pub fn fill_violations(&self, schedule: &Schedule) -> Vec<(usize, usize)> {
+        let periods = &schedule.periods;
+        let started = |n: usize| {
+            self.layout.stopes[n]
+                .inds()
+                .filter_map(|ind| periods.get(&ind).copied())
+                .min()
+        };
+        (0..self.preds.len())
+            .filter_map(|n| Some((n, started(n)?)))
+            .flat_map(|(n, first)| {
+                self.preds[n]
+                    .iter()
+                    .filter(move |m| match self.span(**m, periods) {
+                        Some((_, last)) => first < last + self.fill_delay,
+                        None => true,
+                    })
+                    .map(move |m| (*m, n))
+            })
+            .collect()
+    }
+
+    //periods of the schedule with each stope pushed back until its predecessors are mined out
+    //and filled, in rank order, blocks pushed past the horizon and stopes whose predecessors
+    //end up incomplete are left unmined, only stope order is kept so rebuild the schedule
+    //with Schedule::new and recheck other precedence and capacities
+    pub fn apply_fill_delay(&self, schedule: &Schedule) -> HashMap<BlockIndex, usize> {
+        let mut periods = schedule.periods.clone();
+        let mut order = (0..self.preds.len()).collect::<Vec<_>>();
+        order.sort_by_key(|n| self.ranks[*n]);
+        for n in order {
+            let inds = self.layout.stopes[n].inds().collect::<Vec<_>>();
+            let Some(first) = inds
+                .iter()
+                .filter_map(|ind| periods.get(ind))
+                .min()
+                .copied()
+            else {
+                continue;
+            };
+            let ready = self.preds[n].iter().try_fold(0, |ready: usize, m| {
+                let (_, last) = self.span(*m, &periods)?;
+                Some(ready.max(last + self.fill_delay))
+            });
+            let Some(ready) = ready else {
+                for ind in &inds {
+                    periods.remove(ind);
+                }
+                continue;
+            };
+            let shift = ready.saturating_sub(first);
+            for ind in &inds {
+                if let Some(t) = periods.get(ind).map(|t| t + shift) {
+                    if t < schedule.num_periods {
+                        periods.insert(*ind, t);
+                    } else {
+                        periods.remove(ind);
+                    }
+                }
+            }
+        }
+        periods
+    }
+}
+
+impl BlockDependenceInterface for StopeSequence {
+    fn inds<B: BlockInterface, S: BlockStorage<B>>(
+        &self,
+        _mdl: &BlockModel<B, S>,
+        ind: BlockIndex,
+    ) -> Vec<BlockIndex> {
+        let Some(n) = self.stope_of.get(&ind) else {
+            return Vec::new();
+        };
+        self.preds[*n]
+            .iter()
+            .flat_map(|m| self.layout.stopes[*m].inds())
+            .collect()
+    }
+}
+
+//predecessors of both rules, e.g. level order together with stope sequencing, rules that
+//disagree, such as a level reach crossing into later stopes, leave cycles in the graph
+#[derive(Debug, Clone, PartialEq)]
+pub struct CombinedPrecedence<A, B>(pub A, pub B);
+
+impl<P, Q> BlockDependenceInterface for CombinedPrecedence<P, Q>
+where
+    P: BlockDependenceInterface,
+    Q: BlockDependenceInterface,
+{
+    fn inds<B: BlockInterface, S: BlockStorage<B>>(
+        &self,
+        mdl: &BlockModel<B, S>,
+        ind: BlockIndex,
+    ) -> Vec<BlockIndex> {
+        let mut inds = self.0.inds(mdl, ind);
+        inds.extend(self.1.inds(mdl, ind));
+        inds.sort();
+        inds.dedup();
+        inds
+    }
+}
@@ -0,0 +1,136 @@
+use crate::block::{BlockCoordinates, BlockIndex, BlockInterface, BlockSize, NamedAttribute};
+use crate::block_model::BlockModel;
+use crate::frame::FrameRotation;
+use crate::storage::BlockStorage;
+
+use std::collections::HashMap;
+
+//problems found in a set of blocks before building a model
+//blocks are identified by their position in the input
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ValidationReport {
+    pub num_blocks: usize,
+    //size of the first block, other blocks are compared against it
+    pub block_size: Option<BlockSize>,
+    //centroid of block (0, 0, 0) implied by the minimum coordinates, along the rotated axes
+    //when the blocks only align on a rotated lattice
+    pub origin: Option<BlockCoordinates>,
+    //rotation detected as from_unindexed would when the blocks do not align with the axes
+    pub rotation: Option<FrameRotation>,
+    pub mixed_sizes: Vec<usize>,
+    //centroids off the lattice defined by origin, rotation and block_size
+    pub misaligned: Vec<usize>,
+    //indices claimed by more than one block, with every claiming block
+    pub duplicates: Vec<(BlockIndex, Vec<usize>)>,
+    //block position and attribute name
+    pub nan_values: Vec<(usize, String)>,
+    //i, j, k extents of the aligned blocks and the empty cells inside them
+    pub dims: [usize; 3],
+    pub missing_cells: usize,
+}
+
+impl ValidationReport {
+    //true if a model can be built from the blocks, index gaps are allowed
+    pub fn is_valid(&self) -> bool {
+        self.num_blocks > 0
+            && self.mixed_sizes.is_empty()
+            && self.misaligned.is_empty()
+            && self.duplicates.is_empty()
+    }
+
+    pub fn has_nan(&self) -> bool {
+        !self.nan_values.is_empty()
+    }
+
+    //true if every cell inside the extents holds a block
+    pub fn is_dense(&self) -> bool {
+        self.missing_cells == 0
+    }
+}
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface,
+    S: BlockStorage<B>,
+{
+    //check unindexed blocks for every problem from_unindexed would reject, plus NaN
+    //attribute values and index gaps, without stopping at the first failure
+    pub fn validate(blocks: &[B], attributes: &[NamedAttribute<B>]) -> ValidationReport {
+        let mut report = ValidationReport {
+            num_blocks: blocks.len(),
+            ..Default::default()
+        };
+
+        let Some(first) = blocks.first() else {
+            return report;
+        };
+        let size = first.size();
+        report.block_size = Some(size);
+
+        let origin = blocks.iter().fold(
+            BlockCoordinates {
+                x: f32::MAX,
+                y: f32::MAX,
+                z: f32::MAX,
+            },
+            |o, b| {
+                let c = b.coordinates();
+                BlockCoordinates {
+                    x: o.x.min(c.x),
+                    y: o.y.min(c.y),
+                    z: o.z.min(c.z),
+                }
+            },
+        );
+        report.origin = Some(origin);
+
+        //axis aligned lattice first, then a detected rotation as from_unindexed falls back to
+        let mut inds = blocks
+            .iter()
+            .map(|b| Self::block_ind(b, origin, size).ok())
+            .collect::<Vec<_>>();
+        if inds.iter().any(|ind| ind.is_none()) {
+            if let Some(rotation) = Self::detect_rotation(blocks, size) {
+                let (rotated_origin, rotated) = Self::rotated_inds(blocks, size, rotation);
+                report.origin = Some(rotated_origin);
+                report.rotation = Some(rotation);
+                inds = rotated;
+            }
+        }
+
+        let mut claims = HashMap::<BlockIndex, Vec<usize>>::new();
+        for (n, (b, ind)) in blocks.iter().zip(inds).enumerate() {
+            if b.size() != size {
+                report.mixed_sizes.push(n);
+            }
+
+            for (name, attr) in attributes {
+                if attr(b).is_nan() {
+                    report.nan_values.push((n, name.to_string()));
+                }
+            }
+
+            match ind {
+                Some(ind) => claims.entry(ind).or_default().push(n),
+                None => report.misaligned.push(n),
+            }
+        }
+
+        report.dims = claims.keys().fold([0; 3], |d, ind| {
+            [
+                d[0].max(ind.i + 1),
+                d[1].max(ind.j + 1),
+                d[2].max(ind.k + 1),
+            ]
+        });
+        report.missing_cells = report.dims.iter().product::<usize>() - claims.len();
+
+        report.duplicates = claims
+            .into_iter()
+            .filter(|(_, claimed)| claimed.len() > 1)
+            .collect();
+        report.duplicates.sort_by_key(|(ind, _)| *ind);
+
+        report
+    }
+}
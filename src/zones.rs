@@ -0,0 +1,190 @@
+use rayon::prelude::*;
+
+use crate::block::{BlockIndex, BlockInterface};
+use crate::block_model::BlockModel;
+use crate::storage::BlockStorage;
+
+use std::collections::{HashMap, VecDeque};
+
+//neighbours considered adjacent when labelling zones
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub enum Connectivity {
+    //6 blocks sharing a face
+    #[default]
+    Face,
+    //18 blocks sharing a face or an edge
+    Edge,
+    //26 blocks sharing a face, edge or corner
+    Vertex,
+}
+
+impl Connectivity {
+    pub(crate) fn offsets(&self) -> Vec<[i64; 3]> {
+        let max = match self {
+            Self::Face => 1,
+            Self::Edge => 2,
+            Self::Vertex => 3,
+        };
+
+        let mut offsets = Vec::new();
+        for di in -1..=1i64 {
+            for dj in -1..=1i64 {
+                for dk in -1..=1i64 {
+                    let n = di.abs() + dj.abs() + dk.abs();
+                    if n > 0 && n <= max {
+                        offsets.push([di, dj, dk]);
+                    }
+                }
+            }
+        }
+        offsets
+    }
+}
+
+//size of one connected zone
+#[derive(Debug, Clone, PartialEq)]
+pub struct ZoneStats {
+    pub num_blocks: usize,
+    pub volume: f64,
+    pub tonnage: f64,
+}
+
+//connected zones of blocks, ids are assigned in index order of the first block of each zone
+#[derive(Debug, Clone, PartialEq)]
+pub struct Zones {
+    pub labels: HashMap<BlockIndex, usize>,
+    pub stats: Vec<ZoneStats>,
+}
+
+impl Zones {
+    pub fn len(&self) -> usize {
+        self.stats.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stats.is_empty()
+    }
+
+    pub fn zone(&self, ind: BlockIndex) -> Option<usize> {
+        self.labels.get(&ind).copied()
+    }
+
+    //blocks of zone n
+    pub fn blocks(&self, n: usize) -> Vec<BlockIndex> {
+        let mut inds = self
+            .labels
+            .iter()
+            .filter(|(_, z)| **z == n)
+            .map(|(ind, _)| *ind)
+            .collect::<Vec<_>>();
+        inds.sort();
+        inds
+    }
+}
+
+impl<B, S> BlockModel<B, S>
+where
+    B: BlockInterface,
+    S: BlockStorage<B>,
+{
+    //present neighbours of ind under connectivity
+    pub(crate) fn neighbours<'a>(
+        &'a self,
+        ind: BlockIndex,
+        offsets: &'a [[i64; 3]],
+    ) -> impl Iterator<Item = BlockIndex> + 'a {
+        let [ni, nj, nk] = self.dims();
+        offsets.iter().filter_map(move |[di, dj, dk]| {
+            let i = ind.i as i64 + di;
+            let j = ind.j as i64 + dj;
+            let k = ind.k as i64 + dk;
+            if i < 0 || j < 0 || k < 0 || i >= ni as i64 || j >= nj as i64 || k >= nk as i64 {
+                return None;
+            }
+
+            let n = BlockIndex {
+                i: i as usize,
+                j: j as usize,
+                k: k as usize,
+            };
+            self.block(n).is_some().then_some(n)
+        })
+    }
+
+    //group contiguous blocks satisfying pred into zones
+    pub fn label_zones<P, T>(&self, pred: P, connectivity: Connectivity, tonnage: T) -> Zones
+    where
+        P: Fn(&B) -> bool,
+        T: Fn(&B) -> f64,
+    {
+        let offsets = connectivity.offsets();
+        let size = self.block_size();
+        let block_volume = size.x_size as f64 * size.y_size as f64 * size.z_size as f64;
+
+        let mut seeds = self
+            .indexed_iter()
+            .filter(|(_, b)| pred(b))
+            .map(|(ind, _)| ind)
+            .collect::<Vec<_>>();
+        seeds.sort();
+
+        let mut labels = HashMap::with_capacity(seeds.len());
+        let mut stats = Vec::new();
+        let mut queue = VecDeque::new();
+        for seed in seeds {
+            if labels.contains_key(&seed) {
+                continue;
+            }
+
+            let zone = stats.len();
+            let mut zone_stats = ZoneStats {
+                num_blocks: 0,
+                volume: 0.0,
+                tonnage: 0.0,
+            };
+            labels.insert(seed, zone);
+            queue.push_back(seed);
+
+            while let Some(ind) = queue.pop_front() {
+                let b = self.block(ind).unwrap();
+                zone_stats.num_blocks += 1;
+                zone_stats.volume += block_volume;
+                zone_stats.tonnage += tonnage(b);
+
+                for n in self.neighbours(ind, &offsets) {
+                    if !labels.contains_key(&n) && pred(self.block(n).unwrap()) {
+                        labels.insert(n, zone);
+                        queue.push_back(n);
+                    }
+                }
+            }
+
+            stats.push(zone_stats);
+        }
+
+        Zones { labels, stats }
+    }
+
+    //label zones and write each labelled block's zone id
+    pub fn assign_zones<P, T, F>(
+        &mut self,
+        pred: P,
+        connectivity: Connectivity,
+        tonnage: T,
+        set: F,
+    ) -> Vec<ZoneStats>
+    where
+        B: Send,
+        P: Fn(&B) -> bool,
+        T: Fn(&B) -> f64,
+        F: Fn(&mut B, usize) + Sync,
+    {
+        let zones = self.label_zones(pred, connectivity, tonnage);
+        self.par_indexed_iter_mut().for_each(|(ind, b)| {
+            if let Some(zone) = zones.labels.get(&ind) {
+                set(b, *zone);
+            }
+        });
+        zones.stats
+    }
+}
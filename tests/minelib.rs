@@ -0,0 +1,31 @@
+use block_model_utils::io::minelib::{MineLibProblem, MineLibProblemType};
+
+use std::path::PathBuf;
+
+fn fixture(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name)
+}
+
+#[test]
+fn reads_published_upit_header() {
+    let problem = MineLibProblem::read(fixture("minelib_upit.upit")).unwrap();
+    assert_eq!(problem.name, "tiny_upit");
+    assert_eq!(problem.kind, MineLibProblemType::Upit);
+    assert_eq!(problem.values.len(), 3);
+    assert_eq!(problem.values[&2], 4.25);
+}
+
+#[test]
+fn reads_published_cpit_header() {
+    let problem = MineLibProblem::read(fixture("minelib_cpit.cpit")).unwrap();
+    assert_eq!(problem.kind, MineLibProblemType::Cpit);
+    assert_eq!(problem.num_periods, 2);
+    assert_eq!(problem.num_resources, 1);
+    assert_eq!(problem.discount_rate, 0.1);
+    assert_eq!(problem.values[&0], -1.5);
+    assert_eq!(problem.limits.len(), 2);
+    assert!(problem.limits.iter().all(|l| l.max == 2.0));
+    assert_eq!(problem.coefficients[&(1, 0)], 1.0);
+}